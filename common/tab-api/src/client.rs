@@ -3,8 +3,9 @@
 use crate::chunk::OutputChunk;
 use crate::{
     chunk::InputChunk,
-    tab::{CreateTabMetadata, TabId, TabMetadata},
+    tab::{CreateTabMetadata, ExitedTab, TabExit, TabId, TabMetadata},
 };
+use lifeline::impl_storage_clone;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 /// A request, sent from a CLI connection to the daemon process.
@@ -14,7 +15,8 @@ pub enum Request {
     /// The WebSocket will produce a series of Chunk messages,
     /// The messages will have incrementing (but not sequential) indices.
     /// The messages may begin with data from the scrollback buffer
-    Subscribe(TabId),
+    /// Read-only and capture subscriptions receive output, but the daemon rejects their input & resize requests.
+    Subscribe(TabId, SubscriptionMode),
 
     /// Deactivates the subscription for the given tab.
     Unsubscribe(TabId),
@@ -31,14 +33,65 @@ pub enum Request {
     /// Re-tasks clients with the tabid selected to the given tab
     Retask(TabId, RetaskTarget),
 
-    /// Terminates the shell on the given tab
+    /// Terminates the shell on the given tab, or dismisses the tab if the shell has exited
     CloseTab(TabId),
 
     /// Disconnects any sessions for the given tab
     DisconnectTab(TabId),
 
+    /// Searches the scrollback of all running tabs, including output which has been spilled to disk.
+    /// The daemon replies with a single Grep response.
+    Grep(GrepQuery),
+
+    /// Requests the rendered lines of the subscribed tab's history & screen, for paging through the scrollback.
+    /// The daemon replies with a ScrollbackLines response, which is empty if the tab is not subscribed.
+    ScrollbackLines(TabId),
+
+    /// Requests an acknowledgement that the daemon has received the previous requests on this connection.
+    /// The daemon replies with a Flushed response, once the previous requests have been delivered to the tabs.
+    Flush,
+
+    /// Starts recording the output of the given tab to an asciicast file, in the recordings directory.
+    /// Ignored if the tab is already being recorded.
+    RecordTab(TabId),
+
     /// Shuts down all tab processes, including the daemon and all ptys
     GlobalShutdown,
+
+    /// Shuts down the daemon, and hands the running tabs over to the next daemon.
+    /// The ptys keep running, and reconnect when the next daemon is launched.
+    UpgradeDaemon,
+}
+
+/// Determines whether a subscribed client can interact with the tab.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionMode {
+    /// The client can send input to the tab, and resize it
+    Interactive,
+    /// The client can only view the tab output
+    ReadOnly,
+    /// The client can only view the tab output, and receives the raw scrollback rather than a redraw of the terminal state
+    Capture,
+}
+
+impl_storage_clone!(SubscriptionMode);
+
+/// A search through the scrollback of running tabs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GrepQuery {
+    /// The text to search for.  ANSI escape sequences are removed before lines are matched,
+    /// and an empty pattern matches every line.
+    pub pattern: String,
+    /// The maximum number of matching lines for each tab.  If set, the most recent matches are kept.
+    pub limit: Option<usize>,
+}
+
+/// A line of tab output, which matched a grep query.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GrepMatch {
+    pub id: TabId,
+    pub name: String,
+    pub line: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -49,20 +102,29 @@ pub enum RetaskTarget {
 }
 
 /// A response, sent from the daemon process to a connected CLI
+#[allow(clippy::large_enum_variant)]
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum Response {
     /// An initial 'hello' message with introductory state, including a full list of running tabs.
     Init(InitResponse),
     /// A raw output chunk, identified by a `TabId` and an index.
     Output(TabId, OutputChunk),
+    /// A notification that the scrollback for the subscribed tab has been sent.  Any further output is live.
+    ScrollbackEnd(TabId),
     /// A notification that metadata about a running tab has changed.
     TabUpdate(TabMetadata),
     /// A notification that the client is being re-tasks, and will now be serving the user on another tab.
     Retask(RetaskTarget),
-    /// A notification that the active tab has been terminated
-    TabTerminated(TabId),
+    /// A notification that the active tab has been terminated, with the exit status of the shell
+    TabTerminated(TabId, TabExit),
     /// A notification that the client should disconnect
     Disconnect,
+    /// The lines of tab output which matched a grep query, ordered by tab name and then by output position.
+    Grep(Vec<GrepMatch>),
+    /// The rendered lines of a tab's history & screen.  Each line begins and ends with the default graphic rendition.
+    ScrollbackLines(TabId, Vec<String>),
+    /// An acknowledgement that the requests sent before a Flush request have been received by the daemon.
+    Flushed,
 }
 
 /// An initialization message sent to CLI connections.
@@ -70,4 +132,7 @@ pub enum Response {
 pub struct InitResponse {
     /// A complete set of active tabs, identified by TabId values.
     pub tabs: HashMap<TabId, TabMetadata>,
+    /// Tabs whose shell exited on their own, which have not yet been dismissed.
+    #[serde(default)]
+    pub exited: HashMap<TabId, ExitedTab>,
 }
@@ -1,15 +1,20 @@
 use anyhow::Result;
 use lifeline::impl_storage_clone;
-use serde::Deserialize;
 use serde::Serialize;
-use std::{env, fs::File, io::BufReader, path::PathBuf};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{env, fs::File, io::BufReader, path::PathBuf, str::FromStr};
 use sysinfo::{ProcessExt, RefreshKind, SystemExt};
 
+use crate::tab::{OutputLogLimit, ScrollbackLimit, TabId};
+
 /// Config created for each daemon process
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DaemonConfig {
     pub pid: i32,
     pub port: u16,
+    /// The Unix socket the daemon listens on.  If None, the daemon listens on the TCP port.
+    #[serde(default)]
+    pub socket: Option<PathBuf>,
     pub executable: Option<String>,
     pub tab_version: Option<String>,
     pub auth_token: String,
@@ -17,6 +22,16 @@ pub struct DaemonConfig {
 
 impl_storage_clone!(DaemonConfig);
 
+impl DaemonConfig {
+    /// The websocket URL for the given request path (e.g. `/cli`), over the daemon's socket or TCP port.
+    pub fn websocket_url(&self, path: &str) -> String {
+        match self.socket {
+            Some(ref socket) => format!("ws+unix://{}:{}", socket.to_string_lossy(), path),
+            None => format!("ws://127.0.0.1:{}{}", self.port, path),
+        }
+    }
+}
+
 /// Creates the data path.
 pub fn mkdir() -> Result<()> {
     let data_path = data_path()?;
@@ -60,6 +75,15 @@ pub fn is_running(config: &DaemonConfig) -> bool {
     }
 }
 
+/// Returns the path to the daemon's Unix socket, which is unique to the daemon process.
+/// The socket is created in the `sockets` directory, which is only accessible by the current user.
+pub fn daemon_socket(pid: u32) -> Result<PathBuf> {
+    let mut dir = data_path()?;
+    dir.push("sockets");
+    dir.push(format!("daemon-{}.sock", pid));
+    Ok(dir)
+}
+
 /// Returns the path to the daemon's logfile.
 pub fn daemon_log() -> Result<PathBuf> {
     let mut dir = data_path()?;
@@ -74,6 +98,45 @@ pub fn pty_log() -> Result<PathBuf> {
     Ok(dir)
 }
 
+/// Returns the path to the session directory, which stores tabs & scrollback across daemon restarts.
+pub fn session_path() -> Result<PathBuf> {
+    let mut dir = data_path()?;
+    dir.push("session");
+    Ok(dir)
+}
+
+/// Returns the path to the directory which stores the spilled scrollback of the given tab.
+pub fn scrollback_path(id: TabId) -> Result<PathBuf> {
+    let mut dir = data_path()?;
+    dir.push("scrollback");
+    dir.push(id.0.to_string());
+    Ok(dir)
+}
+
+/// Returns the path to the directory which stores asciicast recordings of tab output.
+pub fn recordings_path() -> Result<PathBuf> {
+    let mut dir = data_path()?;
+    dir.push("recordings");
+    Ok(dir)
+}
+
+/// Returns the path to the directory which stores the rolling output logs of tabs.
+pub fn logs_path() -> Result<PathBuf> {
+    let mut dir = data_path()?;
+    dir.push("logs");
+    Ok(dir)
+}
+
+/// Returns the path to the rolling output log of the tab with the given name.
+/// Slashes in the tab name are replaced with underscores, so `proj/run/` is logged to `logs/proj_run.log`.
+pub fn output_log_path(name: &str) -> Result<PathBuf> {
+    let name = name.trim_end_matches('/').replace("/", "_");
+
+    let mut path = logs_path()?;
+    path.push(format!("{}.log", name));
+    Ok(path)
+}
+
 /// Returns the path to a unique logfile fro the given shell process, and tab name.
 pub fn history_path(shell: &str, name: &str) -> Result<PathBuf> {
     let mut path = data_path()?;
@@ -103,6 +166,135 @@ pub fn load_daemon_file() -> anyhow::Result<Option<DaemonConfig>> {
     Ok(Some(config))
 }
 
+/// The scrollback options in the global configuration file, which are read by the daemon when it launches.
+/// The keys are prefixed, as `scrollback` is a tab option in the global workspace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ScrollbackConfig {
+    /// The default scrollback limit for each tab
+    #[serde(default, rename = "scrollback_limit")]
+    pub limit: Option<ScrollbackLimit>,
+    /// The maximum number of scrollback bytes retained by the daemon, across all tabs.
+    /// When the budget is exceeded, the oldest scrollback is evicted from idle tabs first.
+    #[serde(default, rename = "scrollback_budget")]
+    pub budget: Option<usize>,
+}
+
+impl_storage_clone!(ScrollbackConfig);
+
+/// Loads the scrollback options from the global configuration file, or returns the default options
+pub fn load_scrollback_config() -> anyhow::Result<ScrollbackConfig> {
+    load_global_config()
+}
+
+/// The output log options in the global configuration file, which are read by the daemon when it launches.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct OutputLogConfig {
+    /// The limits of the rolling output log, for tabs with `log: true`.  If None, the default limits are used.
+    #[serde(default, rename = "log_limit")]
+    pub limit: Option<OutputLogLimit>,
+}
+
+impl_storage_clone!(OutputLogConfig);
+
+/// Loads the output log options from the global configuration file, or returns the default options
+pub fn load_output_log_config() -> anyhow::Result<OutputLogConfig> {
+    load_global_config()
+}
+
+/// The transport options in the global configuration file, which are read by the daemon when it launches.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct TransportConfig {
+    /// Listens on a Unix socket in the data directory, rather than a TCP port.
+    /// If the socket cannot be bound, the daemon falls back to TCP.
+    #[serde(default)]
+    pub unix_socket: bool,
+}
+
+impl TransportConfig {
+    /// Applies the `TAB_UNIX_SOCKET` environment variable, which overrides the configured transport
+    pub fn with_env_override(mut self) -> Self {
+        if let Some(unix_socket) = crate::env::unix_socket_override() {
+            self.unix_socket = unix_socket;
+        }
+
+        self
+    }
+}
+
+/// Loads the transport options from the global configuration file, or returns the default options
+pub fn load_transport_config() -> anyhow::Result<TransportConfig> {
+    load_global_config()
+}
+
+/// Determines the size of a tab, when several clients with different dimensions are attached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResizePolicy {
+    /// The tab uses the smallest columns & rows of the attached clients
+    #[default]
+    Smallest,
+    /// The tab uses the largest columns & rows of the attached clients
+    Largest,
+    /// The tab uses the dimensions of the most recently active client
+    Recent,
+}
+
+impl FromStr for ResizePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "smallest" => Ok(Self::Smallest),
+            "largest" => Ok(Self::Largest),
+            "recent" => Ok(Self::Recent),
+            _ => Err(anyhow::anyhow!(
+                "unknown resize policy '{}', expected one of: smallest, largest, recent",
+                s
+            )),
+        }
+    }
+}
+
+/// The resize options in the global configuration file, which are read by the daemon when it launches.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ResizeConfig {
+    /// Configures how the daemon sizes tabs with several attached clients.
+    /// Defaults to `ResizePolicy::Smallest`, so output is never garbled on the smaller terminal.
+    #[serde(default, rename = "resize_policy")]
+    pub policy: ResizePolicy,
+}
+
+impl_storage_clone!(ResizeConfig);
+
+impl ResizeConfig {
+    /// Applies the `TAB_RESIZE_POLICY` environment variable, which overrides the configured policy
+    pub fn with_env_override(mut self) -> Self {
+        if let Some(policy) = crate::env::resize_policy_override() {
+            self.policy = policy;
+        }
+
+        self
+    }
+}
+
+/// Loads the resize options from the global configuration file, or returns the default options
+pub fn load_resize_config() -> anyhow::Result<ResizeConfig> {
+    load_global_config()
+}
+
+fn load_global_config<T: DeserializeOwned + Default>() -> anyhow::Result<T> {
+    let path = match global_config_file() {
+        Some(path) => path,
+        None => return Ok(T::default()),
+    };
+
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let config = serde_yaml::from_reader(reader)?;
+
+    Ok(config)
+}
+
 /// The full path to the global configuration file
 pub fn global_config_file() -> Option<PathBuf> {
     // Use $TAB_CONFIG as the config file if available
@@ -132,7 +324,11 @@ pub fn global_config_file() -> Option<PathBuf> {
 
 #[cfg(test)]
 mod tests {
-    use super::{daemon_file, data_path};
+    use super::{
+        daemon_file, data_path, output_log_path, DaemonConfig, OutputLogConfig, ResizeConfig,
+        ResizePolicy, ScrollbackConfig, TransportConfig,
+    };
+    use crate::tab::{OutputLogLimit, ScrollbackLimit};
 
     #[test]
     fn data_path_matches() {
@@ -154,4 +350,100 @@ mod tests {
         assert!(path.is_ok());
         assert_eq!(expected, path.unwrap());
     }
+
+    #[test]
+    fn websocket_url() {
+        let mut config = DaemonConfig {
+            pid: 1,
+            port: 1234,
+            socket: None,
+            executable: None,
+            tab_version: None,
+            auth_token: "TOKEN".into(),
+        };
+
+        assert_eq!("ws://127.0.0.1:1234/cli", config.websocket_url("/cli"));
+
+        config.socket = Some("/tmp/daemon-1.sock".into());
+        assert_eq!(
+            "ws+unix:///tmp/daemon-1.sock:/cli",
+            config.websocket_url("/cli")
+        );
+    }
+
+    #[test]
+    fn scrollback_config() -> anyhow::Result<()> {
+        let yaml = "scrollback_limit:\n  lines: 5000\nscrollback_budget: 1048576\nworkspace: []\n";
+        let config: ScrollbackConfig = serde_yaml::from_str(yaml)?;
+
+        assert_eq!(
+            ScrollbackConfig {
+                limit: Some(ScrollbackLimit::Lines(5000)),
+                budget: Some(1048576)
+            },
+            config
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn scrollback_config_default() -> anyhow::Result<()> {
+        let config: ScrollbackConfig = serde_yaml::from_str("workspace: []\n")?;
+        assert_eq!(ScrollbackConfig::default(), config);
+
+        Ok(())
+    }
+
+    #[test]
+    fn transport_config() -> anyhow::Result<()> {
+        let config: TransportConfig = serde_yaml::from_str("unix_socket: true\nworkspace: []\n")?;
+        assert!(config.unix_socket);
+
+        let config: TransportConfig = serde_yaml::from_str("workspace: []\n")?;
+        assert_eq!(TransportConfig::default(), config);
+
+        Ok(())
+    }
+
+    #[test]
+    fn resize_config() -> anyhow::Result<()> {
+        let yaml = "resize_policy: recent\nworkspace: []\n";
+        let config: ResizeConfig = serde_yaml::from_str(yaml)?;
+        assert_eq!(ResizePolicy::Recent, config.policy);
+
+        let config: ResizeConfig = serde_yaml::from_str("workspace: []\n")?;
+        assert_eq!(ResizePolicy::Smallest, config.policy);
+
+        Ok(())
+    }
+
+    #[test]
+    fn output_log_config() -> anyhow::Result<()> {
+        let yaml = "log_limit:\n  bytes: 1048576\nworkspace: []\n";
+        let config: OutputLogConfig = serde_yaml::from_str(yaml)?;
+
+        assert_eq!(
+            OutputLogConfig {
+                limit: Some(OutputLogLimit {
+                    bytes: 1048576,
+                    files: 5
+                })
+            },
+            config
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn output_log_path_matches() {
+        let mut expected = data_path().expect("data path required");
+        expected.push("logs");
+        expected.push("proj_run.log");
+
+        let path = output_log_path("proj/run/");
+        assert!(path.is_ok());
+        assert_eq!(expected, path.unwrap());
+    }
 }
@@ -0,0 +1,1811 @@
+//! A VT100/xterm state machine, which tracks the screen contents, cursor, and modes of a tab.
+//!
+//! The daemon maintains an emulator for each tab, and the client maintains an emulator for each pane.
+//!
+//! When a client attaches, the daemon synthesizes a redraw from this state, rather than replaying the raw pty output.
+//! This restores full-screen applications (which use the alternate screen), and never replays queries
+//! such as Device Status Reports, which would cause the terminal emulator to echo characters into the tab.
+
+use std::{collections::VecDeque, fmt, fmt::Write};
+
+use crate::{chunk::OutputChunk, tab::ScrollbackLimit};
+use unicode_width::UnicodeWidthChar;
+use vte::{Params, Parser, Perform};
+
+/// The default number of lines that are kept after they scroll off the top of the primary screen.
+/// Also caps the history when the tab's scrollback is limited in bytes.
+const MAX_HISTORY_LINES: usize = 10_000;
+const TAB_WIDTH: usize = 8;
+
+/// Parses pty output, and maintains the terminal state of a tab
+pub struct TerminalEmulator {
+    parser: Parser,
+    screen: Screen,
+    /// The index of the next byte of pty output, which has not yet been processed
+    end: usize,
+}
+
+impl fmt::Debug for TerminalEmulator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TerminalEmulator")
+            .field("cols", &self.screen.cols)
+            .field("rows", &self.screen.rows)
+            .field("history", &self.screen.history.len())
+            .field("end", &self.end)
+            .finish()
+    }
+}
+
+impl TerminalEmulator {
+    /// Constructs an emulator with the given dimensions, in (cols, rows)
+    pub fn new(dimensions: (u16, u16)) -> Self {
+        Self {
+            parser: Parser::new(),
+            screen: Screen::new(dimensions.0 as usize, dimensions.1 as usize),
+            end: 0,
+        }
+    }
+
+    /// Processes a chunk of pty output.  Data which has already been processed is skipped.
+    pub fn push(&mut self, chunk: &OutputChunk) {
+        if chunk.is_before(self.end) {
+            return;
+        }
+
+        let skip = self.end.saturating_sub(chunk.start());
+        self.process(&chunk.data[skip..]);
+        self.end = chunk.end();
+    }
+
+    /// Processes raw pty output
+    pub fn process(&mut self, data: &[u8]) {
+        for byte in data {
+            self.parser.advance(&mut self.screen, *byte);
+        }
+    }
+
+    /// Limits the line history, evicting the oldest lines if they exceed the limit
+    pub fn set_history_limit(&mut self, limit: ScrollbackLimit) {
+        self.screen.history_limit = limit;
+        self.screen.trim_history();
+    }
+
+    /// The total length of the lines in the history
+    pub fn history_size(&self) -> usize {
+        self.screen.history_bytes
+    }
+
+    /// Removes the oldest lines from the history, until at least `len` bytes are freed.
+    /// Returns the number of bytes which were freed.
+    pub fn evict_history(&mut self, len: usize) -> usize {
+        let mut freed = 0;
+
+        while freed < len {
+            match self.screen.history.pop_front() {
+                Some(line) => freed += line.len(),
+                None => break,
+            }
+        }
+
+        self.screen.history_bytes = self.screen.history_bytes.saturating_sub(freed);
+        freed
+    }
+
+    /// Resizes the screen to the given dimensions, in (cols, rows)
+    pub fn resize(&mut self, dimensions: (u16, u16)) {
+        self.screen
+            .resize(dimensions.0 as usize, dimensions.1 as usize);
+    }
+
+    /// The index of the end of the processed pty output
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Synthesizes output which redraws the current state on a freshly reset terminal.
+    /// The output includes the line history, the screen contents, the cursor, and any enabled modes.
+    /// If no output has been processed, the redraw is empty.
+    pub fn redraw(&self) -> Vec<u8> {
+        if self.end == 0 {
+            return Vec::new();
+        }
+
+        self.screen.redraw().into_bytes()
+    }
+
+    /// Renders the line history and the primary screen as styled lines, for paging through the scrollback.
+    /// Each line begins and ends with the default graphic rendition.
+    pub fn lines(&self) -> Vec<String> {
+        self.screen.lines()
+    }
+
+    /// Renders the visible rows of the active screen as styled lines, padded to the screen width, for display in a pane.
+    /// Each line begins and ends with the default graphic rendition.
+    pub fn rows(&self) -> Vec<String> {
+        self.screen.rows()
+    }
+
+    /// The cursor position in (col, row), or None if the application has hidden the cursor
+    pub fn cursor(&self) -> Option<(u16, u16)> {
+        if !self.screen.modes.cursor_visible {
+            return None;
+        }
+
+        let cursor = self.screen.cursor;
+        Some((cursor.col as u16, cursor.row as u16))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Default,
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    fn write_sgr(&self, out: &mut String, base: u8, bright: u8, extended: u8) {
+        let _ = match *self {
+            Color::Default => Ok(()),
+            Color::Indexed(n) if n < 8 => write!(out, ";{}", base + n),
+            Color::Indexed(n) if n < 16 => write!(out, ";{}", bright + n - 8),
+            Color::Indexed(n) => write!(out, ";{};5;{}", extended, n),
+            Color::Rgb(r, g, b) => write!(out, ";{};2;{};{};{}", extended, r, g, b),
+        };
+    }
+}
+
+/// The graphic rendition of a cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pen {
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    blink: bool,
+    inverse: bool,
+    hidden: bool,
+    strike: bool,
+    fg: Color,
+    bg: Color,
+}
+
+impl Default for Pen {
+    fn default() -> Self {
+        Self {
+            bold: false,
+            dim: false,
+            italic: false,
+            underline: false,
+            blink: false,
+            inverse: false,
+            hidden: false,
+            strike: false,
+            fg: Color::Default,
+            bg: Color::Default,
+        }
+    }
+}
+
+impl Pen {
+    /// The pen used to erase cells, which keeps only the background color
+    fn erase(&self) -> Pen {
+        Pen {
+            bg: self.bg,
+            ..Pen::default()
+        }
+    }
+
+    /// Writes a complete SGR sequence, which resets the terminal to this pen
+    fn write_sgr(&self, out: &mut String) {
+        out.push_str("\x1b[0");
+
+        let flags = [
+            (self.bold, "1"),
+            (self.dim, "2"),
+            (self.italic, "3"),
+            (self.underline, "4"),
+            (self.blink, "5"),
+            (self.inverse, "7"),
+            (self.hidden, "8"),
+            (self.strike, "9"),
+        ];
+
+        for (enabled, code) in flags.iter() {
+            if *enabled {
+                out.push(';');
+                out.push_str(code);
+            }
+        }
+
+        self.fg.write_sgr(out, 30, 90, 38);
+        self.bg.write_sgr(out, 40, 100, 48);
+        out.push('m');
+    }
+
+    fn sgr(&mut self, params: &Params) {
+        let params: Vec<&[u16]> = params.iter().collect();
+        if params.is_empty() {
+            *self = Pen::default();
+            return;
+        }
+
+        let mut i = 0;
+        while i < params.len() {
+            let param = params[i];
+            match param[0] {
+                0 => *self = Pen::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = param.get(1).map(|style| *style != 0).unwrap_or(true),
+                5 | 6 => self.blink = true,
+                7 => self.inverse = true,
+                8 => self.hidden = true,
+                9 => self.strike = true,
+                21 => self.underline = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                25 => self.blink = false,
+                27 => self.inverse = false,
+                28 => self.hidden = false,
+                29 => self.strike = false,
+                n @ 30..=37 => self.fg = Color::Indexed((n - 30) as u8),
+                38 => {
+                    if let Some(color) = Self::extended_color(&params, &mut i) {
+                        self.fg = color;
+                    }
+                }
+                39 => self.fg = Color::Default,
+                n @ 40..=47 => self.bg = Color::Indexed((n - 40) as u8),
+                48 => {
+                    if let Some(color) = Self::extended_color(&params, &mut i) {
+                        self.bg = color;
+                    }
+                }
+                49 => self.bg = Color::Default,
+                n @ 90..=97 => self.fg = Color::Indexed((n - 90 + 8) as u8),
+                n @ 100..=107 => self.bg = Color::Indexed((n - 100 + 8) as u8),
+                _ => {}
+            }
+
+            i += 1;
+        }
+    }
+
+    /// Parses an extended color, in either the `38;5;n` or `38:5:n` forms.
+    /// Advances the index past any parameters that were consumed.
+    fn extended_color(params: &[&[u16]], i: &mut usize) -> Option<Color> {
+        let param = params[*i];
+
+        // colon-separated subparameters
+        if param.len() > 1 {
+            return match param[1] {
+                5 => param.get(2).map(|n| Color::Indexed(*n as u8)),
+                2 => {
+                    // the colorspace id is optional
+                    let rgb = if param.len() >= 6 {
+                        &param[3..6]
+                    } else {
+                        param.get(2..5)?
+                    };
+                    Some(Color::Rgb(rgb[0] as u8, rgb[1] as u8, rgb[2] as u8))
+                }
+                _ => None,
+            };
+        }
+
+        // semicolon-separated parameters
+        let next = |offset: usize| params.get(*i + offset).map(|param| param[0]);
+        match next(1)? {
+            5 => {
+                let color = Color::Indexed(next(2)? as u8);
+                *i += 2;
+                Some(color)
+            }
+            2 => {
+                let color = Color::Rgb(next(2)? as u8, next(3)? as u8, next(4)? as u8);
+                *i += 4;
+                Some(color)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Width {
+    Narrow,
+    /// The first cell of a double-width character
+    Wide,
+    /// The second cell of a double-width character, which is never rendered
+    Spacer,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cell {
+    c: char,
+    combining: Vec<char>,
+    width: Width,
+    pen: Pen,
+}
+
+impl Cell {
+    fn blank(pen: Pen) -> Self {
+        Self {
+            c: ' ',
+            combining: Vec::new(),
+            width: Width::Narrow,
+            pen,
+        }
+    }
+
+    /// Whether the cell has no visible content
+    fn is_blank(&self) -> bool {
+        self.c == ' '
+            && self.combining.is_empty()
+            && self.pen.bg == Color::Default
+            && !self.pen.inverse
+            && !self.pen.underline
+            && !self.pen.strike
+    }
+}
+
+type Row = Vec<Cell>;
+
+fn blank_row(cols: usize, pen: Pen) -> Row {
+    vec![Cell::blank(pen); cols]
+}
+
+/// Renders the row, with SGR sequences.  Trailing blank cells are omitted, unless they are before `min_len`.
+fn render_row(row: &[Cell], min_len: usize, out: &mut String) {
+    let end = row
+        .iter()
+        .rposition(|cell| !cell.is_blank())
+        .map(|index| index + 1)
+        .unwrap_or(0)
+        .max(min_len.min(row.len()));
+
+    let mut pen = Pen::default();
+    for cell in &row[..end] {
+        if cell.width == Width::Spacer {
+            continue;
+        }
+
+        if cell.pen != pen {
+            cell.pen.write_sgr(out);
+            pen = cell.pen;
+        }
+
+        out.push(cell.c);
+        out.extend(cell.combining.iter());
+    }
+
+    if pen != Pen::default() {
+        out.push_str("\x1b[0m");
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Charset {
+    Ascii,
+    /// The DEC Special Graphics set, used for line drawing
+    DecSpecial,
+}
+
+impl Charset {
+    fn translate(&self, c: char) -> char {
+        if *self == Charset::Ascii {
+            return c;
+        }
+
+        match c {
+            '`' => '◆',
+            'a' => '▒',
+            'f' => '°',
+            'g' => '±',
+            'j' => '┘',
+            'k' => '┐',
+            'l' => '┌',
+            'm' => '└',
+            'n' => '┼',
+            'o' => '⎺',
+            'p' => '⎻',
+            'q' => '─',
+            'r' => '⎼',
+            's' => '⎽',
+            't' => '├',
+            'u' => '┤',
+            'v' => '┴',
+            'w' => '┬',
+            'x' => '│',
+            'y' => '≤',
+            'z' => '≥',
+            '{' => 'π',
+            '|' => '≠',
+            '}' => '£',
+            '~' => '·',
+            c => c,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cursor {
+    row: usize,
+    col: usize,
+    /// Set when a character is written to the last column.  The next character wraps to the next line.
+    pending_wrap: bool,
+}
+
+/// The state saved by DECSC, and restored by DECRC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SavedCursor {
+    cursor: Cursor,
+    pen: Pen,
+    origin: bool,
+    charsets: [Charset; 2],
+    active_charset: usize,
+}
+
+/// Terminal modes which affect how the application receives input, or how the cursor is displayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Modes {
+    application_cursor: bool,
+    application_keypad: bool,
+    autowrap: bool,
+    bracketed_paste: bool,
+    cursor_visible: bool,
+    /// The DECSCUSR cursor style
+    cursor_style: u16,
+    focus_events: bool,
+    insert: bool,
+    /// The mouse tracking mode (9, 1000, 1002, or 1003)
+    mouse_tracking: Option<u16>,
+    /// The mouse encoding mode (1005, 1006, or 1015)
+    mouse_encoding: Option<u16>,
+    origin: bool,
+}
+
+impl Default for Modes {
+    fn default() -> Self {
+        Self {
+            application_cursor: false,
+            application_keypad: false,
+            autowrap: true,
+            bracketed_paste: false,
+            cursor_visible: true,
+            cursor_style: 0,
+            focus_events: false,
+            insert: false,
+            mouse_tracking: None,
+            mouse_encoding: None,
+            origin: false,
+        }
+    }
+}
+
+struct Screen {
+    cols: usize,
+    rows: usize,
+    primary: Vec<Row>,
+    alternate: Vec<Row>,
+    alternate_active: bool,
+    /// Rendered lines which have scrolled off the top of the primary screen
+    history: VecDeque<String>,
+    /// The total length of the lines in the history
+    history_bytes: usize,
+    history_limit: ScrollbackLimit,
+    cursor: Cursor,
+    /// The primary screen cursor, while the alternate screen is active
+    primary_cursor: Cursor,
+    pen: Pen,
+    saved: [Option<SavedCursor>; 2],
+    scroll_top: usize,
+    scroll_bottom: usize,
+    modes: Modes,
+    charsets: [Charset; 2],
+    active_charset: usize,
+    title: Option<String>,
+    last_char: Option<char>,
+}
+
+impl Screen {
+    fn new(cols: usize, rows: usize) -> Self {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+
+        let cursor = Cursor {
+            row: 0,
+            col: 0,
+            pending_wrap: false,
+        };
+
+        Self {
+            cols,
+            rows,
+            primary: vec![blank_row(cols, Pen::default()); rows],
+            alternate: vec![blank_row(cols, Pen::default()); rows],
+            alternate_active: false,
+            history: VecDeque::new(),
+            history_bytes: 0,
+            history_limit: ScrollbackLimit::Lines(MAX_HISTORY_LINES),
+            cursor,
+            primary_cursor: cursor,
+            pen: Pen::default(),
+            saved: [None, None],
+            scroll_top: 0,
+            scroll_bottom: rows - 1,
+            modes: Modes::default(),
+            charsets: [Charset::Ascii, Charset::Ascii],
+            active_charset: 0,
+            title: None,
+            last_char: None,
+        }
+    }
+
+    fn grid(&mut self) -> &mut Vec<Row> {
+        if self.alternate_active {
+            &mut self.alternate
+        } else {
+            &mut self.primary
+        }
+    }
+
+    fn row(&mut self) -> &mut Row {
+        let row = self.cursor.row;
+        &mut self.grid()[row]
+    }
+
+    fn push_history(&mut self, row: &[Cell]) {
+        let mut line = String::new();
+        render_row(row, 0, &mut line);
+        self.history_bytes += line.len();
+        self.history.push_back(line);
+
+        self.trim_history();
+    }
+
+    fn trim_history(&mut self) {
+        while self.history_exceeds_limit() {
+            if let Some(line) = self.history.pop_front() {
+                self.history_bytes = self.history_bytes.saturating_sub(line.len());
+            }
+        }
+    }
+
+    fn history_exceeds_limit(&self) -> bool {
+        match self.history_limit {
+            ScrollbackLimit::Lines(lines) => self.history.len() > lines,
+            ScrollbackLimit::Bytes(bytes) => {
+                self.history.len() > MAX_HISTORY_LINES || self.history_bytes > bytes
+            }
+        }
+    }
+
+    fn clear_history(&mut self) {
+        self.history.clear();
+        self.history_bytes = 0;
+    }
+
+    fn print_char(&mut self, c: char) {
+        let c = self.charsets[self.active_charset].translate(c);
+        let width = match c.width() {
+            Some(width) => width,
+            None => return,
+        };
+
+        if width == 0 {
+            self.print_combining(c);
+            return;
+        }
+
+        if self.cursor.pending_wrap && self.modes.autowrap {
+            self.cursor.col = 0;
+            self.linefeed();
+        }
+        self.cursor.pending_wrap = false;
+
+        // a double-width character which doesn't fit on the line
+        if width == 2 && self.cursor.col + 1 >= self.cols {
+            if self.cols < 2 {
+                return;
+            }
+
+            if self.modes.autowrap {
+                self.erase_cells(self.cursor.col, self.cols);
+                self.cursor.col = 0;
+                self.linefeed();
+            } else {
+                self.cursor.col = self.cols - 2;
+            }
+        }
+
+        if self.modes.insert {
+            self.insert_cells(width);
+        }
+
+        let col = self.cursor.col;
+        self.clear_wide(col);
+        if width == 2 {
+            self.clear_wide(col + 1);
+        }
+
+        let pen = self.pen;
+        let row = self.row();
+        row[col] = Cell {
+            c,
+            combining: Vec::new(),
+            width: if width == 2 {
+                Width::Wide
+            } else {
+                Width::Narrow
+            },
+            pen,
+        };
+
+        if width == 2 {
+            row[col + 1] = Cell {
+                width: Width::Spacer,
+                ..Cell::blank(pen)
+            };
+        }
+
+        self.last_char = Some(c);
+        self.cursor.col += width;
+        if self.cursor.col >= self.cols {
+            self.cursor.col = self.cols - 1;
+            self.cursor.pending_wrap = self.modes.autowrap;
+        }
+    }
+
+    fn print_combining(&mut self, c: char) {
+        let mut col = if self.cursor.pending_wrap {
+            self.cursor.col
+        } else if self.cursor.col > 0 {
+            self.cursor.col - 1
+        } else {
+            return;
+        };
+
+        let row = self.row();
+        if row[col].width == Width::Spacer && col > 0 {
+            col -= 1;
+        }
+
+        row[col].combining.push(c);
+    }
+
+    /// If the cell is half of a double-width character, blanks the other half.
+    fn clear_wide(&mut self, col: usize) {
+        let cols = self.cols;
+        let row = self.row();
+
+        match row[col].width {
+            Width::Wide if col + 1 < cols => row[col + 1] = Cell::blank(row[col].pen),
+            Width::Spacer if col > 0 => row[col - 1] = Cell::blank(row[col].pen),
+            _ => {}
+        }
+    }
+
+    fn linefeed(&mut self) {
+        if self.cursor.row == self.scroll_bottom {
+            self.scroll_up(1);
+        } else if self.cursor.row + 1 < self.rows {
+            self.cursor.row += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        if self.cursor.row == self.scroll_top {
+            self.scroll_down(1);
+        } else if self.cursor.row > 0 {
+            self.cursor.row -= 1;
+        }
+    }
+
+    /// Scrolls the scroll region up, pushing lines which leave the primary screen into the history
+    fn scroll_up(&mut self, n: usize) {
+        let (top, bottom) = (self.scroll_top, self.scroll_bottom);
+        let blank = blank_row(self.cols, self.pen.erase());
+        let record = !self.alternate_active && top == 0;
+
+        for _ in 0..n.min(bottom - top + 1) {
+            let row = self.grid().remove(top);
+            if record {
+                self.push_history(&row);
+            }
+
+            self.grid().insert(bottom, blank.clone());
+        }
+    }
+
+    fn scroll_down(&mut self, n: usize) {
+        let (top, bottom) = (self.scroll_top, self.scroll_bottom);
+        let blank = blank_row(self.cols, self.pen.erase());
+
+        for _ in 0..n.min(bottom - top + 1) {
+            self.grid().remove(bottom);
+            self.grid().insert(top, blank.clone());
+        }
+    }
+
+    fn insert_lines(&mut self, n: usize) {
+        let row = self.cursor.row;
+        if row < self.scroll_top || row > self.scroll_bottom {
+            return;
+        }
+
+        let bottom = self.scroll_bottom;
+        let blank = blank_row(self.cols, self.pen.erase());
+        for _ in 0..n.min(bottom - row + 1) {
+            self.grid().remove(bottom);
+            self.grid().insert(row, blank.clone());
+        }
+
+        self.cursor.col = 0;
+    }
+
+    fn delete_lines(&mut self, n: usize) {
+        let row = self.cursor.row;
+        if row < self.scroll_top || row > self.scroll_bottom {
+            return;
+        }
+
+        let bottom = self.scroll_bottom;
+        let blank = blank_row(self.cols, self.pen.erase());
+        for _ in 0..n.min(bottom - row + 1) {
+            self.grid().remove(row);
+            self.grid().insert(bottom, blank.clone());
+        }
+
+        self.cursor.col = 0;
+    }
+
+    fn insert_cells(&mut self, n: usize) {
+        let col = self.cursor.col;
+        let cols = self.cols;
+        let blank = Cell::blank(self.pen.erase());
+        let row = self.row();
+
+        for _ in 0..n.min(cols - col) {
+            row.pop();
+            row.insert(col, blank.clone());
+        }
+    }
+
+    fn delete_cells(&mut self, n: usize) {
+        let col = self.cursor.col;
+        let cols = self.cols;
+        let blank = Cell::blank(self.pen.erase());
+        let row = self.row();
+
+        for _ in 0..n.min(cols - col) {
+            row.remove(col);
+            row.push(blank.clone());
+        }
+    }
+
+    /// Erases the cells in the range [start, end) on the cursor row
+    fn erase_cells(&mut self, start: usize, end: usize) {
+        let end = end.min(self.cols);
+        let blank = Cell::blank(self.pen.erase());
+        let row = self.row();
+
+        for cell in row.iter_mut().take(end).skip(start) {
+            *cell = blank.clone();
+        }
+    }
+
+    /// Erases the rows in the range [start, end)
+    fn erase_rows(&mut self, start: usize, end: usize) {
+        let blank = blank_row(self.cols, self.pen.erase());
+        let end = end.min(self.rows);
+
+        for row in self.grid().iter_mut().take(end).skip(start) {
+            *row = blank.clone();
+        }
+    }
+
+    fn erase_display(&mut self, mode: u16) {
+        let row = self.cursor.row;
+        match mode {
+            0 => {
+                self.erase_cells(self.cursor.col, self.cols);
+                self.erase_rows(row + 1, self.rows);
+            }
+            1 => {
+                self.erase_rows(0, row);
+                self.erase_cells(0, self.cursor.col + 1);
+            }
+            2 => self.erase_rows(0, self.rows),
+            3 => self.clear_history(),
+            _ => {}
+        }
+    }
+
+    fn erase_line(&mut self, mode: u16) {
+        match mode {
+            0 => self.erase_cells(self.cursor.col, self.cols),
+            1 => self.erase_cells(0, self.cursor.col + 1),
+            2 => self.erase_cells(0, self.cols),
+            _ => {}
+        }
+    }
+
+    /// Moves the cursor to the given row & column, which are relative to the scroll region in origin mode
+    fn goto(&mut self, row: usize, col: usize) {
+        let (top, bottom) = if self.modes.origin {
+            (self.scroll_top, self.scroll_bottom)
+        } else {
+            (0, self.rows - 1)
+        };
+
+        self.cursor.row = (top + row).min(bottom);
+        self.cursor.col = col.min(self.cols - 1);
+        self.cursor.pending_wrap = false;
+    }
+
+    fn goto_row(&mut self, row: usize) {
+        let col = self.cursor.col;
+        self.goto(row, col);
+    }
+
+    fn move_up(&mut self, n: usize) {
+        let top = if self.cursor.row >= self.scroll_top {
+            self.scroll_top
+        } else {
+            0
+        };
+
+        self.cursor.row = self.cursor.row.saturating_sub(n).max(top);
+        self.cursor.pending_wrap = false;
+    }
+
+    fn move_down(&mut self, n: usize) {
+        let bottom = if self.cursor.row <= self.scroll_bottom {
+            self.scroll_bottom
+        } else {
+            self.rows - 1
+        };
+
+        self.cursor.row = (self.cursor.row + n).min(bottom);
+        self.cursor.pending_wrap = false;
+    }
+
+    fn tab(&mut self) {
+        let next = (self.cursor.col / TAB_WIDTH + 1) * TAB_WIDTH;
+        self.cursor.col = next.min(self.cols - 1);
+        self.cursor.pending_wrap = false;
+    }
+
+    fn save_cursor(&mut self) {
+        let index = self.alternate_active as usize;
+        self.saved[index] = Some(SavedCursor {
+            cursor: self.cursor,
+            pen: self.pen,
+            origin: self.modes.origin,
+            charsets: self.charsets,
+            active_charset: self.active_charset,
+        });
+    }
+
+    fn restore_cursor(&mut self) {
+        let index = self.alternate_active as usize;
+        match self.saved[index] {
+            Some(saved) => {
+                self.cursor = saved.cursor;
+                self.pen = saved.pen;
+                self.modes.origin = saved.origin;
+                self.charsets = saved.charsets;
+                self.active_charset = saved.active_charset;
+            }
+            None => {
+                self.cursor = Cursor {
+                    row: 0,
+                    col: 0,
+                    pending_wrap: false,
+                };
+                self.pen = Pen::default();
+                self.modes.origin = false;
+            }
+        }
+
+        self.cursor.row = self.cursor.row.min(self.rows - 1);
+        self.cursor.col = self.cursor.col.min(self.cols - 1);
+    }
+
+    fn set_alternate(&mut self, active: bool, mode: u16) {
+        if active == self.alternate_active {
+            return;
+        }
+
+        if active {
+            self.primary_cursor = self.cursor;
+            if mode == 1049 {
+                self.save_cursor();
+            }
+
+            self.alternate_active = true;
+            if mode != 47 {
+                self.erase_rows(0, self.rows);
+            }
+        } else {
+            if mode == 1047 {
+                self.erase_rows(0, self.rows);
+            }
+
+            self.alternate_active = false;
+            if mode == 1049 {
+                self.restore_cursor();
+            }
+        }
+    }
+
+    fn set_private_mode(&mut self, mode: u16, enabled: bool) {
+        match mode {
+            1 => self.modes.application_cursor = enabled,
+            6 => {
+                self.modes.origin = enabled;
+                self.goto(0, 0);
+            }
+            7 => self.modes.autowrap = enabled,
+            25 => self.modes.cursor_visible = enabled,
+            47 | 1047 | 1049 => self.set_alternate(enabled, mode),
+            9 | 1000 | 1002 | 1003 => {
+                if enabled {
+                    self.modes.mouse_tracking = Some(mode);
+                } else if self.modes.mouse_tracking == Some(mode) {
+                    self.modes.mouse_tracking = None;
+                }
+            }
+            1005 | 1006 | 1015 => {
+                if enabled {
+                    self.modes.mouse_encoding = Some(mode);
+                } else if self.modes.mouse_encoding == Some(mode) {
+                    self.modes.mouse_encoding = None;
+                }
+            }
+            1004 => self.modes.focus_events = enabled,
+            1048 => {
+                if enabled {
+                    self.save_cursor();
+                } else {
+                    self.restore_cursor();
+                }
+            }
+            2004 => self.modes.bracketed_paste = enabled,
+            _ => {}
+        }
+    }
+
+    fn set_scroll_region(&mut self, top: usize, bottom: usize) {
+        let top = top.max(1) - 1;
+        let bottom = if bottom == 0 { self.rows } else { bottom }.min(self.rows) - 1;
+
+        if top < bottom {
+            self.scroll_top = top;
+            self.scroll_bottom = bottom;
+            self.goto(0, 0);
+        }
+    }
+
+    fn reset(&mut self) {
+        let history = std::mem::take(&mut self.history);
+        let (history_bytes, history_limit) = (self.history_bytes, self.history_limit);
+
+        *self = Screen::new(self.cols, self.rows);
+        self.history = history;
+        self.history_bytes = history_bytes;
+        self.history_limit = history_limit;
+    }
+
+    fn resize(&mut self, cols: usize, rows: usize) {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+
+        if cols == self.cols && rows == self.rows {
+            return;
+        }
+
+        let cursor_row = self.cursor.row;
+        let primary_row = if self.alternate_active {
+            self.primary_cursor.row
+        } else {
+            cursor_row
+        };
+
+        let mut primary = std::mem::take(&mut self.primary);
+        let removed = self.resize_grid(&mut primary, cols, rows, primary_row, true);
+        self.primary = primary;
+        self.primary_cursor.row = self.primary_cursor.row.saturating_sub(removed);
+
+        let mut alternate = std::mem::take(&mut self.alternate);
+        let alternate_row = if self.alternate_active { cursor_row } else { 0 };
+        let alternate_removed = self.resize_grid(&mut alternate, cols, rows, alternate_row, false);
+        self.alternate = alternate;
+
+        let removed = if self.alternate_active {
+            alternate_removed
+        } else {
+            removed
+        };
+
+        self.cols = cols;
+        self.rows = rows;
+        self.scroll_top = 0;
+        self.scroll_bottom = rows - 1;
+
+        self.cursor.row = self.cursor.row.saturating_sub(removed).min(rows - 1);
+        self.cursor.col = self.cursor.col.min(cols - 1);
+        self.cursor.pending_wrap = false;
+
+        self.primary_cursor.row = self.primary_cursor.row.min(rows - 1);
+        self.primary_cursor.col = self.primary_cursor.col.min(cols - 1);
+    }
+
+    /// Resizes the grid, and returns the number of rows removed from the top.
+    /// When rows are removed, blank rows below the cursor are removed first.
+    fn resize_grid(
+        &mut self,
+        grid: &mut Vec<Row>,
+        cols: usize,
+        rows: usize,
+        cursor_row: usize,
+        record: bool,
+    ) -> usize {
+        let mut removed = 0;
+
+        while grid.len() > rows {
+            let last = grid.len() - 1;
+            if last > cursor_row && grid[last].iter().all(Cell::is_blank) {
+                grid.pop();
+            } else {
+                let row = grid.remove(0);
+                if record {
+                    self.push_history(&row);
+                }
+                removed += 1;
+            }
+        }
+
+        while grid.len() < rows {
+            grid.push(blank_row(cols, Pen::default()));
+        }
+
+        for row in grid.iter_mut() {
+            row.resize(cols, Cell::blank(Pen::default()));
+            if let Some(last) = row.last_mut() {
+                if last.width == Width::Wide {
+                    *last = Cell::blank(last.pen);
+                }
+            }
+        }
+
+        removed
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.history.iter().cloned().collect();
+
+        // trailing blank rows are not included
+        if let Some(last) = self
+            .primary
+            .iter()
+            .rposition(|row| !row.iter().all(Cell::is_blank))
+        {
+            for row in self.primary[..=last].iter() {
+                let mut line = String::new();
+                render_row(row, 0, &mut line);
+                lines.push(line);
+            }
+        }
+
+        lines
+    }
+
+    fn rows(&self) -> Vec<String> {
+        let grid = if self.alternate_active {
+            &self.alternate
+        } else {
+            &self.primary
+        };
+
+        grid.iter()
+            .map(|row| {
+                let mut line = String::new();
+                render_row(row, row.len(), &mut line);
+                line
+            })
+            .collect()
+    }
+
+    fn redraw(&self) -> String {
+        let mut out = String::new();
+
+        if let Some(ref title) = self.title {
+            let _ = write!(out, "\x1b]0;{}\x07", title);
+        }
+
+        out.push_str("\x1b[0m");
+        for line in self.history.iter() {
+            out.push_str(line);
+            out.push_str("\r\n");
+        }
+
+        // blank rows below the content & cursor are not drawn, so the redraw doesn't end with empty lines
+        let primary_cursor = if self.alternate_active {
+            self.primary_cursor
+        } else {
+            self.cursor
+        };
+
+        let last = self
+            .primary
+            .iter()
+            .rposition(|row| !row.iter().all(Cell::is_blank))
+            .unwrap_or(0)
+            .max(primary_cursor.row);
+
+        for (i, row) in self.primary[..=last].iter().enumerate() {
+            // blanks before the cursor are drawn, which preserves the trailing space of a shell prompt
+            let min_len = if i == primary_cursor.row {
+                primary_cursor.col
+            } else {
+                0
+            };
+
+            render_row(row, min_len, &mut out);
+
+            if i < last {
+                out.push_str("\r\n");
+            }
+        }
+
+        // move to the primary cursor, relative to the last row that was drawn
+        out.push('\r');
+        if last > primary_cursor.row {
+            let _ = write!(out, "\x1b[{}A", last - primary_cursor.row);
+        }
+
+        if primary_cursor.col > 0 {
+            let _ = write!(out, "\x1b[{}G", primary_cursor.col + 1);
+        }
+
+        if self.alternate_active {
+            // the terminal saves the primary cursor when switching to the alternate screen
+            out.push_str("\x1b[?1049h");
+
+            for (i, row) in self.alternate.iter().enumerate() {
+                let _ = write!(out, "\x1b[{};1H", i + 1);
+                render_row(row, 0, &mut out);
+            }
+        }
+
+        if self.scroll_top != 0 || self.scroll_bottom != self.rows - 1 {
+            let _ = write!(
+                out,
+                "\x1b[{};{}r",
+                self.scroll_top + 1,
+                self.scroll_bottom + 1
+            );
+        }
+
+        self.redraw_modes(&mut out);
+        self.redraw_cursor(&mut out);
+
+        out
+    }
+
+    fn redraw_modes(&self, out: &mut String) {
+        let modes = &self.modes;
+
+        let flags = [
+            (modes.application_cursor, "\x1b[?1h"),
+            (modes.application_keypad, "\x1b="),
+            (!modes.autowrap, "\x1b[?7l"),
+            (modes.insert, "\x1b[4h"),
+            (modes.focus_events, "\x1b[?1004h"),
+            (modes.bracketed_paste, "\x1b[?2004h"),
+            (!modes.cursor_visible, "\x1b[?25l"),
+        ];
+
+        for (enabled, sequence) in flags.iter() {
+            if *enabled {
+                out.push_str(sequence);
+            }
+        }
+
+        if let Some(mode) = modes.mouse_tracking {
+            let _ = write!(out, "\x1b[?{}h", mode);
+        }
+
+        if let Some(mode) = modes.mouse_encoding {
+            let _ = write!(out, "\x1b[?{}h", mode);
+        }
+
+        if modes.cursor_style != 0 {
+            let _ = write!(out, "\x1b[{} q", modes.cursor_style);
+        }
+
+        if self.charsets[0] == Charset::DecSpecial {
+            out.push_str("\x1b(0");
+        }
+
+        if self.charsets[1] == Charset::DecSpecial {
+            out.push_str("\x1b)0");
+        }
+
+        if self.active_charset == 1 {
+            out.push('\x0e');
+        }
+    }
+
+    fn redraw_cursor(&self, out: &mut String) {
+        let cursor = self.cursor;
+
+        // the primary cursor has already been restored, unless the alternate screen or scroll region moved it
+        let scroll_region = self.scroll_top != 0 || self.scroll_bottom != self.rows - 1;
+        if self.alternate_active || scroll_region || self.modes.origin {
+            // origin mode is enabled after the scroll region, as it moves the cursor to the region origin.
+            let row = if self.modes.origin {
+                out.push_str("\x1b[?6h");
+                cursor.row.saturating_sub(self.scroll_top)
+            } else {
+                cursor.row
+            };
+
+            let _ = write!(out, "\x1b[{};{}H", row + 1, cursor.col + 1);
+        }
+
+        // re-printing the last cell restores the pending wrap in the terminal
+        if cursor.pending_wrap {
+            let grid = if self.alternate_active {
+                &self.alternate
+            } else {
+                &self.primary
+            };
+
+            let cell = &grid[cursor.row][cursor.col];
+            if cell.width == Width::Narrow {
+                cell.pen.write_sgr(out);
+                out.push(cell.c);
+                out.extend(cell.combining.iter());
+            }
+        }
+
+        self.pen.write_sgr(out);
+    }
+}
+
+/// Reads the parameter at the index, or the default if the parameter is missing or zero
+fn param(params: &Params, index: usize, default: u16) -> u16 {
+    match params.iter().nth(index).map(|param| param[0]) {
+        Some(0) | None => default,
+        Some(value) => value,
+    }
+}
+
+impl Perform for Screen {
+    fn print(&mut self, c: char) {
+        self.print_char(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            0x08 => {
+                self.cursor.col = self.cursor.col.saturating_sub(1);
+                self.cursor.pending_wrap = false;
+            }
+            0x09 => self.tab(),
+            0x0a..=0x0c => {
+                self.cursor.pending_wrap = false;
+                self.linefeed();
+            }
+            0x0d => {
+                self.cursor.col = 0;
+                self.cursor.pending_wrap = false;
+            }
+            0x0e => self.active_charset = 1,
+            0x0f => self.active_charset = 0,
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        if ignore {
+            return;
+        }
+
+        let n = param(params, 0, 1) as usize;
+
+        match (intermediates, action) {
+            ([], 'A') => self.move_up(n),
+            ([], 'B') | ([], 'e') => self.move_down(n),
+            ([], 'C') | ([], 'a') => {
+                self.cursor.col = (self.cursor.col + n).min(self.cols - 1);
+                self.cursor.pending_wrap = false;
+            }
+            ([], 'D') => {
+                self.cursor.col = self.cursor.col.saturating_sub(n);
+                self.cursor.pending_wrap = false;
+            }
+            ([], 'E') => {
+                self.move_down(n);
+                self.cursor.col = 0;
+            }
+            ([], 'F') => {
+                self.move_up(n);
+                self.cursor.col = 0;
+            }
+            ([], 'G') | ([], '`') => {
+                self.cursor.col = (n - 1).min(self.cols - 1);
+                self.cursor.pending_wrap = false;
+            }
+            ([], 'H') | ([], 'f') => {
+                let col = param(params, 1, 1) as usize;
+                self.goto(n - 1, col - 1);
+            }
+            ([], 'd') => self.goto_row(n - 1),
+            ([], 'J') => self.erase_display(param(params, 0, 0)),
+            ([], 'K') => self.erase_line(param(params, 0, 0)),
+            ([], 'L') => self.insert_lines(n),
+            ([], 'M') => self.delete_lines(n),
+            ([], '@') => self.insert_cells(n),
+            ([], 'P') => self.delete_cells(n),
+            ([], 'X') => self.erase_cells(self.cursor.col, self.cursor.col + n),
+            ([], 'S') => self.scroll_up(n),
+            ([], 'T') if params.len() <= 1 => self.scroll_down(n),
+            ([], 'b') => {
+                if let Some(c) = self.last_char {
+                    for _ in 0..n.min(self.cols * self.rows) {
+                        self.print_char(c);
+                    }
+                }
+            }
+            ([], 'r') => {
+                let top = param(params, 0, 1) as usize;
+                let bottom = param(params, 1, self.rows as u16) as usize;
+                self.set_scroll_region(top, bottom);
+            }
+            ([], 's') => self.save_cursor(),
+            ([], 'u') => self.restore_cursor(),
+            ([], 'h') | ([], 'l') if params.iter().any(|param| param[0] == 4) => {
+                self.modes.insert = action == 'h';
+            }
+            ([b'?'], 'h') | ([b'?'], 'l') => {
+                for param in params.iter() {
+                    self.set_private_mode(param[0], action == 'h');
+                }
+            }
+            ([], 'm') => self.pen.sgr(params),
+            ([b' '], 'q') => self.modes.cursor_style = param(params, 0, 0),
+            // queries (such as device status & attributes) and window operations are not part of the screen state
+            _ => {}
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+        match (intermediates, byte) {
+            ([], b'7') => self.save_cursor(),
+            ([], b'8') => self.restore_cursor(),
+            ([], b'D') => self.linefeed(),
+            ([], b'E') => {
+                self.cursor.col = 0;
+                self.linefeed();
+            }
+            ([], b'M') => self.reverse_index(),
+            ([], b'c') => self.reset(),
+            ([], b'=') => self.modes.application_keypad = true,
+            ([], b'>') => self.modes.application_keypad = false,
+            ([b'('], b'0') => self.charsets[0] = Charset::DecSpecial,
+            ([b'('], _) => self.charsets[0] = Charset::Ascii,
+            ([b')'], b'0') => self.charsets[1] = Charset::DecSpecial,
+            ([b')'], _) => self.charsets[1] = Charset::Ascii,
+            _ => {}
+        }
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        if let [b"0", title @ ..] | [b"2", title @ ..] = params {
+            let title: Vec<String> = title
+                .iter()
+                .map(|part| String::from_utf8_lossy(part).to_string())
+                .collect();
+
+            self.title = Some(title.join(";"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TerminalEmulator, MAX_HISTORY_LINES};
+    use crate::{chunk::OutputChunk, tab::ScrollbackLimit};
+
+    fn emulate(cols: u16, rows: u16, data: &str) -> TerminalEmulator {
+        let mut emulator = TerminalEmulator::new((cols, rows));
+        emulator.push(&OutputChunk {
+            index: 0,
+            data: data.as_bytes().to_vec(),
+        });
+        emulator
+    }
+
+    fn render(emulator: &TerminalEmulator) -> String {
+        String::from_utf8(emulator.redraw()).expect("redraw should be utf8")
+    }
+
+    /// Renders the visible rows of the active screen as plain text
+    fn screen(emulator: &TerminalEmulator) -> Vec<String> {
+        let screen = &emulator.screen;
+        let grid = if screen.alternate_active {
+            &screen.alternate
+        } else {
+            &screen.primary
+        };
+
+        grid.iter()
+            .map(|row| {
+                let line: String = row.iter().map(|cell| cell.c).collect();
+                line.trim_end().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn empty() {
+        let emulator = TerminalEmulator::new((80, 24));
+        assert!(emulator.redraw().is_empty());
+    }
+
+    #[test]
+    fn print() {
+        let emulator = emulate(10, 3, "hello\r\nworld");
+        assert_eq!(vec!["hello", "world", ""], screen(&emulator));
+        assert_eq!(1, emulator.screen.cursor.row);
+        assert_eq!(5, emulator.screen.cursor.col);
+    }
+
+    #[test]
+    fn wrap() {
+        let emulator = emulate(4, 3, "abcdef");
+        assert_eq!(vec!["abcd", "ef", ""], screen(&emulator));
+    }
+
+    #[test]
+    fn pending_wrap() {
+        let emulator = emulate(4, 3, "abcd");
+        assert_eq!(vec!["abcd", "", ""], screen(&emulator));
+        assert!(emulator.screen.cursor.pending_wrap);
+        assert!(render(&emulator).ends_with("\r\x1b[4G\x1b[0md\x1b[0m"));
+    }
+
+    #[test]
+    fn redraw_omits_blank_rows() {
+        let emulator = emulate(10, 5, "a\r\nb\x1b[1;3H");
+        assert_eq!("\x1b[0ma \r\nb\r\x1b[1A\x1b[3G\x1b[0m", render(&emulator));
+    }
+
+    #[test]
+    fn scroll_into_history() {
+        let emulator = emulate(10, 2, "one\r\ntwo\r\nthree\r\nfour");
+        assert_eq!(vec!["three", "four"], screen(&emulator));
+        assert_eq!(2, emulator.screen.history.len());
+
+        let redraw = render(&emulator);
+        assert!(redraw.starts_with("\x1b[0mone\r\ntwo\r\nthree\r\nfour"));
+    }
+
+    #[test]
+    fn lines() {
+        let emulator = emulate(10, 4, "one\r\n\x1b[31mtwo\x1b[0m\r\nthree\r\nfour\r\nfive");
+        assert_eq!(
+            vec!["one", "\x1b[0;31mtwo\x1b[0m", "three", "four", "five"],
+            emulator.lines()
+        );
+
+        let emulator = emulate(10, 4, "one\r\ntwo");
+        assert_eq!(vec!["one", "two"], emulator.lines());
+    }
+
+    #[test]
+    fn rows() {
+        let emulator = emulate(4, 2, "ab\x1b[31mc");
+        assert_eq!(
+            vec!["ab\x1b[0;31mc\x1b[0m ".to_string(), "    ".to_string()],
+            emulator.rows()
+        );
+        assert_eq!(Some((3, 0)), emulator.cursor());
+
+        let emulator = emulate(4, 2, "\x1b[?25l");
+        assert_eq!(None, emulator.cursor());
+    }
+
+    #[test]
+    fn history_limit() {
+        let data = "line\r\n".repeat(MAX_HISTORY_LINES + 10);
+        let emulator = emulate(10, 2, data.as_str());
+        assert_eq!(MAX_HISTORY_LINES, emulator.screen.history.len());
+    }
+
+    #[test]
+    fn history_limit_lines() {
+        let mut emulator = emulate(10, 2, "one\r\ntwo\r\nthree\r\nfour\r\n");
+        emulator.set_history_limit(ScrollbackLimit::Lines(2));
+        assert_eq!(emulator.screen.history, vec!["two", "three"]);
+
+        emulator.process(b"five\r\n");
+        assert_eq!(emulator.screen.history, vec!["three", "four"]);
+    }
+
+    #[test]
+    fn history_limit_bytes() {
+        let mut emulator = TerminalEmulator::new((10, 2));
+        emulator.set_history_limit(ScrollbackLimit::Bytes(8));
+        emulator.process(b"one\r\ntwo\r\nthree\r\nfour\r\n");
+
+        assert_eq!(emulator.screen.history, vec!["two", "three"]);
+        assert_eq!(8, emulator.screen.history_bytes);
+    }
+
+    #[test]
+    fn evict_history() {
+        let mut emulator = emulate(10, 2, "one\r\ntwo\r\nthree\r\nfour\r\n");
+        assert_eq!(11, emulator.history_size());
+
+        assert_eq!(6, emulator.evict_history(4));
+        assert_eq!(emulator.screen.history, vec!["three"]);
+        assert_eq!(5, emulator.history_size());
+
+        assert_eq!(5, emulator.evict_history(100));
+        assert_eq!(0, emulator.history_size());
+    }
+
+    #[test]
+    fn erase() {
+        let emulator = emulate(10, 3, "abc\r\ndef\x1b[1;2H\x1b[K\x1b[2;2H\x1b[1K");
+        assert_eq!(vec!["a", "  f", ""], screen(&emulator));
+
+        let emulator = emulate(10, 3, "abc\r\ndef\x1b[2J");
+        assert_eq!(vec!["", "", ""], screen(&emulator));
+    }
+
+    #[test]
+    fn clear_history() {
+        let emulator = emulate(10, 2, "one\r\ntwo\r\nthree\x1b[H\x1b[2J\x1b[3J");
+        assert!(emulator.screen.history.is_empty());
+    }
+
+    #[test]
+    fn cursor_movement() {
+        let emulator = emulate(10, 5, "\x1b[3;4Hx\x1b[2Ay\x1b[5Cz\x1b[10D\x1b[Bw");
+        assert_eq!(vec!["    y    z", "w", "   x", "", ""], screen(&emulator));
+    }
+
+    #[test]
+    fn insert_delete_characters() {
+        let emulator = emulate(10, 1, "abcdef\x1b[1;2H\x1b[2@\x1b[1;6H\x1b[1P");
+        assert_eq!(vec!["a  bcef"], screen(&emulator));
+    }
+
+    #[test]
+    fn insert_delete_lines() {
+        let emulator = emulate(5, 4, "a\r\nb\r\nc\r\nd\x1b[2;1H\x1b[L");
+        assert_eq!(vec!["a", "", "b", "c"], screen(&emulator));
+
+        let emulator = emulate(5, 4, "a\r\nb\r\nc\r\nd\x1b[2;1H\x1b[2M");
+        assert_eq!(vec!["a", "d", "", ""], screen(&emulator));
+    }
+
+    #[test]
+    fn scroll_region() {
+        let emulator = emulate(5, 4, "a\r\nb\r\nc\r\nd\x1b[2;3r\x1b[3;1H\nx");
+        assert_eq!(vec!["a", "c", "x", "d"], screen(&emulator));
+        // lines which leave a scroll region are not recorded in the history
+        assert!(emulator.screen.history.is_empty());
+        assert!(render(&emulator).contains("\x1b[2;3r"));
+    }
+
+    #[test]
+    fn reverse_index() {
+        let emulator = emulate(5, 3, "a\r\nb\r\nc\x1b[H\x1bMx");
+        assert_eq!(vec!["x", "a", "b"], screen(&emulator));
+    }
+
+    #[test]
+    fn sgr() {
+        let emulator = emulate(
+            20,
+            1,
+            "\x1b[1;31mred\x1b[0m \x1b[38;5;200mpink\x1b[48;2;1;2;3mrgb",
+        );
+        let redraw = render(&emulator);
+
+        assert!(redraw.contains("\x1b[0;1;31mred\x1b[0m "));
+        assert!(redraw.contains("\x1b[0;38;5;200mpink"));
+        assert!(redraw.contains("\x1b[0;38;5;200;48;2;1;2;3mrgb"));
+    }
+
+    #[test]
+    fn sgr_colon_subparameters() {
+        let emulator = emulate(20, 1, "\x1b[38:2::10:20:30mx\x1b[4:0my");
+        let redraw = render(&emulator);
+
+        assert!(redraw.contains("\x1b[0;38;2;10;20;30mx"));
+        assert!(!emulator.screen.pen.underline);
+    }
+
+    #[test]
+    fn bright_colors() {
+        let emulator = emulate(10, 1, "\x1b[92;105mx");
+        assert!(render(&emulator).contains("\x1b[0;92;105mx"));
+    }
+
+    #[test]
+    fn wide_characters() {
+        let emulator = emulate(4, 2, "a漢b漢");
+        assert_eq!(
+            vec!["a漢b", "漢"],
+            screen(&emulator)
+                .iter()
+                .map(|line| line.replace(' ', ""))
+                .collect::<Vec<_>>()
+        );
+
+        let redraw = render(&emulator);
+        assert!(redraw.contains("a漢b"));
+    }
+
+    #[test]
+    fn combining_characters() {
+        let emulator = emulate(10, 1, "e\u{301}x");
+        assert!(render(&emulator).contains("e\u{301}x"));
+    }
+
+    #[test]
+    fn line_drawing() {
+        let emulator = emulate(10, 1, "\x1b(0lqk\x1b(Bx");
+        assert_eq!(vec!["┌─┐x"], screen(&emulator));
+    }
+
+    #[test]
+    fn alternate_screen() {
+        let emulator = emulate(10, 3, "$ vim\x1b[?1049h\x1b[H\x1b[2Jeditor\x1b[?1h\x1b=");
+        assert_eq!(vec!["editor", "", ""], screen(&emulator));
+
+        let redraw = render(&emulator);
+        let switch = redraw
+            .find("\x1b[?1049h")
+            .expect("alternate screen should be redrawn");
+
+        // the primary screen is drawn first, then the cursor is saved by the terminal
+        assert!(redraw[..switch].contains("$ vim"));
+        assert!(redraw[..switch].ends_with("\r\x1b[6G"));
+        assert!(redraw[switch..].contains("\x1b[1;1Heditor"));
+        assert!(redraw.contains("\x1b[?1h"));
+        assert!(redraw.contains("\x1b="));
+    }
+
+    #[test]
+    fn alternate_screen_exit() {
+        let emulator = emulate(10, 3, "$ vim\x1b[?1049heditor\x1b[?1049l");
+        assert_eq!(vec!["$ vim", "", ""], screen(&emulator));
+        assert_eq!(5, emulator.screen.cursor.col);
+        assert!(!render(&emulator).contains("editor"));
+    }
+
+    #[test]
+    fn modes() {
+        let emulator = emulate(
+            10,
+            3,
+            "x\x1b[?25l\x1b[?2004h\x1b[?1002h\x1b[?1006h\x1b[?1004h\x1b[4 q",
+        );
+        let redraw = render(&emulator);
+
+        assert!(redraw.contains("\x1b[?25l"));
+        assert!(redraw.contains("\x1b[?2004h"));
+        assert!(redraw.contains("\x1b[?1002h"));
+        assert!(redraw.contains("\x1b[?1006h"));
+        assert!(redraw.contains("\x1b[?1004h"));
+        assert!(redraw.contains("\x1b[4 q"));
+
+        let emulator = emulate(10, 3, "x\x1b[?2004h\x1b[?2004l\x1b[?1000h\x1b[?1000l");
+        let redraw = render(&emulator);
+        assert!(!redraw.contains("\x1b[?2004h"));
+        assert!(!redraw.contains("\x1b[?1000h"));
+    }
+
+    #[test]
+    fn save_restore_cursor() {
+        let emulator = emulate(10, 3, "\x1b[2;3H\x1b7\x1b[1;1Ha\x1b8b");
+        assert_eq!(vec!["a", "  b", ""], screen(&emulator));
+    }
+
+    #[test]
+    fn title() {
+        let emulator = emulate(10, 3, "\x1b]0;my title\x07x");
+        assert!(render(&emulator).starts_with("\x1b]0;my title\x07"));
+    }
+
+    #[test]
+    fn reset() {
+        let emulator = emulate(10, 2, "one\r\ntwo\r\nthree\x1b[?2004h\x1bcx");
+        assert_eq!(vec!["x", ""], screen(&emulator));
+        assert!(!render(&emulator).contains("\x1b[?2004h"));
+        assert_eq!(1, emulator.screen.history.len());
+    }
+
+    #[test]
+    fn resize_shrink() {
+        let mut emulator = emulate(10, 4, "a\r\nb\r\nc");
+        emulator.resize((5, 2));
+
+        // the blank row below the cursor is removed first, then the top row
+        assert_eq!(vec!["b", "c"], screen(&emulator));
+        assert_eq!(1, emulator.screen.history.len());
+        assert_eq!(1, emulator.screen.cursor.row);
+    }
+
+    #[test]
+    fn resize_grow() {
+        let mut emulator = emulate(3, 2, "abc\x1b[1;3H");
+        emulator.resize((6, 3));
+
+        assert_eq!(vec!["abc", "", ""], screen(&emulator));
+        emulator.process(b"def");
+        assert_eq!(vec!["abdef", "", ""], screen(&emulator));
+    }
+
+    #[test]
+    fn push_skips_processed_output() {
+        let mut emulator = TerminalEmulator::new((10, 2));
+        emulator.push(&OutputChunk {
+            index: 0,
+            data: b"abc".to_vec(),
+        });
+        emulator.push(&OutputChunk {
+            index: 1,
+            data: b"bcd".to_vec(),
+        });
+        emulator.push(&OutputChunk {
+            index: 0,
+            data: b"ab".to_vec(),
+        });
+
+        assert_eq!(vec!["abcd", ""], screen(&emulator));
+        assert_eq!(4, emulator.end());
+    }
+
+    #[test]
+    fn device_status_report() {
+        let redraw = render(&emulate(20, 1, "start-\x1b[6n-end"));
+        assert!(redraw.contains("start--end"));
+        assert!(!redraw.contains("\x1b[6n"));
+    }
+
+    #[test]
+    fn operating_system_command() {
+        let redraw = render(&emulate(20, 1, "start-\x1b]10;?\x07-end"));
+        assert!(redraw.contains("start--end"));
+        assert!(!redraw.contains("\x1b]10"));
+    }
+
+    #[test]
+    fn device_attributes() {
+        let redraw = render(&emulate(20, 1, "a\x1b[c\x1b[>0;1;0c\x1b[=0cb"));
+        assert!(redraw.contains("ab"));
+        assert!(!redraw.contains("c\x1b"));
+    }
+
+    #[test]
+    fn bug_open_source() {
+        let redraw = render(&emulate(20, 1, "open-source"));
+        assert!(redraw.contains("open-source"));
+    }
+}
@@ -1,4 +1,5 @@
-use log::debug;
+use crate::config::ResizePolicy;
+use log::{debug, warn};
 use tokio::process::Command;
 
 /// Instructs the command module that it should interact with the terminal in raw mode
@@ -11,10 +12,38 @@ pub fn is_raw_mode() -> bool {
         .unwrap_or(true)
 }
 
+/// Overrides the `unix_socket` option of the global configuration file, if set.
+pub fn unix_socket_override() -> Option<bool> {
+    let unix = std::env::var("TAB_UNIX_SOCKET").ok()?;
+
+    match unix.parse() {
+        Ok(unix) => Some(unix),
+        Err(e) => {
+            warn!("invalid TAB_UNIX_SOCKET value '{}': {}", unix, e);
+            None
+        }
+    }
+}
+
+/// Overrides the `resize_policy` of the global configuration file, if set.
+pub fn resize_policy_override() -> Option<ResizePolicy> {
+    let policy = std::env::var("TAB_RESIZE_POLICY").ok()?;
+
+    match policy.parse() {
+        Ok(policy) => Some(policy),
+        Err(e) => {
+            warn!("{}", e);
+            None
+        }
+    }
+}
+
 /// Environment variables that should be forwarded from the command, to Daemon and pty processes.
 pub const FORWARD_ENV_VARS: &[&str] = &[
     "TAB_RUNTIME_DIR", // The daemon & pty should inherit the runtime directory of the command client
     "TAB_RAW_MODE", // Raw mode controls stderr forwarding.  When disabled, the command stderr pipe is inherited by the daemon/client
+    "TAB_UNIX_SOCKET", // Overrides whether the daemon listens on a Unix socket, rather than a TCP port
+    "TAB_RESIZE_POLICY", // Overrides the policy the daemon uses to size tabs with several attached clients
     "TAB_BIN", // path to the initiating tab binary (where the command was launched).  used by integration tests to launch tab within a shell
 ];
 
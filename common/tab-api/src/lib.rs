@@ -5,6 +5,7 @@
 pub mod chunk;
 pub mod client;
 pub mod config;
+pub mod emulator;
 pub mod env;
 pub mod launch;
 pub mod log;
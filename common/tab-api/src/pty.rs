@@ -2,21 +2,27 @@
 
 use crate::{
     chunk::{InputChunk, OutputChunk},
-    tab::TabMetadata,
+    tab::{TabExit, TabMetadata},
 };
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum PtyWebsocketResponse {
     Started(TabMetadata),
+    /// Sent after a pty reconnects to a new daemon, to re-register the running tab.
+    Resumed(TabMetadata),
     Output(OutputChunk),
-    Stopped,
+    /// The shell has exited, with the given status
+    Stopped(TabExit),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)]
 pub enum PtyWebsocketRequest {
     Init(TabMetadata),
     Input(InputChunk),
     Resize((u16, u16)),
     Terminate,
+    /// Requests that the pty keeps the shell running, and reconnects to the next daemon.
+    Reconnect,
 }
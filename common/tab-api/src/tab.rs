@@ -3,7 +3,7 @@
 use lifeline::impl_storage_clone;
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
-use std::{collections::HashMap, fmt::Display, num::ParseIntError, str::FromStr};
+use std::{collections::HashMap, convert::TryFrom, fmt::Display, num::ParseIntError, str::FromStr};
 
 pub fn normalize_name(name: &str) -> String {
     let name = name.to_string().trim().to_string();
@@ -71,7 +71,32 @@ pub struct TabMetadata {
     pub env: HashMap<String, String>,
     pub shell: String,
     pub dir: String,
+    pub command: Option<String>,
     pub selected: u128,
+    /// The number of client connections which are subscribed to the tab
+    #[serde(default)]
+    pub attached: usize,
+    /// The scrollback limit for the tab.  If None, the daemon's default limit is used.
+    #[serde(default)]
+    pub scrollback: Option<ScrollbackLimit>,
+    /// If true, the daemon records the output of the tab to an asciicast file, in the recordings directory.
+    #[serde(default)]
+    pub record: bool,
+    /// If true, the tab is kept open when the shell exits, with a banner which offers to restart or close the tab.
+    #[serde(default)]
+    pub remain_on_exit: bool,
+    /// If set, the daemon appends the ANSI-stripped output of the tab to a rolling log file, in the logs directory.
+    #[serde(default)]
+    pub log: Option<OutputLogLimit>,
+    /// Monitors the tab for activity or silence, which raises an alert
+    #[serde(default)]
+    pub monitor: Option<TabMonitor>,
+    /// A shell command which is run by the daemon when the tab monitor raises an alert
+    #[serde(default)]
+    pub notify: Option<String>,
+    /// The alert raised by the tab monitor.  Cleared when a client attaches to the tab.
+    #[serde(default)]
+    pub alert: Option<TabAlert>,
 }
 
 impl TabMetadata {
@@ -84,7 +109,20 @@ impl TabMetadata {
             env: create.env,
             shell: create.shell,
             dir: create.dir,
+            command: create.command,
             selected: unix_time(),
+            attached: 0,
+            scrollback: create.scrollback,
+            record: create.record,
+            remain_on_exit: create.remain_on_exit,
+            log: if create.log {
+                Some(OutputLogLimit::default())
+            } else {
+                None
+            },
+            monitor: create.monitor,
+            notify: create.notify,
+            alert: None,
         }
     }
 
@@ -102,4 +140,228 @@ pub struct CreateTabMetadata {
     pub env: HashMap<String, String>,
     pub shell: String,
     pub dir: String,
+    pub command: Option<String>,
+    #[serde(default)]
+    pub scrollback: Option<ScrollbackLimit>,
+    #[serde(default)]
+    pub record: bool,
+    #[serde(default)]
+    pub remain_on_exit: bool,
+    #[serde(default)]
+    pub log: bool,
+    #[serde(default)]
+    pub monitor: Option<TabMonitor>,
+    #[serde(default)]
+    pub notify: Option<String>,
+}
+
+/// A limit on the scrollback which the daemon retains for a tab.
+/// Configured in YAML as `bytes: 1048576`, or `lines: 10000`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollbackLimit {
+    Bytes(usize),
+    Lines(usize),
+}
+
+/// The size and count limits of a tab's rolling output log.
+/// Configured in YAML as `bytes: 10485760` and `files: 5`.
+/// When the log exceeds `bytes`, it is rotated, and only the newest `files` rotated logs are kept.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutputLogLimit {
+    #[serde(default = "OutputLogLimit::default_bytes")]
+    pub bytes: u64,
+    #[serde(default = "OutputLogLimit::default_files")]
+    pub files: usize,
+}
+
+impl OutputLogLimit {
+    fn default_bytes() -> u64 {
+        10 * 1024 * 1024
+    }
+
+    fn default_files() -> usize {
+        5
+    }
+}
+
+impl Default for OutputLogLimit {
+    fn default() -> Self {
+        Self {
+            bytes: Self::default_bytes(),
+            files: Self::default_files(),
+        }
+    }
+}
+
+/// Monitors a tab for output, and raises an alert.
+/// Configured in YAML as `monitor: activity`, or `monitor: silence 30s` (with a duration in `s`, `m` or `h`).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum TabMonitor {
+    /// Raises an alert when the tab produces output, while no client is attached
+    Activity,
+    /// Raises an alert when the tab stops producing output for the given number of seconds
+    Silence(u64),
+}
+
+impl FromStr for TabMonitor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+
+        let monitor = match (words.next(), words.next()) {
+            (Some("activity"), None) => TabMonitor::Activity,
+            (Some("silence"), Some(duration)) => TabMonitor::Silence(parse_seconds(duration)?),
+            (Some("silence"), None) => {
+                return Err("silence requires a duration, such as `silence 30s`".into())
+            }
+            _ => {
+                return Err(format!(
+                    "unknown monitor `{}`, expected `activity` or `silence <duration>`",
+                    s
+                ))
+            }
+        };
+
+        if words.next().is_some() {
+            return Err(format!("unexpected arguments in monitor `{}`", s));
+        }
+
+        Ok(monitor)
+    }
+}
+
+/// Parses a duration such as `30s`, `5m`, or `1h` into seconds.  Plain numbers are seconds.
+fn parse_seconds(duration: &str) -> Result<u64, String> {
+    let (number, multiplier) = match duration.char_indices().last() {
+        Some((index, 's')) => (&duration[..index], 1),
+        Some((index, 'm')) => (&duration[..index], 60),
+        Some((index, 'h')) => (&duration[..index], 60 * 60),
+        _ => (duration, 1),
+    };
+
+    match number.parse::<u64>() {
+        Ok(seconds) if seconds > 0 => Ok(seconds * multiplier),
+        _ => Err(format!(
+            "invalid duration `{}`, expected a positive number of seconds, such as `30s`",
+            duration
+        )),
+    }
+}
+
+impl TryFrom<String> for TabMonitor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<TabMonitor> for String {
+    fn from(monitor: TabMonitor) -> Self {
+        monitor.to_string()
+    }
+}
+
+impl Display for TabMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TabMonitor::Activity => f.write_str("activity"),
+            TabMonitor::Silence(seconds) => write!(f, "silence {}s", seconds),
+        }
+    }
+}
+
+/// An alert raised by a tab monitor
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TabAlert {
+    /// The tab produced output
+    Activity,
+    /// The tab stopped producing output
+    Silence,
+}
+
+impl Display for TabAlert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TabAlert::Activity => f.write_str("activity"),
+            TabAlert::Silence => f.write_str("silence"),
+        }
+    }
+}
+
+/// The exit status of a tab's shell process
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TabExit {
+    /// The exit code of the shell, or 128 + the signal number if the shell was killed by a signal.
+    /// None if the status is unknown (e.g. the shell was terminated by the daemon).
+    pub code: Option<i32>,
+}
+
+impl TabExit {
+    pub fn code(code: i32) -> Self {
+        Self { code: Some(code) }
+    }
+}
+
+impl Display for TabExit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "exited (code {})", code),
+            None => f.write_str("exited"),
+        }
+    }
+}
+
+/// A tab whose shell exited on its own.
+/// The daemon keeps exited tabs until they are dismissed with `tab --close`, or a tab with the same name is launched.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExitedTab {
+    pub metadata: TabMetadata,
+    pub exit: TabExit,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TabExit, TabMonitor};
+
+    #[test]
+    fn monitor_activity() {
+        assert_eq!(Ok(TabMonitor::Activity), "activity".parse());
+    }
+
+    #[test]
+    fn monitor_silence() {
+        assert_eq!(Ok(TabMonitor::Silence(30)), "silence 30s".parse());
+        assert_eq!(Ok(TabMonitor::Silence(300)), "silence 5m".parse());
+        assert_eq!(Ok(TabMonitor::Silence(3600)), "silence 1h".parse());
+        assert_eq!(Ok(TabMonitor::Silence(10)), "silence 10".parse());
+    }
+
+    #[test]
+    fn monitor_invalid() {
+        assert!("silence".parse::<TabMonitor>().is_err());
+        assert!("silence 0s".parse::<TabMonitor>().is_err());
+        assert!("silence 30x".parse::<TabMonitor>().is_err());
+        assert!("activity 30s".parse::<TabMonitor>().is_err());
+        assert!("bell".parse::<TabMonitor>().is_err());
+    }
+
+    #[test]
+    fn monitor_yaml() -> anyhow::Result<()> {
+        let monitor: TabMonitor = serde_yaml::from_str("silence 30s")?;
+        assert_eq!(TabMonitor::Silence(30), monitor);
+        assert_eq!("---\nsilence 30s\n", serde_yaml::to_string(&monitor)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn exit_display() {
+        assert_eq!("exited (code 3)", TabExit::code(3).to_string());
+        assert_eq!("exited", TabExit::default().to_string());
+    }
 }
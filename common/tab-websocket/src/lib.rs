@@ -1,29 +1,40 @@
 use async_tungstenite::{
-    tokio::{connect_async, TokioAdapter},
+    tokio::{client_async, TokioAdapter},
     WebSocketStream,
 };
 use serde::{de::DeserializeOwned, Serialize};
 
-use tokio::net::TcpStream;
+use tokio::net::{TcpStream, UnixStream};
 
 use auth::AuthHandler;
 
 use message::listener::RequestMetadata;
 use resource::listener::WebsocketAuthToken;
-use tungstenite::{handshake::client::Request, Message};
+use transport::Transport;
+use tungstenite::{
+    error::UrlError,
+    handshake::client::Request,
+    http::{self, Uri},
+    Message,
+};
 mod auth;
 pub mod bus;
 mod common;
 pub mod message;
 pub mod resource;
 pub mod service;
+pub mod transport;
+
+pub type WebsocketConnection = WebSocketStream<TokioAdapter<Transport>>;
 
-pub type WebsocketConnection = WebSocketStream<TokioAdapter<TcpStream>>;
+/// The URL scheme for connections over a Unix socket, in the format `ws+unix:///path/to/socket:/request/path`
+pub const UNIX_SCHEME: &str = "ws+unix://";
 
 /// Connects to the provided URL, with no authentication token
 pub async fn connect(url: String) -> Result<WebsocketConnection, tungstenite::Error> {
-    let tuple = connect_async(url).await?;
-    Ok(tuple.0)
+    let (transport, url) = open(url).await?;
+    let (stream, _resp) = client_async(url, transport).await?;
+    Ok(stream)
 }
 
 /// Connects to the provided URL, given an authentication token
@@ -31,23 +42,41 @@ pub async fn connect_authorized(
     url: String,
     token: String,
 ) -> Result<WebsocketConnection, tungstenite::Error> {
+    let (transport, url) = open(url).await?;
     let request = Request::builder()
         .uri(url)
         .header("Authorization", token.trim())
         .body(())?;
 
-    let (stream, _resp) = connect_async(request).await?;
+    let (stream, _resp) = client_async(request, transport).await?;
     Ok(stream)
 }
 
-/// Binds to the TCP stream as a server, requring the auth token, and capturing request metadata via a lifeline request.
+/// Opens the transport for the ws:// or ws+unix:// URL, and returns the URL for the websocket request.
+async fn open(url: String) -> Result<(Transport, String), tungstenite::Error> {
+    if let Some(address) = url.strip_prefix(UNIX_SCHEME) {
+        let (socket, path) = address.rsplit_once(':').unwrap_or((address, "/"));
+        let stream = UnixStream::connect(socket).await?;
+
+        return Ok((Transport::Unix(stream), format!("ws://localhost{}", path)));
+    }
+
+    let uri: Uri = url.parse().map_err(http::Error::from)?;
+    let host = uri.host().ok_or(UrlError::NoHostName)?;
+    let port = uri.port_u16().unwrap_or(80);
+    let stream = TcpStream::connect((host, port)).await?;
+
+    Ok((Transport::Tcp(stream), url))
+}
+
+/// Binds to the stream as a server, requring the auth token, and capturing request metadata via a lifeline request.
 pub async fn bind(
-    tcp: TcpStream,
+    stream: Transport,
     auth_token: WebsocketAuthToken,
     request_metadata: lifeline::request::Request<(), RequestMetadata>,
 ) -> Result<WebsocketConnection, tungstenite::Error> {
     let auth = AuthHandler::with_metadata(auth_token, Some(request_metadata));
-    async_tungstenite::tokio::accept_hdr_async(tcp, auth).await
+    async_tungstenite::tokio::accept_hdr_async(stream, auth).await
 }
 
 /// Decodes the bincode-serialized message
@@ -1,9 +1,9 @@
+use crate::transport::Listener;
 use lifeline::{impl_storage_clone, impl_storage_take};
-use tokio::net::TcpListener;
 
-/// A resource which wraps an established TCP listener.  Taken from the bus
+/// A resource which wraps an established TCP or Unix socket listener.  Taken from the bus
 #[derive(Debug)]
-pub struct WebsocketListenerResource(pub TcpListener);
+pub struct WebsocketListenerResource(pub Listener);
 
 impl_storage_take!(WebsocketListenerResource);
 
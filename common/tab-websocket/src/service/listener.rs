@@ -1,4 +1,5 @@
 use crate::bus::WebsocketConnectionBus;
+use crate::transport::Listener;
 use crate::{
     bind,
     bus::WebsocketListenerBus,
@@ -13,7 +14,6 @@ use log::{debug, error};
 use lifeline::prelude::*;
 use lifeline::{dyn_bus::DynBus, request::Request as LifelineRequest};
 use postage::sink::Sink;
-use tokio::net::TcpListener;
 
 /// An established listener service, which transmits WebsocketConnectionMessages over the listener bus.
 pub struct WebsocketListenerService {
@@ -37,15 +37,22 @@ impl Service for WebsocketListenerService {
 
 /// The main runloop for the WebsocketListenerService
 async fn accept_connections(
-    listener: TcpListener,
+    listener: Listener,
     mut tx: impl Sink<Item = WebsocketConnectionMessage> + Unpin,
     auth_token: WebsocketAuthToken,
 ) -> anyhow::Result<()> {
     loop {
         let (stream, addr) = listener.accept().await?;
+        debug!("connection opened from {}", addr);
 
-        // TODO: only accept connections from loopback address
-        debug!("connection opened from {:?}", addr);
+        match stream.authorize_peer() {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                error!("failed to authorize peer {}: {}", addr, e);
+                continue;
+            }
+        }
 
         let conn_bus = WebsocketConnectionBus::default();
         let (request, recv_metadata) = LifelineRequest::send(());
@@ -82,9 +89,9 @@ pub(crate) async fn serve(
     let bus = WebsocketListenerBus::default();
     bus.store_resource::<WebsocketAuthToken>(token.into());
 
-    let server = TcpListener::bind("127.0.0.1:0").await?;
+    let server = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
     let addr = server.local_addr()?;
-    let websocket = WebsocketListenerResource(server);
+    let websocket = WebsocketListenerResource(Listener::Tcp(server));
     bus.store_resource(websocket);
 
     let lifeline = WebsocketListenerService::spawn(&bus)?;
@@ -105,11 +112,11 @@ mod tests {
             listener::{WebsocketAuthToken, WebsocketListenerResource},
         },
         service::WebsocketService,
+        transport::Listener,
     };
     use lifeline::{assert_completes, dyn_bus::DynBus, prelude::*};
     use postage::{sink::Sink, stream::Stream};
     use std::net::SocketAddr;
-    use tokio::net::TcpListener;
 
     async fn connect(
         addr: SocketAddr,
@@ -131,8 +138,7 @@ mod tests {
         let bus = WebsocketListenerBus::default();
         bus.store_resource(WebsocketAuthToken::unauthenticated());
 
-        let server = TcpListener::bind("127.0.0.1:0").await?;
-        let websocket = WebsocketListenerResource(server);
+        let websocket = WebsocketListenerResource(Listener::bind_tcp().await?);
         bus.store_resource(websocket);
 
         let _listener = WebsocketListenerService::spawn(&bus)?;
@@ -214,4 +220,64 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_unix_send_request() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("sockets").join("daemon.sock");
+
+        let listener_bus = WebsocketListenerBus::default();
+        listener_bus.store_resource::<WebsocketAuthToken>("TOKEN".into());
+        listener_bus.store_resource(WebsocketListenerResource(Listener::bind_unix(&path)?));
+        let _serve = WebsocketListenerService::spawn(&listener_bus)?;
+
+        let url = format!("{}{}:/cli", crate::UNIX_SCHEME, path.to_string_lossy());
+        let connection = crate::connect_authorized(url, "TOKEN".to_string()).await?;
+
+        let bus = WebsocketConnectionBus::default();
+        bus.store_resource(WebsocketResource(connection));
+        let _connect = WebsocketService::spawn(&bus)?;
+
+        let mut rx_conn = listener_bus.rx::<WebsocketConnectionMessage>()?;
+        let conn = rx_conn.recv().await.expect("rx_conn recv");
+        assert_eq!("/cli", conn.request.uri.path());
+        let _serve = WebsocketService::spawn(&conn.bus)?;
+
+        let mut tx_request = bus.tx::<WebsocketSend>()?;
+        let mut rx_request = conn.bus.rx::<WebsocketRecv>()?;
+
+        tx_request
+            .send(WebsocketSend(tungstenite::Message::Text(
+                "request".to_string(),
+            )))
+            .await?;
+
+        assert_completes!(async move {
+            let request_recv = rx_request.recv().await.expect("rx_request recv");
+            let request_recv = request_recv.0.into_text().expect("into text");
+            assert_eq!("request", request_recv);
+        });
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unix_shared_dir() -> anyhow::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir()?;
+        let shared = std::fs::Permissions::from_mode(0o755);
+        std::fs::set_permissions(dir.path(), shared)?;
+
+        let path = dir.path().join("daemon.sock");
+        assert!(Listener::bind_unix(&path).is_err());
+
+        // the existing directory is not modified
+        let mode = std::fs::metadata(dir.path())?.permissions().mode();
+        assert_eq!(0o755, mode & 0o777);
+        assert!(!path.exists());
+
+        Ok(())
+    }
 }
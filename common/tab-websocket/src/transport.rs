@@ -0,0 +1,164 @@
+//! The transports which carry websocket connections.
+//! Connections are accepted on a TCP port bound to the loopback address, or a Unix domain socket.
+
+use std::{
+    io,
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use log::warn;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{TcpListener, TcpStream, UnixListener, UnixStream},
+};
+
+/// An established stream, over which the websocket protocol runs.
+#[derive(Debug)]
+pub enum Transport {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl Transport {
+    /// Determines whether the peer is allowed to connect.
+    /// TCP peers must connect from the loopback address, and Unix peers must be running as the current user.
+    pub fn authorize_peer(&self) -> io::Result<bool> {
+        match self {
+            Transport::Tcp(stream) => {
+                let addr = stream.peer_addr()?;
+                if !addr.ip().is_loopback() {
+                    warn!("rejecting connection from non-loopback address {}", addr);
+                    return Ok(false);
+                }
+
+                Ok(true)
+            }
+            Transport::Unix(stream) => {
+                let peer = stream.peer_cred()?.uid();
+                let uid = nix::unistd::getuid().as_raw();
+
+                if peer != uid {
+                    warn!("rejecting connection from uid {}", peer);
+                    return Ok(false);
+                }
+
+                Ok(true)
+            }
+        }
+    }
+}
+
+impl AsyncRead for Transport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Transport::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            Transport::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Transport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Transport::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            Transport::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Transport::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            Transport::Unix(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Transport::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            Transport::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+/// A bound listener, which accepts transports.
+#[derive(Debug)]
+pub enum Listener {
+    Tcp(TcpListener),
+    Unix(UnixListener),
+}
+
+impl Listener {
+    /// Binds to a random port on the loopback address
+    pub async fn bind_tcp() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        Ok(Self::Tcp(listener))
+    }
+
+    /// Binds to the Unix socket at the given path.  The socket is only accessible by the current user.
+    ///
+    /// The parent directory is created with the permissions `700` if it doesn't exist,
+    /// so other users can't connect before the socket permissions are set.
+    /// An existing parent directory is never modified, and the bind fails if other users can access it.
+    pub fn bind_unix(path: &Path) -> io::Result<Self> {
+        use std::fs::{DirBuilder, Permissions};
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+        if let Some(dir) = path.parent() {
+            if !dir.exists() {
+                if let Some(parent) = dir.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+
+                DirBuilder::new().mode(0o700).create(dir)?;
+            }
+
+            let mode = std::fs::metadata(dir)?.permissions().mode();
+            if mode & 0o077 != 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!(
+                        "the socket directory {} is accessible by other users",
+                        dir.to_string_lossy()
+                    ),
+                ));
+            }
+        }
+
+        let listener = UnixListener::bind(path)?;
+        std::fs::set_permissions(path, Permissions::from_mode(0o600))?;
+
+        Ok(Self::Unix(listener))
+    }
+
+    /// The bound TCP port, if this is a TCP listener
+    pub fn port(&self) -> io::Result<Option<u16>> {
+        match self {
+            Listener::Tcp(listener) => Ok(Some(listener.local_addr()?.port())),
+            Listener::Unix(_) => Ok(None),
+        }
+    }
+
+    /// Accepts the next connection, and returns the transport and a description of the peer address.
+    pub async fn accept(&self) -> io::Result<(Transport, String)> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, addr) = listener.accept().await?;
+                Ok((Transport::Tcp(stream), format!("{}", addr)))
+            }
+            Listener::Unix(listener) => {
+                let (stream, addr) = listener.accept().await?;
+                Ok((Transport::Unix(stream), format!("{:?}", addr)))
+            }
+        }
+    }
+}
@@ -2,6 +2,7 @@ use postage::{broadcast, mpsc, watch};
 
 use crate::{
     message::fuzzy::FuzzyEvent,
+    message::fuzzy::FuzzyOutputRequest,
     message::fuzzy::FuzzySelection,
     message::fuzzy::FuzzyShutdown,
     message::terminal::TerminalSend,
@@ -9,6 +10,7 @@ use crate::{
     prelude::*,
     state::fuzzy::FuzzyMatchState,
     state::fuzzy::FuzzyOutputEvent,
+    state::fuzzy::FuzzyOutputLinesState,
     state::fuzzy::FuzzyQueryState,
     state::fuzzy::FuzzySelectState,
     state::fuzzy::FuzzyTabsState,
@@ -21,6 +23,14 @@ impl Message<FuzzyBus> for Option<FuzzyTabsState> {
     type Channel = watch::Sender<Self>;
 }
 
+impl Message<FuzzyBus> for Option<FuzzyOutputLinesState> {
+    type Channel = watch::Sender<Self>;
+}
+
+impl Message<FuzzyBus> for FuzzyOutputRequest {
+    type Channel = mpsc::Sender<Self>;
+}
+
 impl Message<FuzzyBus> for FuzzyQueryState {
     type Channel = watch::Sender<Self>;
 }
@@ -53,7 +63,9 @@ impl Resource<FuzzyBus> for FuzzyEscapeState {}
 
 pub struct TerminalFuzzyCarrier {
     _recv: Lifeline,
+    _recv_output_lines: Lifeline,
     _selection: Lifeline,
+    _output_request: Lifeline,
     _forward_shutdown: Lifeline,
 }
 
@@ -72,6 +84,17 @@ impl CarryFrom<TerminalBus> for FuzzyBus {
             })
         };
 
+        let _recv_output_lines = {
+            let mut rx = from.rx::<Option<FuzzyOutputLinesState>>()?;
+            let mut tx = self.tx::<Option<FuzzyOutputLinesState>>()?;
+
+            Self::task("recv_output_lines", async move {
+                while let Some(msg) = rx.recv().await {
+                    tx.send(msg).await.ok();
+                }
+            })
+        };
+
         let _selection = {
             let mut rx = self.rx::<FuzzySelection>()?;
             let mut tx = from.tx::<TerminalSend>()?;
@@ -83,6 +106,17 @@ impl CarryFrom<TerminalBus> for FuzzyBus {
             })
         };
 
+        let _output_request = {
+            let mut rx = self.rx::<FuzzyOutputRequest>()?;
+            let mut tx = from.tx::<TerminalSend>()?;
+
+            Self::task("output_request", async move {
+                while let Some(msg) = rx.recv().await {
+                    tx.send(TerminalSend::OutputLinesRequest(msg.0)).await.ok();
+                }
+            })
+        };
+
         let _forward_shutdown = {
             let mut rx = self.rx::<FuzzyShutdown>()?;
             let mut tx = from.tx::<TerminalShutdown>()?;
@@ -96,7 +130,9 @@ impl CarryFrom<TerminalBus> for FuzzyBus {
 
         Ok(TerminalFuzzyCarrier {
             _recv,
+            _recv_output_lines,
             _selection,
+            _output_request,
             _forward_shutdown,
         })
     }
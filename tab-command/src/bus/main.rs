@@ -1,5 +1,5 @@
 use crate::{
-    message::main::{MainRecv, MainShutdown},
+    message::main::{DetachCommand, MainRecv, MainShutdown},
     message::tabs::TabRecv,
     message::terminal::TerminalRecv,
     state::tabs::ActiveTabsState,
@@ -9,6 +9,7 @@ use crate::{prelude::*, state::tab::TabState};
 use lifeline::prelude::*;
 
 use postage::{broadcast, mpsc, watch};
+use tab_api::{client::SubscriptionMode, config::DaemonConfig};
 use tab_websocket::{bus::WebsocketMessageBus, resource::connection::WebsocketResource};
 
 lifeline_bus!(
@@ -19,6 +20,10 @@ impl Message<MainBus> for MainShutdown {
     type Channel = mpsc::Sender<Self>;
 }
 
+impl Message<MainBus> for Option<DetachCommand> {
+    type Channel = watch::Sender<Self>;
+}
+
 impl Message<MainBus> for MainRecv {
     type Channel = broadcast::Sender<Self>;
 }
@@ -59,6 +64,8 @@ impl Message<MainBus> for Response {
 }
 
 impl Resource<MainBus> for WebsocketResource {}
+impl Resource<MainBus> for DaemonConfig {}
+impl Resource<MainBus> for SubscriptionMode {}
 
 impl WebsocketMessageBus for MainBus {
     type Send = Request;
@@ -10,7 +10,7 @@ use crate::{
     state::tab::DeselectTab,
     state::{
         tab::TabMetadataState,
-        tab::{SelectOrRetaskTab, SelectTab, TabState},
+        tab::{SelectNamedPanes, SelectOrRetaskTab, SelectTab, TabState},
         tabs::ActiveTabsState,
         terminal::TerminalMode,
         workspace::WorkspaceState,
@@ -18,13 +18,15 @@ use crate::{
 };
 use anyhow::Context;
 
-use tab_api::client::RetaskTarget;
+use tab_api::client::{RetaskTarget, SubscriptionMode};
 use tokio::time;
 
 use postage::{mpsc, watch};
 
 lifeline_bus!(pub struct TabBus);
 
+impl Resource<TabBus> for SubscriptionMode {}
+
 impl Message<TabBus> for Request {
     type Channel = mpsc::Sender<Self>;
 }
@@ -37,6 +39,10 @@ impl Message<TabBus> for SelectOrRetaskTab {
     type Channel = mpsc::Sender<Self>;
 }
 
+impl Message<TabBus> for SelectNamedPanes {
+    type Channel = mpsc::Sender<Self>;
+}
+
 impl Message<TabBus> for DeselectTab {
     type Channel = mpsc::Sender<Self>;
 }
@@ -96,8 +102,10 @@ impl CarryFrom<MainBus> for TabBus {
         let _forward_recv = {
             let mut rx_tab = from.rx::<TabRecv>()?;
             let mut tx_select = self.tx::<SelectOrRetaskTab>()?;
+            let mut tx_select_panes = self.tx::<SelectNamedPanes>()?;
             let mut tx_deselect = self.tx::<DeselectTab>()?;
             let mut tx_scan = self.tx::<ScanWorkspace>()?;
+            let mut tx_create = self.tx::<CreateTabRequest>()?;
 
             Self::try_task("forward_create", async move {
                 while let Some(msg) = rx_tab.recv().await {
@@ -105,6 +113,12 @@ impl CarryFrom<MainBus> for TabBus {
                         TabRecv::SelectNamedTab { name, env_tab } => {
                             tx_select.send(SelectOrRetaskTab { name, env_tab }).await?;
                         }
+                        TabRecv::CreateNamedTab { name } => {
+                            tx_create.send(CreateTabRequest::Named(name)).await?;
+                        }
+                        TabRecv::SelectNamedPanes(layout) => {
+                            tx_select_panes.send(SelectNamedPanes(layout)).await?;
+                        }
                         TabRecv::DeselectTab => {
                             tx_deselect.send(DeselectTab {}).await?;
                         }
@@ -169,10 +183,18 @@ impl CarryFrom<MainBus> for TabBus {
 
             Self::try_task("forward_request", async move {
                 while let Some(state) = rx.recv().await {
-                    if let TabState::Selected(id) = state {
-                        tx.send(TerminalRecv::Mode(TerminalMode::Echo(id)))
-                            .await
-                            .context("tx TerminalMode")?;
+                    match state {
+                        TabState::Selected(id) => {
+                            tx.send(TerminalRecv::Mode(TerminalMode::Echo(id)))
+                                .await
+                                .context("tx TerminalMode")?;
+                        }
+                        TabState::Panes(layout) => {
+                            tx.send(TerminalRecv::Mode(TerminalMode::Panes(layout)))
+                                .await
+                                .context("tx TerminalMode")?;
+                        }
+                        _ => {}
                     }
                 }
 
@@ -183,6 +205,8 @@ impl CarryFrom<MainBus> for TabBus {
         let _rx_response = {
             let mut rx_response = from.rx::<Response>()?;
 
+            let rx_tab_state = self.rx::<TabState>()?;
+
             let mut tx_tabs = self.tx::<TabsRecv>()?;
             let mut tx_select_tab = self.tx::<SelectTab>()?;
             let mut tx_main_recv = from.tx::<MainRecv>()?;
@@ -194,7 +218,7 @@ impl CarryFrom<MainBus> for TabBus {
                     match response {
                         Response::Init(init) => {
                             tx_tabs
-                                .send(TabsRecv::Init(init.tabs.clone()))
+                                .send(TabsRecv::Init(init.tabs, init.exited))
                                 .await
                                 .context("tx TabsRecv::Init")?;
                         }
@@ -204,16 +228,27 @@ impl CarryFrom<MainBus> for TabBus {
                                 .await
                                 .context("tx TabsRecv::Update")?;
                         }
-                        Response::TabTerminated(_id) => {
-                            debug!("Received termination notice on tab {:?}", _id);
+                        Response::TabTerminated(id, exit) => {
+                            debug!("Received termination notice on tab {:?}: {}", id, exit);
+
+                            // a pane is closed when it's tab terminates, and the command exits with the last pane
+                            let state = rx_tab_state.borrow().clone();
+                            if let TabState::Panes(layout) = state {
+                                if let Some(layout) = layout.remove(&id) {
+                                    tx_select_tab.send(SelectTab::Panes(layout)).await?;
+                                    continue;
+                                }
+                            }
+
                             // wait just a few moments for messages to settle.
                             // if we terminate immediately, there could be terminal I/O going on.
                             // example:
                             //   05:39:38 [ERROR] ERR: TerminalEchoService/stdout: task was cancelled
                             time::sleep(Duration::from_millis(25)).await;
 
+                            // the command exits with the exit code of the shell
                             tx_shutdown
-                                .send(MainShutdown(0))
+                                .send(MainShutdown(exit.code.unwrap_or(0)))
                                 .await
                                 .context("tx MainShutdown")?;
                         }
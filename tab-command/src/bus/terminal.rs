@@ -1,10 +1,12 @@
 use crate::{
-    message::main::MainRecv, message::terminal::TerminalRecv, message::terminal::TerminalSend,
-    prelude::*, state::workspace::WorkspaceState,
+    config::load_status_bar_config, message::main::MainRecv, message::terminal::TerminalRecv,
+    message::terminal::TerminalScrollback, message::terminal::TerminalSend, prelude::*,
+    state::fuzzy::FuzzyOutputLinesState, state::tabs::ActiveTabsState,
+    state::workspace::WorkspaceState,
 };
 use crate::{
     message::{
-        main::MainShutdown,
+        main::{DetachCommand, MainShutdown},
         terminal::{TerminalInput, TerminalOutput, TerminalShutdown},
     },
     state::{
@@ -15,7 +17,11 @@ use crate::{
 use anyhow::Context;
 use lifeline::prelude::*;
 use postage::{broadcast, mpsc, watch};
-use tab_api::chunk::InputChunk;
+use std::sync::Arc;
+use tab_api::{
+    chunk::InputChunk,
+    client::{GrepQuery, SubscriptionMode},
+};
 
 lifeline_bus!(pub struct TerminalBus);
 
@@ -39,6 +45,10 @@ impl Message<TerminalBus> for TerminalSend {
     type Channel = mpsc::Sender<Self>;
 }
 
+impl Message<TerminalBus> for TerminalScrollback {
+    type Channel = broadcast::Sender<Self>;
+}
+
 impl Message<TerminalBus> for TerminalMode {
     type Channel = watch::Sender<Self>;
 }
@@ -47,15 +57,29 @@ impl Message<TerminalBus> for Option<WorkspaceState> {
     type Channel = watch::Sender<Self>;
 }
 
+impl Message<TerminalBus> for Option<FuzzyOutputLinesState> {
+    type Channel = watch::Sender<Self>;
+}
+
+impl Message<TerminalBus> for Option<ActiveTabsState> {
+    type Channel = watch::Sender<Self>;
+}
+
 /// Carries messages between the MainBus, and the TerminalBus.
 ///
 /// Listens to MainRecv and sends TerminalMode,
 /// forwards TerminalShutdown, and carries Input, Output, and Resize events.
+/// Forwards the active tabs, which are shown in the status bar.
+/// Requests recent output lines for the fuzzy finder, and forwards the results.
+/// Enters & exits scroll mode for the selected tab, and forwards the scrollback lines.
 pub struct MainTerminalCarrier {
     _recv: Lifeline,
     _send: Lifeline,
     _forward_shutdown: Lifeline,
     _forward_workspace: Lifeline,
+    _forward_tabs: Lifeline,
+    _forward_output_lines: Lifeline,
+    _forward_scrollback: Lifeline,
     _echo_output: Lifeline,
     _read_input: Lifeline,
 }
@@ -82,6 +106,14 @@ impl CarryFrom<MainBus> for TerminalBus {
         let _send = {
             let mut rx = self.rx::<TerminalSend>()?;
             let mut tx = from.tx::<MainRecv>()?;
+            let mut tx_request = from.tx::<Request>()?;
+            let mut tx_terminal = from.tx::<TerminalRecv>()?;
+            let mut tx_detach = from.tx::<Option<DetachCommand>>()?;
+            let mut tx_shutdown = from.tx::<MainShutdown>()?;
+            let rx_tab_state = from.rx::<TabState>()?;
+            let rx_size = self.rx::<TerminalSizeState>()?;
+            let mode = from.resource::<SubscriptionMode>()?;
+            let status_bar = load_status_bar_config();
 
             Self::try_task("send", async move {
                 while let Some(msg) = rx.recv().await {
@@ -90,6 +122,53 @@ impl CarryFrom<MainBus> for TerminalBus {
                         TerminalSend::FuzzySelection(selection) => {
                             tx.send(MainRecv::SelectTab(selection)).await?
                         }
+                        TerminalSend::OutputLinesRequest(limit) => {
+                            let query = GrepQuery {
+                                pattern: "".to_string(),
+                                limit: Some(limit),
+                            };
+
+                            tx_request.send(Request::Grep(query)).await?
+                        }
+                        TerminalSend::ScrollRequest => {
+                            let tab = rx_tab_state.borrow().clone();
+
+                            if let TabState::Selected(id) = tab {
+                                tx_terminal
+                                    .send(TerminalRecv::Mode(TerminalMode::Scroll(id)))
+                                    .await?;
+                            }
+                        }
+                        TerminalSend::ScrollbackRequest(id) => {
+                            tx_request.send(Request::ScrollbackLines(id)).await?
+                        }
+                        TerminalSend::ScrollExit(id) => {
+                            tx_terminal
+                                .send(TerminalRecv::Mode(TerminalMode::Echo(id)))
+                                .await?;
+
+                            // output was not shown while scrolling.  resubscribe for a fresh redraw of the tab
+                            tx_request.send(Request::Unsubscribe(id)).await?;
+                            tx_request.send(Request::Subscribe(id, mode)).await?;
+
+                            if mode == SubscriptionMode::Interactive {
+                                let size = status_bar.tab_size(rx_size.borrow().0);
+                                tx_request.send(Request::ResizeTab(id, size)).await?;
+                            }
+                        }
+                        TerminalSend::SelectTab(name) => tx.send(MainRecv::SelectTab(name)).await?,
+                        TerminalSend::CloseTab(id) => {
+                            if mode == SubscriptionMode::ReadOnly {
+                                debug!("ignoring close from read-only client on tab {}", id.0);
+                                continue;
+                            }
+
+                            tx_request.send(Request::CloseTab(id)).await?
+                        }
+                        TerminalSend::DetachAndRun(command) => {
+                            tx_detach.send(Some(DetachCommand(command))).await?;
+                            tx_shutdown.send(MainShutdown(0)).await?;
+                        }
                     }
                 }
 
@@ -123,15 +202,62 @@ impl CarryFrom<MainBus> for TerminalBus {
             })
         };
 
+        let _forward_tabs = {
+            let mut rx = from.rx::<Option<ActiveTabsState>>()?;
+            let mut tx = self.tx::<Option<ActiveTabsState>>()?;
+
+            Self::try_task("forward_tabs", async move {
+                while let Some(msg) = rx.recv().await {
+                    tx.send(msg).await?;
+                }
+
+                Ok(())
+            })
+        };
+
+        let _forward_output_lines = {
+            let mut rx_response = from.rx::<Response>()?;
+            let mut tx = self.tx::<Option<FuzzyOutputLinesState>>()?;
+
+            Self::try_task("forward_output_lines", async move {
+                while let Some(response) = rx_response.recv().await {
+                    if let Response::Grep(lines) = response {
+                        let state = FuzzyOutputLinesState {
+                            lines: Arc::new(lines),
+                        };
+
+                        tx.send(Some(state)).await?;
+                    }
+                }
+
+                Ok(())
+            })
+        };
+
+        let _forward_scrollback = {
+            let mut rx_response = from.rx::<Response>()?;
+            let mut tx = self.tx::<TerminalScrollback>()?;
+
+            Self::try_task("forward_scrollback", async move {
+                while let Some(response) = rx_response.recv().await {
+                    if let Response::ScrollbackLines(id, lines) = response {
+                        tx.send(TerminalScrollback { id, lines }).await?;
+                    }
+                }
+
+                Ok(())
+            })
+        };
+
         let _echo_output = {
             let mut rx_response = from.rx::<Response>()?;
             let mut tx_output = self.tx::<TerminalOutput>()?;
 
             Self::try_task("main_recv", async move {
                 while let Some(response) = rx_response.recv().await {
-                    if let Response::Output(_id, stdout) = response {
+                    if let Response::Output(id, stdout) = response {
                         tx_output
-                            .send(TerminalOutput::Stdout(stdout.data))
+                            .send(TerminalOutput::Stdout(id, stdout.data))
                             .await
                             .context("tx TerminalRecv::Stdout")?;
                     }
@@ -143,11 +269,17 @@ impl CarryFrom<MainBus> for TerminalBus {
 
         let _read_input = {
             let rx_tab_state = from.rx::<TabState>()?;
+            let mode = from.resource::<SubscriptionMode>()?;
             let mut rx_terminal_input = self.rx::<TerminalInput>()?;
             let mut tx_request = from.tx::<Request>()?;
+            let status_bar = load_status_bar_config();
 
             Self::try_task("main_recv", async move {
                 while let Some(msg) = rx_terminal_input.recv().await {
+                    if mode == SubscriptionMode::ReadOnly {
+                        continue;
+                    }
+
                     match msg {
                         TerminalInput::Stdin(data) => {
                             let tab = rx_tab_state.borrow().clone();
@@ -161,10 +293,19 @@ impl CarryFrom<MainBus> for TerminalBus {
                             let tab = rx_tab_state.borrow().clone();
 
                             if let TabState::Selected(id) = tab {
+                                let size = status_bar.tab_size(size);
                                 debug!("setting size: {} {:?}", &id.0, &size);
                                 tx_request.send(Request::ResizeTab(id, size)).await?;
                             }
                         }
+                        TerminalInput::PaneStdin(id, data) => {
+                            let chunk = InputChunk { data };
+                            tx_request.send(Request::Input(id, chunk)).await?;
+                        }
+                        TerminalInput::PaneResize(id, size) => {
+                            debug!("setting pane size: {} {:?}", &id.0, &size);
+                            tx_request.send(Request::ResizeTab(id, size)).await?;
+                        }
                     }
                 }
 
@@ -177,6 +318,9 @@ impl CarryFrom<MainBus> for TerminalBus {
             _send,
             _forward_shutdown,
             _forward_workspace,
+            _forward_tabs,
+            _forward_output_lines,
+            _forward_scrollback,
             _echo_output,
             _read_input,
         })
@@ -1,3 +1,4 @@
+use log::warn;
 use serde::Deserialize;
 use std::{fs::File, io::BufReader};
 
@@ -19,7 +20,10 @@ pub fn load_global_config() -> anyhow::Result<Config> {
 #[derive(Deserialize, Default)]
 pub struct Config {
     pub key_bindings: Option<Vec<KeyBinding>>,
+    #[serde(default)]
     pub fuzzy: FuzzyConfig,
+    #[serde(default)]
+    pub status_bar: StatusBarConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -40,14 +44,117 @@ fn default_create_tab() -> bool {
     true
 }
 
+/// Parses the status bar config, or returns the default config if the global config could not be parsed
+pub fn load_status_bar_config() -> StatusBarConfig {
+    let status_bar = load_global_config().map(|c| c.status_bar);
+
+    if let Err(e) = status_bar.as_ref() {
+        warn!(
+            "Using default status bar config.  failed to parse global config: {}",
+            e
+        );
+    }
+
+    status_bar.unwrap_or_else(|_e| StatusBarConfig::default())
+}
+
+/// The status line, which is reserved at the bottom of the screen while tabs are selected
+#[derive(Debug, Clone, Deserialize)]
+pub struct StatusBarConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_clock")]
+    pub clock: bool,
+}
+
+impl StatusBarConfig {
+    /// The size available to tabs, within a terminal of the given size.  If enabled, the status bar reserves the bottom row.
+    pub fn tab_size(&self, size: (u16, u16)) -> (u16, u16) {
+        if self.enabled {
+            (size.0, size.1.saturating_sub(1).max(1))
+        } else {
+            size
+        }
+    }
+}
+
+impl Default for StatusBarConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            clock: default_clock(),
+        }
+    }
+}
+
+fn default_clock() -> bool {
+    true
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct KeyBinding {
     pub action: Action,
     pub keys: String,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 pub enum Action {
     Disconnect,
     SelectInteractive,
+    ScrollMode,
+    /// Moves focus to the next pane, when tabs are open in panes
+    FocusNextPane,
+    /// Moves focus to the previous pane, when tabs are open in panes
+    FocusPreviousPane,
+    /// Selects the previously selected tab, like `tab -`
+    SelectPrevious,
+    /// Selects the next tab, from most to least recently selected.  Repeating the action steps through the tabs.
+    NextTab,
+    /// Selects the previous tab, from least to most recently selected.  Repeating the action steps back through the tabs.
+    PrevTab,
+    /// Closes the current tab, or the tab in the focused pane
+    CloseTab,
+    /// Selects the named tab, configured as `SelectTab: proj/server/`
+    SelectTab(String),
+    /// Disconnects from the tab, and runs the shell command in the terminal, configured as `DetachAndRun: git status`
+    DetachAndRun(String),
+    /// Sends the first key of the binding to the tab, so a key which begins other bindings can be typed (e.g. `ctrl-T ctrl-T`)
+    SendPrefix,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, StatusBarConfig};
+
+    #[test]
+    fn status_bar_default() -> anyhow::Result<()> {
+        let config: Config = serde_yaml::from_str("key_bindings: []")?;
+
+        assert!(!config.status_bar.enabled);
+        assert!(config.status_bar.clock);
+        assert_eq!((80, 24), config.status_bar.tab_size((80, 24)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn status_bar_enabled() -> anyhow::Result<()> {
+        let config: Config = serde_yaml::from_str("status_bar:\n  enabled: true\n  clock: false")?;
+
+        assert!(config.status_bar.enabled);
+        assert!(!config.status_bar.clock);
+        assert_eq!((80, 23), config.status_bar.tab_size((80, 24)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn status_bar_tab_size_min() {
+        let config = StatusBarConfig {
+            enabled: true,
+            clock: true,
+        };
+
+        assert_eq!((80, 1), config.tab_size((80, 1)));
+    }
 }
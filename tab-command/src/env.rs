@@ -1,6 +1,8 @@
 use crossterm::terminal;
 use tab_api::env::is_raw_mode;
 
+use crate::config::load_status_bar_config;
+
 pub fn terminal_size() -> anyhow::Result<(u16, u16)> {
     if is_raw_mode() {
         terminal::size().map_err(|err| err.into())
@@ -8,3 +10,9 @@ pub fn terminal_size() -> anyhow::Result<(u16, u16)> {
         Ok((80, 24))
     }
 }
+
+/// The size of a tab's pty, which excludes the status bar (if enabled)
+pub fn tab_size() -> anyhow::Result<(u16, u16)> {
+    let size = terminal_size()?;
+    Ok(load_status_bar_config().tab_size(size))
+}
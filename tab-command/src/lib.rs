@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{os::unix::process::CommandExt, path::Path, process::Command, time::Duration};
 
 use clap::ArgMatches;
 use semver::Version;
@@ -9,10 +9,14 @@ use service::{main::*, terminal::disable_raw_mode, terminal::reset_terminal_stat
 use simplelog::{ColorChoice, TermLogger, TerminalMode};
 
 use crate::bus::MainBus;
-use message::main::{MainRecv, MainShutdown};
+use message::main::{CaptureOptions, DetachCommand, ListFormat, MainRecv, MainShutdown};
+use state::pane::{PaneLayout, SplitDirection};
 
 use lifeline::dyn_bus::DynBus;
-use tab_api::{config::DaemonConfig, launch::*, log::get_level, tab::normalize_name};
+use postage::watch;
+use tab_api::{
+    client::SubscriptionMode, config::DaemonConfig, launch::*, log::get_level, tab::normalize_name,
+};
 use tab_websocket::resource::connection::WebsocketResource;
 
 mod bus;
@@ -20,6 +24,7 @@ mod config;
 mod env;
 mod message;
 mod prelude;
+mod replay;
 mod service;
 mod state;
 mod utils;
@@ -56,27 +61,53 @@ pub fn command_main(args: ArgMatches, tab_version: &'static str) -> anyhow::Resu
 }
 
 async fn main_async(matches: ArgMatches<'_>, tab_version: &'static str) -> anyhow::Result<i32> {
+    let capture_tab = matches.value_of("CAPTURE-TAB");
     let check_workspace = matches.is_present("CHECK-WORKSPACE");
     let close_completion = matches.is_present("AUTOCOMPLETE-CLOSE-TAB");
     let close_tabs = matches.values_of("CLOSE-TAB");
     let completion = matches.is_present("AUTOCOMPLETE-TAB");
     let disconnect_tabs = matches.values_of("DISCONNECT-TAB");
+    let grep = matches.value_of("GREP");
+    let panes = matches.values_of("PANES");
+    let record_tab = matches.value_of("RECORD-TAB");
     let select_tab = matches.value_of("TAB-NAME");
+    let send_tab = matches.values_of("SEND-TAB");
     let shutdown = matches.is_present("SHUTDOWN");
+    let upgrade_daemon = matches.is_present("UPGRADE-DAEMON");
+    let watch_tab = matches.value_of("WATCH-TAB");
 
-    let spawn_result = spawn(tab_version).await;
+    // replay runs locally, and does not need the daemon
+    if let Some(file) = matches.value_of("REPLAY") {
+        let speed = matches.value_of("SPEED").map(str::parse).transpose()?;
+        info!("CLI Match: Replay({}, {:?})", file, speed);
+
+        replay::replay(Path::new(file), speed.unwrap_or(1.0)).await?;
+        reset_terminal_state();
+        return Ok(0);
+    }
+
+    let mode = if watch_tab.is_some() {
+        SubscriptionMode::ReadOnly
+    } else {
+        SubscriptionMode::Interactive
+    };
+
+    let spawn_result = spawn(tab_version, !upgrade_daemon, mode).await;
 
     if let Err(e) = spawn_result {
         error!("Failed to initialize the tab command: {}", e);
         return Err(e);
     }
 
-    let (mut tx, rx_shutdown, _service) = spawn_result.unwrap();
+    let (mut tx, rx_shutdown, rx_detach, _service) = spawn_result.unwrap();
 
     debug!("Parsing CLI arguments...");
     if shutdown {
         info!("CLI Match: GlobalShutdown");
         tx.send(MainRecv::GlobalShutdown).await?;
+    } else if upgrade_daemon {
+        info!("CLI Match: UpgradeDaemon");
+        tx.send(MainRecv::UpgradeDaemon).await?;
     } else if completion {
         info!("CLI Match: AutocompleteTab");
         tx.send(MainRecv::AutocompleteTab).await?;
@@ -87,8 +118,14 @@ async fn main_async(matches: ArgMatches<'_>, tab_version: &'static str) -> anyho
         info!("CLI Match: CheckWorkspace");
         tx.send(MainRecv::CheckWorkspace).await?;
     } else if matches.is_present("LIST") {
-        info!("CLI Match: ListTabs");
-        tx.send(MainRecv::ListTabs).await?;
+        let format = match matches.value_of("FORMAT") {
+            Some("json") => ListFormat::Json,
+            Some("yaml") => ListFormat::Yaml,
+            _ => ListFormat::Text,
+        };
+
+        info!("CLI Match: ListTabs({:?})", format);
+        tx.send(MainRecv::ListTabs(format)).await?;
     } else if let Some(tab) = select_tab {
         if tab == "-" {
             info!("CLI Match: SelectPreviousTab");
@@ -97,6 +134,42 @@ async fn main_async(matches: ArgMatches<'_>, tab_version: &'static str) -> anyho
             info!("CLI Match: SelectTab({})", &tab);
             tx.send(MainRecv::SelectTab(tab.to_string())).await?;
         }
+    } else if let Some(tabs) = panes {
+        let direction = if matches.is_present("VERTICAL") {
+            SplitDirection::Vertical
+        } else {
+            SplitDirection::Horizontal
+        };
+
+        let panes = tabs.map(normalize_name).map(PaneLayout::Pane).collect();
+
+        let layout = PaneLayout::Split(direction, panes);
+        info!("CLI Match: SelectPanes({:?})", &layout);
+        tx.send(MainRecv::SelectPanes(layout)).await?;
+    } else if let Some(tab) = watch_tab {
+        info!("CLI Match: WatchTab({})", &tab);
+        tx.send(MainRecv::WatchTab(tab.to_string())).await?;
+    } else if let Some(tab) = capture_tab {
+        let options = CaptureOptions {
+            name: tab.to_string(),
+            lines: matches.value_of("LINES").map(str::parse).transpose()?,
+            strip_ansi: matches.is_present("STRIP-ANSI"),
+            follow: matches.is_present("FOLLOW"),
+        };
+
+        info!("CLI Match: CaptureTab({:?})", &options);
+        tx.send(MainRecv::CaptureTab(options)).await?;
+    } else if let Some(pattern) = grep {
+        info!("CLI Match: GrepTabs({})", &pattern);
+        tx.send(MainRecv::GrepTabs(pattern.to_string())).await?;
+    } else if let Some(tab) = record_tab {
+        info!("CLI Match: RecordTab({})", &tab);
+        tx.send(MainRecv::RecordTab(tab.to_string())).await?;
+    } else if let Some(mut values) = send_tab {
+        let tab = values.next().unwrap_or_default();
+        let text = values.next().map(str::to_string);
+        info!("CLI Match: SendTab({}, {:?})", &tab, &text);
+        tx.send(MainRecv::SendTab(tab.to_string(), text)).await?;
     } else if let Some(tabs) = close_tabs {
         info!("CLI Match: CloseTabs({:?})", &tabs);
         let tabs: Vec<String> = tabs.map(normalize_name).collect();
@@ -116,19 +189,33 @@ async fn main_async(matches: ArgMatches<'_>, tab_version: &'static str) -> anyho
 
     debug!("tab-command shutdown.");
 
+    let detach = rx_detach.borrow().clone();
+    if let Some(DetachCommand(command)) = detach {
+        info!("running detach command: {}", &command);
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+        let error = Command::new(shell).arg("-c").arg(command).exec();
+        return Err(error.into());
+    }
+
     Ok(exit.0)
 }
 
 async fn spawn(
     tab_version: &'static str,
+    validate: bool,
+    mode: SubscriptionMode,
 ) -> anyhow::Result<(
     impl Sink<Item = MainRecv> + Unpin,
     impl Stream<Item = MainShutdown> + Unpin,
+    watch::Receiver<Option<DetachCommand>>,
     MainService,
 )> {
     let daemon_file = launch_daemon().await?;
-    validate_daemon(&daemon_file, tab_version);
-    let ws_url = format!("ws://127.0.0.1:{}/cli", daemon_file.port);
+    if validate {
+        validate_daemon(&daemon_file, tab_version);
+    }
+
+    let ws_url = daemon_file.websocket_url("/cli");
 
     debug!("daemon is ready");
 
@@ -140,16 +227,19 @@ async fn spawn(
         tab_websocket::connect_authorized(ws_url, daemon_file.auth_token.clone()).await?;
     let websocket = WebsocketResource(websocket);
     bus.store_resource(websocket);
+    bus.store_resource(daemon_file);
+    bus.store_resource(mode);
 
     info!("Launching MainService");
     let service = MainService::spawn(&bus)?;
 
     let tx = bus.tx::<MainRecv>()?;
     let main_shutdown = bus.rx::<MainShutdown>()?;
+    let rx_detach = bus.rx::<Option<DetachCommand>>()?;
 
     debug!("Main spawn complete");
 
-    Ok((tx, main_shutdown, service))
+    Ok((tx, main_shutdown, rx_detach, service))
 }
 
 fn validate_daemon(config: &DaemonConfig, tab_version: &'static str) {
@@ -170,7 +260,7 @@ fn validate_daemon(config: &DaemonConfig, tab_version: &'static str) {
         if tab_version.major != daemon_version.major || tab_version.minor != daemon_version.minor {
             eprintln!("Warning: The tab command (v{}) has a different version than the running daemon (v{})", tab_version, daemon_version);
             eprintln!(
-                "  You should run `tab --shutdown` to terminate your tabs and relaunch the daemon."
+                "  You should run `tab --upgrade-daemon` to relaunch the daemon, without terminating your tabs."
             );
 
             return;
@@ -182,7 +272,7 @@ fn validate_daemon(config: &DaemonConfig, tab_version: &'static str) {
             eprintln!(
                 "Warning: The tab command has a different executable path than the running daemon."
             );
-            eprintln!("  You may want to run `tab --shutdown` to terminate your tabs and relaunch the daemon.");
+            eprintln!("  You may want to run `tab --upgrade-daemon` to relaunch the daemon, without terminating your tabs.");
 
             eprintln!("  Tab command: {}", executable);
             eprintln!("  Daemon command: {}", daemon_exec);
@@ -10,6 +10,8 @@ pub enum FuzzyEvent {
     Delete,
     Enter,
     Resize(u16, u16),
+    /// Toggles matching against the recent output lines of each tab
+    ToggleOutput,
 }
 
 #[derive(Debug, Clone)]
@@ -17,3 +19,7 @@ pub struct FuzzySelection(pub String);
 
 #[derive(Debug, Clone)]
 pub struct FuzzyShutdown;
+
+/// Requests up to N recent output lines from each running tab
+#[derive(Debug, Clone)]
+pub struct FuzzyOutputRequest(pub usize);
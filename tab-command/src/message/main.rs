@@ -1,3 +1,4 @@
+use crate::state::pane::PaneLayout;
 use tab_api::{
     chunk::{InputChunk, OutputChunk},
     tab::TabId,
@@ -12,18 +13,49 @@ impl Default for MainShutdown {
     }
 }
 
+/// A shell command, which is run in place of the tab command, once the terminal has been restored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetachCommand(pub String);
+
 #[derive(Debug, Clone)]
 pub enum MainRecv {
     AutocompleteCloseTab,
     AutocompleteTab,
+    CaptureTab(CaptureOptions),
     CheckWorkspace,
     CloseTabs(Vec<String>),
     DisconnectTabs(Vec<String>),
     GlobalShutdown,
-    ListTabs,
+    GrepTabs(String),
+    UpgradeDaemon,
+    ListTabs(ListFormat),
+    RecordTab(String),
     SelectInteractive,
+    /// Opens the named tabs in panes
+    SelectPanes(PaneLayout<String>),
     SelectPreviousTab,
     SelectTab(String),
+    SendTab(String, Option<String>),
+    WatchTab(String),
+}
+
+/// The output format of the `tab --list` command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListFormat {
+    Text,
+    Json,
+    Yaml,
+}
+
+/// The options of the `tab --capture` command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureOptions {
+    pub name: String,
+    /// If set, only the last N lines of the scrollback are printed
+    pub lines: Option<usize>,
+    pub strip_ansi: bool,
+    /// If true, new output is printed until the tab terminates
+    pub follow: bool,
 }
 
 #[derive(Debug)]
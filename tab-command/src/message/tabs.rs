@@ -1,20 +1,29 @@
-use tab_api::tab::{TabId, TabMetadata};
+use tab_api::tab::{ExitedTab, TabId, TabMetadata};
 
 use std::collections::HashMap;
 
+use crate::state::pane::PaneLayout;
+
 #[derive(Debug, Clone)]
 pub enum TabRecv {
     SelectNamedTab {
         name: String,
         env_tab: Option<TabId>,
     },
+    CreateNamedTab {
+        name: String,
+    },
+    /// Selects the named tabs in panes, creating them if needed
+    SelectNamedPanes(PaneLayout<String>),
     DeselectTab,
     ScanWorkspace,
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum TabsRecv {
-    Init(HashMap<TabId, TabMetadata>),
+    /// The running tabs, and the exited tabs
+    Init(HashMap<TabId, TabMetadata>, HashMap<TabId, ExitedTab>),
     Update(TabMetadata),
 }
 
@@ -1,3 +1,5 @@
+use tab_api::tab::TabId;
+
 use crate::state::terminal::TerminalMode;
 
 #[derive(Debug, Clone)]
@@ -9,17 +11,43 @@ pub enum TerminalRecv {
 pub enum TerminalSend {
     FuzzyRequest,
     FuzzySelection(String),
+    /// Requests up to N recent output lines from each running tab, for the fuzzy finder
+    OutputLinesRequest(usize),
+    /// Requests scroll mode, for the selected tab
+    ScrollRequest,
+    /// Requests the rendered scrollback lines of the tab, for the scroll mode pager
+    ScrollbackRequest(TabId),
+    /// Notifies that scroll mode has exited, and output from the tab should be resumed
+    ScrollExit(TabId),
+    /// Selects the named tab, from a key binding
+    SelectTab(String),
+    /// Closes the tab, from a key binding
+    CloseTab(TabId),
+    /// Disconnects from the tab, and runs the shell command once the terminal has been restored
+    DetachAndRun(String),
+}
+
+/// The rendered lines of a tab's history & screen, for the scroll mode pager
+#[derive(Debug, Clone)]
+pub struct TerminalScrollback {
+    pub id: TabId,
+    pub lines: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub enum TerminalInput {
     Stdin(Vec<u8>),
     Resize((u16, u16)),
+    /// Input for the tab in the focused pane
+    PaneStdin(TabId, Vec<u8>),
+    /// The (cols, rows) size of the pane, which displays the tab
+    PaneResize(TabId, (u16, u16)),
 }
 
 #[derive(Debug, Clone)]
 pub enum TerminalOutput {
-    Stdout(Vec<u8>),
+    /// Output from the given tab
+    Stdout(TabId, Vec<u8>),
 }
 
 #[derive(Debug, Clone)]
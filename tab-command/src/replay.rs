@@ -0,0 +1,152 @@
+//! Plays back asciicast v2 recordings, which are written by the daemon for tabs with `record: true`.
+
+use std::{
+    io::{stdout, Write},
+    path::Path,
+    time::{Duration, TryFromFloatSecsError},
+};
+
+use anyhow::Context;
+use crossterm::terminal;
+use serde::Deserialize;
+use tokio::time::{self, Instant};
+
+/// The first line of an asciicast v2 file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Header {
+    version: u32,
+    width: u16,
+    height: u16,
+}
+
+/// An event line of an asciicast v2 file: the time in seconds, the event code, and the event data
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Event(f64, String, String);
+
+/// Plays the recording to stdout.  Events are delayed to match the original timing, divided by `speed`.
+pub async fn replay(path: &Path, speed: f64) -> anyhow::Result<()> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read recording {}", path.to_string_lossy()))?;
+
+    let (header, events) = parse(contents.as_str())?;
+
+    if let Ok((cols, rows)) = terminal::size() {
+        if cols < header.width || rows < header.height {
+            eprintln!(
+                "Warning: The recording is {}x{}, but the terminal is {}x{}.  Output may be garbled.",
+                header.width, header.height, cols, rows
+            );
+        }
+    }
+
+    let start = Instant::now();
+    let mut stdout = stdout();
+
+    for Event(timestamp, code, data) in events {
+        if code != "o" {
+            continue;
+        }
+
+        let deadline = event_offset(timestamp, speed)
+            .ok()
+            .and_then(|offset| start.checked_add(offset))
+            .with_context(|| format!("invalid event time {} at speed {}", timestamp, speed))?;
+        time::sleep_until(deadline).await;
+
+        stdout.write_all(data.as_bytes())?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Returns the delay of an event from the start of playback, or an error if the delay cannot be represented
+fn event_offset(timestamp: f64, speed: f64) -> Result<Duration, TryFromFloatSecsError> {
+    Duration::try_from_secs_f64(timestamp.max(0.0) / speed)
+}
+
+fn parse(contents: &str) -> anyhow::Result<(Header, Vec<Event>)> {
+    let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+
+    let header = lines.next().context("the recording is empty")?;
+    let header: Header =
+        serde_json::from_str(header).context("failed to parse the recording header")?;
+
+    if header.version != 2 {
+        anyhow::bail!("unsupported asciicast version: {}", header.version);
+    }
+
+    let mut events = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let event: Event = serde_json::from_str(line)
+            .with_context(|| format!("failed to parse recording event {}", index + 1))?;
+        events.push(event);
+    }
+
+    Ok((header, events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{event_offset, parse, Event, Header};
+    use std::time::{Duration, TryFromFloatSecsError};
+
+    #[test]
+    fn parse_recording() -> anyhow::Result<()> {
+        let contents = concat!(
+            r#"{"version":2,"width":80,"height":24,"timestamp":1600000000,"title":"ops/","env":{"SHELL":"/bin/bash"}}"#,
+            "\n",
+            r#"[0.25,"o","hello\r\n"]"#,
+            "\n",
+            r#"[1.5,"r","120x40"]"#,
+            "\n"
+        );
+
+        let (header, events) = parse(contents)?;
+
+        assert_eq!(
+            Header {
+                version: 2,
+                width: 80,
+                height: 24
+            },
+            header
+        );
+
+        assert_eq!(
+            vec![
+                Event(0.25, "o".into(), "hello\r\n".into()),
+                Event(1.5, "r".into(), "120x40".into())
+            ],
+            events
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_unsupported_version() {
+        let contents = r#"{"version":1,"width":80,"height":24}"#;
+        assert!(parse(contents).is_err());
+    }
+
+    #[test]
+    fn parse_invalid_event() {
+        let contents = concat!(r#"{"version":2,"width":80,"height":24}"#, "\n", "[0.25]");
+        assert!(parse(contents).is_err());
+    }
+
+    #[test]
+    fn event_offset_speed() -> Result<(), TryFromFloatSecsError> {
+        assert_eq!(Duration::from_secs(1), event_offset(2.0, 2.0)?);
+        assert_eq!(Duration::from_secs(0), event_offset(-1.0, 1.0)?);
+        Ok(())
+    }
+
+    #[test]
+    fn event_offset_overflow() {
+        assert!(event_offset(1e300, 1.0).is_err());
+        assert!(event_offset(1.0, 1e-300).is_err());
+    }
+}
@@ -1,10 +1,13 @@
 use self::{
     autocomplete_close_tab::MainAutocompleteCloseTabsService,
-    autocomplete_tab::MainAutocompleteTabsService, check_workspace::MainCheckWorkspaceService,
-    close_tabs::MainCloseTabsService, disconnect_tabs::MainDisconnectTabsService,
-    global_shutdown::MainGlobalShutdownService, list_tabs::MainListTabsService,
-    select_interactive::MainSelectInteractiveService,
-    select_previous::MainSelectPreviousTabService, select_tab::MainSelectTabService,
+    autocomplete_tab::MainAutocompleteTabsService, capture_tab::MainCaptureTabService,
+    check_workspace::MainCheckWorkspaceService, close_tabs::MainCloseTabsService,
+    disconnect_tabs::MainDisconnectTabsService, global_shutdown::MainGlobalShutdownService,
+    grep_tabs::MainGrepTabsService, list_tabs::MainListTabsService,
+    record_tab::MainRecordTabService, select_interactive::MainSelectInteractiveService,
+    select_panes::MainSelectPanesService, select_previous::MainSelectPreviousTabService,
+    select_tab::MainSelectTabService, send_tab::MainSendTabService,
+    upgrade_daemon::MainUpgradeDaemonService, watch_tab::MainWatchTabService,
 };
 
 use super::{
@@ -17,7 +20,7 @@ use crate::prelude::*;
 
 use lifeline::dyn_bus::DynBus;
 
-use tab_api::tab::TabId;
+use tab_api::{client::SubscriptionMode, tab::TabId};
 use tab_websocket::{
     bus::{WebsocketCarrier, WebsocketConnectionBus},
     resource::connection::WebsocketResource,
@@ -25,27 +28,41 @@ use tab_websocket::{
 
 mod autocomplete_close_tab;
 mod autocomplete_tab;
+mod capture_tab;
 mod check_workspace;
 mod close_tabs;
 mod disconnect_tabs;
 mod global_shutdown;
+mod grep_tabs;
 mod list_tabs;
+mod record_tab;
 mod select_interactive;
+mod select_panes;
 mod select_previous;
 mod select_tab;
+mod send_tab;
+mod upgrade_daemon;
+mod watch_tab;
 
 /// Launches the tab-command client, including websocket, tab state, and terminal services.
 pub struct MainService {
     _main_autocomplete: MainAutocompleteTabsService,
     _main_autocomplete_close: MainAutocompleteCloseTabsService,
+    _main_capture_tab: MainCaptureTabService,
     _main_close_tabs: MainCloseTabsService,
     _main_check_workspace: MainCheckWorkspaceService,
     _main_disconnect_tabs: MainDisconnectTabsService,
     _main_global_shutdown: MainGlobalShutdownService,
+    _main_grep_tabs: MainGrepTabsService,
     _main_list_tabs: MainListTabsService,
+    _main_record_tab: MainRecordTabService,
     _main_select_interactive: MainSelectInteractiveService,
+    _main_select_panes: MainSelectPanesService,
     _main_select_previous_tab: MainSelectPreviousTabService,
     _main_select_tab: MainSelectTabService,
+    _main_send_tab: MainSendTabService,
+    _main_upgrade_daemon: MainUpgradeDaemonService,
+    _main_watch_tab: MainWatchTabService,
     _main_tab: MainTabCarrier,
     _main_websocket: WebsocketCarrier,
     _select_tab: SelectTabService,
@@ -63,16 +80,25 @@ impl Service for MainService {
     fn spawn(main_bus: &MainBus) -> anyhow::Result<Self> {
         let _main_autocomplete = MainAutocompleteTabsService::spawn(main_bus)?;
         let _main_autocomplete_close = MainAutocompleteCloseTabsService::spawn(main_bus)?;
+        let _main_capture_tab = MainCaptureTabService::spawn(main_bus)?;
         let _main_check_workspace = MainCheckWorkspaceService::spawn(main_bus)?;
         let _main_close_tabs = MainCloseTabsService::spawn(main_bus)?;
         let _main_disconnect_tabs = MainDisconnectTabsService::spawn(main_bus)?;
         let _main_global_shutdown = MainGlobalShutdownService::spawn(main_bus)?;
+        let _main_grep_tabs = MainGrepTabsService::spawn(main_bus)?;
         let _main_list_tabs = MainListTabsService::spawn(main_bus)?;
+        let _main_record_tab = MainRecordTabService::spawn(main_bus)?;
         let _main_select_interactive = MainSelectInteractiveService::spawn(main_bus)?;
+        let _main_select_panes = MainSelectPanesService::spawn(main_bus)?;
         let _main_select_tab = MainSelectTabService::spawn(main_bus)?;
         let _main_select_previous_tab = MainSelectPreviousTabService::spawn(main_bus)?;
+        let _main_send_tab = MainSendTabService::spawn(main_bus)?;
+        let _main_upgrade_daemon = MainUpgradeDaemonService::spawn(main_bus)?;
+        let _main_watch_tab = MainWatchTabService::spawn(main_bus)?;
 
         let tab_bus = TabBus::default();
+        let mode = main_bus.resource::<SubscriptionMode>()?;
+        tab_bus.store_resource(mode);
 
         let _main_tab = tab_bus.carry_from(main_bus)?;
 
@@ -91,14 +117,21 @@ impl Service for MainService {
         Ok(Self {
             _main_autocomplete,
             _main_autocomplete_close,
+            _main_capture_tab,
             _main_close_tabs,
             _main_check_workspace,
             _main_disconnect_tabs,
             _main_global_shutdown,
+            _main_grep_tabs,
             _main_list_tabs,
+            _main_record_tab,
             _main_select_interactive,
+            _main_select_panes,
             _main_select_previous_tab,
             _main_select_tab,
+            _main_send_tab,
+            _main_upgrade_daemon,
+            _main_watch_tab,
             _main_tab,
             _main_websocket,
             _select_tab,
@@ -0,0 +1,212 @@
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use tab_api::{client::SubscriptionMode, tab::normalize_name, tab::TabId};
+use tokio::time;
+
+use crate::{
+    message::main::CaptureOptions,
+    message::main::MainRecv,
+    message::main::MainShutdown,
+    prelude::*,
+    state::tabs::ActiveTabsState,
+    utils::{await_state, subscribe_responses},
+};
+
+/// Prints the scrollback of a running tab to stdout, without attaching the terminal.
+/// With `--follow`, new output is printed until the tab terminates.
+pub struct MainCaptureTabService {
+    _run: Lifeline,
+}
+
+impl Service for MainCaptureTabService {
+    type Bus = MainBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let mut rx = bus.rx::<MainRecv>()?;
+        let mut rx_active = bus.rx::<Option<ActiveTabsState>>()?;
+
+        let mut tx_request = bus.tx::<Request>()?;
+        let mut tx_shutdown = bus.tx::<MainShutdown>()?;
+        let tx_response = bus.tx::<Response>()?;
+
+        let _run = Self::try_task("run", async move {
+            while let Some(msg) = rx.recv().await {
+                if let MainRecv::CaptureTab(options) = msg {
+                    let mut rx_response = subscribe_responses(&tx_response);
+                    let name = normalize_name(options.name.as_str());
+                    let state = await_state(&mut rx_active).await?;
+
+                    let id = match state.find_name(name.as_str()) {
+                        Some(tab) => tab.id,
+                        None => {
+                            eprintln!("Tab not running: {}", name);
+                            tx_shutdown.send(MainShutdown(1)).await?;
+                            break;
+                        }
+                    };
+
+                    info!("capturing tab: {}", name);
+                    tx_request
+                        .send(Request::Subscribe(id, SubscriptionMode::Capture))
+                        .await?;
+
+                    let mut scrollback = Vec::new();
+                    while let Some(response) = rx_response.recv().await {
+                        match response {
+                            Response::Output(tab, chunk) if tab == id => {
+                                scrollback.extend_from_slice(chunk.data.as_slice())
+                            }
+                            Response::ScrollbackEnd(tab) if tab == id => break,
+                            _ => {}
+                        }
+                    }
+
+                    {
+                        let stdout = io::stdout();
+                        let mut stdout = stdout.lock();
+                        stdout.write_all(capture(scrollback, &options)?.as_slice())?;
+                        stdout.flush()?;
+                    }
+
+                    if options.follow {
+                        Self::follow(id, &options, &mut rx_response).await?;
+                    } else {
+                        tx_request.send(Request::Unsubscribe(id)).await?;
+                    }
+
+                    time::sleep(Duration::from_millis(5)).await;
+                    tx_shutdown.send(MainShutdown(0)).await?;
+                    break;
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _run })
+    }
+}
+
+impl MainCaptureTabService {
+    /// Prints live output to stdout, until the connection closes.
+    /// When the tab terminates, the MainTabCarrier shuts down the command.
+    async fn follow(
+        id: TabId,
+        options: &CaptureOptions,
+        rx_response: &mut (impl Stream<Item = Response> + Unpin),
+    ) -> anyhow::Result<()> {
+        let mut stdout: Box<dyn Write + Send> = if options.strip_ansi {
+            Box::new(strip_ansi_escapes::Writer::new(io::stdout()))
+        } else {
+            Box::new(io::stdout())
+        };
+
+        while let Some(response) = rx_response.recv().await {
+            if let Response::Output(tab, chunk) = response {
+                if tab == id {
+                    stdout.write_all(chunk.data.as_slice())?;
+                    stdout.flush()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Applies the capture options to the scrollback, stripping escape sequences and truncating to the last lines.
+fn capture(scrollback: Vec<u8>, options: &CaptureOptions) -> io::Result<Vec<u8>> {
+    let mut data = if options.strip_ansi {
+        strip_ansi_escapes::strip(scrollback)?
+    } else {
+        scrollback
+    };
+
+    if let Some(lines) = options.lines {
+        let start = tail_start(data.as_slice(), lines);
+        data.drain(..start);
+    }
+
+    Ok(data)
+}
+
+/// Finds the start index of the last `lines` lines in the data.
+/// A trailing newline does not begin a new line, but an unterminated final line (e.g. a shell prompt) is counted.
+fn tail_start(data: &[u8], lines: usize) -> usize {
+    if lines == 0 {
+        return data.len();
+    }
+
+    let end = match data.last() {
+        Some(b'\n') => data.len() - 1,
+        _ => data.len(),
+    };
+
+    data[..end]
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, byte)| **byte == b'\n')
+        .nth(lines - 1)
+        .map(|(index, _)| index + 1)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{capture, tail_start};
+    use crate::message::main::CaptureOptions;
+
+    fn options(lines: Option<usize>, strip_ansi: bool) -> CaptureOptions {
+        CaptureOptions {
+            name: "tab/".into(),
+            lines,
+            strip_ansi,
+            follow: false,
+        }
+    }
+
+    #[test]
+    fn tail_start_lines() {
+        assert_eq!(2, tail_start(b"a\nb\nc\n", 2));
+        assert_eq!(0, tail_start(b"a\nb\nc\n", 3));
+        assert_eq!(0, tail_start(b"a\nb\nc\n", 5));
+    }
+
+    #[test]
+    fn tail_start_unterminated() {
+        assert_eq!(4, tail_start(b"a\nb\n$ ", 1));
+        assert_eq!(2, tail_start(b"a\nb\n$ ", 2));
+    }
+
+    #[test]
+    fn tail_start_zero() {
+        assert_eq!(4, tail_start(b"a\nb\n", 0));
+    }
+
+    #[test]
+    fn capture_strip_ansi() -> anyhow::Result<()> {
+        let scrollback = b"\x1b[32mgreen\x1b[0m\r\n$ ".to_vec();
+        assert_eq!(
+            b"green\n$ ".to_vec(),
+            capture(scrollback, &options(None, true))?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn capture_lines() -> anyhow::Result<()> {
+        let scrollback = b"one\ntwo\nthree\n".to_vec();
+        assert_eq!(
+            b"two\nthree\n".to_vec(),
+            capture(scrollback, &options(Some(2), false))?
+        );
+
+        Ok(())
+    }
+}
@@ -71,6 +71,14 @@ impl MainCloseTabsService {
             let name = normalize_name(tab.as_str());
 
             if !running_tabs.contains(&name) {
+                if let Some(exited) = state.find_exited(name.as_str()) {
+                    eprintln!("Dismissing exited tab: {}", name);
+                    tx_websocket
+                        .send(Request::CloseTab(exited.metadata.id))
+                        .await?;
+                    continue;
+                }
+
                 eprintln!("Tab not running: {}", name);
                 continue;
             }
@@ -0,0 +1,106 @@
+use std::io::{self, Write};
+
+use tab_api::client::{GrepMatch, GrepQuery};
+
+use crate::{
+    message::main::MainRecv, message::main::MainShutdown, prelude::*, utils::subscribe_responses,
+};
+
+/// Searches the scrollback of all running tabs, and prints the matching lines as `tab/: line`.
+/// Exits with status 1 if no lines matched.
+pub struct MainGrepTabsService {
+    _run: Lifeline,
+}
+
+impl Service for MainGrepTabsService {
+    type Bus = MainBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let mut rx = bus.rx::<MainRecv>()?;
+
+        let mut tx_request = bus.tx::<Request>()?;
+        let mut tx_shutdown = bus.tx::<MainShutdown>()?;
+        let tx_response = bus.tx::<Response>()?;
+
+        let _run = Self::try_task("run", async move {
+            while let Some(msg) = rx.recv().await {
+                if let MainRecv::GrepTabs(pattern) = msg {
+                    let mut rx_response = subscribe_responses(&tx_response);
+                    info!("searching tabs for: {}", &pattern);
+
+                    let query = GrepQuery {
+                        pattern,
+                        limit: None,
+                    };
+                    tx_request.send(Request::Grep(query)).await?;
+
+                    let mut matches = Vec::new();
+                    while let Some(response) = rx_response.recv().await {
+                        if let Response::Grep(results) = response {
+                            matches = results;
+                            break;
+                        }
+                    }
+
+                    {
+                        let stdout = io::stdout();
+                        let mut stdout = stdout.lock();
+                        stdout.write_all(format_matches(&matches).as_bytes())?;
+                        stdout.flush()?;
+                    }
+
+                    let code = if matches.is_empty() { 1 } else { 0 };
+                    tx_shutdown.send(MainShutdown(code)).await?;
+                    break;
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _run })
+    }
+}
+
+fn format_matches(matches: &[GrepMatch]) -> String {
+    let mut output = String::new();
+
+    for grep_match in matches {
+        output += grep_match.name.as_str();
+        output += ": ";
+        output += grep_match.line.as_str();
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_matches;
+    use tab_api::{client::GrepMatch, tab::TabId};
+
+    fn grep_match(id: u16, name: &str, line: &str) -> GrepMatch {
+        GrepMatch {
+            id: TabId(id),
+            name: name.to_string(),
+            line: line.to_string(),
+        }
+    }
+
+    #[test]
+    fn format() {
+        let matches = vec![
+            grep_match(0, "a/", "error: one"),
+            grep_match(1, "b/", "error: two"),
+        ];
+
+        assert_eq!("a/: error: one\nb/: error: two\n", format_matches(&matches));
+    }
+
+    #[test]
+    fn format_empty() {
+        assert_eq!("", format_matches(&[]));
+    }
+}
@@ -1,10 +1,14 @@
 use crate::{
-    message::main::MainRecv, message::main::MainShutdown, prelude::*,
-    state::workspace::WorkspaceState, state::workspace::WorkspaceTab, utils::await_state,
+    message::main::ListFormat, message::main::MainRecv, message::main::MainShutdown, prelude::*,
+    state::tabs::ActiveTabsState, state::workspace::WorkspaceState, state::workspace::WorkspaceTab,
+    utils::await_state,
 };
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::io::stdout;
 use std::path::PathBuf;
 use std::{env, path::Path};
+use tab_api::tab::{TabAlert, TabExit, TabId, TabMetadata};
 
 use crossterm::{
     execute,
@@ -22,12 +26,13 @@ impl Service for MainListTabsService {
     fn spawn(bus: &Self::Bus) -> Self::Lifeline {
         let mut rx = bus.rx::<MainRecv>()?;
         let mut rx_workspace = bus.rx::<Option<WorkspaceState>>()?;
+        let mut rx_active = bus.rx::<Option<ActiveTabsState>>()?;
 
         let mut tx_shutdown = bus.tx::<MainShutdown>()?;
 
         let _run = Self::try_task("run", async move {
             while let Some(msg) = rx.recv().await {
-                if let MainRecv::ListTabs = msg {
+                if let MainRecv::ListTabs(format) = msg {
                     let workspace = await_state(&mut rx_workspace).await?;
 
                     if workspace.errors.is_empty() {
@@ -35,7 +40,22 @@ impl Service for MainListTabsService {
                         eprintln!();
                     }
 
-                    Self::echo_tabs(&workspace.tabs);
+                    match format {
+                        ListFormat::Text => Self::echo_tabs(&workspace.tabs),
+                        ListFormat::Json | ListFormat::Yaml => {
+                            let active = await_state(&mut rx_active).await?;
+                            let entries = Self::list_entries(&workspace.tabs, &active);
+
+                            let output = if format == ListFormat::Json {
+                                serde_json::to_string_pretty(&entries)?
+                            } else {
+                                serde_yaml::to_string(&entries)?
+                            };
+
+                            println!("{}", output);
+                        }
+                    }
+
                     tx_shutdown.send(MainShutdown(0)).await.ok();
                     break;
                 }
@@ -48,7 +68,108 @@ impl Service for MainListTabsService {
     }
 }
 
+/// A tab in the `tab --list --format [json|yaml]` output
+#[derive(Serialize, Debug, PartialEq)]
+struct ListEntry {
+    name: String,
+    doc: Option<String>,
+    directory: PathBuf,
+    shell: Option<String>,
+    /// Present if the tab is running
+    running: Option<RunningEntry>,
+    /// Present if the shell has exited, and the tab has not been dismissed
+    exited: Option<TabExit>,
+}
+
+/// The state of a running tab, in the `tab --list --format [json|yaml]` output
+#[derive(Serialize, Debug, PartialEq)]
+struct RunningEntry {
+    id: TabId,
+    dimensions: (u16, u16),
+    /// The time the tab was last selected, in milliseconds since the unix epoch
+    selected: u64,
+    attached: bool,
+    /// The alert raised by the tab monitor, if any
+    alert: Option<TabAlert>,
+}
+
+impl From<&TabMetadata> for RunningEntry {
+    fn from(metadata: &TabMetadata) -> Self {
+        Self {
+            id: metadata.id,
+            dimensions: metadata.dimensions,
+            selected: metadata.selected as u64,
+            attached: metadata.attached > 0,
+            alert: metadata.alert,
+        }
+    }
+}
+
 impl MainListTabsService {
+    /// Merges the workspace tabs with the running tabs, sorted by name
+    fn list_entries(workspace: &[WorkspaceTab], active: &ActiveTabsState) -> Vec<ListEntry> {
+        let mut entries = BTreeMap::new();
+
+        for tab in workspace {
+            let running = active.find_name(tab.name.as_str());
+            let entry = ListEntry {
+                name: tab.name.clone(),
+                doc: tab.doc.clone(),
+                directory: tab.directory.clone(),
+                shell: tab
+                    .shell
+                    .clone()
+                    .or_else(|| running.map(|metadata| metadata.shell.clone())),
+                running: running.map(RunningEntry::from),
+                exited: match running {
+                    Some(_) => None,
+                    None => active
+                        .find_exited(tab.name.as_str())
+                        .map(|exited| exited.exit),
+                },
+            };
+
+            entries.insert(tab.name.clone(), entry);
+        }
+
+        for metadata in active.tabs.values() {
+            if entries.contains_key(&metadata.name) {
+                continue;
+            }
+
+            let entry = ListEntry {
+                name: metadata.name.clone(),
+                doc: metadata.doc.clone(),
+                directory: PathBuf::from(&metadata.dir),
+                shell: Some(metadata.shell.clone()),
+                running: Some(RunningEntry::from(metadata)),
+                exited: None,
+            };
+
+            entries.insert(metadata.name.clone(), entry);
+        }
+
+        for exited in active.exited.values() {
+            let metadata = &exited.metadata;
+            if entries.contains_key(&metadata.name) {
+                continue;
+            }
+
+            let entry = ListEntry {
+                name: metadata.name.clone(),
+                doc: metadata.doc.clone(),
+                directory: PathBuf::from(&metadata.dir),
+                shell: Some(metadata.shell.clone()),
+                running: None,
+                exited: Some(exited.exit),
+            };
+
+            entries.insert(metadata.name.clone(), entry);
+        }
+
+        entries.into_values().collect()
+    }
+
     fn echo_tabs(tabs: &[WorkspaceTab]) {
         debug!("echo tabs: {:?}", &tabs);
 
@@ -75,8 +196,21 @@ impl MainListTabsService {
                 print!("{}", name);
             }
 
+            let mut len = name.len();
+            if let Some(alert) = tab.alert {
+                let alert = format!(" [{}]", alert);
+                len += alert.len();
+                color_active_tabs(alert.as_str(), Color::Yellow);
+            }
+
+            if let Some(exit) = tab.exit {
+                let exit = format!(" [{}]", exit);
+                len += exit.len();
+                color_active_tabs(exit.as_str(), Color::DarkGrey);
+            }
+
             if let Some(ref doc) = tab.doc {
-                for _ in name.len()..target_len {
+                for _ in len..target_len {
                     print!(" ");
                 }
                 println!("({})", doc);
@@ -102,3 +236,117 @@ fn get_working_tab() -> String {
         .unwrap_or_default()
         .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ListEntry, MainListTabsService, RunningEntry};
+    use crate::state::{tabs::ActiveTabsState, workspace::WorkspaceTab};
+    use std::{collections::HashMap, path::PathBuf};
+    use tab_api::tab::{ExitedTab, TabExit, TabId, TabMetadata};
+
+    fn metadata(id: u16, name: &str) -> TabMetadata {
+        TabMetadata {
+            id: TabId(id),
+            name: name.into(),
+            doc: Some("running doc".into()),
+            dimensions: (80, 24),
+            env: HashMap::new(),
+            shell: "bash".into(),
+            dir: "/running".into(),
+            command: None,
+            selected: 10,
+            attached: 1,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
+        }
+    }
+
+    #[test]
+    fn list_entries() {
+        let workspace = vec![
+            WorkspaceTab::builder()
+                .name("a/".into())
+                .doc("workspace doc".into())
+                .directory(PathBuf::from("/a"))
+                .build(),
+            WorkspaceTab::builder()
+                .name("b/".into())
+                .directory(PathBuf::from("/b"))
+                .shell("zsh".into())
+                .build(),
+        ];
+
+        let mut active = ActiveTabsState::default();
+        active.tabs.insert(TabId(0), metadata(0, "a/"));
+        active.tabs.insert(TabId(1), metadata(1, "c/"));
+        active.exited.insert(
+            TabId(2),
+            ExitedTab {
+                metadata: metadata(2, "b/"),
+                exit: TabExit::code(1),
+            },
+        );
+        active.exited.insert(
+            TabId(3),
+            ExitedTab {
+                metadata: metadata(3, "d/"),
+                exit: TabExit::code(0),
+            },
+        );
+
+        let entries = MainListTabsService::list_entries(&workspace, &active);
+
+        let running = |id| {
+            Some(RunningEntry {
+                id: TabId(id),
+                dimensions: (80, 24),
+                selected: 10,
+                attached: true,
+                alert: None,
+            })
+        };
+
+        assert_eq!(
+            vec![
+                ListEntry {
+                    name: "a/".into(),
+                    doc: Some("workspace doc".into()),
+                    directory: PathBuf::from("/a"),
+                    shell: Some("bash".into()),
+                    running: running(0),
+                    exited: None,
+                },
+                ListEntry {
+                    name: "b/".into(),
+                    doc: None,
+                    directory: PathBuf::from("/b"),
+                    shell: Some("zsh".into()),
+                    running: None,
+                    exited: Some(TabExit::code(1)),
+                },
+                ListEntry {
+                    name: "c/".into(),
+                    doc: Some("running doc".into()),
+                    directory: PathBuf::from("/running"),
+                    shell: Some("bash".into()),
+                    running: running(1),
+                    exited: None,
+                },
+                ListEntry {
+                    name: "d/".into(),
+                    doc: Some("running doc".into()),
+                    directory: PathBuf::from("/running"),
+                    shell: Some("bash".into()),
+                    running: None,
+                    exited: Some(TabExit::code(0)),
+                },
+            ],
+            entries
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use tab_api::{config::recordings_path, tab::normalize_name};
+use tokio::time;
+
+use crate::{
+    message::main::MainRecv,
+    message::main::MainShutdown,
+    message::tabs::TabRecv,
+    prelude::*,
+    state::tabs::ActiveTabsState,
+    utils::{find_or_create_tab, subscribe_responses},
+};
+
+/// Starts recording the output of a tab to an asciicast file, and exits.
+/// If the tab is not running, it is created from the workspace configuration.
+pub struct MainRecordTabService {
+    _run: Lifeline,
+}
+
+impl Service for MainRecordTabService {
+    type Bus = MainBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let mut rx = bus.rx::<MainRecv>()?;
+        let mut rx_active = bus.rx::<Option<ActiveTabsState>>()?;
+
+        let mut tx_tab = bus.tx::<TabRecv>()?;
+        let mut tx_request = bus.tx::<Request>()?;
+        let mut tx_shutdown = bus.tx::<MainShutdown>()?;
+        let tx_response = bus.tx::<Response>()?;
+
+        let _run = Self::try_task("run", async move {
+            while let Some(msg) = rx.recv().await {
+                if let MainRecv::RecordTab(name) = msg {
+                    let mut rx_response = subscribe_responses(&tx_response);
+                    let name = normalize_name(name.as_str());
+
+                    let id = find_or_create_tab(
+                        name.as_str(),
+                        &mut rx_active,
+                        &mut tx_tab,
+                        &mut rx_response,
+                        &mut tx_request,
+                    )
+                    .await?;
+
+                    info!("recording tab {}", name);
+                    tx_request.send(Request::RecordTab(id)).await?;
+
+                    if let Ok(path) = recordings_path() {
+                        println!("Recording tab {} to {}", name, path.to_string_lossy());
+                    }
+
+                    time::sleep(Duration::from_millis(25)).await;
+                    tx_shutdown.send(MainShutdown(0)).await?;
+                    break;
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _run })
+    }
+}
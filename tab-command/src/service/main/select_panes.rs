@@ -0,0 +1,42 @@
+use crate::{
+    message::main::MainRecv, message::main::MainShutdown, message::tabs::TabRecv, prelude::*,
+};
+
+use super::env_tab_id;
+
+/// Opens several tabs side by side, in panes.  Tabs are created if they are not running.
+/// Panes are rendered by the terminal, so they can't be opened from within an active session.
+pub struct MainSelectPanesService {
+    _run: Lifeline,
+}
+
+impl Service for MainSelectPanesService {
+    type Bus = MainBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let mut rx = bus.rx::<MainRecv>()?;
+
+        let mut tx_tab = bus.tx::<TabRecv>()?;
+        let mut tx_shutdown = bus.tx::<MainShutdown>()?;
+
+        let _run = Self::try_task("run", async move {
+            while let Some(msg) = rx.recv().await {
+                if let MainRecv::SelectPanes(layout) = msg {
+                    if env_tab_id().is_some() {
+                        eprintln!("Panes can't be opened from within a tab.  Disconnect from the tab, and run the command again.");
+                        tx_shutdown.send(MainShutdown(1)).await?;
+                        break;
+                    }
+
+                    info!("selecting panes: {:?}", layout);
+                    tx_tab.send(TabRecv::SelectNamedPanes(layout)).await?;
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _run })
+    }
+}
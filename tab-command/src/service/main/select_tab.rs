@@ -1,10 +1,13 @@
 use tab_api::tab::normalize_name;
 
 use crate::message::tabs::TabRecv;
+use crate::state::workspace::WorkspaceState;
+use crate::utils::await_state;
 use crate::{message::main::MainRecv, prelude::*};
 
 use super::env_tab_id;
 
+/// Selects a tab by name.  If the name refers to a layout in the workspace, the layout's panes are opened.
 pub struct MainSelectTabService {
     _run: Lifeline,
 }
@@ -15,13 +18,16 @@ impl Service for MainSelectTabService {
 
     fn spawn(bus: &Self::Bus) -> Self::Lifeline {
         let mut rx = bus.rx::<MainRecv>()?;
+        let mut rx_workspace = bus.rx::<Option<WorkspaceState>>()?;
 
         let mut tx_tab = bus.tx::<TabRecv>()?;
+        let mut tx_main = bus.tx::<MainRecv>()?;
 
         let _run = Self::try_task("run", async move {
             while let Some(recv) = rx.recv().await {
                 if let MainRecv::SelectTab(tab) = recv {
-                    Self::select_tab(tab, &mut tx_tab).await?;
+                    let workspace = await_state(&mut rx_workspace).await?;
+                    Self::select_tab(tab, &workspace, &mut tx_tab, &mut tx_main).await?;
                 }
             }
 
@@ -35,10 +41,25 @@ impl Service for MainSelectTabService {
 impl MainSelectTabService {
     async fn select_tab(
         name: String,
+        workspace: &WorkspaceState,
         mut tx_tab: impl Sink<Item = TabRecv> + Unpin,
+        mut tx_main: impl Sink<Item = MainRecv> + Unpin,
     ) -> anyhow::Result<()> {
         info!("MainRecv::SelectTab({}) running", &name);
         let name = normalize_name(name.as_str());
+
+        let layout = workspace
+            .tabs
+            .iter()
+            .find(|tab| tab.name == name)
+            .and_then(|tab| tab.layout.clone());
+
+        if let Some(layout) = layout {
+            info!("selecting layout: {}", name);
+            tx_main.send(MainRecv::SelectPanes(layout)).await?;
+            return Ok(());
+        }
+
         let env_tab = env_tab_id();
 
         info!("selecting tab: {}", name);
@@ -0,0 +1,142 @@
+use tab_api::{chunk::InputChunk, tab::normalize_name};
+use tokio::io::AsyncReadExt;
+
+use crate::{
+    message::main::MainRecv,
+    message::main::MainShutdown,
+    message::tabs::TabRecv,
+    prelude::*,
+    state::tabs::ActiveTabsState,
+    utils::{await_flushed, find_or_create_tab, subscribe_responses},
+};
+
+/// Sends input to a tab without attaching the terminal, and exits.
+/// If the tab is not running, it is created from the workspace configuration.
+pub struct MainSendTabService {
+    _run: Lifeline,
+}
+
+impl Service for MainSendTabService {
+    type Bus = MainBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let mut rx = bus.rx::<MainRecv>()?;
+        let mut rx_active = bus.rx::<Option<ActiveTabsState>>()?;
+
+        let mut tx_tab = bus.tx::<TabRecv>()?;
+        let mut tx_request = bus.tx::<Request>()?;
+        let mut tx_shutdown = bus.tx::<MainShutdown>()?;
+        let tx_response = bus.tx::<Response>()?;
+
+        let _run = Self::try_task("run", async move {
+            while let Some(msg) = rx.recv().await {
+                if let MainRecv::SendTab(name, text) = msg {
+                    let mut rx_response = subscribe_responses(&tx_response);
+                    let name = normalize_name(name.as_str());
+                    let data = match text {
+                        Some(text) => unescape(text.as_str()),
+                        None => {
+                            let mut data = Vec::new();
+                            tokio::io::stdin().read_to_end(&mut data).await?;
+                            data
+                        }
+                    };
+
+                    let id = find_or_create_tab(
+                        name.as_str(),
+                        &mut rx_active,
+                        &mut tx_tab,
+                        &mut rx_response,
+                        &mut tx_request,
+                    )
+                    .await?;
+
+                    info!("sending {} bytes to tab {}", data.len(), name);
+                    let request = Request::Input(id, InputChunk { data });
+                    tx_request.send(request).await?;
+
+                    // the input is lost if the connection closes before the daemon receives it
+                    await_flushed(&mut rx_response, &mut tx_request).await?;
+                    tx_shutdown.send(MainShutdown(0)).await?;
+                    break;
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _run })
+    }
+}
+
+/// Interprets the backslash escapes in the text, so `tab --send` can be given special keys.
+/// Supports `\n`, `\r`, `\t`, `\e`, `\0`, `\\`, and `\xHH`.  Unknown escapes are sent as-is.
+fn unescape(text: &str) -> Vec<u8> {
+    let mut data = Vec::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            data.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        match chars.peek().copied() {
+            Some('n') => data.push(b'\n'),
+            Some('r') => data.push(b'\r'),
+            Some('t') => data.push(b'\t'),
+            Some('e') => data.push(0x1b),
+            Some('0') => data.push(0),
+            Some('\\') => data.push(b'\\'),
+            Some('x') => {
+                let hex: String = chars.clone().skip(1).take(2).collect();
+                if hex.len() != 2 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                    data.push(b'\\');
+                    continue;
+                }
+
+                data.push(u8::from_str_radix(hex.as_str(), 16).unwrap());
+                chars.next();
+                chars.next();
+            }
+            _ => {
+                data.push(b'\\');
+                continue;
+            }
+        }
+
+        chars.next();
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unescape;
+
+    #[test]
+    fn unescape_plain() {
+        assert_eq!(b"cargo test".to_vec(), unescape("cargo test"));
+    }
+
+    #[test]
+    fn unescape_escapes() {
+        assert_eq!(
+            b"a\nb\r\t\x1b\0\\".to_vec(),
+            unescape("a\\nb\\r\\t\\e\\0\\\\")
+        );
+    }
+
+    #[test]
+    fn unescape_hex() {
+        assert_eq!(vec![0x03, b'c'], unescape("\\x03c"));
+    }
+
+    #[test]
+    fn unescape_unknown() {
+        assert_eq!(b"\\q\\x0z\\".to_vec(), unescape("\\q\\x0z\\"));
+    }
+}
@@ -0,0 +1,79 @@
+use std::time::{Duration, Instant};
+
+use anyhow::bail;
+use tab_api::{
+    config::{is_running, DaemonConfig},
+    launch::launch_daemon,
+};
+use tokio::time;
+
+use crate::{message::main::MainRecv, message::main::MainShutdown, prelude::*};
+
+/// How long to wait for the previous daemon to hand off the running tabs.
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Relaunches the daemon with the current executable.
+/// The running tabs are handed off to the new daemon, and the pty sessions reconnect to it.
+pub struct MainUpgradeDaemonService {
+    _run: Lifeline,
+}
+
+impl Service for MainUpgradeDaemonService {
+    type Bus = MainBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let mut rx = bus.rx::<MainRecv>()?;
+        let daemon = bus.resource::<DaemonConfig>()?;
+
+        let mut tx = bus.tx::<Request>()?;
+        let mut tx_shutdown = bus.tx::<MainShutdown>()?;
+
+        let _run = Self::try_task("run", async move {
+            while let Some(msg) = rx.recv().await {
+                if let MainRecv::UpgradeDaemon = msg {
+                    tx.send(Request::UpgradeDaemon).await?;
+
+                    let code = match Self::relaunch(&daemon).await {
+                        Ok(next) => {
+                            println!(
+                                "Upgraded the tab daemon (pid {} -> {})",
+                                daemon.pid, next.pid
+                            );
+                            0
+                        }
+                        Err(e) => {
+                            eprintln!("Failed to upgrade the tab daemon: {}", e);
+                            1
+                        }
+                    };
+
+                    tx_shutdown.send(MainShutdown(code)).await?;
+                    break;
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _run })
+    }
+}
+
+impl MainUpgradeDaemonService {
+    /// Waits for the previous daemon to exit, and launches the next daemon.
+    async fn relaunch(previous: &DaemonConfig) -> anyhow::Result<DaemonConfig> {
+        let start = Instant::now();
+
+        while is_running(previous) {
+            if Instant::now().duration_since(start) > HANDOFF_TIMEOUT {
+                bail!("timeout while waiting for the daemon to hand off the tabs");
+            }
+
+            time::sleep(Duration::from_millis(50)).await;
+        }
+
+        info!("previous daemon has exited, launching the next daemon");
+        launch_daemon().await
+    }
+}
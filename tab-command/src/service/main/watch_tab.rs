@@ -0,0 +1,51 @@
+use tab_api::tab::normalize_name;
+
+use crate::{
+    message::main::MainRecv, message::main::MainShutdown, message::tabs::TabRecv, prelude::*,
+    state::tabs::ActiveTabsState, utils::await_state,
+};
+
+/// Attaches to a running tab in read-only mode.
+/// Unlike `tab <name>`, the tab is never created, and the client is never retasked from within an active session.
+pub struct MainWatchTabService {
+    _run: Lifeline,
+}
+
+impl Service for MainWatchTabService {
+    type Bus = MainBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let mut rx = bus.rx::<MainRecv>()?;
+        let mut rx_active = bus.rx::<Option<ActiveTabsState>>()?;
+
+        let mut tx_tab = bus.tx::<TabRecv>()?;
+        let mut tx_shutdown = bus.tx::<MainShutdown>()?;
+
+        let _run = Self::try_task("run", async move {
+            while let Some(msg) = rx.recv().await {
+                if let MainRecv::WatchTab(name) = msg {
+                    let name = normalize_name(name.as_str());
+                    let state = await_state(&mut rx_active).await?;
+
+                    if state.find_name(name.as_str()).is_none() {
+                        eprintln!("Tab not running: {}", name);
+                        tx_shutdown.send(MainShutdown(1)).await?;
+                        break;
+                    }
+
+                    info!("watching tab: {}", name);
+                    let message = TabRecv::SelectNamedTab {
+                        name,
+                        env_tab: None,
+                    };
+                    tx_tab.send(message).await?;
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _run })
+    }
+}
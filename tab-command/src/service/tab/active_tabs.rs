@@ -20,10 +20,11 @@ impl Service for ActiveTabsService {
             while let Some(recv) = rx.recv().await {
                 info!("{:?}", recv);
                 match recv {
-                    TabsRecv::Init(tabs) => {
+                    TabsRecv::Init(tabs, exited) => {
                         let mut state = tx.borrow_mut();
                         let state = state_or_default(&mut *state);
                         state.tabs.extend(tabs.into_iter());
+                        state.exited.extend(exited.into_iter());
                     }
                     TabsRecv::Update(metadata) => {
                         let mut state = tx.borrow_mut();
@@ -1,5 +1,5 @@
 use crate::{
-    env::terminal_size,
+    env::tab_size,
     message::tabs::CreateTabRequest,
     prelude::*,
     state::{
@@ -64,7 +64,7 @@ impl CreateTabService {
         let name = normalize_name(name.as_str());
         let workspace_tab = workspace.iter().find(|tab| tab.name == name);
 
-        let dimensions = terminal_size()?;
+        let dimensions = tab_size()?;
         let shell = Self::compute_shell(workspace_tab);
         let directory = Self::compute_directory(workspace_tab)?;
         let env = Self::compute_env(workspace_tab);
@@ -84,6 +84,15 @@ impl CreateTabService {
             env,
             dimensions,
             shell,
+            command: workspace_tab.and_then(|tab| tab.command.clone()),
+            scrollback: workspace_tab.and_then(|tab| tab.scrollback),
+            record: workspace_tab.and_then(|tab| tab.record).unwrap_or(false),
+            remain_on_exit: workspace_tab
+                .and_then(|tab| tab.remain_on_exit)
+                .unwrap_or(false),
+            log: workspace_tab.and_then(|tab| tab.log).unwrap_or(false),
+            monitor: workspace_tab.and_then(|tab| tab.monitor),
+            notify: workspace_tab.and_then(|tab| tab.notify.clone()),
         };
 
         let request = Request::CreateTab(metadata);
@@ -7,12 +7,13 @@ use tokio::time;
 
 use crate::{
     message::tabs::CreateTabRequest, message::tabs::TabShutdown, prelude::*,
-    state::tab::SelectOrRetaskTab, state::tab::SelectTab, state::tabs::ActiveTabsState,
-    utils::await_condition,
+    state::tab::SelectNamedPanes, state::tab::SelectOrRetaskTab, state::tab::SelectTab,
+    state::tabs::ActiveTabsState, utils::await_condition,
 };
 
 pub struct SelectTabService {
     _run: Lifeline,
+    _panes: Lifeline,
 }
 
 impl Service for SelectTabService {
@@ -45,7 +46,44 @@ impl Service for SelectTabService {
             Ok(())
         });
 
-        Ok(Self { _run })
+        let _panes = {
+            let mut rx = bus.rx::<SelectNamedPanes>()?;
+            let mut rx_tabs_state = bus.rx::<Option<ActiveTabsState>>()?;
+
+            let mut tx_create = bus.tx::<CreateTabRequest>()?;
+            let mut tx_select = bus.tx::<SelectTab>()?;
+
+            Self::try_task("panes", async move {
+                while let Some(SelectNamedPanes(layout)) = rx.recv().await {
+                    for name in layout.tabs() {
+                        tx_create
+                            .send(CreateTabRequest::Named(name.clone()))
+                            .await?;
+                    }
+
+                    debug!(
+                        "select panes - waiting for creation of tabs {:?}",
+                        layout.tabs()
+                    );
+                    let state = await_condition(&mut rx_tabs_state, |state| {
+                        layout.tabs().iter().all(|name| state.contains_name(name))
+                    })
+                    .await?;
+
+                    let layout = layout
+                        .try_map(&|name| state.find_name(name).map(|metadata| metadata.id))
+                        .ok_or_else(|| {
+                            anyhow::format_err!("failed to resolve the tabs of the panes")
+                        })?;
+
+                    tx_select.send(SelectTab::Panes(layout)).await?;
+                }
+
+                Ok(())
+            })
+        };
+
+        Ok(Self { _run, _panes })
     }
 }
 
@@ -1,4 +1,8 @@
-use crate::{env::terminal_size, prelude::*, state::tab::TabMetadataState};
+use std::mem::discriminant;
+
+use tab_api::client::SubscriptionMode;
+
+use crate::{env::tab_size, prelude::*, state::tab::TabMetadataState};
 use crate::{
     state::{
         tab::{DeselectTab, SelectTab, TabState},
@@ -50,6 +54,14 @@ impl Service for TabStateService {
                             debug!("selected tab: {}", id);
                             tx.send(TabState::Selected(id)).await?;
                         }
+                        SelectTab::Panes(layout) => {
+                            if state == TabState::Panes(layout.clone()) {
+                                continue;
+                            }
+
+                            debug!("selected panes: {:?}", layout);
+                            tx.send(TabState::Panes(layout)).await?;
+                        }
                     }
                 }
 
@@ -95,7 +107,7 @@ impl Service for TabStateService {
                         let tab = state.get(&id).unwrap();
                         tx.send(TabMetadataState::Selected(tab.clone())).await?;
                         debug!("await resolved tab metadata: {:?}", tab);
-                    } else if let TabState::None = state {
+                    } else if let TabState::None | TabState::Panes(_) = state {
                         tx.send(TabMetadataState::None).await?;
                     }
                 }
@@ -131,27 +143,41 @@ impl Service for TabStateService {
 
         let _websocket = {
             let mut rx = bus.rx::<TabState>()?;
+            let mode = bus.resource::<SubscriptionMode>()?;
 
             let mut tx_websocket = bus.tx::<Request>()?;
 
             Self::try_task("websocket", async move {
                 let mut last_state = TabState::None;
                 while let Some(state) = rx.recv().await {
+                    // the terminal is reset when switching between a tab and panes,
+                    // so tabs are only kept subscribed within the same kind of state.
+                    let same_kind = discriminant(&last_state) == discriminant(&state);
+                    let previous = last_state.subscribed();
+                    let subscribed = state.subscribed();
+
+                    for prev_id in previous.iter().copied() {
+                        if !same_kind || !subscribed.contains(&prev_id) {
+                            debug!("unsubscribing from previous tab {}", prev_id);
+                            tx_websocket.send(Request::Unsubscribe(prev_id)).await?;
+                        }
+                    }
+
                     if let TabState::Selected(id) = state {
-                        tx_websocket.send(Request::Subscribe(id)).await?;
-
-                        let terminal_size = terminal_size()?;
-                        tx_websocket
-                            .send(Request::ResizeTab(id, terminal_size))
-                            .await?;
-                    } else if let (TabState::Selected(prev_id), &TabState::None) =
-                        (last_state, &state)
-                    {
-                        debug!(
-                            "new state is none, unsubscribing from previous tab {}",
-                            prev_id
-                        );
-                        tx_websocket.send(Request::Unsubscribe(prev_id)).await?;
+                        tx_websocket.send(Request::Subscribe(id, mode)).await?;
+
+                        // read-only clients can't resize the tab
+                        if mode == SubscriptionMode::Interactive {
+                            let tab_size = tab_size()?;
+                            tx_websocket.send(Request::ResizeTab(id, tab_size)).await?;
+                        }
+                    } else if let TabState::Panes(_) = state {
+                        // panes are resized by the terminal, which knows the size of each pane
+                        for id in subscribed {
+                            if !same_kind || !previous.contains(&id) {
+                                tx_websocket.send(Request::Subscribe(id, mode)).await?;
+                            }
+                        }
                     }
 
                     last_state = state;
@@ -15,7 +15,7 @@ use crate::{
     state::{tab::TabMetadataState, workspace::WorkspaceTab},
 };
 use lifeline::Service;
-use tab_api::tab::TabMetadata;
+use tab_api::tab::{ExitedTab, TabMetadata};
 
 use self::loader::{scan_config, WorkspaceTabs};
 
@@ -28,6 +28,7 @@ pub struct WorkspaceService {
     _scan: Lifeline,
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 enum Event {
     ScanWorkspace,
@@ -146,6 +147,12 @@ impl WorkspaceService {
         // let workspace_tabs = scan.as_name_set();
         let mut tabs = Vec::with_capacity(scan.len() + active_tabs.tabs.len());
 
+        let mut exited_tabs: HashMap<String, &ExitedTab> = active_tabs
+            .exited
+            .values()
+            .map(|tab| (tab.metadata.name.clone(), tab))
+            .collect();
+
         let mut active_tabs: HashMap<String, &TabMetadata> = active_tabs
             .tabs
             .values()
@@ -161,28 +168,34 @@ impl WorkspaceService {
         let keys: BTreeSet<String> = active_tabs
             .keys()
             .chain(workspace_tabs.keys())
+            .chain(exited_tabs.keys())
             .map(String::clone)
             .collect();
 
         for tab in keys {
             let active_tab = active_tabs.remove(&tab);
             let workspace_tab = workspace_tabs.remove(&tab);
+            let exited_tab = exited_tabs.remove(&tab);
 
             let tab = match (active_tab, workspace_tab) {
                 (Some(active), Some(mut workspace)) => {
                     workspace.last_selected = Some(active.selected);
+                    workspace.alert = active.alert;
+                    workspace
+                }
+                (Some(metadata), None) => Self::metadata_tab(metadata),
+                (None, Some(mut workspace)) => {
+                    workspace.exit = exited_tab.map(|exited| exited.exit);
                     workspace
                 }
-                (Some(metadata), None) => WorkspaceTab {
-                    name: metadata.name.clone(),
-                    doc: metadata.doc.clone(),
-                    directory: PathBuf::from(&metadata.dir),
-                    shell: None,
-                    env: None,
-                    last_selected: Some(metadata.selected),
+                (None, None) => match exited_tab {
+                    Some(exited) => WorkspaceTab {
+                        alert: None,
+                        exit: Some(exited.exit),
+                        ..Self::metadata_tab(&exited.metadata)
+                    },
+                    None => continue,
                 },
-                (None, Some(workspace)) => workspace,
-                (None, None) => continue,
             };
 
             tabs.push(tab)
@@ -190,11 +203,36 @@ impl WorkspaceService {
 
         tabs
     }
+
+    /// Describes a tab which is not in the workspace configuration
+    fn metadata_tab(metadata: &TabMetadata) -> WorkspaceTab {
+        WorkspaceTab {
+            name: metadata.name.clone(),
+            doc: metadata.doc.clone(),
+            directory: PathBuf::from(&metadata.dir),
+            shell: None,
+            env: None,
+            command: None,
+            scrollback: None,
+            record: None,
+            remain_on_exit: None,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: metadata.alert,
+            exit: None,
+            last_selected: Some(metadata.selected),
+            layout: None,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::state::workspace::WorkspaceTab;
+    use crate::state::{
+        pane::{PaneLayout, SplitDirection},
+        workspace::WorkspaceTab,
+    };
     use anyhow::bail;
     use pretty_assertions::assert_eq;
     use std::{collections::HashMap, path::PathBuf};
@@ -457,6 +495,117 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn command_test() -> anyhow::Result<()> {
+        let (dir, tabs) = load("command")?;
+
+        let expected = vec![
+            WorkspaceTab::builder()
+                .name("command/".into())
+                .doc(doc!("workspace tab for command"))
+                .directory(dir!(dir))
+                .command("workspace-command".into())
+                .build(),
+            WorkspaceTab::builder()
+                .name("workspace-command/".into())
+                .directory(dir!(dir))
+                .command("tab-command".into())
+                .build(),
+            WorkspaceTab::builder()
+                .name("workspace-no-command/".into())
+                .directory(dir!(dir))
+                .build(),
+            WorkspaceTab::builder()
+                .name("project/".into())
+                .directory(dir!(dir, "project"))
+                .command("project-command".into())
+                .build(),
+            WorkspaceTab::builder()
+                .name("project/project-no-command/".into())
+                .directory(dir!(dir, "project"))
+                .build(),
+            WorkspaceTab::builder()
+                .name("project/project-run/".into())
+                .directory(dir!(dir, "project"))
+                .command("tab-run".into())
+                .build(),
+        ];
+
+        assert_eq!(expected, tabs);
+
+        Ok(())
+    }
+
+    #[test]
+    fn layout_test() -> anyhow::Result<()> {
+        let (dir, tabs) = load_ok("layout")?;
+
+        let pane = |name: &str| PaneLayout::Pane(name.to_string());
+
+        let expected = vec![
+            WorkspaceTab::builder()
+                .name("layout/".into())
+                .doc(doc!("workspace tab for layout"))
+                .directory(dir!(dir))
+                .build(),
+            WorkspaceTab::builder()
+                .name("project/".into())
+                .directory(dir!(dir, "project"))
+                .build(),
+            WorkspaceTab::builder()
+                .name("project/editor/".into())
+                .directory(dir!(dir, "project"))
+                .build(),
+            WorkspaceTab::builder()
+                .name("project/server/".into())
+                .directory(dir!(dir, "project"))
+                .build(),
+            WorkspaceTab::builder()
+                .name("project/test/".into())
+                .directory(dir!(dir, "project"))
+                .build(),
+            WorkspaceTab::builder()
+                .name("project/dev/".into())
+                .doc(doc!("editor, server and tests"))
+                .directory(dir!(dir, "project"))
+                .layout(PaneLayout::Split(
+                    SplitDirection::Horizontal,
+                    vec![
+                        pane("project/editor/"),
+                        PaneLayout::Split(
+                            SplitDirection::Vertical,
+                            vec![pane("project/server/"), pane("project/test/")],
+                        ),
+                    ],
+                ))
+                .build(),
+            WorkspaceTab::builder()
+                .name("project/missing/".into())
+                .directory(dir!(dir, "project"))
+                .layout(PaneLayout::Split(
+                    SplitDirection::Vertical,
+                    vec![pane("project/server/"), pane("project/logs/")],
+                ))
+                .build(),
+        ];
+
+        assert_eq!(expected, tabs);
+
+        let path = test_dir("layout")?;
+        let errors: Vec<String> = scan_config(path.as_path(), Some(path.as_path()), None)
+            .errors()
+            .into_iter()
+            .map(|err| err.to_string())
+            .collect();
+
+        assert_eq!(
+            vec!["Layout project/missing/ has a pane for tab project/logs/, which is not defined in the repository".to_string()],
+            errors
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn workspace_name_test() -> anyhow::Result<()> {
         let (dir, tabs) = load("workspace-tab")?;
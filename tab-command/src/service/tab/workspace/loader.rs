@@ -203,6 +203,7 @@ fn scan_global_config(builder: &mut WorkspaceBuilder) {
     };
 }
 
+#[allow(clippy::large_enum_variant)]
 pub enum YmlResult {
     Ok(Config),
     Err(LoadYamlError),
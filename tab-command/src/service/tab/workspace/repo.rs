@@ -3,7 +3,10 @@ use std::path::Path;
 use log::info;
 use tab_api::tab::normalize_name;
 
-use crate::state::workspace::{Repo, WorkspaceTab};
+use crate::state::{
+    workspace::{Repo, WorkspaceTab},
+    workspace_err::WorkspaceError,
+};
 
 use super::loader::WorkspaceBuilder;
 
@@ -22,6 +25,7 @@ pub fn build_repo(builder: &mut WorkspaceBuilder, path: &Path, repo: Repo) {
         path.to_path_buf(),
         repo.tab_options.clone(),
     );
+    let mut tab_names = vec![tab.name.clone()];
     builder.tab(tab);
 
     // and then for any tabs the user defined
@@ -37,6 +41,29 @@ pub fn build_repo(builder: &mut WorkspaceBuilder, path: &Path, repo: Repo) {
         let options = tab.options.or(repo.tab_options.clone());
 
         let tab = WorkspaceTab::with_options(tab_name.as_str(), directory, options);
+        tab_names.push(tab.name.clone());
+        builder.tab(tab);
+    }
+
+    // and then a tab for each layout, which opens the repository tabs in panes
+    for layout in repo.layout.into_iter().flat_map(|l| l.into_iter()) {
+        let layout_name = repo_name.clone() + normalize_name(layout.layout.as_str()).as_str();
+        let panes = layout
+            .split
+            .to_pane_layout(&|name| repo_name.clone() + normalize_name(name).as_str());
+
+        for pane in panes.tabs() {
+            if !tab_names.contains(pane) {
+                builder.err(WorkspaceError::layout_tab_not_found(
+                    layout_name.clone(),
+                    pane.clone(),
+                ));
+            }
+        }
+
+        let mut tab = WorkspaceTab::new(layout_name.as_str(), path.to_path_buf());
+        tab.doc = layout.doc;
+        tab.layout = Some(panes);
         builder.tab(tab);
     }
 
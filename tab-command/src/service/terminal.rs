@@ -22,9 +22,16 @@ use terminal_event::TerminalEventService;
 mod echo_input;
 mod echo_mode;
 mod fuzzy;
+mod keys;
+mod pane_mode;
+mod scroll_mode;
+mod status_bar;
+mod tab_cycle;
 mod terminal_event;
 
-use self::fuzzy::FuzzyFinderService;
+use self::{
+    fuzzy::FuzzyFinderService, pane_mode::TerminalPaneService, scroll_mode::TerminalScrollService,
+};
 
 static RESET_ENABLED: AtomicBool = AtomicBool::new(false);
 
@@ -61,16 +68,28 @@ pub fn reset_terminal_state() {
     }
 }
 
-/// Reads TerminalMode, and launches/cancels the TerminalEchoService / TerminalCrosstermService
+/// Reads TerminalMode, and launches/cancels the TerminalEchoService / FuzzyFinderService / TerminalScrollService / TerminalPaneService
 pub struct TerminalService {
     _main_terminal: MainTerminalCarrier,
     _terminal_mode: Lifeline,
     _terminal_event: TerminalEventService,
 }
 
+/// Holds the service for the current terminal mode, which is cancelled when the mode changes.
 enum ServiceLifeline {
-    Echo(TerminalEchoService),
-    FuzzyFinder(FuzzyFinderService, TerminalFuzzyCarrier),
+    Echo {
+        _service: TerminalEchoService,
+    },
+    FuzzyFinder {
+        _service: FuzzyFinderService,
+        _carrier: TerminalFuzzyCarrier,
+    },
+    Scroll {
+        _service: TerminalScrollService,
+    },
+    Panes {
+        _service: TerminalPaneService,
+    },
     None,
 }
 
@@ -111,7 +130,8 @@ impl Service for TerminalService {
                     Self::set_raw_mode(&mode);
 
                     service = Self::spawn_service(&mode, &terminal_bus)?;
-                } else if reset_terminal {
+                } else if reset_terminal && !matches!(mode, TerminalMode::Panes(_)) {
+                    // the pane service re-arranges the panes when the layout changes
                     reset_terminal_state();
                     Self::set_raw_mode(&mode);
                 }
@@ -139,6 +159,12 @@ impl TerminalService {
             TerminalMode::FuzzyFinder(_) => {
                 enable_raw_mode(false);
             }
+            TerminalMode::Scroll(_) => {
+                enable_raw_mode(true);
+            }
+            TerminalMode::Panes(_) => {
+                enable_raw_mode(true);
+            }
             TerminalMode::None => {
                 disable_raw_mode();
             }
@@ -155,7 +181,7 @@ impl TerminalService {
                 info!("TerminalService switching to echo mode for tab {}", name);
 
                 let service = TerminalEchoService::spawn(&terminal_bus)?;
-                ServiceLifeline::Echo(service)
+                ServiceLifeline::Echo { _service: service }
             }
             TerminalMode::FuzzyFinder(back) => {
                 info!("TerminalService switching to fuzzy finder mode");
@@ -165,7 +191,22 @@ impl TerminalService {
                 let carrier = fuzzy_bus.carry_from(&terminal_bus)?;
 
                 let service = FuzzyFinderService::spawn(&fuzzy_bus)?;
-                ServiceLifeline::FuzzyFinder(service, carrier)
+                ServiceLifeline::FuzzyFinder {
+                    _service: service,
+                    _carrier: carrier,
+                }
+            }
+            TerminalMode::Scroll(id) => {
+                info!("TerminalService switching to scroll mode for tab {}", id);
+
+                let service = TerminalScrollService::spawn(&terminal_bus)?;
+                ServiceLifeline::Scroll { _service: service }
+            }
+            TerminalMode::Panes(_) => {
+                info!("TerminalService switching to pane mode");
+
+                let service = TerminalPaneService::spawn(&terminal_bus)?;
+                ServiceLifeline::Panes { _service: service }
             }
         };
 
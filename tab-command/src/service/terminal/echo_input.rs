@@ -1,7 +1,14 @@
-use thiserror::Error;
+use std::time::Duration;
+
+use tokio::{
+    io::{AsyncReadExt, Stdin},
+    time,
+};
 
 use crate::config::{load_global_config, Action};
 
+use super::keys::{key_len, parse_keys, KeyParseError, ESC};
+
 /// Parses the bindings in the global workspace config file.
 ///
 /// If the config file does not exists, returns the default bindings.
@@ -16,9 +23,11 @@ pub fn key_bindings() -> anyhow::Result<KeyBindings> {
     let bindings = {
         let mut parsed_bindings = Vec::with_capacity(bindings.len());
         for binding in bindings {
-            let sequence = parse(binding.keys.as_str())?;
-            let action = binding.action;
-            parsed_bindings.push(KeyBinding { sequence, action });
+            // the binding matches any of the encodings which the terminal may send for the keys
+            for sequence in encodings(binding.keys.as_str())? {
+                let action = binding.action.clone();
+                parsed_bindings.push(KeyBinding { sequence, action });
+            }
         }
 
         KeyBindings {
@@ -29,49 +38,25 @@ pub fn key_bindings() -> anyhow::Result<KeyBindings> {
     Ok(bindings)
 }
 
-#[derive(Debug, Error, PartialEq)]
-pub enum KeyParseError {
-    #[error("Invalid `ctrl-X` sequence: {0} - char '{1}' is not an ASCII control character")]
-    InvalidCtrlCharacter(String, char),
-    #[error("Invalid sequence: '{0}' - use spaces to separate individual characters")]
-    InvalidSequence(String),
-}
-
-pub fn parse(seq: &str) -> Result<Vec<u8>, KeyParseError> {
-    let mut chars = String::new();
-
-    for entry in seq.split(' ') {
-        let entry = entry.trim().to_lowercase();
-
-        if entry.trim().is_empty() {
-            continue;
-        }
-
-        if entry.len() == 1 {
-            let ch = entry.chars().next().unwrap().to_ascii_lowercase();
-            chars.push(ch);
-            continue;
-        }
-
-        if entry.to_ascii_lowercase() == "esc" {
-            chars.push('\x1b');
-            continue;
-        }
-
-        if entry.starts_with("ctrl-") && entry.len() == 6 {
-            let ch = entry.chars().nth(5).unwrap().to_ascii_uppercase();
-            if ch.is_ascii() && ch >= '@' && ch <= '_' {
-                let code = ch as u8 - 64;
-                chars.push(code as char);
-            } else {
-                return Err(KeyParseError::InvalidCtrlCharacter(entry, ch));
-            }
-        } else {
-            return Err(KeyParseError::InvalidSequence(entry));
-        }
+/// Parses the key sequence, and returns each combination of the encodings of the keys
+pub fn encodings(seq: &str) -> Result<Vec<Vec<u8>>, KeyParseError> {
+    let mut sequences = vec![Vec::new()];
+
+    for key in parse_keys(seq)? {
+        let encodings = key.encodings();
+
+        sequences = sequences
+            .iter()
+            .flat_map(|prefix| {
+                encodings
+                    .iter()
+                    .map(move |encoding| [prefix.as_slice(), encoding.as_slice()].concat())
+            })
+            .collect();
     }
 
-    Ok(chars.into_bytes())
+    sequences.retain(|sequence| !sequence.is_empty());
+    Ok(sequences)
 }
 
 #[derive(Debug, Clone)]
@@ -79,20 +64,31 @@ pub struct KeyBindings {
     pub bindings: Vec<KeyBinding>,
 }
 
+impl KeyBindings {
+    fn with_keys(bindings: &[(&str, Action)]) -> Self {
+        let bindings = bindings
+            .iter()
+            .flat_map(|(keys, action)| {
+                encodings(keys)
+                    .expect("default key binding")
+                    .into_iter()
+                    .map(move |sequence| KeyBinding {
+                        sequence,
+                        action: action.clone(),
+                    })
+            })
+            .collect();
+
+        Self { bindings }
+    }
+}
+
 impl Default for KeyBindings {
     fn default() -> Self {
-        Self {
-            bindings: vec![
-                KeyBinding {
-                    action: Action::Disconnect,
-                    sequence: vec![0x14, 0x03],
-                },
-                KeyBinding {
-                    action: Action::SelectInteractive,
-                    sequence: vec![0x14],
-                },
-            ],
-        }
+        Self::with_keys(&[
+            ("ctrl-T ctrl-C", Action::Disconnect),
+            ("ctrl-T", Action::SelectInteractive),
+        ])
     }
 }
 
@@ -141,10 +137,38 @@ impl BindingFilter {
 
         None
     }
+
+    /// The number of keys in the sequence which have been matched, by the most recent input
+    pub fn progress(&self) -> usize {
+        self.index
+    }
+
+    pub fn reset(&mut self) {
+        self.index = 0;
+    }
+
+    fn ends_with_esc(&self) -> bool {
+        self.sequence.last() == Some(&ESC)
+    }
 }
 
+/// The time to wait for the next key, when the input matches a binding, and is also the beginning of a longer binding
+const BINDING_TIMEOUT: Duration = Duration::from_millis(300);
+/// The time to wait for the next key after ESC.  If it arrives, the ESC is the beginning of an escape sequence, rather than a key.
+const ESC_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Filters key bindings from stdin.
+///
+/// Input which may be the beginning of a binding is held until the binding is resolved.
+/// If input matches a binding, and is also the beginning of a longer binding, the filter waits for the next key.
+/// If the next key doesn't arrive within BINDING_TIMEOUT, `flush` resolves the shorter binding.
+/// Bindings which end with ESC wait for ESC_TIMEOUT, and are cancelled if more input arrives, as the terminal sends escape sequences all at once.
 pub struct InputFilter {
     bindings: Vec<BindingFilter>,
+    /// Input which is part of a partially matched binding
+    pending: Vec<u8>,
+    /// A binding which matched the pending input, at the given index, but is the beginning of a longer binding
+    matched: Option<(usize, usize)>,
 }
 
 impl From<KeyBindings> for InputFilter {
@@ -156,67 +180,232 @@ impl From<KeyBindings> for InputFilter {
 impl InputFilter {
     pub fn new(binds: Vec<KeyBinding>) -> Self {
         Self {
-            bindings: binds.into_iter().map(BindingFilter::new).collect(),
+            bindings: binds
+                .into_iter()
+                .filter(|binding| !binding.sequence.is_empty())
+                .map(BindingFilter::new)
+                .collect(),
+            pending: Vec::new(),
+            matched: None,
         }
     }
 
-    pub fn input<'a>(&mut self, data: &'a [u8]) -> Input<'a> {
-        for binding in self.bindings.iter_mut() {
-            if let Some(match_index) = binding.find(data) {
+    /// True if input is held, because it may be the beginning of a binding
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Filters the input.  Returns the first action, and the data which should be sent to the tab.
+    /// Data which follows the action is discarded.
+    pub fn input(&mut self, data: &[u8]) -> Input {
+        let mut output = Vec::new();
+
+        for byte in data.iter().copied() {
+            if let Some(action) = self.push(byte, &mut output) {
                 return Input {
-                    action: Some(binding.action),
-                    data: &data[0..match_index],
+                    action: Some(action),
+                    data: output,
                 };
             }
         }
 
-        Input { action: None, data }
+        Input {
+            action: None,
+            data: output,
+        }
+    }
+
+    /// Resolves the pending input, when no more input has arrived
+    pub fn flush(&mut self) -> Input {
+        let mut output = Vec::new();
+
+        let action = match self.matched.take() {
+            Some((binding, start)) => self.fire(binding, start, &mut output),
+            None => {
+                output.append(&mut self.pending);
+                None
+            }
+        };
+
+        self.reset();
+
+        Input {
+            action,
+            data: output,
+        }
+    }
+
+    /// The time to wait for the next key, before the pending input is resolved
+    pub fn timeout(&self) -> Duration {
+        if self.pending.last() == Some(&ESC) {
+            ESC_TIMEOUT
+        } else {
+            BINDING_TIMEOUT
+        }
+    }
+
+    fn push(&mut self, byte: u8, output: &mut Vec<u8>) -> Option<Action> {
+        // input which follows ESC before the timeout is part of an escape sequence, so the ESC binding is cancelled
+        if let Some((binding, _)) = self.matched {
+            if self.bindings[binding].ends_with_esc() {
+                self.matched = None;
+            }
+        }
+
+        self.pending.push(byte);
+
+        // if several bindings are completed by the key, the longest binding wins
+        let mut completed: Option<(usize, usize)> = None;
+        for (index, binding) in self.bindings.iter_mut().enumerate() {
+            if binding.find(&[byte]).is_none() {
+                continue;
+            }
+
+            let len = binding.sequence.len();
+            if completed.map(|(_, prev)| len > prev).unwrap_or(true) {
+                completed = Some((index, len));
+            }
+        }
+
+        // the longest partial match, which covers the end of the pending input.
+        // completed bindings are reset, so if it is as long as the completed binding, a longer binding may follow.
+        let extension = self
+            .bindings
+            .iter()
+            .map(BindingFilter::progress)
+            .max()
+            .unwrap_or(0);
+
+        if let Some((binding, len)) = completed {
+            let start = self.pending.len() - len;
+
+            if extension < len && !self.bindings[binding].ends_with_esc() {
+                let action = self.fire(binding, start, output);
+                self.reset();
+                return action;
+            }
+
+            self.matched = Some((binding, start));
+        } else if let Some((binding, start)) = self.matched {
+            // the longer binding didn't match, so the shorter binding is resolved
+            if extension < self.pending.len() - start {
+                self.matched = None;
+                let action = self.fire(binding, start, output);
+                self.reset();
+                return action;
+            }
+        }
+
+        // release the input which isn't part of a partial match, or the matched binding
+        let mut release = self.pending.len() - extension.min(self.pending.len());
+        if let Some((_, start)) = self.matched {
+            release = release.min(start);
+        }
+
+        output.extend(self.pending.drain(..release));
+
+        if let Some((_, ref mut start)) = self.matched {
+            *start -= release;
+        }
+
+        None
+    }
+
+    /// Resolves the binding, which matched the pending input at the start index.
+    /// The input which preceeds the binding is released, and the rest is discarded.
+    fn fire(&mut self, binding: usize, start: usize, output: &mut Vec<u8>) -> Option<Action> {
+        output.extend(self.pending.drain(..start));
+        self.pending.clear();
+
+        let binding = &self.bindings[binding];
+        match binding.action {
+            Action::SendPrefix => {
+                let prefix = &binding.sequence[..key_len(&binding.sequence)];
+                output.extend_from_slice(prefix);
+                None
+            }
+            ref action => Some(action.clone()),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.pending.clear();
+        self.matched = None;
+
+        for binding in self.bindings.iter_mut() {
+            binding.reset();
+        }
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Input<'a> {
+pub struct Input {
     pub action: Option<Action>,
-    pub data: &'a [u8],
+    pub data: Vec<u8>,
+}
+
+/// Reads the next input from stdin, and filters it for key bindings.
+/// Returns None if stdin could not be read.
+pub async fn read_input(
+    stdin: &mut Stdin,
+    buffer: &mut [u8],
+    filter: &mut InputFilter,
+) -> Option<Input> {
+    loop {
+        let read = if filter.is_pending() {
+            match time::timeout(filter.timeout(), stdin.read(buffer)).await {
+                Ok(read) => read,
+                Err(_elapsed) => return Some(filter.flush()),
+            }
+        } else {
+            stdin.read(buffer).await
+        };
+
+        match read {
+            Ok(0) => continue,
+            Ok(read) => return Some(filter.input(&buffer[0..read])),
+            Err(_) => return None,
+        }
+    }
 }
 
 #[cfg(test)]
 mod parse_tests {
-    use super::{parse, KeyParseError};
+    use super::{encodings, KeyParseError};
 
     #[test]
     fn single_char() {
-        let parsed = parse("a");
-        assert_eq!(Ok(vec![0x61]), parsed);
+        let parsed = encodings("a");
+        assert_eq!(Ok(vec![vec![0x61]]), parsed);
     }
 
     #[test]
     fn upper_char() {
-        let parsed = parse("A");
-        assert_eq!(Ok(vec![0x61]), parsed);
+        let parsed = encodings("A");
+        assert_eq!(Ok(vec![vec![0x61]]), parsed);
     }
 
     #[test]
     fn two_chars() {
-        let parsed = parse("a b");
-        assert_eq!(Ok(vec![0x61, 0x62]), parsed);
+        let parsed = encodings("a b");
+        assert_eq!(Ok(vec![vec![0x61, 0x62]]), parsed);
     }
 
     #[test]
     fn ctrl_char() {
-        let parsed = parse("ctrl-A");
-        assert_eq!(Ok(vec![0x01]), parsed);
+        let parsed = encodings("ctrl-A");
+        assert_eq!(Ok(vec![vec![0x01], b"\x1b[97;5u".to_vec()]), parsed);
     }
 
     #[test]
     fn ctrl_char_lowercase() {
-        let parsed = parse("ctrl-a");
-        assert_eq!(Ok(vec![0x01]), parsed);
+        let parsed = encodings("ctrl-a");
+        assert_eq!(Ok(vec![vec![0x01], b"\x1b[97;5u".to_vec()]), parsed);
     }
 
     #[test]
     fn invalid_seq() {
-        let parsed = parse("bad");
+        let parsed = encodings("bad");
         assert_eq!(
             Err(KeyParseError::InvalidSequence("bad".to_string())),
             parsed
@@ -225,7 +414,7 @@ mod parse_tests {
 
     #[test]
     fn invalid_ctrl_low() {
-        let parsed = parse("ctrl-?");
+        let parsed = encodings("ctrl-?");
         assert_eq!(
             Err(KeyParseError::InvalidCtrlCharacter(
                 "ctrl-?".to_string(),
@@ -237,7 +426,7 @@ mod parse_tests {
 
     #[test]
     fn invalid_ctrl_high() {
-        let parsed = parse("ctrl-`");
+        let parsed = encodings("ctrl-`");
         assert_eq!(
             Err(KeyParseError::InvalidCtrlCharacter(
                 "ctrl-`".to_string(),
@@ -246,6 +435,24 @@ mod parse_tests {
             parsed
         );
     }
+
+    #[test]
+    fn combinations() {
+        let parsed = encodings("ctrl-A b");
+        assert_eq!(Ok(vec![vec![0x01, 0x62], b"\x1b[97;5ub".to_vec()]), parsed);
+
+        let parsed = encodings("ctrl-A ctrl-B");
+        assert_eq!(Ok(4), parsed.map(|sequences| sequences.len()));
+    }
+
+    #[test]
+    fn named_keys() {
+        let parsed = encodings("alt-t");
+        assert_eq!(Ok(vec![b"\x1bt".to_vec(), b"\x1b[116;3u".to_vec()]), parsed);
+
+        let parsed = encodings("f12");
+        assert_eq!(Ok(vec![b"\x1b[24~".to_vec()]), parsed);
+    }
 }
 
 #[cfg(test)]
@@ -332,7 +539,7 @@ mod filter_tests {
 mod input_tests {
     use crate::config::Action;
 
-    use super::{Input, InputFilter, KeyBinding};
+    use super::{Input, InputFilter, KeyBinding, ESC_TIMEOUT};
 
     #[test]
     fn simple() {
@@ -347,7 +554,7 @@ mod input_tests {
         assert_eq!(
             Input {
                 action: Some(Action::Disconnect),
-                data: &[0]
+                data: vec![0]
             },
             result
         );
@@ -366,7 +573,7 @@ mod input_tests {
         assert_eq!(
             Input {
                 action: None,
-                data: &[0, 1]
+                data: vec![0]
             },
             result
         );
@@ -376,7 +583,7 @@ mod input_tests {
         assert_eq!(
             Input {
                 action: Some(Action::Disconnect),
-                data: &[]
+                data: vec![]
             },
             result
         );
@@ -400,9 +607,228 @@ mod input_tests {
         assert_eq!(
             Input {
                 action: Some(Action::Disconnect),
-                data: &[0]
+                data: vec![0]
+            },
+            result
+        );
+    }
+
+    #[test]
+    fn longest_match() {
+        let disconnect = KeyBinding {
+            sequence: vec![1, 2],
+            action: Action::Disconnect,
+        };
+
+        let interactive = KeyBinding {
+            sequence: vec![1],
+            action: Action::SelectInteractive,
+        };
+
+        let mut filter = InputFilter::new(vec![interactive, disconnect]);
+        let result = filter.input(&[0, 1]);
+        assert_eq!(
+            Input {
+                action: None,
+                data: vec![0]
+            },
+            result
+        );
+        assert!(filter.is_pending());
+
+        let result = filter.input(&[2, 3]);
+        assert_eq!(
+            Input {
+                action: Some(Action::Disconnect),
+                data: vec![]
+            },
+            result
+        );
+        assert!(!filter.is_pending());
+    }
+
+    #[test]
+    fn shorter_match() {
+        let disconnect = KeyBinding {
+            sequence: vec![1, 2],
+            action: Action::Disconnect,
+        };
+
+        let interactive = KeyBinding {
+            sequence: vec![1],
+            action: Action::SelectInteractive,
+        };
+
+        let mut filter = InputFilter::new(vec![disconnect, interactive]);
+        let result = filter.input(&[0, 1, 3]);
+        assert_eq!(
+            Input {
+                action: Some(Action::SelectInteractive),
+                data: vec![0]
+            },
+            result
+        );
+
+        let result = filter.input(&[1]);
+        assert_eq!(
+            Input {
+                action: None,
+                data: vec![]
             },
             result
         );
+
+        assert_eq!(
+            Input {
+                action: Some(Action::SelectInteractive),
+                data: vec![]
+            },
+            filter.flush()
+        );
+    }
+
+    #[test]
+    fn flush_partial() {
+        let binding = KeyBinding {
+            sequence: vec![1, 2],
+            action: Action::Disconnect,
+        };
+
+        let mut filter = InputFilter::new(vec![binding]);
+        filter.input(&[0, 1]);
+
+        assert_eq!(
+            Input {
+                action: None,
+                data: vec![1]
+            },
+            filter.flush()
+        );
+        assert!(!filter.is_pending());
+    }
+
+    #[test]
+    fn send_prefix() {
+        let prefix = KeyBinding {
+            sequence: vec![1, 1],
+            action: Action::SendPrefix,
+        };
+
+        let disconnect = KeyBinding {
+            sequence: vec![1, 2],
+            action: Action::Disconnect,
+        };
+
+        let mut filter = InputFilter::new(vec![prefix, disconnect]);
+        assert_eq!(
+            Input {
+                action: None,
+                data: vec![0, 1, 3]
+            },
+            filter.input(&[0, 1, 1, 3])
+        );
+
+        assert_eq!(
+            Input {
+                action: Some(Action::Disconnect),
+                data: vec![]
+            },
+            filter.input(&[1, 2])
+        );
+    }
+
+    #[test]
+    fn esc_timeout() {
+        let binding = KeyBinding {
+            sequence: vec![0x1b],
+            action: Action::Disconnect,
+        };
+
+        let mut filter = InputFilter::new(vec![binding]);
+        assert_eq!(
+            Input {
+                action: None,
+                data: vec![0]
+            },
+            filter.input(&[0, 0x1b])
+        );
+        assert_eq!(ESC_TIMEOUT, filter.timeout());
+
+        assert_eq!(
+            Input {
+                action: Some(Action::Disconnect),
+                data: vec![]
+            },
+            filter.flush()
+        );
+    }
+
+    #[test]
+    fn esc_sequence() {
+        let binding = KeyBinding {
+            sequence: vec![0x1b],
+            action: Action::Disconnect,
+        };
+
+        let mut filter = InputFilter::new(vec![binding]);
+        assert_eq!(
+            Input {
+                action: None,
+                data: b"\x1b[A".to_vec()
+            },
+            filter.input(b"\x1b[A")
+        );
+        assert!(!filter.is_pending());
+    }
+
+    #[test]
+    fn alt_key() {
+        let binding = KeyBinding {
+            sequence: b"\x1bt".to_vec(),
+            action: Action::Disconnect,
+        };
+
+        let mut filter = InputFilter::new(vec![binding]);
+        assert_eq!(
+            Input {
+                action: Some(Action::Disconnect),
+                data: vec![]
+            },
+            filter.input(b"\x1bt")
+        );
+
+        // a bare ESC, followed by t after the timeout, is not alt-t
+        filter.input(b"\x1b");
+        assert_eq!(
+            Input {
+                action: None,
+                data: vec![0x1b]
+            },
+            filter.flush()
+        );
+        assert_eq!(
+            Input {
+                action: None,
+                data: b"t".to_vec()
+            },
+            filter.input(b"t")
+        );
+    }
+
+    #[test]
+    fn send_prefix_multibyte() {
+        let prefix = KeyBinding {
+            sequence: b"\x1ba\x1ba".to_vec(),
+            action: Action::SendPrefix,
+        };
+
+        let mut filter = InputFilter::new(vec![prefix]);
+        assert_eq!(
+            Input {
+                action: None,
+                data: b"\x1ba".to_vec()
+            },
+            filter.input(b"\x1ba\x1ba")
+        );
     }
 }
@@ -1,17 +1,29 @@
 use std::time::Duration;
 
 use crate::{
-    config::Action,
+    config::{load_status_bar_config, Action},
     message::terminal::{TerminalInput, TerminalOutput, TerminalShutdown},
+    state::{
+        tabs::ActiveTabsState,
+        terminal::{TerminalMode, TerminalSizeState},
+    },
 };
 use crate::{message::terminal::TerminalSend, prelude::*};
 use anyhow::Context;
+use postage::watch;
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt, Stdout},
-    time,
+    io::{AsyncWriteExt, Stdout},
+    select, time,
 };
 
-use super::echo_input::{key_bindings, InputFilter, KeyBindings};
+use super::{
+    echo_input::{key_bindings, read_input, InputFilter, KeyBindings},
+    status_bar::EchoStatusBar,
+    tab_cycle::{target_tab, TabCycle},
+};
+
+/// The interval at which the status bar checks whether it should be redrawn
+const STATUS_BAR_TICK: Duration = Duration::from_millis(250);
 
 pub struct TerminalEchoService {
     _input: Lifeline,
@@ -23,19 +35,46 @@ impl Service for TerminalEchoService {
     type Lifeline = anyhow::Result<Self>;
 
     fn spawn(bus: &TerminalBus) -> anyhow::Result<Self> {
-        let rx = bus.rx::<TerminalOutput>()?;
-        let _output = Self::try_task("stdout", print_stdout(rx));
-
-        let tx = bus.tx::<TerminalInput>()?;
-        let tx_terminal = bus.tx::<TerminalSend>()?;
-        let tx_shutdown = bus.tx::<TerminalShutdown>()?;
-        let _input = Self::try_task("stdin", forward_stdin(tx, tx_terminal, tx_shutdown));
+        let _output = {
+            let rx = bus.rx::<TerminalOutput>()?;
+            let rx_mode = bus.rx::<TerminalMode>()?;
+            let rx_size = bus.rx::<TerminalSizeState>()?;
+            let rx_tabs = bus.rx::<Option<ActiveTabsState>>()?;
+
+            let config = load_status_bar_config();
+            let status_bar = match *rx_mode.borrow() {
+                TerminalMode::Echo(id) if config.enabled => {
+                    Some(EchoStatusBar::new(config, id, rx_size.borrow().0))
+                }
+                _ => None,
+            };
+
+            Self::try_task(
+                "stdout",
+                print_stdout(rx, status_bar, rx_mode, rx_size, rx_tabs),
+            )
+        };
+
+        let _input = {
+            let rx_mode = bus.rx::<TerminalMode>()?;
+            let rx_tabs = bus.rx::<Option<ActiveTabsState>>()?;
+            let tx = bus.tx::<TerminalInput>()?;
+            let tx_terminal = bus.tx::<TerminalSend>()?;
+            let tx_shutdown = bus.tx::<TerminalShutdown>()?;
+
+            Self::try_task(
+                "stdin",
+                forward_stdin(rx_mode, rx_tabs, tx, tx_terminal, tx_shutdown),
+            )
+        };
 
         Ok(TerminalEchoService { _input, _output })
     }
 }
 
 async fn forward_stdin(
+    rx_mode: watch::Receiver<TerminalMode>,
+    rx_tabs: watch::Receiver<Option<ActiveTabsState>>,
     mut tx: impl Sink<Item = TerminalInput> + Unpin,
     mut tx_terminal: impl Sink<Item = TerminalSend> + Unpin,
     mut tx_shutdown: impl Sink<Item = TerminalShutdown> + Unpin,
@@ -53,23 +92,30 @@ async fn forward_stdin(
     };
 
     let mut filter: InputFilter = key_bindings.into();
+    let mut cycle: Option<TabCycle> = None;
 
-    while let Ok(read) = stdin.read(buffer.as_mut_slice()).await {
-        if read == 0 {
-            continue;
-        }
-
-        let input = filter.input(&buffer[0..read]);
-
-        let buf: Vec<u8> = input.data.into();
+    while let Some(input) = read_input(&mut stdin, buffer.as_mut_slice(), &mut filter).await {
+        debug!("stdin chunk of len {}", input.data.len());
 
-        debug!("stdin chunk of len {}", read);
+        if !input.data.is_empty() {
+            cycle = None;
+            tx.send(TerminalInput::Stdin(input.data))
+                .await
+                .context("tx TerminalSend::Stdin")?;
+        }
 
-        tx.send(TerminalInput::Stdin(buf))
-            .await
-            .context("tx TerminalSend::Stdin")?;
+        let current = match *rx_mode.borrow() {
+            TerminalMode::Echo(id) => Some(id),
+            _ => None,
+        };
 
         if let Some(action) = input.action {
+            let tabs = rx_tabs.borrow().clone().unwrap_or_default();
+            if let Some(name) = target_tab(&action, &mut cycle, &tabs, current) {
+                tx_terminal.send(TerminalSend::SelectTab(name)).await?;
+                continue;
+            }
+
             match action {
                 Action::Disconnect => {
                     tx_shutdown.send(TerminalShutdown {}).await?;
@@ -77,6 +123,29 @@ async fn forward_stdin(
                 Action::SelectInteractive => {
                     tx_terminal.send(TerminalSend::FuzzyRequest).await?;
                 }
+                Action::ScrollMode => {
+                    tx_terminal.send(TerminalSend::ScrollRequest).await?;
+                }
+                Action::DetachAndRun(command) => {
+                    tx_terminal
+                        .send(TerminalSend::DetachAndRun(command))
+                        .await?;
+                }
+                Action::CloseTab => {
+                    if let Some(id) = current {
+                        tx_terminal.send(TerminalSend::CloseTab(id)).await?;
+                    }
+
+                    continue;
+                }
+                // a single tab has no panes to focus, and the tab selection actions have no other tab to select
+                Action::FocusNextPane
+                | Action::FocusPreviousPane
+                | Action::SelectPrevious
+                | Action::NextTab
+                | Action::PrevTab
+                | Action::SelectTab(_)
+                | Action::SendPrefix => continue,
             }
 
             break;
@@ -88,23 +157,57 @@ async fn forward_stdin(
     Ok(())
 }
 
-async fn print_stdout(mut rx: impl Stream<Item = TerminalOutput> + Unpin) -> anyhow::Result<()> {
+async fn print_stdout(
+    mut rx: impl Stream<Item = TerminalOutput> + Unpin,
+    mut status_bar: Option<EchoStatusBar>,
+    mut rx_mode: watch::Receiver<TerminalMode>,
+    mut rx_size: watch::Receiver<TerminalSizeState>,
+    mut rx_tabs: watch::Receiver<Option<ActiveTabsState>>,
+) -> anyhow::Result<()> {
     trace!("Waiting on messages...");
 
     let mut stdout = tokio::io::stdout();
     let mut error_printed = false;
+    let mut status_tick = time::interval(STATUS_BAR_TICK);
+
+    loop {
+        select! {
+            message = rx.recv() => match message {
+                Some(TerminalOutput::Stdout(_id, data)) => {
+                    let result = write_stdout(&mut stdout, data).await;
+
+                    if let Err(e) = result {
+                        if !error_printed {
+                            error!("failed to print stdout: {}", e);
+                            error_printed = true;
+                        }
+                    }
 
-    while let Some(message) = rx.recv().await {
-        match message {
-            TerminalOutput::Stdout(data) => {
-                let result = write_stdout(&mut stdout, data).await;
-
-                if let Err(e) = result {
-                    if !error_printed {
-                        error!("failed to print stdout: {}", e);
-                        error_printed = true;
+                    if let Some(ref mut status_bar) = status_bar {
+                        status_bar.output();
                     }
                 }
+                None => break,
+            },
+            mode = rx_mode.recv(), if status_bar.is_some() => match (mode, &mut status_bar) {
+                (Some(TerminalMode::Echo(id)), Some(status_bar)) => status_bar.select(id),
+                (Some(_), _) => {}
+                (None, _) => break,
+            },
+            size = rx_size.recv(), if status_bar.is_some() => match (size, &mut status_bar) {
+                (Some(TerminalSizeState(size)), Some(status_bar)) => status_bar.resize(size),
+                (Some(_), _) => {}
+                (None, _) => break,
+            },
+            tabs = rx_tabs.recv(), if status_bar.is_some() => match (tabs, &mut status_bar) {
+                (Some(Some(tabs)), Some(status_bar)) => status_bar.update_tabs(tabs),
+                (Some(_), _) => {}
+                (None, _) => break,
+            },
+            _ = status_tick.tick(), if status_bar.is_some() => {
+                if let Some(ref mut status_bar) = status_bar {
+                    status_bar.tick(&mut std::io::stdout())?;
+                }
             }
         }
     }
@@ -4,12 +4,14 @@ use crate::{
     config::{load_global_config, FuzzyConfig},
     env::terminal_size,
     message::fuzzy::FuzzyEvent,
+    message::fuzzy::FuzzyOutputRequest,
     message::fuzzy::FuzzySelection,
     message::fuzzy::FuzzyShutdown,
     prelude::*,
     state::fuzzy::FuzzyMatch,
     state::fuzzy::FuzzyMatchState,
     state::fuzzy::FuzzyOutputEvent,
+    state::fuzzy::FuzzyOutputLinesState,
     state::fuzzy::FuzzyOutputMatch,
     state::fuzzy::FuzzyQueryState,
     state::fuzzy::FuzzySelectState,
@@ -39,6 +41,9 @@ const RESERVED_ROWS: usize = 2;
 /// Columns reserved by the UI for non-match items
 const RESERVED_COLUMNS: usize = 2;
 
+/// The number of recent output lines requested from each tab, when output matching is enabled
+const OUTPUT_LINES: usize = 50;
+
 pub struct FuzzyFinderService {
     _input: Lifeline,
     _query_state: Lifeline,
@@ -71,12 +76,23 @@ impl Service for FuzzyFinderService {
         let _filter_state = {
             let rx = bus.rx::<Option<FuzzyTabsState>>()?;
             let rx_query = bus.rx::<FuzzyQueryState>()?;
+            let rx_lines = bus.rx::<Option<FuzzyOutputLinesState>>()?;
+            let rx_event = bus.rx::<FuzzyEvent>()?;
             let tx = bus.tx::<FuzzyMatchState>()?;
+            let tx_request = bus.tx::<FuzzyOutputRequest>()?;
             let fuzzy_config = load_fuzzy_config();
 
             Self::try_task(
                 "filter_state",
-                Self::filter_state(rx, rx_query, tx, fuzzy_config),
+                Self::filter_state(
+                    rx,
+                    rx_query,
+                    rx_lines,
+                    rx_event,
+                    tx,
+                    tx_request,
+                    fuzzy_config,
+                ),
             )
         };
 
@@ -145,6 +161,8 @@ fn load_fuzzy_config() -> FuzzyConfig {
 enum FilterEvent {
     Tabs(Option<FuzzyTabsState>),
     Query(FuzzyQueryState),
+    Lines(Option<FuzzyOutputLinesState>),
+    Event(FuzzyEvent),
 }
 
 impl FuzzyFinderService {
@@ -214,6 +232,7 @@ impl FuzzyFinderService {
                                 match ch {
                                     'k' | 'p' => tx_event.send(FuzzyEvent::MoveUp {}).await?,
                                     'j' | 'n' => tx_event.send(FuzzyEvent::MoveDown {}).await?,
+                                    'o' => tx_event.send(FuzzyEvent::ToggleOutput).await?,
                                     'c' | 'x' | 'w' => Self::shutdown(&mut tx_shutdown).await?,
                                     _ => continue,
                                 }
@@ -303,23 +322,47 @@ impl FuzzyFinderService {
         Ok(())
     }
 
+    /// Matches the query against the tab entries.
+    /// If output matching is enabled, the recent output lines of each tab are also matched.
     async fn filter_state(
         rx: impl Stream<Item = Option<FuzzyTabsState>> + Unpin,
         rx_query: impl Stream<Item = FuzzyQueryState> + Unpin,
+        rx_lines: impl Stream<Item = Option<FuzzyOutputLinesState>> + Unpin,
+        rx_event: impl Stream<Item = FuzzyEvent> + Unpin,
         mut tx: impl Sink<Item = FuzzyMatchState> + Unpin,
+        mut tx_request: impl Sink<Item = FuzzyOutputRequest> + Unpin,
         fuzzy_config: FuzzyConfig,
     ) -> anyhow::Result<()> {
         let matcher = SkimMatcherV2::default().ignore_case();
 
         let mut rx = rx
             .map(FilterEvent::Tabs)
-            .merge(rx_query.map(FilterEvent::Query));
+            .merge(rx_query.map(FilterEvent::Query))
+            .merge(rx_lines.map(FilterEvent::Lines))
+            .merge(rx_event.map(FilterEvent::Event));
 
         let mut entries: Vec<Arc<TabEntry>> = vec![];
+        let mut lines: Option<FuzzyOutputLinesState> = None;
+        let mut output_entries: Vec<Arc<TabEntry>> = vec![];
+        let mut output = false;
         let mut query = None;
 
         while let Some(event) = rx.recv().await {
             match event {
+                FilterEvent::Event(FuzzyEvent::ToggleOutput) => {
+                    output = !output;
+
+                    if output {
+                        // the lines are refreshed each time output matching is enabled
+                        tx_request.send(FuzzyOutputRequest(OUTPUT_LINES)).await?;
+                    }
+                }
+                FilterEvent::Event(_) => {
+                    continue;
+                }
+                FilterEvent::Lines(state) => {
+                    lines = state;
+                }
                 FilterEvent::Tabs(state) => {
                     if let Some(tabs) = state {
                         entries.clear();
@@ -349,6 +392,18 @@ impl FuzzyFinderService {
                 }
             }
 
+            output_entries.clear();
+            if let (true, Some(lines)) = (output, lines.as_ref()) {
+                for line in lines.lines.iter() {
+                    let last_selected = entries
+                        .iter()
+                        .find(|entry| entry.name == line.name)
+                        .and_then(|entry| entry.last_selected);
+
+                    output_entries.push(Arc::new(TabEntry::entry_output(line, last_selected)));
+                }
+            }
+
             let create_entry = if fuzzy_config.create_tab {
                 Self::create_tab_entry(&entries, &query).map(Arc::new)
             } else {
@@ -357,7 +412,11 @@ impl FuzzyFinderService {
 
             let mut matches = Vec::new();
             let mut pattern = "".to_string();
-            for entry in entries.iter().chain(create_entry.iter()) {
+            for entry in entries
+                .iter()
+                .chain(output_entries.iter())
+                .chain(create_entry.iter())
+            {
                 if entry.sticky {
                     let name_len = entry.name.len();
                     matches.push(FuzzyMatch {
@@ -405,7 +464,8 @@ impl FuzzyFinderService {
 
             tx.send(FuzzyMatchState {
                 matches,
-                total: entries.len() + create_entry.iter().count(),
+                total: entries.len() + output_entries.len() + create_entry.iter().count(),
+                output,
             })
             .await?;
         }
@@ -554,6 +614,7 @@ impl FuzzyFinderService {
         let mut query_state = Arc::new(FuzzyQueryState::default());
         let mut match_state = Arc::new(vec![]);
         let mut total = 0usize;
+        let mut output = false;
         let mut doc_index = 4;
         let mut select_state = Arc::new(None);
 
@@ -570,6 +631,7 @@ impl FuzzyFinderService {
                 }
                 OutputRecv::Matches(matches) => {
                     total = matches.total;
+                    output = matches.output;
 
                     doc_index = matches
                         .matches
@@ -584,9 +646,10 @@ impl FuzzyFinderService {
                         .matches
                         .into_iter()
                         .map(|mat| {
+                            let alert = mat.tab.alert;
                             let (name, doc) = Self::parse(mat, doc_index);
 
-                            FuzzyOutputMatch { name, doc }
+                            FuzzyOutputMatch { name, doc, alert }
                         })
                         .collect();
 
@@ -608,6 +671,7 @@ impl FuzzyFinderService {
                 select_state: select_state.clone(),
                 matches: match_state.clone(),
                 total,
+                output,
             };
 
             tx_state.send(event).await.ok();
@@ -649,6 +713,9 @@ impl FuzzyFinderService {
         stdout.queue(PrintStyledContent(matches.len().to_string().bold()))?;
         stdout.queue(PrintStyledContent("/".bold()))?;
         stdout.queue(PrintStyledContent(state.total.to_string().bold()))?;
+        if state.output {
+            stdout.queue(Print("  (matching output, ctrl-O to toggle)".dark_grey()))?;
+        }
         stdout.queue(Clear(ClearType::UntilNewLine))?;
 
         for (row, output_match) in (RESERVED_ROWS..terminal_height as usize).zip(matches.iter()) {
@@ -667,7 +734,7 @@ impl FuzzyFinderService {
                 }
             } else {
                 stdout.queue(Print("  "))?;
-                Self::print_tab(stdout, name)?;
+                Self::print_tab(stdout, name, output_match.alert.is_some())?;
 
                 if let Some(doc) = doc {
                     Self::print_doc(stdout, doc)?;
@@ -717,14 +784,23 @@ impl FuzzyFinderService {
         Ok(())
     }
 
-    fn print_tab(stdout: &mut std::io::Stdout, tokens: &[Token]) -> anyhow::Result<()> {
+    /// Prints an unselected tab name.  Tabs with an alert from the tab monitor are highlighted.
+    fn print_tab(
+        stdout: &mut std::io::Stdout,
+        tokens: &[Token],
+        alert: bool,
+    ) -> anyhow::Result<()> {
         for token in tokens.iter() {
-            match token {
-                Token::Unmatched(s) => stdout.queue(PrintStyledContent(s.as_str().bold()))?,
-                Token::Matched(s) => {
-                    stdout.queue(PrintStyledContent(s.as_str().bold().underlined()))?
-                }
+            let content = match token {
+                Token::Unmatched(s) => s.as_str().bold(),
+                Token::Matched(s) => s.as_str().bold().underlined(),
             };
+
+            if alert {
+                stdout.queue(PrintStyledContent(content.yellow()))?;
+            } else {
+                stdout.queue(PrintStyledContent(content))?;
+            }
         }
 
         stdout.queue(Clear(ClearType::UntilNewLine))?;
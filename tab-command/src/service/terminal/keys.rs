@@ -0,0 +1,439 @@
+use thiserror::Error;
+
+pub const ESC: u8 = 0x1b;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum KeyParseError {
+    #[error("Invalid `ctrl-X` sequence: {0} - char '{1}' is not an ASCII control character")]
+    InvalidCtrlCharacter(String, char),
+    #[error("Invalid sequence: '{0}' - use spaces to separate individual characters")]
+    InvalidSequence(String),
+}
+
+/// A key, parsed from the notation used in key bindings.
+///
+/// Keys are written as a character or key name, with optional `ctrl-`, `alt-` and `shift-` modifiers.
+/// Examples: `a`, `ctrl-T`, `alt-t`, `ctrl-space`, `f12`, `pageup`, `ctrl-shift-left`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: Modifiers,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyCode {
+    Char(char),
+    Space,
+    Escape,
+    Enter,
+    Tab,
+    Backspace,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Insert,
+    Delete,
+    PageUp,
+    PageDown,
+    /// The function keys F1 through F12
+    Function(u8),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Modifiers {
+    shift: bool,
+    alt: bool,
+    ctrl: bool,
+}
+
+impl Modifiers {
+    fn is_empty(&self) -> bool {
+        !self.shift && !self.alt && !self.ctrl
+    }
+
+    /// The modifier parameter used by xterm & CSI-u sequences
+    fn param(&self) -> u8 {
+        1 + self.shift as u8 + 2 * self.alt as u8 + 4 * self.ctrl as u8
+    }
+}
+
+impl Key {
+    pub fn parse(entry: &str) -> Result<Self, KeyParseError> {
+        let entry = entry.trim().to_lowercase();
+
+        let mut modifiers = Modifiers::default();
+        let mut name = entry.as_str();
+        loop {
+            if let Some(rest) = strip_modifier(name, &["ctrl-"]) {
+                modifiers.ctrl = true;
+                name = rest;
+            } else if let Some(rest) = strip_modifier(name, &["alt-", "meta-"]) {
+                modifiers.alt = true;
+                name = rest;
+            } else if let Some(rest) = strip_modifier(name, &["shift-"]) {
+                modifiers.shift = true;
+                name = rest;
+            } else {
+                break;
+            }
+        }
+
+        let code = match name {
+            "esc" | "escape" => KeyCode::Escape,
+            "space" => KeyCode::Space,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "insert" => KeyCode::Insert,
+            "delete" => KeyCode::Delete,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            _ => {
+                let function = name
+                    .strip_prefix('f')
+                    .and_then(|n| n.parse::<u8>().ok())
+                    .filter(|n| (1..=12).contains(n));
+
+                let mut chars = name.chars();
+                match (function, chars.next(), chars.next()) {
+                    (Some(n), _, _) => KeyCode::Function(n),
+                    (None, Some(ch), None) => KeyCode::Char(ch),
+                    _ => return Err(KeyParseError::InvalidSequence(entry)),
+                }
+            }
+        };
+
+        if let (KeyCode::Char(ch), true) = (code, modifiers.ctrl) {
+            let ch = ch.to_ascii_uppercase();
+            if !('@'..='_').contains(&ch) {
+                return Err(KeyParseError::InvalidCtrlCharacter(entry, ch));
+            }
+        }
+
+        Ok(Self { code, modifiers })
+    }
+
+    /// The byte sequences which the terminal may send for the key.
+    /// The first is the traditional xterm encoding, if the key has one.
+    /// The others are alternate encodings, such as the application cursor mode, or the kitty keyboard protocol (CSI-u).
+    pub fn encodings(&self) -> Vec<Vec<u8>> {
+        let mut encodings = self.xterm();
+
+        if let Some(csi_u) = self.csi_u() {
+            encodings.push(csi_u);
+        }
+
+        let mut unique = Vec::with_capacity(encodings.len());
+        for encoding in encodings {
+            if !unique.contains(&encoding) {
+                unique.push(encoding);
+            }
+        }
+
+        unique
+    }
+
+    fn xterm(&self) -> Vec<Vec<u8>> {
+        let modifiers = self.modifiers;
+
+        // keys which are sent as text, and prefixed with ESC when alt is held
+        let text = match self.code {
+            KeyCode::Char(ch) if modifiers.ctrl => Some(vec![ch.to_ascii_uppercase() as u8 - 64]),
+            KeyCode::Char(ch) if modifiers.shift => Some(encode_char(ch.to_ascii_uppercase())),
+            KeyCode::Char(ch) => Some(encode_char(ch)),
+            KeyCode::Space if modifiers.ctrl => Some(vec![0]),
+            KeyCode::Space if !modifiers.shift => Some(vec![b' ']),
+            KeyCode::Escape if !modifiers.ctrl && !modifiers.shift => Some(vec![ESC]),
+            KeyCode::Enter if !modifiers.ctrl && !modifiers.shift => Some(vec![b'\r']),
+            KeyCode::Tab if !modifiers.ctrl && !modifiers.shift => Some(vec![b'\t']),
+            KeyCode::Backspace if modifiers.ctrl && !modifiers.shift => Some(vec![0x08]),
+            KeyCode::Backspace if !modifiers.shift => Some(vec![0x7f]),
+            _ => None,
+        };
+
+        if let Some(text) = text {
+            return if modifiers.alt {
+                vec![[&[ESC], text.as_slice()].concat()]
+            } else {
+                vec![text]
+            };
+        }
+
+        let param = modifiers.param();
+        match self.code {
+            KeyCode::Tab if modifiers.shift && !modifiers.ctrl && !modifiers.alt => {
+                vec![b"\x1b[Z".to_vec()]
+            }
+            KeyCode::Up => cursor(b'A', modifiers),
+            KeyCode::Down => cursor(b'B', modifiers),
+            KeyCode::Right => cursor(b'C', modifiers),
+            KeyCode::Left => cursor(b'D', modifiers),
+            KeyCode::Home => cursor(b'H', modifiers),
+            KeyCode::End => cursor(b'F', modifiers),
+            KeyCode::Insert => vec![tilde(2, param)],
+            KeyCode::Delete => vec![tilde(3, param)],
+            KeyCode::PageUp => vec![tilde(5, param)],
+            KeyCode::PageDown => vec![tilde(6, param)],
+            KeyCode::Function(n @ 1..=4) => {
+                let letter = b'P' + (n - 1);
+                if !modifiers.is_empty() {
+                    // the kitty keyboard protocol sends F3 as CSI 13 ~, as CSI R is a cursor position report
+                    let mut encodings =
+                        vec![format!("\x1b[1;{}{}", param, letter as char).into_bytes()];
+                    if n == 3 {
+                        encodings.push(tilde(13, param));
+                    }
+
+                    return encodings;
+                }
+
+                // function keys are sent as SS3 X, and as CSI X by the kitty keyboard protocol
+                match n {
+                    3 => vec![vec![ESC, b'O', letter], tilde(13, param)],
+                    _ => vec![vec![ESC, b'O', letter], vec![ESC, b'[', letter]],
+                }
+            }
+            KeyCode::Function(n) => {
+                let number = match n {
+                    5 => 15,
+                    6 => 17,
+                    7 => 18,
+                    8 => 19,
+                    9 => 20,
+                    10 => 21,
+                    11 => 23,
+                    _ => 24,
+                };
+
+                vec![tilde(number, param)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The CSI-u encoding of the key, which is used by the kitty keyboard protocol.
+    /// Keys which are unambiguous in the xterm encoding are sent as text, and have no CSI-u encoding.
+    fn csi_u(&self) -> Option<Vec<u8>> {
+        let modifiers = self.modifiers;
+
+        let code = match self.code {
+            KeyCode::Char(ch) if modifiers.ctrl || modifiers.alt => ch as u32,
+            KeyCode::Char(_) => return None,
+            KeyCode::Space if !modifiers.is_empty() => 32,
+            KeyCode::Escape => 27,
+            KeyCode::Enter if !modifiers.is_empty() => 13,
+            KeyCode::Tab if !modifiers.is_empty() => 9,
+            KeyCode::Backspace if !modifiers.is_empty() => 127,
+            _ => return None,
+        };
+
+        let encoding = if modifiers.is_empty() {
+            format!("\x1b[{}u", code)
+        } else {
+            format!("\x1b[{};{}u", code, modifiers.param())
+        };
+
+        Some(encoding.into_bytes())
+    }
+}
+
+/// Parses a sequence of keys, separated by spaces
+pub fn parse_keys(seq: &str) -> Result<Vec<Key>, KeyParseError> {
+    seq.split(' ')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(Key::parse)
+        .collect()
+}
+
+/// The length of the first key in the encoded input
+pub fn key_len(input: &[u8]) -> usize {
+    match input {
+        [] => 0,
+        // CSI sequences end with a byte in the range @ to ~
+        [ESC, b'[', rest @ ..] => rest
+            .iter()
+            .position(|byte| (0x40..=0x7e).contains(byte))
+            .map(|end| end + 3)
+            .unwrap_or(input.len()),
+        [ESC, b'O', _, ..] => 3,
+        // alt is sent as an ESC prefix
+        [ESC, rest @ ..] if !rest.is_empty() => 1 + key_len(rest),
+        [byte, ..] => {
+            let len = match byte {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 1,
+            };
+
+            len.min(input.len())
+        }
+    }
+}
+
+fn strip_modifier<'a>(name: &'a str, prefixes: &[&str]) -> Option<&'a str> {
+    prefixes
+        .iter()
+        .filter_map(|prefix| name.strip_prefix(prefix))
+        .find(|rest| !rest.is_empty())
+}
+
+fn encode_char(ch: char) -> Vec<u8> {
+    let mut buffer = [0u8; 4];
+    ch.encode_utf8(&mut buffer).as_bytes().to_vec()
+}
+
+/// Cursor keys are sent as CSI X, or SS3 X in application cursor mode, and as CSI 1 ; modifiers X when modified
+fn cursor(letter: u8, modifiers: Modifiers) -> Vec<Vec<u8>> {
+    if modifiers.is_empty() {
+        vec![vec![ESC, b'[', letter], vec![ESC, b'O', letter]]
+    } else {
+        vec![format!("\x1b[1;{}{}", modifiers.param(), letter as char).into_bytes()]
+    }
+}
+
+/// Editing & function keys are sent as CSI number ~, and as CSI number ; modifiers ~ when modified
+fn tilde(number: u8, param: u8) -> Vec<u8> {
+    if param == 1 {
+        format!("\x1b[{}~", number).into_bytes()
+    } else {
+        format!("\x1b[{};{}~", number, param).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{key_len, parse_keys, Key, KeyParseError};
+
+    fn encodings(entry: &str) -> Vec<Vec<u8>> {
+        Key::parse(entry).expect("parse failed").encodings()
+    }
+
+    #[test]
+    fn char_keys() {
+        assert_eq!(vec![b"a".to_vec()], encodings("a"));
+        assert_eq!(vec![b"A".to_vec()], encodings("shift-a"));
+        assert_eq!(vec![b"-".to_vec()], encodings("-"));
+    }
+
+    #[test]
+    fn ctrl_keys() {
+        assert_eq!(
+            vec![vec![0x14], b"\x1b[116;5u".to_vec()],
+            encodings("ctrl-T")
+        );
+        assert_eq!(
+            vec![vec![0], b"\x1b[32;5u".to_vec()],
+            encodings("ctrl-space")
+        );
+        assert_eq!(
+            vec![vec![0x08], b"\x1b[127;5u".to_vec()],
+            encodings("ctrl-backspace")
+        );
+    }
+
+    #[test]
+    fn alt_keys() {
+        assert_eq!(
+            vec![b"\x1bt".to_vec(), b"\x1b[116;3u".to_vec()],
+            encodings("alt-t")
+        );
+        assert_eq!(encodings("alt-t"), encodings("meta-t"));
+        assert_eq!(
+            vec![vec![0x1b, 0x14], b"\x1b[116;7u".to_vec()],
+            encodings("ctrl-alt-t")
+        );
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(vec![vec![0x1b], b"\x1b[27u".to_vec()], encodings("esc"));
+        assert_eq!(vec![b"\x1b[27;2u".to_vec()], encodings("shift-escape"));
+    }
+
+    #[test]
+    fn arrows() {
+        assert_eq!(
+            vec![b"\x1b[D".to_vec(), b"\x1bOD".to_vec()],
+            encodings("left")
+        );
+        assert_eq!(vec![b"\x1b[1;6D".to_vec()], encodings("ctrl-shift-left"));
+        assert_eq!(encodings("ctrl-shift-left"), encodings("shift-ctrl-left"));
+        assert_eq!(vec![b"\x1b[1;3A".to_vec()], encodings("alt-up"));
+    }
+
+    #[test]
+    fn function_keys() {
+        assert_eq!(
+            vec![b"\x1bOP".to_vec(), b"\x1b[P".to_vec()],
+            encodings("f1")
+        );
+        assert_eq!(
+            vec![b"\x1b[1;5R".to_vec(), b"\x1b[13;5~".to_vec()],
+            encodings("ctrl-f3")
+        );
+        assert_eq!(
+            vec![b"\x1bOR".to_vec(), b"\x1b[13~".to_vec()],
+            encodings("f3")
+        );
+        assert_eq!(vec![b"\x1b[15~".to_vec()], encodings("f5"));
+        assert_eq!(vec![b"\x1b[24~".to_vec()], encodings("F12"));
+        assert_eq!(vec![b"\x1b[24;2~".to_vec()], encodings("shift-f12"));
+    }
+
+    #[test]
+    fn editing_keys() {
+        assert_eq!(vec![b"\x1b[5~".to_vec()], encodings("pageup"));
+        assert_eq!(vec![b"\x1b[3;5~".to_vec()], encodings("ctrl-delete"));
+        assert_eq!(
+            vec![b"\x1b[Z".to_vec(), b"\x1b[9;2u".to_vec()],
+            encodings("shift-tab")
+        );
+        assert_eq!(vec![b"\r".to_vec()], encodings("enter"));
+    }
+
+    #[test]
+    fn invalid_keys() {
+        assert_eq!(
+            Err(KeyParseError::InvalidSequence("f13".to_string())),
+            Key::parse("f13")
+        );
+
+        assert_eq!(
+            Err(KeyParseError::InvalidSequence("ctrl-bad".to_string())),
+            Key::parse("ctrl-bad")
+        );
+
+        assert_eq!(
+            Err(KeyParseError::InvalidSequence("super-a".to_string())),
+            Key::parse("super-a")
+        );
+    }
+
+    #[test]
+    fn sequence() -> Result<(), KeyParseError> {
+        let keys = parse_keys("ctrl-A  alt-t f12")?;
+        assert_eq!(3, keys.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn first_key_len() {
+        assert_eq!(1, key_len(&[0x01, 0x01]));
+        assert_eq!(2, key_len(b"\x1bta"));
+        assert_eq!(7, key_len(b"\x1b[97;5u\x1b[97;5u"));
+        assert_eq!(3, key_len(b"\x1bOPa"));
+        assert_eq!(2, key_len("éa".as_bytes()));
+        assert_eq!(1, key_len(b"\x1b"));
+    }
+}
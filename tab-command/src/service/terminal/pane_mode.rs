@@ -0,0 +1,457 @@
+use std::{collections::HashMap, io::Write};
+
+use crate::{
+    config::{load_status_bar_config, Action, StatusBarConfig},
+    message::terminal::{TerminalInput, TerminalOutput, TerminalSend, TerminalShutdown},
+    prelude::*,
+    state::{
+        pane::{Arrangement, PaneLayout, Rect, SplitDirection},
+        tabs::ActiveTabsState,
+        terminal::{TerminalMode, TerminalSizeState},
+    },
+};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    style::{Print, PrintStyledContent, Stylize},
+    QueueableCommand,
+};
+use postage::{mpsc, watch};
+use tab_api::{emulator::TerminalEmulator, tab::TabId};
+use tokio::{select, time};
+
+use super::{
+    echo_input::{key_bindings, read_input, InputFilter, KeyBindings},
+    scroll_mode::AlternateScreen,
+    status_bar::{clock, queue_status_line, status_line, REDRAW_INTERVAL},
+    tab_cycle::target_tab,
+};
+
+/// Renders several tabs in panes, in the alternate screen.
+///
+/// - Maintains a terminal emulator for each pane, and draws the screen of each emulator within the pane.
+/// - Sizes each tab to fit it's pane, and re-arranges the panes when the terminal or the layout changes.
+/// - Sends stdin to the focused pane.  The FocusNextPane and FocusPreviousPane actions move the focus.
+/// - The CloseTab action closes the focused tab, and the tab selection actions select a tab relative to the focused tab.
+/// - If enabled, draws the status bar below the panes, with the focused tab as the current tab.
+pub struct TerminalPaneService {
+    _input: Lifeline,
+    _output: Lifeline,
+}
+
+impl Service for TerminalPaneService {
+    type Bus = TerminalBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let (tx_focus, rx_focus) = mpsc::channel(16);
+
+        let _output = {
+            let rx_mode = bus.rx::<TerminalMode>()?;
+            let rx_output = bus.rx::<TerminalOutput>()?;
+            let rx_size = bus.rx::<TerminalSizeState>()?;
+            let rx_tabs = bus.rx::<Option<ActiveTabsState>>()?;
+            let tx_input = bus.tx::<TerminalInput>()?;
+
+            Self::try_task(
+                "output",
+                draw_panes(rx_mode, rx_output, rx_size, rx_tabs, rx_focus, tx_input),
+            )
+        };
+
+        let _input = {
+            let rx_mode = bus.rx::<TerminalMode>()?;
+            let rx_tabs = bus.rx::<Option<ActiveTabsState>>()?;
+            let tx_input = bus.tx::<TerminalInput>()?;
+            let tx_terminal = bus.tx::<TerminalSend>()?;
+            let tx_shutdown = bus.tx::<TerminalShutdown>()?;
+
+            Self::try_task(
+                "input",
+                forward_stdin(
+                    rx_mode,
+                    rx_tabs,
+                    tx_focus,
+                    tx_input,
+                    tx_terminal,
+                    tx_shutdown,
+                ),
+            )
+        };
+
+        Ok(Self { _input, _output })
+    }
+}
+
+/// The tabs in the current pane layout
+fn layout_tabs(rx_mode: &watch::Receiver<TerminalMode>) -> Vec<TabId> {
+    match *rx_mode.borrow() {
+        TerminalMode::Panes(ref layout) => layout.tabs().into_iter().copied().collect(),
+        _ => Vec::new(),
+    }
+}
+
+async fn forward_stdin(
+    rx_mode: watch::Receiver<TerminalMode>,
+    rx_tabs: watch::Receiver<Option<ActiveTabsState>>,
+    mut tx_focus: impl Sink<Item = TabId> + Unpin,
+    mut tx: impl Sink<Item = TerminalInput> + Unpin,
+    mut tx_terminal: impl Sink<Item = TerminalSend> + Unpin,
+    mut tx_shutdown: impl Sink<Item = TerminalShutdown> + Unpin,
+) -> anyhow::Result<()> {
+    let mut stdin = tokio::io::stdin();
+    let mut buffer = vec![0u8; 512];
+
+    let key_bindings = match key_bindings() {
+        Ok(bindings) => bindings,
+        Err(e) => {
+            eprintln!("Warning: using default keybindings.  failed to parse key bindings in global config: {}", e);
+            KeyBindings::default()
+        }
+    };
+
+    let mut filter: InputFilter = key_bindings.into();
+    let mut focus: Option<TabId> = None;
+
+    while let Some(input) = read_input(&mut stdin, buffer.as_mut_slice(), &mut filter).await {
+        // the focused pane is closed when it's tab terminates
+        let tabs = layout_tabs(&rx_mode);
+        let index = focus
+            .and_then(|id| tabs.iter().position(|tab| *tab == id))
+            .unwrap_or(0);
+
+        let focused = tabs.get(index).copied();
+        if let Some(id) = focused {
+            if !input.data.is_empty() {
+                tx.send(TerminalInput::PaneStdin(id, input.data)).await?;
+            }
+        }
+
+        let action = match input.action {
+            Some(action) => action,
+            None => continue,
+        };
+
+        // tab selection actions leave the panes, and select the tab relative to the focused pane
+        let active_tabs = rx_tabs.borrow().clone().unwrap_or_default();
+        if let Some(name) = target_tab(&action, &mut None, &active_tabs, focused) {
+            tx_terminal.send(TerminalSend::SelectTab(name)).await?;
+            break;
+        }
+
+        match action {
+            Action::Disconnect => {
+                tx_shutdown.send(TerminalShutdown {}).await?;
+                break;
+            }
+            Action::SelectInteractive => {
+                tx_terminal.send(TerminalSend::FuzzyRequest).await?;
+                break;
+            }
+            Action::DetachAndRun(command) => {
+                tx_terminal
+                    .send(TerminalSend::DetachAndRun(command))
+                    .await?;
+                break;
+            }
+            Action::CloseTab => {
+                // the pane is closed when the tab terminates
+                if let Some(id) = focused {
+                    tx_terminal.send(TerminalSend::CloseTab(id)).await?;
+                }
+            }
+            Action::FocusNextPane if !tabs.is_empty() => {
+                let id = tabs[(index + 1) % tabs.len()];
+                focus = Some(id);
+                tx_focus.send(id).await?;
+            }
+            Action::FocusPreviousPane if !tabs.is_empty() => {
+                let id = tabs[(index + tabs.len() - 1) % tabs.len()];
+                focus = Some(id);
+                tx_focus.send(id).await?;
+            }
+            // scroll mode pages through a single tab, and is not available in panes
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+async fn draw_panes(
+    mut rx_mode: watch::Receiver<TerminalMode>,
+    mut rx_output: impl Stream<Item = TerminalOutput> + Unpin,
+    mut rx_size: watch::Receiver<TerminalSizeState>,
+    mut rx_tabs: watch::Receiver<Option<ActiveTabsState>>,
+    mut rx_focus: impl Stream<Item = TabId> + Unpin,
+    mut tx_input: impl Sink<Item = TerminalInput> + Unpin,
+) -> anyhow::Result<()> {
+    let mut stdout = std::io::stdout();
+    let _screen = AlternateScreen::enter()?;
+
+    let mut panes = Panes::new(rx_size.borrow().0, load_status_bar_config());
+    let mut clock_tick = time::interval(REDRAW_INTERVAL);
+
+    let mode = rx_mode.borrow().clone();
+    if let TerminalMode::Panes(layout) = mode {
+        for (id, size) in panes.set_layout(layout) {
+            tx_input.send(TerminalInput::PaneResize(id, size)).await?;
+        }
+
+        panes.draw(&mut stdout)?;
+    }
+
+    loop {
+        select! {
+            mode = rx_mode.recv() => match mode {
+                Some(TerminalMode::Panes(layout)) => {
+                    for (id, size) in panes.set_layout(layout) {
+                        tx_input.send(TerminalInput::PaneResize(id, size)).await?;
+                    }
+
+                    panes.draw(&mut stdout)?;
+                }
+                Some(_) => {}
+                None => break,
+            },
+            size = rx_size.recv() => match size {
+                Some(TerminalSizeState(size)) => {
+                    for (id, size) in panes.resize(size) {
+                        tx_input.send(TerminalInput::PaneResize(id, size)).await?;
+                    }
+
+                    panes.draw(&mut stdout)?;
+                }
+                None => break,
+            },
+            tabs = rx_tabs.recv(), if panes.status_bar.enabled => match tabs {
+                Some(Some(tabs)) => {
+                    panes.tabs = tabs;
+                    panes.draw_status_bar(&mut stdout)?;
+                }
+                Some(None) => {}
+                None => break,
+            },
+            _ = clock_tick.tick(), if panes.status_bar.enabled => {
+                panes.draw_status_bar(&mut stdout)?;
+            },
+            focus = rx_focus.recv() => match focus {
+                Some(id) => {
+                    panes.focus = Some(id);
+                    panes.draw(&mut stdout)?;
+                }
+                None => break,
+            },
+            output = rx_output.recv() => match output {
+                Some(TerminalOutput::Stdout(id, data)) => {
+                    panes.output(id, data.as_slice());
+                    panes.draw_pane(&mut stdout, id)?;
+                }
+                None => break,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The emulated screens of the panes, and their arrangement within the terminal
+struct Panes {
+    layout: Option<PaneLayout<TabId>>,
+    size: (u16, u16),
+    focus: Option<TabId>,
+    emulators: HashMap<TabId, TerminalEmulator>,
+    status_bar: StatusBarConfig,
+    tabs: ActiveTabsState,
+}
+
+impl Panes {
+    pub fn new(size: (u16, u16), status_bar: StatusBarConfig) -> Self {
+        Self {
+            layout: None,
+            size,
+            focus: None,
+            emulators: HashMap::new(),
+            status_bar,
+            tabs: ActiveTabsState::default(),
+        }
+    }
+
+    /// Replaces the layout, and returns the new size of each pane
+    pub fn set_layout(&mut self, layout: PaneLayout<TabId>) -> Vec<(TabId, (u16, u16))> {
+        let tabs = layout.tabs();
+        self.emulators.retain(|id, _| tabs.contains(&id));
+        self.layout = Some(layout);
+
+        self.resize(self.size)
+    }
+
+    /// Resizes the terminal, and returns the new size of each pane
+    pub fn resize(&mut self, size: (u16, u16)) -> Vec<(TabId, (u16, u16))> {
+        self.size = size;
+
+        let sizes: Vec<_> = self
+            .arrangement()
+            .panes
+            .into_iter()
+            .map(|(id, rect)| (*id, (rect.cols.max(1), rect.rows.max(1))))
+            .collect();
+
+        for (id, size) in sizes.iter() {
+            self.emulators
+                .entry(*id)
+                .and_modify(|emulator| emulator.resize(*size))
+                .or_insert_with(|| TerminalEmulator::new(*size));
+        }
+
+        sizes
+    }
+
+    pub fn output(&mut self, id: TabId, data: &[u8]) {
+        if let Some(emulator) = self.emulators.get_mut(&id) {
+            emulator.process(data);
+        }
+    }
+
+    fn arrangement(&self) -> Arrangement<&TabId> {
+        let (cols, rows) = self.status_bar.tab_size(self.size);
+        let rect = Rect::new(0, 0, cols, rows);
+
+        self.layout
+            .as_ref()
+            .map(|layout| layout.arrange(rect))
+            .unwrap_or_default()
+    }
+
+    /// The index of the focused pane in the arrangement.  The first pane is focused by default.
+    fn focused(arrangement: &Arrangement<&TabId>, focus: Option<TabId>) -> usize {
+        focus
+            .and_then(|focus| arrangement.panes.iter().position(|(id, _)| **id == focus))
+            .unwrap_or(0)
+    }
+
+    /// Draws the panes and dividers
+    pub fn draw(&self, stdout: &mut impl Write) -> anyhow::Result<()> {
+        let arrangement = self.arrangement();
+        let focused = Self::focused(&arrangement, self.focus);
+
+        stdout.queue(Hide)?;
+
+        for (id, rect) in arrangement.panes.iter() {
+            self.queue_pane(stdout, **id, *rect)?;
+        }
+
+        for divider in arrangement.dividers.iter() {
+            let line = match divider.direction {
+                SplitDirection::Horizontal => "│",
+                SplitDirection::Vertical => "─",
+            };
+
+            // the dividers around the focused pane are highlighted
+            let line = if divider.adjacent.contains(&focused) {
+                line.green()
+            } else {
+                line.dark_grey()
+            };
+
+            for row in 0..divider.rect.rows {
+                for col in 0..divider.rect.cols {
+                    stdout.queue(MoveTo(divider.rect.col + col, divider.rect.row + row))?;
+                    stdout.queue(PrintStyledContent(line))?;
+                }
+            }
+        }
+
+        self.queue_status_bar(stdout, &arrangement, focused)?;
+        self.queue_cursor(stdout, &arrangement, focused)?;
+        stdout.flush()?;
+
+        Ok(())
+    }
+
+    /// Draws the status bar, if enabled
+    pub fn draw_status_bar(&self, stdout: &mut impl Write) -> anyhow::Result<()> {
+        let arrangement = self.arrangement();
+        let focused = Self::focused(&arrangement, self.focus);
+
+        stdout.queue(Hide)?;
+        self.queue_status_bar(stdout, &arrangement, focused)?;
+        self.queue_cursor(stdout, &arrangement, focused)?;
+        stdout.flush()?;
+
+        Ok(())
+    }
+
+    /// Draws the pane which displays the tab
+    pub fn draw_pane(&self, stdout: &mut impl Write, id: TabId) -> anyhow::Result<()> {
+        let arrangement = self.arrangement();
+        let focused = Self::focused(&arrangement, self.focus);
+
+        let rect = match arrangement.panes.iter().find(|(tab, _)| **tab == id) {
+            Some((_, rect)) => *rect,
+            None => return Ok(()),
+        };
+
+        stdout.queue(Hide)?;
+        self.queue_pane(stdout, id, rect)?;
+        self.queue_cursor(stdout, &arrangement, focused)?;
+        stdout.flush()?;
+
+        Ok(())
+    }
+
+    fn queue_pane(&self, stdout: &mut impl Write, id: TabId, rect: Rect) -> anyhow::Result<()> {
+        let emulator = match self.emulators.get(&id) {
+            Some(emulator) if rect.cols > 0 => emulator,
+            _ => return Ok(()),
+        };
+
+        for (row, line) in emulator
+            .rows()
+            .into_iter()
+            .take(rect.rows as usize)
+            .enumerate()
+        {
+            stdout.queue(MoveTo(rect.col, rect.row + row as u16))?;
+            stdout.queue(Print(line))?;
+        }
+
+        Ok(())
+    }
+
+    fn queue_status_bar(
+        &self,
+        stdout: &mut impl Write,
+        arrangement: &Arrangement<&TabId>,
+        focused: usize,
+    ) -> anyhow::Result<()> {
+        if !self.status_bar.enabled || self.size.1 < 2 {
+            return Ok(());
+        }
+
+        let current = arrangement.panes.get(focused).map(|(id, _)| **id);
+        let clock = clock(&self.status_bar);
+        let line = status_line(current, &self.tabs, clock.as_deref(), self.size.0 as usize);
+
+        queue_status_line(stdout, line, self.size.1)
+    }
+
+    /// Places the cursor within the focused pane, if the tab shows it's cursor
+    fn queue_cursor(
+        &self,
+        stdout: &mut impl Write,
+        arrangement: &Arrangement<&TabId>,
+        focused: usize,
+    ) -> anyhow::Result<()> {
+        let cursor = arrangement.panes.get(focused).and_then(|(id, rect)| {
+            let (col, row) = self.emulators.get(id)?.cursor()?;
+            Some((rect.col + col, rect.row + row))
+        });
+
+        if let Some((col, row)) = cursor {
+            stdout.queue(MoveTo(col, row))?;
+            stdout.queue(Show)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,556 @@
+use std::io::Write;
+
+use crate::{
+    message::terminal::{TerminalScrollback, TerminalSend},
+    prelude::*,
+    state::terminal::{TerminalMode, TerminalSizeState},
+};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{Event, EventStream, KeyCode, KeyEvent, KeyModifiers},
+    style::{Print, PrintStyledContent, Stylize},
+    terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    QueueableCommand,
+};
+
+/// Disables line wrapping (DECAWM), so long lines are clipped at the edge of the terminal
+const DISABLE_AUTOWRAP: &str = "\x1b[?7l";
+
+/// Re-enables line wrapping (DECAWM)
+const ENABLE_AUTOWRAP: &str = "\x1b[?7h";
+
+/// Pages through the scrollback of the selected tab, in the alternate screen.
+///
+/// - Requests the rendered lines of the tab's history & screen from the daemon.
+///   Output from the tab is frozen while the pager is active.
+/// - Supports vi-style movement, and searches with `/` and `?`.
+/// - Copies the selected lines to the clipboard with OSC 52.
+/// - On exit, notifies the carrier with TerminalSend::ScrollExit, so the tab can be resumed.
+pub struct TerminalScrollService {
+    _run: Lifeline,
+}
+
+impl Service for TerminalScrollService {
+    type Bus = TerminalBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let rx_mode = bus.rx::<TerminalMode>()?;
+        let mut rx_scrollback = bus.rx::<TerminalScrollback>()?;
+        let rx_size = bus.rx::<TerminalSizeState>()?;
+        let mut tx = bus.tx::<TerminalSend>()?;
+
+        let _run = Self::try_task("run", async move {
+            let id = match *rx_mode.borrow() {
+                TerminalMode::Scroll(id) => id,
+                _ => return Ok(()),
+            };
+
+            tx.send(TerminalSend::ScrollbackRequest(id)).await?;
+
+            let lines = loop {
+                match rx_scrollback.recv().await {
+                    Some(scrollback) if scrollback.id == id => break scrollback.lines,
+                    Some(_) => continue,
+                    None => return Ok(()),
+                }
+            };
+
+            let (_cols, rows) = rx_size.borrow().0;
+            let mut pager = Pager::new(lines, rows);
+
+            let result = {
+                let _screen = AlternateScreen::enter()?;
+                Self::run(&mut pager).await
+            };
+
+            tx.send(TerminalSend::ScrollExit(id)).await?;
+
+            result
+        });
+
+        Ok(Self { _run })
+    }
+}
+
+impl TerminalScrollService {
+    async fn run(pager: &mut Pager) -> anyhow::Result<()> {
+        use futures_util::stream::StreamExt;
+
+        let mut stdout = std::io::stdout();
+        let mut reader = EventStream::new();
+
+        Self::draw(&mut stdout, pager)?;
+
+        while let Some(event) = reader.next().await {
+            let action = match event? {
+                Event::Key(key) => pager.key(key),
+                Event::Resize(_cols, rows) => {
+                    pager.resize(rows);
+                    PagerAction::None
+                }
+                Event::Mouse(_mouse) => continue,
+            };
+
+            match action {
+                PagerAction::None => Self::draw(&mut stdout, pager)?,
+                PagerAction::Copy(text) => {
+                    Self::copy(&mut stdout, text.as_str())?;
+                    return Ok(());
+                }
+                PagerAction::Exit => return Ok(()),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(stdout: &mut std::io::Stdout, pager: &Pager) -> anyhow::Result<()> {
+        for (row, line) in pager.view().into_iter().enumerate() {
+            stdout.queue(MoveTo(0, row as u16))?;
+            stdout.queue(Clear(ClearType::CurrentLine))?;
+
+            match line {
+                ViewLine::Styled(line) => stdout.queue(Print(line))?,
+                ViewLine::Highlighted(text) => stdout.queue(PrintStyledContent(text.reverse()))?,
+                ViewLine::Empty => stdout,
+            };
+        }
+
+        stdout.queue(MoveTo(0, pager.rows as u16))?;
+        stdout.queue(Clear(ClearType::CurrentLine))?;
+        stdout.queue(PrintStyledContent(pager.status().reverse()))?;
+
+        stdout.flush()?;
+
+        Ok(())
+    }
+
+    /// Copies the text to the system clipboard, using the OSC 52 sequence
+    fn copy(stdout: &mut std::io::Stdout, text: &str) -> anyhow::Result<()> {
+        let encoded = base64::encode(text);
+        stdout.queue(Print(format!("\x1b]52;c;{}\x07", encoded)))?;
+        stdout.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Enters the alternate screen with line wrapping disabled, and restores the terminal when dropped.
+pub(super) struct AlternateScreen;
+
+impl AlternateScreen {
+    pub fn enter() -> anyhow::Result<Self> {
+        let mut stdout = std::io::stdout();
+        stdout.queue(EnterAlternateScreen)?;
+        stdout.queue(Hide)?;
+        stdout.queue(Print(DISABLE_AUTOWRAP))?;
+        stdout.flush()?;
+
+        Ok(Self)
+    }
+}
+
+impl Drop for AlternateScreen {
+    fn drop(&mut self) {
+        let mut stdout = std::io::stdout();
+        stdout.queue(Print(ENABLE_AUTOWRAP)).ok();
+        stdout.queue(Show).ok();
+        stdout.queue(LeaveAlternateScreen).ok();
+        stdout.flush().ok();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PagerAction {
+    None,
+    /// Copies the text to the clipboard, and exits scroll mode
+    Copy(String),
+    Exit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ViewLine<'a> {
+    /// A line of output, with the original graphic rendition
+    Styled(&'a str),
+    /// The cursor line, or a selected line, which is drawn in reverse video
+    Highlighted(&'a str),
+    /// A row after the last line of output
+    Empty,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Search {
+    query: String,
+    forward: bool,
+}
+
+/// The state of the scroll mode pager, which is updated by key events
+struct Pager {
+    lines: Vec<String>,
+    /// The lines with ANSI escape sequences removed, for search & copy
+    text: Vec<String>,
+    /// The index of the first visible line
+    top: usize,
+    cursor: usize,
+    /// The line where the selection was started, if a selection is active
+    anchor: Option<usize>,
+    /// The number of visible lines, excluding the status line
+    rows: usize,
+    /// The search that is being entered
+    prompt: Option<Search>,
+    /// The last search, which is repeated with n & N
+    search: Option<Search>,
+    message: Option<String>,
+}
+
+impl Pager {
+    pub fn new(lines: Vec<String>, rows: u16) -> Self {
+        let text = lines
+            .iter()
+            .map(|line| {
+                let stripped = strip_ansi_escapes::strip(line.as_bytes())
+                    .unwrap_or_else(|_| line.as_bytes().to_vec());
+                String::from_utf8_lossy(stripped.as_slice()).to_string()
+            })
+            .collect();
+
+        let mut pager = Self {
+            lines,
+            text,
+            top: 0,
+            cursor: 0,
+            anchor: None,
+            rows: 1,
+            prompt: None,
+            search: None,
+            message: None,
+        };
+
+        pager.resize(rows);
+        pager.move_to(pager.last());
+
+        pager
+    }
+
+    pub fn resize(&mut self, rows: u16) {
+        self.rows = (rows as usize).saturating_sub(1).max(1);
+        self.scroll_to_cursor();
+    }
+
+    pub fn key(&mut self, key: KeyEvent) -> PagerAction {
+        self.message = None;
+
+        if self.prompt.is_some() {
+            self.prompt_key(key);
+            return PagerAction::None;
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let half = (self.rows / 2).max(1) as isize;
+        let page = self.rows as isize;
+
+        match key.code {
+            KeyCode::Char('c') if ctrl => return PagerAction::Exit,
+            KeyCode::Char('d') if ctrl => self.move_by(half),
+            KeyCode::Char('u') if ctrl => self.move_by(-half),
+            KeyCode::Char('f') if ctrl => self.move_by(page),
+            KeyCode::Char('b') if ctrl => self.move_by(-page),
+            KeyCode::Char('j') | KeyCode::Down => self.move_by(1),
+            KeyCode::Char('k') | KeyCode::Up => self.move_by(-1),
+            KeyCode::Char(' ') | KeyCode::PageDown => self.move_by(page),
+            KeyCode::PageUp => self.move_by(-page),
+            KeyCode::Char('g') | KeyCode::Home => self.move_to(0),
+            KeyCode::Char('G') | KeyCode::End => self.move_to(self.last()),
+            KeyCode::Char('/') => {
+                self.prompt = Some(Search {
+                    query: String::new(),
+                    forward: true,
+                })
+            }
+            KeyCode::Char('?') => {
+                self.prompt = Some(Search {
+                    query: String::new(),
+                    forward: false,
+                })
+            }
+            KeyCode::Char('n') => self.search_next(false),
+            KeyCode::Char('N') => self.search_next(true),
+            KeyCode::Char('v') | KeyCode::Char('V') => {
+                self.anchor = match self.anchor {
+                    Some(_) => None,
+                    None => Some(self.cursor),
+                }
+            }
+            KeyCode::Char('y') | KeyCode::Enter => return self.copy(),
+            KeyCode::Esc if self.anchor.is_some() => self.anchor = None,
+            KeyCode::Char('q') | KeyCode::Esc => return PagerAction::Exit,
+            _ => {}
+        }
+
+        PagerAction::None
+    }
+
+    /// The visible rows, excluding the status line
+    pub fn view(&self) -> Vec<ViewLine<'_>> {
+        let (start, end) = self.selection();
+
+        (self.top..self.top + self.rows)
+            .map(|index| {
+                if index >= self.lines.len() {
+                    ViewLine::Empty
+                } else if index >= start && index <= end {
+                    ViewLine::Highlighted(self.text[index].as_str())
+                } else {
+                    ViewLine::Styled(self.lines[index].as_str())
+                }
+            })
+            .collect()
+    }
+
+    pub fn status(&self) -> String {
+        if let Some(ref prompt) = self.prompt {
+            let prefix = if prompt.forward { '/' } else { '?' };
+            return format!("{}{}", prefix, prompt.query);
+        }
+
+        if let Some(ref message) = self.message {
+            return message.clone();
+        }
+
+        let (start, end) = self.selection();
+        let selected = match self.anchor {
+            Some(_) => format!(", {} selected", end + 1 - start),
+            None => "".to_string(),
+        };
+
+        format!(
+            "line {}/{}{}  (q to exit, / to search, v to select, y to copy)",
+            (self.cursor + 1).min(self.lines.len()),
+            self.lines.len(),
+            selected
+        )
+    }
+
+    fn prompt_key(&mut self, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let prompt = match self.prompt {
+            Some(ref mut prompt) => prompt,
+            None => return,
+        };
+
+        match key.code {
+            KeyCode::Char('c') if ctrl => self.prompt = None,
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Backspace if prompt.query.is_empty() => self.prompt = None,
+            KeyCode::Backspace => {
+                prompt.query.pop();
+            }
+            KeyCode::Char(ch) => prompt.query.push(ch),
+            KeyCode::Enter => {
+                let prompt = self.prompt.take().unwrap();
+
+                // an empty query repeats the last search, in the new direction
+                if !prompt.query.is_empty() {
+                    self.search = Some(prompt);
+                } else if let Some(ref mut search) = self.search {
+                    search.forward = prompt.forward;
+                }
+
+                self.search_next(false);
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves the cursor to the next line which matches the last search, wrapping around the ends of the scrollback
+    fn search_next(&mut self, reverse: bool) {
+        let search = match self.search {
+            Some(ref search) => search,
+            None => {
+                self.message = Some("No previous search".to_string());
+                return;
+            }
+        };
+
+        let forward = search.forward != reverse;
+        let len = self.text.len();
+
+        for offset in 1..=len {
+            let index = if forward {
+                (self.cursor + offset) % len
+            } else {
+                (self.cursor + len - offset) % len
+            };
+
+            if self.text[index].contains(search.query.as_str()) {
+                self.move_to(index);
+                return;
+            }
+        }
+
+        self.message = Some(format!("Pattern not found: {}", search.query));
+    }
+
+    fn copy(&self) -> PagerAction {
+        if self.text.is_empty() {
+            return PagerAction::Exit;
+        }
+
+        let (start, end) = self.selection();
+        PagerAction::Copy(self.text[start..=end].join("\n"))
+    }
+
+    /// The inclusive range of selected lines.  If no selection is active, this is the cursor line.
+    fn selection(&self) -> (usize, usize) {
+        match self.anchor {
+            Some(anchor) => (anchor.min(self.cursor), anchor.max(self.cursor)),
+            None => (self.cursor, self.cursor),
+        }
+    }
+
+    fn last(&self) -> usize {
+        self.lines.len().saturating_sub(1)
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let cursor = (self.cursor as isize + delta).clamp(0, self.last() as isize);
+        self.move_to(cursor as usize);
+    }
+
+    fn move_to(&mut self, line: usize) {
+        self.cursor = line.min(self.last());
+        self.scroll_to_cursor();
+    }
+
+    fn scroll_to_cursor(&mut self) {
+        if self.cursor < self.top {
+            self.top = self.cursor;
+        } else if self.cursor >= self.top + self.rows {
+            self.top = self.cursor + 1 - self.rows;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pager, PagerAction, ViewLine};
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn pager(lines: &[&str], rows: u16) -> Pager {
+        Pager::new(lines.iter().map(|line| line.to_string()).collect(), rows)
+    }
+
+    fn key(pager: &mut Pager, code: KeyCode) -> PagerAction {
+        pager.key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn keys(pager: &mut Pager, keys: &str) {
+        for ch in keys.chars() {
+            key(pager, KeyCode::Char(ch));
+        }
+    }
+
+    #[test]
+    fn starts_at_bottom() {
+        let pager = pager(&["one", "two", "three", "four"], 3);
+
+        assert_eq!(3, pager.cursor);
+        assert_eq!(
+            vec![ViewLine::Styled("three"), ViewLine::Highlighted("four")],
+            pager.view()
+        );
+    }
+
+    #[test]
+    fn movement() {
+        let mut pager = pager(&["one", "two", "three", "four"], 3);
+
+        keys(&mut pager, "kkk");
+        assert_eq!(0, pager.cursor);
+        assert_eq!(0, pager.top);
+
+        keys(&mut pager, "k");
+        assert_eq!(0, pager.cursor);
+
+        keys(&mut pager, "G");
+        assert_eq!(3, pager.cursor);
+        assert_eq!(2, pager.top);
+
+        keys(&mut pager, "g");
+        pager.key(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL));
+        assert_eq!(1, pager.cursor);
+    }
+
+    #[test]
+    fn search() {
+        let mut pager = pager(&["error one", "ok", "error two", "ok"], 10);
+
+        keys(&mut pager, "/error");
+        assert_eq!("/error", pager.status());
+        key(&mut pager, KeyCode::Enter);
+        assert_eq!(0, pager.cursor);
+
+        keys(&mut pager, "n");
+        assert_eq!(2, pager.cursor);
+
+        keys(&mut pager, "N");
+        assert_eq!(0, pager.cursor);
+
+        keys(&mut pager, "?missing");
+        key(&mut pager, KeyCode::Enter);
+        assert_eq!(0, pager.cursor);
+        assert_eq!("Pattern not found: missing", pager.status());
+    }
+
+    #[test]
+    fn search_cancel() {
+        let mut pager = pager(&["one", "two"], 10);
+
+        keys(&mut pager, "/o");
+        key(&mut pager, KeyCode::Backspace);
+        key(&mut pager, KeyCode::Backspace);
+        assert!(pager.prompt.is_none());
+
+        keys(&mut pager, "q");
+        assert!(pager.prompt.is_none());
+    }
+
+    #[test]
+    fn copy_selection() {
+        let mut pager = pager(&["one", "\x1b[0;31mtwo\x1b[0m", "three"], 10);
+
+        keys(&mut pager, "kv");
+        keys(&mut pager, "j");
+        assert_eq!(
+            vec![
+                ViewLine::Styled("one"),
+                ViewLine::Highlighted("two"),
+                ViewLine::Highlighted("three"),
+                ViewLine::Empty
+            ],
+            pager.view()[0..4].to_vec()
+        );
+
+        let action = key(&mut pager, KeyCode::Char('y'));
+        assert_eq!(PagerAction::Copy("two\nthree".to_string()), action);
+    }
+
+    #[test]
+    fn copy_cursor_line() {
+        let mut pager = pager(&["one", "two"], 10);
+
+        let action = key(&mut pager, KeyCode::Enter);
+        assert_eq!(PagerAction::Copy("two".to_string()), action);
+    }
+
+    #[test]
+    fn exit() {
+        let mut pager = pager(&["one"], 10);
+
+        keys(&mut pager, "v");
+        assert_eq!(PagerAction::None, key(&mut pager, KeyCode::Esc));
+        assert_eq!(PagerAction::Exit, key(&mut pager, KeyCode::Esc));
+        assert_eq!(PagerAction::Exit, key(&mut pager, KeyCode::Char('q')));
+    }
+}
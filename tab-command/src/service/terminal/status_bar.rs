@@ -0,0 +1,272 @@
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use chrono::Local;
+use crossterm::{
+    cursor::MoveTo,
+    style::{Print, PrintStyledContent, Stylize},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+use tab_api::tab::{TabAlert, TabId, TabMetadata};
+
+use crate::{config::StatusBarConfig, state::tabs::ActiveTabsState};
+
+const SAVE_CURSOR: &str = "\x1b7";
+const RESTORE_CURSOR: &str = "\x1b8";
+const RESET_SCROLL_REGION: &str = "\x1b[r";
+
+/// The status bar is only drawn when the tab hasn't produced output for this duration
+const OUTPUT_IDLE: Duration = Duration::from_millis(50);
+/// The status bar is redrawn at this interval, to update the clock
+pub(super) const REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Renders the status line, for a terminal with the given number of columns.
+/// The current tab is shown first, followed by the other running tabs, and the clock.
+/// Tabs with an alert from the tab monitor are marked with `#` (activity) or `~` (silence).
+pub(super) fn status_line(
+    current: Option<TabId>,
+    tabs: &ActiveTabsState,
+    clock: Option<&str>,
+    cols: usize,
+) -> String {
+    let mut left = String::from(" ");
+
+    if let Some(tab) = current.and_then(|id| tabs.get(&id)) {
+        left += tab.name.as_str();
+        left += " │";
+    }
+
+    let mut others: Vec<&TabMetadata> = tabs
+        .tabs
+        .values()
+        .filter(|tab| Some(tab.id) != current)
+        .collect();
+    others.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for tab in others {
+        left += " ";
+        left += tab.name.as_str();
+
+        match tab.alert {
+            Some(TabAlert::Activity) => left += "#",
+            Some(TabAlert::Silence) => left += "~",
+            None => {}
+        }
+    }
+
+    let right = clock
+        .map(|clock| format!(" {} ", clock))
+        .unwrap_or_default();
+    let right_len = right.chars().count();
+
+    let left: String = left.chars().take(cols.saturating_sub(right_len)).collect();
+    let padding = cols.saturating_sub(left.chars().count() + right_len);
+
+    format!("{}{}{}", left, " ".repeat(padding), right)
+        .chars()
+        .take(cols)
+        .collect()
+}
+
+/// The current time, if the clock is enabled
+pub(super) fn clock(config: &StatusBarConfig) -> Option<String> {
+    if config.clock {
+        Some(Local::now().format("%H:%M").to_string())
+    } else {
+        None
+    }
+}
+
+/// Queues the status line in the bottom row of a terminal with the given number of rows
+pub(super) fn queue_status_line(
+    stdout: &mut impl Write,
+    line: String,
+    rows: u16,
+) -> anyhow::Result<()> {
+    stdout.queue(MoveTo(0, rows.saturating_sub(1)))?;
+    stdout.queue(PrintStyledContent(line.reverse()))?;
+
+    Ok(())
+}
+
+/// The status bar for a single tab, in echo mode.
+///
+/// Output from the tab is written directly to the terminal, so the tab's scroll region is set to the rows above the status bar.
+/// The status bar is only drawn while output is idle, so it doesn't interrupt an escape sequence,
+/// and it is redrawn each second, in case the tab has cleared the screen.
+pub(super) struct EchoStatusBar {
+    config: StatusBarConfig,
+    tab: TabId,
+    size: (u16, u16),
+    tabs: ActiveTabsState,
+    dirty: bool,
+    last_output: Instant,
+    last_draw: Option<Instant>,
+}
+
+impl EchoStatusBar {
+    pub fn new(config: StatusBarConfig, tab: TabId, size: (u16, u16)) -> Self {
+        Self {
+            config,
+            tab,
+            size,
+            tabs: ActiveTabsState::default(),
+            dirty: true,
+            last_output: Instant::now(),
+            last_draw: None,
+        }
+    }
+
+    pub fn select(&mut self, tab: TabId) {
+        self.tab = tab;
+        self.dirty = true;
+    }
+
+    pub fn resize(&mut self, size: (u16, u16)) {
+        self.size = size;
+        self.dirty = true;
+    }
+
+    pub fn update_tabs(&mut self, tabs: ActiveTabsState) {
+        self.tabs = tabs;
+        self.dirty = true;
+    }
+
+    /// Records that output was written to the terminal
+    pub fn output(&mut self) {
+        self.last_output = Instant::now();
+    }
+
+    /// Draws the status bar, if output is idle, and the status bar has changed or is due for a redraw
+    pub fn tick(&mut self, stdout: &mut impl Write) -> anyhow::Result<()> {
+        let now = Instant::now();
+        if now.duration_since(self.last_output) < OUTPUT_IDLE {
+            return Ok(());
+        }
+
+        let due = self
+            .last_draw
+            .map(|last_draw| now.duration_since(last_draw) >= REDRAW_INTERVAL)
+            .unwrap_or(true);
+
+        if !self.dirty && !due {
+            return Ok(());
+        }
+
+        self.draw(stdout)?;
+        self.dirty = false;
+        self.last_draw = Some(now);
+
+        Ok(())
+    }
+
+    fn draw(&self, stdout: &mut impl Write) -> anyhow::Result<()> {
+        let (cols, rows) = self.size;
+        if rows < 2 {
+            return Ok(());
+        }
+
+        let clock = clock(&self.config);
+        let line = status_line(Some(self.tab), &self.tabs, clock.as_deref(), cols as usize);
+
+        // setting the scroll region moves the cursor, so it is saved and restored
+        stdout.queue(Print(SAVE_CURSOR))?;
+        stdout.queue(Print(format!("\x1b[1;{}r", rows - 1)))?;
+        queue_status_line(stdout, line, rows)?;
+        stdout.queue(Print(RESTORE_CURSOR))?;
+        stdout.flush()?;
+
+        Ok(())
+    }
+}
+
+impl Drop for EchoStatusBar {
+    fn drop(&mut self) {
+        let mut stdout = std::io::stdout();
+        stdout.queue(Print(SAVE_CURSOR)).ok();
+        stdout.queue(Print(RESET_SCROLL_REGION)).ok();
+        stdout.queue(MoveTo(0, self.size.1.saturating_sub(1))).ok();
+        stdout.queue(Clear(ClearType::CurrentLine)).ok();
+        stdout.queue(Print(RESTORE_CURSOR)).ok();
+        stdout.flush().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tab_api::tab::{TabAlert, TabId, TabMetadata};
+
+    use super::status_line;
+    use crate::state::tabs::ActiveTabsState;
+
+    fn metadata(id: u16, name: &str, alert: Option<TabAlert>) -> TabMetadata {
+        TabMetadata {
+            id: TabId(id),
+            name: name.into(),
+            doc: None,
+            dimensions: (80, 24),
+            env: HashMap::new(),
+            shell: "bash".into(),
+            dir: "/".into(),
+            command: None,
+            selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert,
+        }
+    }
+
+    fn tabs(tabs: &[(u16, &str, Option<TabAlert>)]) -> ActiveTabsState {
+        let tabs: HashMap<TabId, TabMetadata> = tabs
+            .iter()
+            .map(|(id, name, alert)| (TabId(*id), metadata(*id, name, *alert)))
+            .collect();
+
+        ActiveTabsState {
+            tabs,
+            ..ActiveTabsState::default()
+        }
+    }
+
+    #[test]
+    fn current_tab_first() {
+        let tabs = tabs(&[(0, "b/", None), (1, "c/", None), (2, "a/", None)]);
+        let line = status_line(Some(TabId(1)), &tabs, Some("12:34"), 30);
+
+        assert_eq!(" c/ │ a/ b/             12:34 ", line);
+    }
+
+    #[test]
+    fn alert_markers() {
+        let tabs = tabs(&[
+            (0, "a/", None),
+            (1, "b/", Some(TabAlert::Activity)),
+            (2, "c/", Some(TabAlert::Silence)),
+        ]);
+
+        let line = status_line(Some(TabId(0)), &tabs, None, 20);
+
+        assert_eq!(" a/ │ b/# c/~       ", line);
+    }
+
+    #[test]
+    fn truncated() {
+        let tabs = tabs(&[(0, "current/", None), (1, "other/", None)]);
+        let line = status_line(Some(TabId(0)), &tabs, Some("12:34"), 16);
+
+        assert_eq!(" current/ 12:34 ", line);
+
+        let line = status_line(Some(TabId(0)), &tabs, Some("12:34"), 4);
+        assert_eq!(" 12:", line);
+    }
+}
@@ -0,0 +1,176 @@
+use tab_api::tab::TabId;
+
+use crate::{config::Action, state::tabs::ActiveTabsState};
+
+/// Steps through the running tabs, from most to least recently selected.
+///
+/// Selecting a tab changes the recency order, so the order is captured when the cycle begins,
+/// and repeated NextTab / PrevTab actions step through the captured order.
+#[derive(Debug, Clone)]
+pub(super) struct TabCycle {
+    tabs: Vec<String>,
+    index: usize,
+}
+
+impl TabCycle {
+    pub fn new(tabs: &ActiveTabsState, current: Option<TabId>) -> Self {
+        let mut sorted: Vec<_> = tabs.tabs.values().collect();
+        sorted.sort_by(|a, b| b.selected.cmp(&a.selected).then(a.name.cmp(&b.name)));
+
+        let index = sorted
+            .iter()
+            .position(|tab| Some(tab.id) == current)
+            .unwrap_or(0);
+
+        let tabs = sorted.into_iter().map(|tab| tab.name.clone()).collect();
+
+        Self { tabs, index }
+    }
+
+    /// The next tab in the cycle.  Returns None if there is no other tab.
+    pub fn next(&mut self) -> Option<String> {
+        if self.tabs.len() < 2 {
+            return None;
+        }
+
+        self.index = (self.index + 1) % self.tabs.len();
+        Some(self.tabs[self.index].clone())
+    }
+
+    /// The previous tab in the cycle.  Returns None if there is no other tab.
+    pub fn prev(&mut self) -> Option<String> {
+        if self.tabs.len() < 2 {
+            return None;
+        }
+
+        self.index = (self.index + self.tabs.len() - 1) % self.tabs.len();
+        Some(self.tabs[self.index].clone())
+    }
+}
+
+/// Resolves the tab selected by the action, relative to the current tab.
+/// The cycle is retained between repeated NextTab / PrevTab actions, and cleared by any other action.
+pub(super) fn target_tab(
+    action: &Action,
+    cycle: &mut Option<TabCycle>,
+    tabs: &ActiveTabsState,
+    current: Option<TabId>,
+) -> Option<String> {
+    match action {
+        Action::SelectTab(name) => {
+            *cycle = None;
+            Some(name.clone())
+        }
+        Action::SelectPrevious => {
+            *cycle = None;
+            TabCycle::new(tabs, current).next()
+        }
+        Action::NextTab => cycle
+            .get_or_insert_with(|| TabCycle::new(tabs, current))
+            .next(),
+        Action::PrevTab => cycle
+            .get_or_insert_with(|| TabCycle::new(tabs, current))
+            .prev(),
+        _ => {
+            *cycle = None;
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tab_api::tab::{TabId, TabMetadata};
+
+    use super::{target_tab, TabCycle};
+    use crate::{config::Action, state::tabs::ActiveTabsState};
+
+    fn metadata(id: u16, name: &str, selected: u128) -> TabMetadata {
+        TabMetadata {
+            id: TabId(id),
+            name: name.into(),
+            doc: None,
+            dimensions: (80, 24),
+            env: HashMap::new(),
+            shell: "bash".into(),
+            dir: "/".into(),
+            command: None,
+            selected,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
+        }
+    }
+
+    fn tabs() -> ActiveTabsState {
+        let tabs: HashMap<TabId, TabMetadata> = vec![
+            metadata(0, "a/", 1),
+            metadata(1, "b/", 3),
+            metadata(2, "c/", 2),
+        ]
+        .into_iter()
+        .map(|tab| (tab.id, tab))
+        .collect();
+
+        ActiveTabsState {
+            tabs,
+            ..ActiveTabsState::default()
+        }
+    }
+
+    #[test]
+    fn next_by_recency() {
+        let mut cycle = TabCycle::new(&tabs(), Some(TabId(1)));
+
+        assert_eq!(Some("c/".to_string()), cycle.next());
+        assert_eq!(Some("a/".to_string()), cycle.next());
+        assert_eq!(Some("b/".to_string()), cycle.next());
+    }
+
+    #[test]
+    fn prev_by_recency() {
+        let mut cycle = TabCycle::new(&tabs(), Some(TabId(1)));
+
+        assert_eq!(Some("a/".to_string()), cycle.prev());
+        assert_eq!(Some("c/".to_string()), cycle.prev());
+        assert_eq!(Some("a/".to_string()), cycle.next());
+    }
+
+    #[test]
+    fn single_tab() {
+        let tabs = ActiveTabsState {
+            tabs: vec![(TabId(0), metadata(0, "a/", 1))].into_iter().collect(),
+            ..ActiveTabsState::default()
+        };
+
+        let mut cycle = TabCycle::new(&tabs, Some(TabId(0)));
+        assert_eq!(None, cycle.next());
+        assert_eq!(None, cycle.prev());
+    }
+
+    #[test]
+    fn target() {
+        let tabs = tabs();
+        let mut cycle = None;
+
+        let mut target = |action| target_tab(&action, &mut cycle, &tabs, Some(TabId(1)));
+
+        assert_eq!(Some("c/".to_string()), target(Action::SelectPrevious));
+        assert_eq!(Some("c/".to_string()), target(Action::NextTab));
+        assert_eq!(Some("a/".to_string()), target(Action::NextTab));
+        assert_eq!(Some("c/".to_string()), target(Action::PrevTab));
+        assert_eq!(None, target(Action::ScrollMode));
+        assert_eq!(Some("c/".to_string()), target(Action::NextTab));
+        assert_eq!(
+            Some("d/".to_string()),
+            target(Action::SelectTab("d/".into()))
+        );
+    }
+}
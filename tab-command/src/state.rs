@@ -1,4 +1,5 @@
 pub mod fuzzy;
+pub mod pane;
 pub mod tab;
 pub mod tabs;
 pub mod terminal;
@@ -1,10 +1,16 @@
 use std::sync::Arc;
 
 use lifeline::impl_storage_clone;
-use tab_api::tab::normalize_name;
+use tab_api::{
+    client::GrepMatch,
+    tab::{normalize_name, TabAlert},
+};
 
 use super::workspace::{WorkspaceState, WorkspaceTab};
 
+/// The maximum number of characters of an output line which are displayed & matched
+const OUTPUT_LINE_LEN: usize = 160;
+
 #[derive(Debug, Clone, Default)]
 pub struct FuzzyEscapeState(pub Option<String>);
 impl_storage_clone!(FuzzyEscapeState);
@@ -22,6 +28,12 @@ impl From<WorkspaceState> for FuzzyTabsState {
     }
 }
 
+/// The recent output lines of running tabs, which can be matched by the fuzzy finder
+#[derive(Debug, Clone)]
+pub struct FuzzyOutputLinesState {
+    pub lines: Arc<Vec<GrepMatch>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct FuzzyQueryState {
     pub query: String,
@@ -41,6 +53,8 @@ impl Default for FuzzyQueryState {
 pub struct FuzzyMatchState {
     pub total: usize,
     pub matches: Vec<FuzzyMatch>,
+    /// Whether output lines are being matched, as well as tab names
+    pub output: bool,
 }
 
 impl Default for FuzzyMatchState {
@@ -48,6 +62,7 @@ impl Default for FuzzyMatchState {
         Self {
             total: 0,
             matches: Vec::with_capacity(0),
+            output: false,
         }
     }
 }
@@ -72,12 +87,14 @@ pub struct FuzzyOutputEvent {
     pub select_state: Arc<Option<FuzzySelectState>>,
     pub matches: Arc<Vec<FuzzyOutputMatch>>,
     pub total: usize,
+    pub output: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct FuzzyOutputMatch {
     pub name: Vec<Token>,
     pub doc: Option<Vec<Token>>,
+    pub alert: Option<TabAlert>,
 }
 
 #[derive(Debug, Clone)]
@@ -91,6 +108,8 @@ pub struct TabEntry {
     pub doc: Option<String>,
     pub last_selected: Option<u128>,
     pub sticky: bool,
+    /// The alert raised by the tab monitor, which highlights the entry
+    pub alert: Option<TabAlert>,
 }
 
 impl From<&WorkspaceTab> for TabEntry {
@@ -104,6 +123,7 @@ impl From<&WorkspaceTab> for TabEntry {
             }),
             last_selected: tab.last_selected,
             sticky: false,
+            alert: tab.alert,
         }
     }
 }
@@ -118,6 +138,20 @@ impl TabEntry {
             doc: Some(doc.to_string()),
             sticky: true,
             last_selected: None,
+            alert: None,
+        }
+    }
+
+    /// An entry which selects the tab, if the output line is matched
+    pub fn entry_output(line: &GrepMatch, last_selected: Option<u128>) -> TabEntry {
+        let doc = line.line.trim().chars().take(OUTPUT_LINE_LEN).collect();
+
+        TabEntry {
+            name: line.name.clone(),
+            doc: Some(doc),
+            sticky: false,
+            last_selected,
+            alert: None,
         }
     }
 
@@ -130,6 +164,7 @@ impl TabEntry {
             doc: Some(doc.to_string()),
             sticky: true,
             last_selected: None,
+            alert: None,
         }
     }
 
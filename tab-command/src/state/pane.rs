@@ -0,0 +1,356 @@
+use std::ops::Range;
+
+/// The direction in which a pane layout is split
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitDirection {
+    /// Panes are arranged side by side, from left to right
+    Horizontal,
+    /// Panes are stacked, from top to bottom
+    Vertical,
+}
+
+/// An arrangement of tabs in panes, which are rendered in a single client view.
+/// Splits can be nested, and the panes within a split share the space evenly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaneLayout<T> {
+    Pane(T),
+    Split(SplitDirection, Vec<PaneLayout<T>>),
+}
+
+/// A region of the terminal, in cells.  Columns and rows are zero-indexed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub col: u16,
+    pub row: u16,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+impl Rect {
+    pub fn new(col: u16, row: u16, cols: u16, rows: u16) -> Self {
+        Self {
+            col,
+            row,
+            cols,
+            rows,
+        }
+    }
+
+    /// The (cols, rows) dimensions of the region
+    pub fn dimensions(&self) -> (u16, u16) {
+        (self.cols, self.rows)
+    }
+}
+
+/// A line which separates the panes of a split.  Horizontal splits are separated by vertical lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divider {
+    pub rect: Rect,
+    pub direction: SplitDirection,
+    /// The indices of the panes on either side of the divider, in the arrangement
+    pub adjacent: Range<usize>,
+}
+
+/// The regions of the terminal which are occupied by panes and dividers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arrangement<T> {
+    pub panes: Vec<(T, Rect)>,
+    pub dividers: Vec<Divider>,
+}
+
+impl<T> Default for Arrangement<T> {
+    fn default() -> Self {
+        Self {
+            panes: Vec::new(),
+            dividers: Vec::new(),
+        }
+    }
+}
+
+impl<T> PaneLayout<T> {
+    /// The tabs in the layout, from left to right, and top to bottom
+    pub fn tabs(&self) -> Vec<&T> {
+        let mut tabs = Vec::new();
+        self.collect_tabs(&mut tabs);
+        tabs
+    }
+
+    fn collect_tabs<'a>(&'a self, tabs: &mut Vec<&'a T>) {
+        match self {
+            PaneLayout::Pane(tab) => tabs.push(tab),
+            PaneLayout::Split(_, children) => {
+                for child in children {
+                    child.collect_tabs(tabs);
+                }
+            }
+        }
+    }
+
+    /// Maps each tab in the layout.  Returns None if any tab could not be mapped.
+    pub fn try_map<U>(&self, f: &impl Fn(&T) -> Option<U>) -> Option<PaneLayout<U>> {
+        match self {
+            PaneLayout::Pane(tab) => f(tab).map(PaneLayout::Pane),
+            PaneLayout::Split(direction, children) => {
+                let children = children
+                    .iter()
+                    .map(|child| child.try_map(f))
+                    .collect::<Option<Vec<_>>>()?;
+
+                Some(PaneLayout::Split(*direction, children))
+            }
+        }
+    }
+
+    /// Removes the pane which contains the tab.  A split with one remaining pane is replaced by the pane.
+    /// Returns None if no panes remain.
+    pub fn remove(self, target: &T) -> Option<Self>
+    where
+        T: PartialEq,
+    {
+        match self {
+            PaneLayout::Pane(tab) if tab == *target => None,
+            PaneLayout::Pane(tab) => Some(PaneLayout::Pane(tab)),
+            PaneLayout::Split(direction, children) => {
+                let mut children: Vec<_> = children
+                    .into_iter()
+                    .filter_map(|child| child.remove(target))
+                    .collect();
+
+                match children.len() {
+                    0 => None,
+                    1 => children.pop(),
+                    _ => Some(PaneLayout::Split(direction, children)),
+                }
+            }
+        }
+    }
+
+    /// Computes the regions of the panes and dividers, within the given region.
+    /// Panes in a split share the space evenly, and are separated by one-cell dividers.
+    pub fn arrange(&self, rect: Rect) -> Arrangement<&T> {
+        let mut arrangement = Arrangement::default();
+        self.arrange_into(rect, &mut arrangement);
+        arrangement
+    }
+
+    fn arrange_into<'a>(&'a self, rect: Rect, arrangement: &mut Arrangement<&'a T>) {
+        let (direction, children) = match self {
+            PaneLayout::Pane(tab) => {
+                arrangement.panes.push((tab, rect));
+                return;
+            }
+            PaneLayout::Split(direction, children) => (*direction, children),
+        };
+
+        if children.is_empty() {
+            return;
+        }
+
+        let count = children.len() as u16;
+        let total = match direction {
+            SplitDirection::Horizontal => rect.cols,
+            SplitDirection::Vertical => rect.rows,
+        };
+
+        let available = total.saturating_sub(count - 1);
+        let size = available / count;
+        let remainder = available % count;
+
+        let mut offset = 0;
+        let mut previous_start = arrangement.panes.len();
+        for (index, child) in children.iter().enumerate() {
+            let index = index as u16;
+            let len = size + if index < remainder { 1 } else { 0 };
+
+            let divider = if index > 0 {
+                let rect = match direction {
+                    SplitDirection::Horizontal => {
+                        Rect::new(rect.col + offset, rect.row, 1, rect.rows)
+                    }
+                    SplitDirection::Vertical => {
+                        Rect::new(rect.col, rect.row + offset, rect.cols, 1)
+                    }
+                };
+
+                offset += 1;
+                arrangement.dividers.push(Divider {
+                    rect,
+                    direction,
+                    adjacent: previous_start..previous_start,
+                });
+
+                Some(arrangement.dividers.len() - 1)
+            } else {
+                None
+            };
+
+            let child_rect = match direction {
+                SplitDirection::Horizontal => {
+                    Rect::new(rect.col + offset, rect.row, len, rect.rows)
+                }
+                SplitDirection::Vertical => Rect::new(rect.col, rect.row + offset, rect.cols, len),
+            };
+
+            let start = arrangement.panes.len();
+            child.arrange_into(child_rect, arrangement);
+
+            // the divider is adjacent to the panes of the previous child, and this child
+            if let Some(divider) = divider {
+                arrangement.dividers[divider].adjacent = previous_start..arrangement.panes.len();
+            }
+
+            previous_start = start;
+            offset += len;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Divider, PaneLayout, Rect, SplitDirection};
+
+    fn split(direction: SplitDirection, tabs: &[u16]) -> PaneLayout<u16> {
+        PaneLayout::Split(
+            direction,
+            tabs.iter().copied().map(PaneLayout::Pane).collect(),
+        )
+    }
+
+    #[test]
+    fn arrange_pane() {
+        let layout = PaneLayout::Pane(0);
+        let arrangement = layout.arrange(Rect::new(0, 0, 80, 24));
+
+        assert_eq!(vec![(&0, Rect::new(0, 0, 80, 24))], arrangement.panes);
+        assert!(arrangement.dividers.is_empty());
+    }
+
+    #[test]
+    fn arrange_horizontal() {
+        let layout = split(SplitDirection::Horizontal, &[0, 1]);
+        let arrangement = layout.arrange(Rect::new(0, 0, 80, 24));
+
+        assert_eq!(
+            vec![
+                (&0, Rect::new(0, 0, 40, 24)),
+                (&1, Rect::new(41, 0, 39, 24))
+            ],
+            arrangement.panes
+        );
+
+        assert_eq!(
+            vec![Divider {
+                rect: Rect::new(40, 0, 1, 24),
+                direction: SplitDirection::Horizontal,
+                adjacent: 0..2
+            }],
+            arrangement.dividers
+        );
+    }
+
+    #[test]
+    fn arrange_vertical() {
+        let layout = split(SplitDirection::Vertical, &[0, 1, 2]);
+        let arrangement = layout.arrange(Rect::new(0, 0, 80, 24));
+
+        assert_eq!(
+            vec![
+                (&0, Rect::new(0, 0, 80, 8)),
+                (&1, Rect::new(0, 9, 80, 7)),
+                (&2, Rect::new(0, 17, 80, 7))
+            ],
+            arrangement.panes
+        );
+
+        let dividers: Vec<_> = arrangement
+            .dividers
+            .iter()
+            .map(|divider| (divider.rect, divider.adjacent.clone()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (Rect::new(0, 8, 80, 1), 0..2),
+                (Rect::new(0, 16, 80, 1), 1..3)
+            ],
+            dividers
+        );
+    }
+
+    #[test]
+    fn arrange_nested() {
+        let layout = PaneLayout::Split(
+            SplitDirection::Horizontal,
+            vec![
+                PaneLayout::Pane(0),
+                split(SplitDirection::Vertical, &[1, 2]),
+            ],
+        );
+
+        let arrangement = layout.arrange(Rect::new(0, 0, 81, 25));
+
+        assert_eq!(
+            vec![
+                (&0, Rect::new(0, 0, 40, 25)),
+                (&1, Rect::new(41, 0, 40, 12)),
+                (&2, Rect::new(41, 13, 40, 12))
+            ],
+            arrangement.panes
+        );
+
+        let adjacent: Vec<_> = arrangement
+            .dividers
+            .iter()
+            .map(|divider| divider.adjacent.clone())
+            .collect();
+
+        assert_eq!(vec![0..3, 1..3], adjacent);
+    }
+
+    #[test]
+    fn tabs() {
+        let layout = PaneLayout::Split(
+            SplitDirection::Horizontal,
+            vec![
+                PaneLayout::Pane(2),
+                split(SplitDirection::Vertical, &[0, 1]),
+            ],
+        );
+
+        assert_eq!(vec![&2, &0, &1], layout.tabs());
+    }
+
+    #[test]
+    fn try_map() {
+        let layout = split(SplitDirection::Horizontal, &[0, 1]);
+
+        assert_eq!(
+            Some(split(SplitDirection::Horizontal, &[10, 11])),
+            layout.try_map(&|tab| Some(tab + 10))
+        );
+
+        assert_eq!(
+            None,
+            layout.try_map(&|tab| if *tab == 0 { None } else { Some(*tab) })
+        );
+    }
+
+    #[test]
+    fn remove() {
+        let layout = PaneLayout::Split(
+            SplitDirection::Horizontal,
+            vec![
+                PaneLayout::Pane(0),
+                split(SplitDirection::Vertical, &[1, 2]),
+            ],
+        );
+
+        let layout = layout.remove(&1);
+        assert_eq!(Some(split(SplitDirection::Horizontal, &[0, 2])), layout);
+
+        let layout = layout.unwrap().remove(&0);
+        assert_eq!(Some(PaneLayout::Pane(2)), layout);
+
+        assert_eq!(None, layout.unwrap().remove(&2));
+    }
+}
@@ -1,10 +1,14 @@
 use tab_api::tab::{TabId, TabMetadata};
 
+use super::pane::PaneLayout;
+
 /// The select tab action, either by name or id
 #[derive(Debug, Clone, PartialEq)]
 pub enum SelectTab {
     NamedTab(String),
     Tab(TabId),
+    /// Selects several tabs, which are rendered in panes
+    Panes(PaneLayout<TabId>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -13,6 +17,10 @@ pub struct SelectOrRetaskTab {
     pub env_tab: Option<TabId>,
 }
 
+/// Selects several named tabs, which are created if needed, and rendered in panes
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectNamedPanes(pub PaneLayout<String>);
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct DeselectTab {}
 
@@ -22,6 +30,8 @@ pub enum TabState {
     None,
     Awaiting(String),
     Selected(TabId),
+    /// Several tabs are selected, and rendered in panes
+    Panes(PaneLayout<TabId>),
 }
 
 impl TabState {
@@ -37,6 +47,7 @@ impl TabState {
             TabState::None => false,
             TabState::Awaiting(name) => name.as_str() == target_name,
             TabState::Selected(_) => false,
+            TabState::Panes(_) => false,
         }
     }
 
@@ -45,6 +56,17 @@ impl TabState {
             TabState::None => false,
             TabState::Awaiting(_) => false,
             TabState::Selected(id) => id == target_id,
+            TabState::Panes(_) => false,
+        }
+    }
+
+    /// The tabs which the client is subscribed to
+    pub fn subscribed(&self) -> Vec<TabId> {
+        match self {
+            TabState::None => Vec::new(),
+            TabState::Awaiting(_) => Vec::new(),
+            TabState::Selected(id) => vec![*id],
+            TabState::Panes(layout) => layout.tabs().into_iter().copied().collect(),
         }
     }
 }
@@ -57,6 +79,7 @@ impl Default for TabState {
 
 /// The client's selected tab state.
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum TabMetadataState {
     None,
     Selected(TabMetadata),
@@ -1,10 +1,12 @@
 use std::collections::{HashMap, HashSet};
-use tab_api::tab::{TabId, TabMetadata};
+use tab_api::tab::{ExitedTab, TabId, TabMetadata};
 
 /// The client's view of the available tabs.
 #[derive(Clone, Debug, Default)]
 pub struct ActiveTabsState {
     pub tabs: HashMap<TabId, TabMetadata>,
+    /// Tabs whose shell has exited, which have not been dismissed
+    pub exited: HashMap<TabId, ExitedTab>,
 }
 
 impl ActiveTabsState {
@@ -23,4 +25,8 @@ impl ActiveTabsState {
     pub fn contains_name(&self, name: &str) -> bool {
         self.find_name(name).is_some()
     }
+
+    pub fn find_exited(&self, name: &str) -> Option<&ExitedTab> {
+        self.exited.values().find(|elem| elem.metadata.name == name)
+    }
 }
@@ -2,6 +2,8 @@ use tab_api::tab::TabId;
 
 use crate::env::terminal_size;
 
+use super::pane::PaneLayout;
+
 /// The client's view of the current terminal size
 #[derive(Clone, Debug)]
 pub struct TerminalSizeState(pub (u16, u16));
@@ -25,6 +27,10 @@ pub enum TerminalMode {
     Echo(TabId),
     /// Terminal is in interactive finder mode, using Crossterm.  ESC will navigate back to the provided tab.
     FuzzyFinder(Option<String>),
+    /// Terminal is paging through the scrollback of the given tab, in the alternate screen.  Output from the tab is frozen.
+    Scroll(TabId),
+    /// Terminal is rendering several tabs in panes, in the alternate screen.  Input is sent to the focused pane.
+    Panes(PaneLayout<TabId>),
 }
 
 impl Default for TerminalMode {
@@ -1,10 +1,12 @@
 use super::{
-    workspace_err::ConfigVariantError, workspace_err::NoConfigVariantError,
+    pane::{PaneLayout, SplitDirection},
+    workspace_err::ConfigVariantError,
+    workspace_err::NoConfigVariantError,
     workspace_err::WorkspaceError,
 };
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, collections::HashSet, path::Path, path::PathBuf, sync::Arc};
-use tab_api::tab::normalize_name;
+use tab_api::tab::{normalize_name, ScrollbackLimit, TabAlert, TabExit, TabMonitor};
 use typed_builder::TypedBuilder;
 
 /// The client's view of the workspace configuration
@@ -30,9 +32,32 @@ pub struct WorkspaceTab {
     #[builder(default, setter(strip_option))]
     pub shell: Option<String>,
     #[builder(default, setter(strip_option))]
-    pub env: Option<HashMap<String, String>>, // pub command: Option<String>,
+    pub env: Option<HashMap<String, String>>,
+    #[builder(default, setter(strip_option))]
+    pub command: Option<String>,
+    #[builder(default, setter(strip_option))]
+    pub scrollback: Option<ScrollbackLimit>,
+    #[builder(default, setter(strip_option))]
+    pub record: Option<bool>,
+    #[builder(default, setter(strip_option))]
+    pub remain_on_exit: Option<bool>,
+    #[builder(default, setter(strip_option))]
+    pub log: Option<bool>,
+    #[builder(default, setter(strip_option))]
+    pub monitor: Option<TabMonitor>,
+    #[builder(default, setter(strip_option))]
+    pub notify: Option<String>,
+    /// The alert raised by the daemon's tab monitor, if the tab is running
+    #[builder(default, setter(strip_option))]
+    pub alert: Option<TabAlert>,
+    /// The exit status of the shell, if the tab has exited and has not been dismissed
+    #[builder(default, setter(strip_option))]
+    pub exit: Option<TabExit>,
     #[builder(default, setter(strip_option))]
     pub last_selected: Option<u128>,
+    /// If set, selecting the tab opens these tabs in panes, rather than a shell
+    #[builder(default, setter(strip_option))]
+    pub layout: Option<PaneLayout<String>>,
 }
 
 impl WorkspaceTab {
@@ -45,7 +70,17 @@ impl WorkspaceTab {
             shell: None,
             doc: None,
             env: None,
+            command: None,
+            scrollback: None,
+            record: None,
+            remain_on_exit: None,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
+            exit: None,
             last_selected: None,
+            layout: None,
         }
     }
 
@@ -58,7 +93,17 @@ impl WorkspaceTab {
             shell: options.shell,
             doc: options.doc,
             env: options.env,
+            command: options.command,
+            scrollback: options.scrollback,
+            record: options.record,
+            remain_on_exit: options.remain_on_exit,
+            log: options.log,
+            monitor: options.monitor,
+            notify: options.notify,
+            alert: None,
+            exit: None,
             last_selected: None,
+            layout: None,
         }
     }
 }
@@ -132,6 +177,7 @@ pub struct Workspace {
 }
 
 /// An item within the workspace configuration
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum WorkspaceItem {
@@ -159,6 +205,7 @@ pub struct Repo {
     #[serde(flatten)]
     pub tab_options: TabOptions,
     pub tabs: Option<Vec<Tab>>,
+    pub layout: Option<Vec<Layout>>,
 }
 
 /// A tab within the workspace or repository configurations
@@ -168,7 +215,51 @@ pub struct Tab {
     pub dir: Option<String>,
     #[serde(flatten)]
     pub options: TabOptions,
-    // pub command: Option<String>,
+}
+
+/// A layout within the repository configuration, which opens several of the repository's tabs in panes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Layout {
+    pub layout: String,
+    pub doc: Option<String>,
+    #[serde(flatten)]
+    pub split: LayoutSplit,
+}
+
+/// A split within a layout, configured as `horizontal: [panes...]` (side by side), or `vertical: [panes...]` (stacked)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutSplit {
+    Horizontal(Vec<LayoutPane>),
+    Vertical(Vec<LayoutPane>),
+}
+
+/// A pane within a layout, which is either the name of a tab in the repository, or a nested split
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LayoutPane {
+    Tab(String),
+    Split(LayoutSplit),
+}
+
+impl LayoutSplit {
+    /// Converts the split into a pane layout, resolving each tab name with the function
+    pub fn to_pane_layout(&self, tab_name: &impl Fn(&str) -> String) -> PaneLayout<String> {
+        let (direction, panes) = match self {
+            LayoutSplit::Horizontal(panes) => (SplitDirection::Horizontal, panes),
+            LayoutSplit::Vertical(panes) => (SplitDirection::Vertical, panes),
+        };
+
+        let panes = panes
+            .iter()
+            .map(|pane| match pane {
+                LayoutPane::Tab(name) => PaneLayout::Pane(tab_name(name.as_str())),
+                LayoutPane::Split(split) => split.to_pane_layout(tab_name),
+            })
+            .collect();
+
+        PaneLayout::Split(direction, panes)
+    }
 }
 
 /// A tab within the workspace or repository configurations
@@ -177,6 +268,22 @@ pub struct TabOptions {
     pub doc: Option<String>,
     pub shell: Option<String>,
     pub env: Option<HashMap<String, String>>,
+    /// A command which is typed into the shell when the tab starts.
+    /// When the command exits, the tab drops back to the interactive shell.
+    pub command: Option<String>,
+    /// Overrides the daemon's scrollback limit for the tab, in bytes or lines
+    pub scrollback: Option<ScrollbackLimit>,
+    /// Records the output of the tab to an asciicast file, in the daemon's recordings directory
+    pub record: Option<bool>,
+    /// Keeps the tab open when the shell exits, with a banner which offers to restart (r) or close (q) the tab
+    pub remain_on_exit: Option<bool>,
+    /// Appends the ANSI-stripped output of the tab to a rolling log file, in the daemon's logs directory
+    pub log: Option<bool>,
+    /// Monitors the tab for `activity`, or `silence 30s`, and raises an alert in `tab --list` and the fuzzy finder
+    pub monitor: Option<TabMonitor>,
+    /// A shell command which is run when the tab monitor raises an alert.
+    /// The TAB_NAME, TAB_ID and TAB_ALERT environment variables are set.
+    pub notify: Option<String>,
 }
 
 impl Default for TabOptions {
@@ -185,13 +292,20 @@ impl Default for TabOptions {
             doc: None,
             shell: None,
             env: None,
+            command: None,
+            scrollback: None,
+            record: None,
+            remain_on_exit: None,
+            log: None,
+            monitor: None,
+            notify: None,
         }
     }
 }
 
 impl TabOptions {
     /// Computes a new TabOptions struct, delegating properties to Other if not set in Self
-    /// Doc is not inherited.
+    /// Doc and command are not inherited.
     pub fn or(self, other: Self) -> Self {
         let env = if let Some(mut env) = self.env {
             if let Some(other_env) = other.env {
@@ -209,6 +323,13 @@ impl TabOptions {
             doc: self.doc,
             shell: self.shell.or(other.shell),
             env,
+            command: self.command,
+            scrollback: self.scrollback.or(other.scrollback),
+            record: self.record.or(other.record),
+            remain_on_exit: self.remain_on_exit.or(other.remain_on_exit),
+            log: self.log.or(other.log),
+            monitor: self.monitor.or(other.monitor),
+            notify: self.notify.or(other.notify),
         }
     }
 }
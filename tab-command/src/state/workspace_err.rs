@@ -34,6 +34,9 @@ pub enum WorkspaceError {
     TabNameInvalid(TabNameInvalidError),
     #[error("{0}")]
     TabDuplicate(TabDuplicateError),
+
+    #[error("{0}")]
+    LayoutTabNotFound(LayoutTabNotFoundError),
 }
 
 impl WorkspaceError {
@@ -76,6 +79,10 @@ impl WorkspaceError {
     pub fn duplicate_tab(tab: String) -> Self {
         Self::TabDuplicate(TabDuplicateError { tab })
     }
+
+    pub fn layout_tab_not_found(layout: String, tab: String) -> Self {
+        Self::LayoutTabNotFound(LayoutTabNotFoundError { layout, tab })
+    }
 }
 
 #[derive(Error, Debug)]
@@ -153,3 +160,10 @@ pub struct TabNameInvalidError {
 pub struct TabDuplicateError {
     pub tab: String,
 }
+
+#[derive(Error, Debug)]
+#[error("Layout {layout} has a pane for tab {tab}, which is not defined in the repository")]
+pub struct LayoutTabNotFoundError {
+    pub layout: String,
+    pub tab: String,
+}
@@ -1,6 +1,19 @@
+use std::time::Duration;
+
 use lifeline::Receiver;
-use postage::{stream::Stream, watch};
+use log::{info, warn};
+use postage::{broadcast, sink::Sink, stream::Stream, watch};
+use tab_api::{
+    client::{Request, Response, SubscriptionMode},
+    tab::TabId,
+};
 use thiserror::Error;
+use tokio::time;
+
+use crate::{message::tabs::TabRecv, state::tabs::ActiveTabsState};
+
+/// How long to wait for a newly created tab to produce output, before continuing anyway.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Error, Debug)]
 #[error("state never resolved to a value")]
@@ -19,7 +32,6 @@ where
     }
 }
 
-#[allow(dead_code)]
 pub async fn await_message<T: Clone, F, R>(
     channel: &mut impl Receiver<T>,
     mut condition: F,
@@ -76,3 +88,88 @@ where
 
     Err(StateUninitalizedError {})
 }
+
+/// Subscribes to the daemon responses, for the duration of a command.
+///
+/// The response channel is a broadcast, and a receiver which is never read eventually blocks the websocket.
+/// Services which only need responses while a command runs should hold the sender,
+/// and subscribe when the command starts.
+pub fn subscribe_responses(
+    tx_response: &broadcast::Sender<Response>,
+) -> broadcast::Receiver<Response> {
+    tx_response.subscribe()
+}
+
+/// Returns the id of the named tab.  If the tab is not running, it is created from the workspace configuration.
+///
+/// A newly created tab is returned once it produces output, as the daemon drops input and
+/// recording requests until the pty has connected.
+pub async fn find_or_create_tab(
+    name: &str,
+    rx_active: &mut watch::Receiver<Option<ActiveTabsState>>,
+    tx_tab: &mut (impl Sink<Item = TabRecv> + Unpin),
+    rx_response: &mut impl Receiver<Response>,
+    tx_request: &mut (impl Sink<Item = Request> + Unpin),
+) -> anyhow::Result<TabId> {
+    let state = await_state(rx_active).await?;
+    if let Some(tab) = state.find_name(name) {
+        return Ok(tab.id);
+    }
+
+    info!("creating tab {}", name);
+    tx_tab
+        .send(TabRecv::CreateNamedTab {
+            name: name.to_string(),
+        })
+        .await?;
+
+    let state = await_condition(rx_active, |state| state.contains_name(name)).await?;
+    let id = state
+        .find_name(name)
+        .map(|tab| tab.id)
+        .ok_or(StateUninitalizedError {})?;
+
+    await_output(id, rx_response, tx_request).await?;
+
+    Ok(id)
+}
+
+/// Waits until the newly created tab produces output, which means the pty has connected.
+pub async fn await_output(
+    id: TabId,
+    rx_response: &mut impl Receiver<Response>,
+    tx_request: &mut (impl Sink<Item = Request> + Unpin),
+) -> anyhow::Result<()> {
+    tx_request
+        .send(Request::Subscribe(id, SubscriptionMode::Interactive))
+        .await?;
+
+    let output = await_message(rx_response, |response| match response {
+        Response::Output(tab, _) if tab == id => Some(()),
+        _ => None,
+    });
+
+    if time::timeout(STARTUP_TIMEOUT, output).await.is_err() {
+        warn!("tab {} did not produce output before the timeout", id);
+    }
+
+    tx_request.send(Request::Unsubscribe(id)).await?;
+
+    Ok(())
+}
+
+/// Waits until the daemon acknowledges the requests which have been sent on the connection.
+pub async fn await_flushed(
+    rx_response: &mut impl Receiver<Response>,
+    tx_request: &mut (impl Sink<Item = Request> + Unpin),
+) -> anyhow::Result<()> {
+    tx_request.send(Request::Flush).await?;
+
+    await_message(rx_response, |response| match response {
+        Response::Flushed => Some(()),
+        _ => None,
+    })
+    .await?;
+
+    Ok(())
+}
@@ -2,11 +2,13 @@ use crate::{message::cli::CliSubscriptionRecv, message::cli::CliSubscriptionSend
 use crate::{
     message::{
         cli::{CliRecv, CliSend, CliShutdown},
-        listener::ListenerShutdown,
+        grep::{GrepRecv, GrepSend},
+        resize::ResizeRecv,
+        session::SessionRecv,
         tab::{TabInput, TabRecv, TabSend},
         tab_manager::TabManagerRecv,
     },
-    state::tab::TabsState,
+    state::{resize::ConnectionId, tab::TabsState},
 };
 use anyhow::Context;
 use postage::{broadcast, mpsc, sink::Sink, stream::Stream};
@@ -65,12 +67,35 @@ pub struct ListenerConnectionCarrier {
     _forward: Lifeline,
     _reverse: Lifeline,
     _forward_tabs_state: Lifeline,
+    _forward_grep: Lifeline,
+    _disconnect: DisconnectGuard,
+}
+
+/// Notifies the `TabManagerService` and `ResizeService` when the connection is dropped,
+/// so it is no longer attached to the tab, and it's dimensions no longer affect the tab size.
+struct DisconnectGuard {
+    connection: ConnectionId,
+    tx_manager: mpsc::Sender<TabManagerRecv>,
+    tx_resize: mpsc::Sender<ResizeRecv>,
+}
+
+impl Drop for DisconnectGuard {
+    fn drop(&mut self) {
+        self.tx_manager
+            .try_send(TabManagerRecv::Disconnect(self.connection))
+            .ok();
+        self.tx_resize
+            .try_send(ResizeRecv::Disconnect(self.connection))
+            .ok();
+    }
 }
 
 impl CarryFrom<ListenerBus> for CliBus {
     type Lifeline = anyhow::Result<ListenerConnectionCarrier>;
 
     fn carry_from(&self, from: &ListenerBus) -> Self::Lifeline {
+        let connection = ConnectionId::next();
+
         let _forward = {
             let rx_tab = from.rx::<TabSend>()?.log(Level::Debug);
 
@@ -82,23 +107,36 @@ impl CarryFrom<ListenerBus> for CliBus {
 
         let _reverse = {
             let rx_conn = self.rx::<CliSend>()?;
+            let tx_conn = self.tx::<CliRecv>()?;
 
             let tx_tab = from.tx::<TabRecv>()?.log(Level::Debug);
             let tx_manager = from.tx::<TabManagerRecv>()?;
             let tx_shutdown = self.tx::<CliShutdown>()?;
-            let tx_listener_shutdown = from.tx::<ListenerShutdown>()?;
+            let tx_session = from.tx::<SessionRecv>()?;
+            let tx_resize = from.tx::<ResizeRecv>()?;
+            let tx_grep = from.tx::<GrepRecv>()?;
             Self::try_task(
                 "input",
                 Self::run_input(
+                    connection,
                     rx_conn,
+                    tx_conn,
                     tx_tab,
                     tx_manager,
                     tx_shutdown,
-                    tx_listener_shutdown,
+                    tx_session,
+                    tx_resize,
+                    tx_grep,
                 ),
             )
         };
 
+        let _disconnect = DisconnectGuard {
+            connection,
+            tx_manager: from.tx::<TabManagerRecv>()?,
+            tx_resize: from.tx::<ResizeRecv>()?,
+        };
+
         let _forward_tabs_state = {
             let mut rx_tabs_state = from.rx::<TabsState>()?;
             let mut tx_tabs_state = self.tx::<TabsState>()?;
@@ -111,10 +149,28 @@ impl CarryFrom<ListenerBus> for CliBus {
             })
         };
 
+        let _forward_grep = {
+            let mut rx_grep = from.rx::<GrepSend>()?;
+            let mut tx = self.tx::<CliRecv>()?;
+            Self::try_task("forward_grep", async move {
+                while let Some(msg) = rx_grep.recv().await {
+                    if msg.connection != connection {
+                        continue;
+                    }
+
+                    tx.send(CliRecv::Grep(msg.matches)).await?;
+                }
+
+                Ok(())
+            })
+        };
+
         Ok(ListenerConnectionCarrier {
             _forward,
             _reverse,
             _forward_tabs_state,
+            _forward_grep,
+            _disconnect,
         })
     }
 }
@@ -132,12 +188,17 @@ impl CliBus {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn run_input(
+        connection: ConnectionId,
         mut rx: impl Stream<Item = CliSend> + Unpin,
+        mut tx_conn: impl Sink<Item = CliRecv> + Unpin,
         mut tx: impl Sink<Item = TabRecv> + Unpin,
         mut tx_manager: impl Sink<Item = TabManagerRecv> + Unpin,
         mut tx_shutdown: impl Sink<Item = CliShutdown> + Unpin,
-        mut tx_listener_shutdown: impl Sink<Item = ListenerShutdown> + Unpin,
+        mut tx_session: impl Sink<Item = SessionRecv> + Unpin,
+        mut tx_resize: impl Sink<Item = ResizeRecv> + Unpin,
+        mut tx_grep: impl Sink<Item = GrepRecv> + Unpin,
     ) -> anyhow::Result<()> {
         while let Some(msg) = rx.recv().await {
             match msg {
@@ -155,23 +216,40 @@ impl CliBus {
                     );
 
                     tx_manager
-                        .send(TabManagerRecv::UpdateTimestamp(id))
+                        .send(TabManagerRecv::Attach(connection, id))
                         .await
-                        .context("tx TabManagerRecv::UpdateTimestamp")?;
+                        .context("tx TabManagerRecv::Attach")?;
 
                     tx.send(TabRecv::Scrollback(id))
                         .await
                         .context("tx TabRecv::Scrollback")?;
                 }
+                CliSend::Unsubscribe(id) => {
+                    tx_manager
+                        .send(TabManagerRecv::Detach(connection, id))
+                        .await
+                        .context("tx TabManagerRecv::Detach")?;
+
+                    tx_resize
+                        .send(ResizeRecv::Unsubscribe(connection, id))
+                        .await
+                        .context("tx ResizeRecv::Unsubscribe")?;
+                }
                 CliSend::Input(id, input) => {
                     let stdin = Arc::new(input);
                     let input = TabInput { id, stdin };
                     let message = TabRecv::Input(input);
                     tx.send(message).await.context("tx TabRecv closed")?;
+
+                    tx_resize
+                        .send(ResizeRecv::Input(connection, id))
+                        .await
+                        .context("tx ResizeRecv::Input")?;
                 }
                 CliSend::ResizeTab(id, dimensions) => {
-                    let message = TabRecv::Resize(id, dimensions);
-                    tx.send(message).await?;
+                    // the resize service computes the tab size, using the dimensions of all attached clients
+                    let message = ResizeRecv::Resize(connection, id, dimensions);
+                    tx_resize.send(message).await?;
                 }
                 CliSend::Retask(from, to) => {
                     let message = TabRecv::Retask(from, to);
@@ -179,14 +257,29 @@ impl CliBus {
                 }
                 CliSend::GlobalShutdown => {
                     info!("Daemon receieved a global shutdown.");
-                    tx.send(TabRecv::TerminateAll).await?;
-                    tx_listener_shutdown.send(ListenerShutdown {}).await?;
+                    // the session service saves the session, and then terminates the tabs
+                    tx_session.send(SessionRecv::Shutdown).await?;
+                    time::sleep(Duration::from_millis(50)).await;
+                }
+                CliSend::UpgradeDaemon => {
+                    info!("Daemon receieved an upgrade request.");
+                    tx_session.send(SessionRecv::Handoff).await?;
                     time::sleep(Duration::from_millis(50)).await;
                 }
                 CliSend::DisconnectTab(id) => {
                     let message = TabRecv::Retask(id, RetaskTarget::Disconnect);
                     tx.send(message).await?;
                 }
+                CliSend::RecordTab(id) => {
+                    tx.send(TabRecv::Record(id)).await?;
+                }
+                CliSend::Grep(query) => {
+                    tx_grep.send(GrepRecv { connection, query }).await?;
+                }
+                CliSend::Flush => {
+                    // the previous messages have been delivered, in order
+                    tx_conn.send(CliRecv::Flushed).await?;
+                }
             }
         }
 
@@ -203,9 +296,9 @@ impl CliBus {
         match msg {
             TabSend::Started(tab) => tx.send(CliRecv::TabStarted(tab)).await?,
             TabSend::Updated(tab) => tx.send(CliRecv::TabUpdated(tab)).await?,
-            TabSend::Stopped(id) => {
+            TabSend::Stopped(id, exit) => {
                 tx_subscription
-                    .send(CliSubscriptionRecv::Stopped(id))
+                    .send(CliSubscriptionRecv::Stopped(id, exit))
                     .await?;
             }
             TabSend::Scrollback(scrollback) => {
@@ -245,7 +338,7 @@ mod forward_tests {
     use tab_api::{
         chunk::OutputChunk,
         client::RetaskTarget,
-        tab::{TabId, TabMetadata},
+        tab::{TabExit, TabId, TabMetadata},
     };
     use tokio::sync::Mutex;
 
@@ -267,7 +360,16 @@ mod forward_tests {
             env: HashMap::new(),
             shell: "bash".into(),
             dir: "dir".into(),
+            command: None,
             selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
         };
 
         tx.send(TabSend::Started(started.clone())).await?;
@@ -291,12 +393,16 @@ mod forward_tests {
         let mut tx = listener_bus.tx::<TabSend>()?;
         let mut rx = cli_bus.rx::<CliSubscriptionRecv>()?;
 
-        tx.send(TabSend::Stopped(TabId(0))).await?;
+        tx.send(TabSend::Stopped(TabId(0), TabExit::code(0)))
+            .await?;
 
         assert_completes!(async move {
             let msg = rx.recv().await;
             assert!(msg.is_some());
-            assert_eq!(CliSubscriptionRecv::Stopped(TabId(0)), msg.unwrap());
+            assert_eq!(
+                CliSubscriptionRecv::Stopped(TabId(0), TabExit::code(0)),
+                msg.unwrap()
+            );
         });
 
         Ok(())
@@ -433,18 +539,21 @@ mod forward_tests {
 mod reverse_tests {
     use crate::{
         message::{
-            cli::CliSend,
-            listener::ListenerShutdown,
+            cli::{CliRecv, CliSend},
+            grep::{GrepRecv, GrepSend},
+            resize::ResizeRecv,
+            session::SessionRecv,
             tab::{TabInput, TabRecv},
             tab_manager::TabManagerRecv,
         },
         prelude::*,
+        state::resize::ConnectionId,
     };
     use lifeline::assert_completes;
     use std::collections::HashMap;
     use tab_api::{
         chunk::InputChunk,
-        client::RetaskTarget,
+        client::{GrepMatch, GrepQuery, RetaskTarget},
         tab::{CreateTabMetadata, TabId},
     };
 
@@ -465,6 +574,13 @@ mod reverse_tests {
             env: HashMap::new(),
             dimensions: (1, 1),
             dir: "dir".into(),
+            command: None,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: false,
+            monitor: None,
+            notify: None,
         };
 
         tx.send(CliSend::CreateTab(create.clone())).await?;
@@ -545,7 +661,7 @@ mod reverse_tests {
         assert_completes!(async move {
             let msg = rx_manager.recv().await;
             assert!(msg.is_some());
-            assert_eq!(TabManagerRecv::UpdateTimestamp(TabId(0)), msg.unwrap());
+            assert!(matches!(msg, Some(TabManagerRecv::Attach(_, TabId(0)))));
         });
 
         Ok(())
@@ -584,14 +700,55 @@ mod reverse_tests {
         let _carrier = cli_bus.carry_from(&listener_bus)?;
 
         let mut tx = cli_bus.tx::<CliSend>()?;
-        let mut rx = listener_bus.rx::<TabRecv>()?;
+        let mut rx = listener_bus.rx::<ResizeRecv>()?;
 
         tx.send(CliSend::ResizeTab(TabId(0), (1, 2))).await?;
 
         assert_completes!(async move {
             let msg = rx.recv().await;
-            assert!(msg.is_some());
-            assert_eq!(TabRecv::Resize(TabId(0), (1, 2)), msg.unwrap());
+            assert!(matches!(msg, Some(ResizeRecv::Resize(_, TabId(0), (1, 2)))));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unsubscribe() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = cli_bus.carry_from(&listener_bus)?;
+
+        let mut tx = cli_bus.tx::<CliSend>()?;
+        let mut rx = listener_bus.rx::<ResizeRecv>()?;
+
+        tx.send(CliSend::Unsubscribe(TabId(0))).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert!(matches!(msg, Some(ResizeRecv::Unsubscribe(_, TabId(0)))));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn disconnect() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let carrier = cli_bus.carry_from(&listener_bus)?;
+        let mut rx = listener_bus.rx::<ResizeRecv>()?;
+        let mut rx_manager = listener_bus.rx::<TabManagerRecv>()?;
+
+        drop(carrier);
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert!(matches!(msg, Some(ResizeRecv::Disconnect(_))));
+
+            let msg = rx_manager.recv().await;
+            assert!(matches!(msg, Some(TabManagerRecv::Disconnect(_))));
         });
 
         Ok(())
@@ -630,13 +787,116 @@ mod reverse_tests {
         let _carrier = cli_bus.carry_from(&listener_bus)?;
 
         let mut tx = cli_bus.tx::<CliSend>()?;
-        let mut rx = listener_bus.rx::<ListenerShutdown>()?;
+        let mut rx = listener_bus.rx::<SessionRecv>()?;
 
         tx.send(CliSend::GlobalShutdown).await?;
 
         assert_completes!(async move {
             let msg = rx.recv().await;
-            assert!(msg.is_some());
+            assert_eq!(Some(SessionRecv::Shutdown), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn upgrade_daemon() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = cli_bus.carry_from(&listener_bus)?;
+
+        let mut tx = cli_bus.tx::<CliSend>()?;
+        let mut rx = listener_bus.rx::<SessionRecv>()?;
+
+        tx.send(CliSend::UpgradeDaemon).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(SessionRecv::Handoff), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn grep() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = cli_bus.carry_from(&listener_bus)?;
+
+        let mut tx = cli_bus.tx::<CliSend>()?;
+        let mut rx = listener_bus.rx::<GrepRecv>()?;
+        let mut tx_grep = listener_bus.tx::<GrepSend>()?;
+        let mut rx_cli = cli_bus.rx::<CliRecv>()?;
+
+        let query = GrepQuery {
+            pattern: "error".into(),
+            limit: None,
+        };
+        tx.send(CliSend::Grep(query.clone())).await?;
+
+        let matches = vec![GrepMatch {
+            id: TabId(0),
+            name: "name".into(),
+            line: "an error".into(),
+        }];
+
+        assert_completes!(async move {
+            let msg = rx.recv().await.expect("rx GrepRecv");
+            assert_eq!(query, msg.query);
+
+            // results for other connections are ignored
+            let other = ConnectionId::next();
+            tx_grep
+                .send(GrepSend {
+                    connection: other,
+                    matches: Vec::new(),
+                })
+                .await
+                .expect("tx GrepSend");
+
+            tx_grep
+                .send(GrepSend {
+                    connection: msg.connection,
+                    matches: matches.clone(),
+                })
+                .await
+                .expect("tx GrepSend");
+
+            let msg = rx_cli.recv().await;
+            assert_eq!(Some(CliRecv::Grep(matches)), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn flush() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = cli_bus.carry_from(&listener_bus)?;
+
+        let mut tx = cli_bus.tx::<CliSend>()?;
+        let mut rx = listener_bus.rx::<TabRecv>()?;
+        let mut rx_cli = cli_bus.rx::<CliRecv>()?;
+
+        tx.send(CliSend::Input(TabId(0), InputChunk { data: vec![0] }))
+            .await?;
+        tx.send(CliSend::Flush).await?;
+
+        assert_completes!(async move {
+            let msg = rx_cli.recv().await;
+            assert_eq!(Some(CliRecv::Flushed), msg);
+
+            // the input was delivered before the acknowledgement
+            let msg = rx.recv().await;
+            assert_eq!(
+                Some(TabRecv::Input(TabInput::new(TabId(0), vec![0u8]))),
+                msg
+            );
         });
 
         Ok(())
@@ -661,6 +921,7 @@ mod tabs_state_tests {
 
         tx.send(TabsState {
             tabs: HashMap::new(),
+            exited: HashMap::new(),
         })
         .await?;
 
@@ -669,7 +930,8 @@ mod tabs_state_tests {
             assert!(msg.is_some());
             assert_eq!(
                 TabsState {
-                    tabs: HashMap::new()
+                    tabs: HashMap::new(),
+                    exited: HashMap::new(),
                 },
                 msg.unwrap()
             );
@@ -1,8 +1,8 @@
-use crate::message::daemon::DaemonShutdown;
 use crate::prelude::*;
+use crate::{message::daemon::DaemonShutdown, state::session::SessionStore};
 use lifeline::Resource;
 use postage::mpsc;
-use tab_api::config::DaemonConfig;
+use tab_api::config::{DaemonConfig, OutputLogConfig, ResizeConfig, ScrollbackConfig};
 use tab_websocket::resource::listener::{WebsocketAuthToken, WebsocketListenerResource};
 
 lifeline_bus!(pub struct DaemonBus);
@@ -10,6 +10,10 @@ lifeline_bus!(pub struct DaemonBus);
 impl Resource<DaemonBus> for DaemonConfig {}
 impl Resource<DaemonBus> for WebsocketListenerResource {}
 impl Resource<DaemonBus> for WebsocketAuthToken {}
+impl Resource<DaemonBus> for SessionStore {}
+impl Resource<DaemonBus> for ScrollbackConfig {}
+impl Resource<DaemonBus> for OutputLogConfig {}
+impl Resource<DaemonBus> for ResizeConfig {}
 
 impl Message<DaemonBus> for DaemonShutdown {
     type Channel = mpsc::Sender<Self>;
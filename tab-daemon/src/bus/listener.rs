@@ -2,16 +2,20 @@ use crate::prelude::*;
 use crate::{
     message::{
         daemon::DaemonShutdown,
+        grep::{GrepRecv, GrepSend},
         listener::ListenerShutdown,
+        resize::ResizeRecv,
+        session::SessionRecv,
         tab::{TabRecv, TabSend},
         tab_assignment::{AssignTab, TabAssignmentRetraction},
         tab_manager::TabManagerRecv,
     },
-    state::tab::TabsState,
+    state::{scrollback::ScrollbackRegistry, session::SessionStore, tab::TabsState},
 };
 use lifeline::error::into_msg;
 
 use postage::{broadcast, mpsc, watch};
+use tab_api::config::{OutputLogConfig, ResizeConfig, ScrollbackConfig};
 use tab_websocket::{bus::WebsocketListenerBus, message::listener::WebsocketConnectionMessage};
 
 lifeline_bus!(pub struct ListenerBus);
@@ -48,6 +52,28 @@ impl Message<ListenerBus> for TabsState {
     type Channel = watch::Sender<Self>;
 }
 
+impl Message<ListenerBus> for ResizeRecv {
+    type Channel = mpsc::Sender<Self>;
+}
+
+impl Message<ListenerBus> for SessionRecv {
+    type Channel = mpsc::Sender<Self>;
+}
+
+impl Message<ListenerBus> for GrepRecv {
+    type Channel = mpsc::Sender<Self>;
+}
+
+impl Message<ListenerBus> for GrepSend {
+    type Channel = broadcast::Sender<Self>;
+}
+
+impl Resource<ListenerBus> for SessionStore {}
+impl Resource<ListenerBus> for ScrollbackRegistry {}
+impl Resource<ListenerBus> for ScrollbackConfig {}
+impl Resource<ListenerBus> for OutputLogConfig {}
+impl Resource<ListenerBus> for ResizeConfig {}
+
 pub struct ListenerDaemonCarrier {
     _forward_shutdown: Lifeline,
 }
@@ -5,10 +5,14 @@ use crate::{
         tab::{TabOutput, TabRecv, TabScrollback, TabSend},
         tab_manager::TabManagerRecv,
     },
-    state::pty::{PtyScrollback, PtyState},
+    state::{
+        pty::{PtyScrollback, PtyState},
+        session::SessionStore,
+    },
 };
 
 use std::sync::Arc;
+use tokio::select;
 
 use lifeline::Resource;
 use postage::{broadcast, mpsc, watch};
@@ -54,6 +58,7 @@ impl Message<PtyBus> for TabMetadata {
 }
 
 impl Resource<PtyBus> for WebsocketResource {}
+impl Resource<PtyBus> for SessionStore {}
 impl WebsocketMessageBus for PtyBus {
     type Send = PtyWebsocketRequest;
     type Recv = PtyWebsocketResponse;
@@ -77,15 +82,43 @@ impl CarryFrom<ListenerBus> for PtyBus {
             // FIXME I think the bug is here.
             // the channel is being taken from the
             let mut rx_tab = from.rx::<TabRecv>()?;
+            let mut rx_pty = self.rx::<PtySend>()?;
 
             let mut tx_pty = self.tx::<PtyRecv>()?;
             let mut tx_pty_state = self.tx::<PtyState>()?;
+            let mut tx_tab = from.tx::<TabSend>()?;
 
             Self::try_task("to_pty", async move {
-                while let Some(msg) = rx_tab.recv().await {
+                // a tab resumed by this pty, which is waiting for the tab manager to accept it
+                let mut resuming = None;
+
+                loop {
+                    // the resume must be recorded before the tab manager replies to it
+                    let msg = select! {
+                        biased;
+
+                        msg = rx_pty.recv() => {
+                            // a pty reconnected to this daemon, and is still running its tab
+                            match msg {
+                                Some(PtySend::Resumed(metadata)) => {
+                                    info!("PTY reconnected on tab {}", metadata.id);
+                                    resuming = Some(metadata);
+                                }
+                                Some(_) => {}
+                                None => break,
+                            }
+
+                            continue;
+                        }
+                        msg = rx_tab.recv() => match msg {
+                            Some(msg) => msg,
+                            None => break,
+                        },
+                    };
+
                     match msg {
                         TabRecv::Assign(offer) => {
-                            if rx_id.borrow().is_assigned() {
+                            if rx_id.borrow().is_assigned() || resuming.is_some() {
                                 continue;
                             }
 
@@ -125,10 +158,42 @@ impl CarryFrom<ListenerBus> for PtyBus {
 
                             tx_pty.send(PtyRecv::Resize(dimensions)).await?;
                         }
+                        TabRecv::Record(id) => {
+                            if !rx_id.borrow().has_assigned(id) {
+                                continue;
+                            }
+
+                            tx_pty.send(PtyRecv::Record).await?;
+                        }
+                        TabRecv::AcceptResume(metadata) => {
+                            if resuming.as_ref() != Some(&metadata) {
+                                continue;
+                            }
+
+                            resuming = None;
+                            tx_pty_state.send(PtyState::Assigned(metadata.id)).await?;
+                            tx_tab.send(TabSend::Started(metadata)).await.ok();
+                        }
+                        TabRecv::RejectResume(metadata) => {
+                            if resuming.as_ref() != Some(&metadata) {
+                                continue;
+                            }
+
+                            // the resume remains pending, as the pty is terminating, and does not accept assignments
+                            tx_pty.send(PtyRecv::Terminate).await?;
+                        }
                         TabRecv::Retask(_, _) => {}
                         TabRecv::TerminateAll => {
                             tx_pty.send(PtyRecv::Terminate).await?;
                         }
+                        TabRecv::ReconnectAll => {
+                            // idle ptys don't have a tab to resume
+                            if rx_id.borrow().is_assigned() || resuming.is_some() {
+                                tx_pty.send(PtyRecv::Reconnect).await?;
+                            } else {
+                                tx_pty.send(PtyRecv::Terminate).await?;
+                            }
+                        }
                     }
                 }
 
@@ -145,13 +210,28 @@ impl CarryFrom<ListenerBus> for PtyBus {
 
             Self::try_task("to_listener", async move {
                 while let Some(msg) = rx_pty.recv().await {
+                    // a resumed pty is not assigned until the tab manager accepts the resume,
+                    // and until then its output is not forwarded to clients
+                    let assigned = match *rx_id.borrow() {
+                        PtyState::Assigned(id) => Some(id),
+                        PtyState::None => None,
+                    };
+
                     match msg {
                         PtySend::Started(metadata) => {
                             let message = TabSend::Started(metadata);
                             tx_tab.send(message).await.ok();
                         }
+                        PtySend::Resumed(metadata) => {
+                            tx_tab_manager
+                                .send(TabManagerRecv::ResumeTab(metadata))
+                                .await?;
+                        }
                         PtySend::Output(chunk) => {
-                            let id = rx_id.borrow().unwrap();
+                            let id = match assigned {
+                                Some(id) => id,
+                                None => continue,
+                            };
 
                             let output = TabOutput {
                                 id,
@@ -162,18 +242,32 @@ impl CarryFrom<ListenerBus> for PtyBus {
                             tx_tab.send(send).await.ok();
                         }
                         PtySend::Scrollback(scrollback) => {
-                            let id = rx_id.borrow().unwrap();
+                            let id = match assigned {
+                                Some(id) => id,
+                                None => continue,
+                            };
+
                             let scrollback = TabScrollback { id, scrollback };
                             let message = TabSend::Scrollback(scrollback);
                             tx_tab.send(message).await.ok();
                         }
-                        PtySend::Stopped => {
-                            let id = rx_id.borrow().unwrap();
-                            info!("Received termination notice on tab {}", id);
-                            // todo - this should be a notification, not an action
-                            // serious bugs were going on because this was missing, though.
-                            tx_tab_manager.send(TabManagerRecv::CloseTab(id)).await?;
-                            tx_tab.send(TabSend::Stopped(id)).await.ok();
+                        PtySend::Stopped(exit) => {
+                            let id = match assigned {
+                                Some(id) => id,
+                                None => {
+                                    info!(
+                                        "Received termination notice on an unassigned pty: {}",
+                                        exit
+                                    );
+                                    continue;
+                                }
+                            };
+
+                            info!("Received termination notice on tab {}: {}", id, exit);
+                            // the tab manager closes the tab, and notifies clients with TabSend::Stopped
+                            tx_tab_manager
+                                .send(TabManagerRecv::Exited(id, exit))
+                                .await?;
                         }
                     }
                 }
@@ -190,7 +284,158 @@ impl CarryFrom<ListenerBus> for PtyBus {
 }
 
 #[cfg(test)]
-mod to_pty_tests {}
+mod to_pty_tests {
+    use crate::{
+        message::{
+            pty::{PtyRecv, PtySend},
+            tab::{TabInput, TabRecv, TabSend},
+        },
+        prelude::*,
+    };
+    use lifeline::assert_completes;
+    use std::collections::HashMap;
+    use tab_api::tab::{TabId, TabMetadata};
+
+    fn tab() -> TabMetadata {
+        TabMetadata {
+            id: TabId(1),
+            name: "name".into(),
+            doc: None,
+            dimensions: (1, 2),
+            env: HashMap::new(),
+            shell: "shell".into(),
+            dir: "/".into(),
+            command: None,
+            selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn resumed_input() -> anyhow::Result<()> {
+        let pty_bus = PtyBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = pty_bus.carry_from(&listener_bus)?;
+
+        let mut tx_pty = pty_bus.tx::<PtySend>()?;
+        let mut tx_tab = listener_bus.tx::<TabRecv>()?;
+        let mut rx = pty_bus.rx::<PtyRecv>()?;
+        let mut rx_tab = listener_bus.rx::<TabSend>()?;
+
+        tx_pty.send(PtySend::Resumed(tab())).await?;
+        tx_tab.send(TabRecv::AcceptResume(tab())).await?;
+
+        assert_completes!(async {
+            let msg = rx_tab.recv().await;
+            assert!(matches!(msg, Some(TabSend::Started(started)) if started == tab()));
+        });
+
+        let input = TabInput::new(TabId(1), vec![0u8]);
+        tx_tab.send(TabRecv::Input(input)).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert!(matches!(msg, Some(PtyRecv::Input(input)) if input.data == vec![0u8]));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resume_rejected() -> anyhow::Result<()> {
+        let pty_bus = PtyBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = pty_bus.carry_from(&listener_bus)?;
+
+        let mut tx_pty = pty_bus.tx::<PtySend>()?;
+        let mut tx_tab = listener_bus.tx::<TabRecv>()?;
+        let mut rx = pty_bus.rx::<PtyRecv>()?;
+
+        tx_pty.send(PtySend::Resumed(tab())).await?;
+
+        // input for the running tab with the same id must not reach the colliding pty
+        let input = TabInput::new(TabId(1), vec![0u8]);
+        tx_tab.send(TabRecv::Input(input)).await?;
+        tx_tab.send(TabRecv::RejectResume(tab())).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert!(matches!(msg, Some(PtyRecv::Terminate)));
+        });
+
+        Ok(())
+    }
+}
 
 #[cfg(test)]
-mod to_listener_tests {}
+mod to_listener_tests {
+    use crate::{
+        message::{pty::PtySend, tab::TabSend, tab_manager::TabManagerRecv},
+        prelude::*,
+    };
+    use lifeline::{assert_completes, assert_times_out};
+    use std::collections::HashMap;
+    use tab_api::{
+        chunk::OutputChunk,
+        tab::{TabId, TabMetadata},
+    };
+
+    #[tokio::test]
+    async fn resumed() -> anyhow::Result<()> {
+        let pty_bus = PtyBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = pty_bus.carry_from(&listener_bus)?;
+
+        let mut tx = pty_bus.tx::<PtySend>()?;
+        let mut rx_manager = listener_bus.rx::<TabManagerRecv>()?;
+        let mut rx_tab = listener_bus.rx::<TabSend>()?;
+
+        let tab = TabMetadata {
+            id: TabId(1),
+            name: "name".into(),
+            doc: None,
+            dimensions: (1, 2),
+            env: HashMap::new(),
+            shell: "shell".into(),
+            dir: "/".into(),
+            command: None,
+            selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
+        };
+        tx.send(PtySend::Resumed(tab.clone())).await?;
+        tx.send(PtySend::Output(OutputChunk {
+            index: 0,
+            data: vec![0u8],
+        }))
+        .await?;
+
+        assert_completes!(async {
+            let msg = rx_manager.recv().await;
+            assert_eq!(Some(TabManagerRecv::ResumeTab(tab.clone())), msg);
+        });
+
+        // the tab is not started, and output is not forwarded, until the tab manager accepts the resume
+        assert_times_out!(async {
+            rx_tab.recv().await;
+        });
+
+        Ok(())
+    }
+}
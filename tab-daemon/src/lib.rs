@@ -1,20 +1,27 @@
 use daemonfile::DaemonFile;
 
 use crate::bus::DaemonBus;
-use log::{info, LevelFilter};
+use log::{info, warn, LevelFilter};
 
 use lifeline::{dyn_bus::DynBus, prelude::*};
 use message::daemon::DaemonShutdown;
 use service::daemon::DaemonService;
 use simplelog::{ColorChoice, CombinedLogger, TermLogger, TerminalMode, WriteLogger};
-use std::time::Duration;
+use state::session::SessionStore;
+use std::{path::PathBuf, time::Duration};
 use tab_api::{
-    config::{daemon_log, DaemonConfig},
+    config::{
+        daemon_log, daemon_socket, is_running, load_daemon_file, load_output_log_config,
+        load_resize_config, load_scrollback_config, load_transport_config, session_path,
+        DaemonConfig, OutputLogConfig, ResizeConfig, ScrollbackConfig, TransportConfig,
+    },
     launch::wait_for_shutdown,
     log::get_level,
 };
-use tab_websocket::resource::listener::{WebsocketAuthToken, WebsocketListenerResource};
-use tokio::net::TcpListener;
+use tab_websocket::{
+    resource::listener::{WebsocketAuthToken, WebsocketListenerResource},
+    transport::Listener,
+};
 
 mod auth;
 mod bus;
@@ -41,9 +48,32 @@ pub fn daemon_main(tab_version: &'static str) -> anyhow::Result<()> {
 }
 
 pub async fn new_bus(tab_version: &'static str) -> anyhow::Result<DaemonBus> {
-    let server = TcpListener::bind("127.0.0.1:0").await?;
-    let port = server.local_addr()?.port();
-    let websocket = WebsocketListenerResource(server);
+    new_bus_with_socket(tab_version, None).await
+}
+
+/// Creates the daemon bus, listening on the Unix socket if provided.
+/// Falls back to a TCP port if the socket cannot be bound.
+pub async fn new_bus_with_socket(
+    tab_version: &'static str,
+    socket: Option<PathBuf>,
+) -> anyhow::Result<DaemonBus> {
+    let (listener, socket) = match socket {
+        Some(socket) => match Listener::bind_unix(socket.as_path()) {
+            Ok(listener) => (listener, Some(socket)),
+            Err(e) => {
+                warn!(
+                    "Failed to bind the Unix socket {}, falling back to TCP: {}",
+                    socket.display(),
+                    e
+                );
+                (Listener::bind_tcp().await?, None)
+            }
+        },
+        None => (Listener::bind_tcp().await?, None),
+    };
+
+    let port = listener.port()?.unwrap_or(0);
+    let websocket = WebsocketListenerResource(listener);
 
     let auth_token = auth::gen_token();
     let pid = std::process::id();
@@ -55,6 +85,7 @@ pub async fn new_bus(tab_version: &'static str) -> anyhow::Result<DaemonBus> {
     let config = DaemonConfig {
         pid: pid as i32,
         port,
+        socket,
         executable,
         tab_version: Some(tab_version.to_string()),
         auth_token: auth_token.clone(),
@@ -64,6 +95,10 @@ pub async fn new_bus(tab_version: &'static str) -> anyhow::Result<DaemonBus> {
     bus.store_resource::<DaemonConfig>(config);
     bus.store_resource::<WebsocketAuthToken>(auth_token.into());
     bus.store_resource::<WebsocketListenerResource>(websocket);
+    bus.store_resource::<SessionStore>(SessionStore::default());
+    bus.store_resource::<ScrollbackConfig>(ScrollbackConfig::default());
+    bus.store_resource::<OutputLogConfig>(OutputLogConfig::default());
+    bus.store_resource::<ResizeConfig>(ResizeConfig::default());
 
     Ok(bus)
 }
@@ -89,13 +124,83 @@ async fn main_async(tab_version: &'static str) -> anyhow::Result<()> {
 
     log_panics::init();
 
-    let bus = new_bus(tab_version).await?;
+    let transport = load_transport_config()
+        .unwrap_or_else(|e| {
+            warn!(
+                "Failed to load the transport config, using the defaults: {}",
+                e
+            );
+            TransportConfig::default()
+        })
+        .with_env_override();
+
+    let socket = if transport.unix_socket {
+        Some(daemon_socket(std::process::id())?)
+    } else {
+        None
+    };
+
+    let bus = new_bus_with_socket(tab_version, socket).await?;
+    // new_bus stores a disabled session, so tests never touch the user's session directory
+    let store = SessionStore::new(session_path()?);
+
+    // if the previous daemon crashed, its ptys are still running, and will reconnect
+    if let Some(previous) = load_daemon_file()? {
+        if !is_running(&previous) {
+            warn!(
+                "Previous daemon at pid {} did not shut down cleanly.  Awaiting its ptys.",
+                previous.pid
+            );
+            store.mark_handoff()?;
+        }
+    }
+
+    bus.store_resource::<SessionStore>(store);
+
+    let scrollback = load_scrollback_config().unwrap_or_else(|e| {
+        warn!(
+            "Failed to load the scrollback config, using the defaults: {}",
+            e
+        );
+        ScrollbackConfig::default()
+    });
+    bus.store_resource::<ScrollbackConfig>(scrollback);
+
+    let output_log = load_output_log_config().unwrap_or_else(|e| {
+        warn!(
+            "Failed to load the output log config, using the defaults: {}",
+            e
+        );
+        OutputLogConfig::default()
+    });
+    bus.store_resource::<OutputLogConfig>(output_log);
+
+    let resize = load_resize_config()
+        .unwrap_or_else(|e| {
+            warn!(
+                "Failed to load the resize config, using the defaults: {}",
+                e
+            );
+            ResizeConfig::default()
+        })
+        .with_env_override();
+    bus.store_resource::<ResizeConfig>(resize);
+
     let config = bus.resource::<DaemonConfig>()?;
 
-    let daemon_file = DaemonFile::new(&config)?;
+    let daemon_file = match DaemonFile::new(&config) {
+        Ok(daemon_file) => daemon_file,
+        Err(e) => {
+            remove_socket(&config);
+            return Err(e);
+        }
+    };
     info!("Daemon started.");
     info!("Daemon pid: {}", config.pid);
-    info!("Daemon port: {}", config.port);
+    match config.socket {
+        Some(ref socket) => info!("Daemon socket: {}", socket.display()),
+        None => info!("Daemon port: {}", config.port),
+    }
 
     let _service = DaemonService::spawn(&bus)?;
     let shutdown = bus.rx::<DaemonShutdown>()?;
@@ -104,6 +209,13 @@ async fn main_async(tab_version: &'static str) -> anyhow::Result<()> {
 
     info!("Daemon shutdown.");
     drop(daemon_file);
+    remove_socket(&config);
 
     Ok(())
 }
+
+fn remove_socket(config: &DaemonConfig) {
+    if let Some(ref socket) = config.socket {
+        std::fs::remove_file(socket).ok();
+    }
+}
@@ -1,7 +1,10 @@
 pub mod cli;
 pub mod daemon;
+pub mod grep;
 pub mod listener;
 pub mod pty;
+pub mod resize;
+pub mod session;
 pub mod tab;
 pub mod tab_assignment;
 pub mod tab_manager;
@@ -2,8 +2,8 @@ use super::tab::{TabOutput, TabScrollback};
 
 use tab_api::{
     chunk::{InputChunk, OutputChunk},
-    client::RetaskTarget,
-    tab::{CreateTabMetadata, TabId, TabMetadata},
+    client::{GrepMatch, GrepQuery, RetaskTarget},
+    tab::{CreateTabMetadata, TabExit, TabId, TabMetadata},
 };
 
 /// The CLI connection Send message.  Messaged on the CliBus, and
@@ -24,14 +24,24 @@ pub enum CliSend {
     Retask(TabId, RetaskTarget),
     /// Requests the scrollback buffer be read, and replied to as a CliRecv::Scrollback message.
     Subscribe(TabId),
-    /// Resizes the tab to the given number of (cols, rows)
+    /// Detaches the connection from the given tab, so it's dimensions no longer affect the tab size
+    Unsubscribe(TabId),
+    /// Reports the dimensions of the client on the given tab, in (cols, rows)
     ResizeTab(TabId, (u16, u16)),
     /// Closes the tab with the given ID
     CloseTab(TabId),
     /// Disconnects any sessions for the tab with the given ID
     DisconnectTab(TabId),
+    /// Starts recording the output of the tab with the given ID
+    RecordTab(TabId),
+    /// Searches the scrollback of all running tabs.  The results are replied to as a CliRecv::Grep message.
+    Grep(GrepQuery),
+    /// Acknowledges the previous messages, once they have been delivered.  Replied to as a CliRecv::Flushed message.
+    Flush,
     /// Shuts down the Daemon and all PTY processes
     GlobalShutdown,
+    /// Shuts down the Daemon, and requests that PTY processes reconnect to the next daemon
+    UpgradeDaemon,
 }
 
 /// The CLI connection Recv message.  
@@ -49,6 +59,10 @@ pub enum CliRecv {
     TabStarted(TabMetadata),
     /// A notification that a tab with the given metadata has been updated.
     TabUpdated(TabMetadata),
+    /// The results of a grep request, which was sent by this connection.
+    Grep(Vec<GrepMatch>),
+    /// An acknowledgement that the messages sent before a CliSend::Flush have been delivered.
+    Flushed,
 }
 
 /// A message sent to the command client's tab subscription service
@@ -56,17 +70,22 @@ pub enum CliRecv {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CliSubscriptionRecv {
     Subscribe(TabId),
+    /// Subscribes to the tab, replaying the raw scrollback rather than a redraw of the terminal state
+    Capture(TabId),
     Unsubscribe(TabId),
     /// A notification that scrollback is available for the given tab.
-    /// Receivers can clone the scrollback buffer with TabScrollback::scrollback
+    /// Receivers can synthesize a redraw of the terminal state with TabScrollback::snapshot,
+    /// or replay the raw output with TabScrollback::replay
     Scrollback(TabScrollback),
     /// An indexed stdout chunk, for the given tab
     Output(TabOutput),
     /// A notification that a tab has been retasked.  The client may need to request scrollback and change their subscriptions.
     /// If the second argument is None, the client should disconnect.
     Retask(TabId, RetaskTarget),
-    /// Notification that a tab has stopped
-    Stopped(TabId),
+    /// Notification that a tab has stopped, with the exit status of the shell
+    Stopped(TabId, TabExit),
+    /// Requests the rendered lines of the subscribed tab's history & screen
+    Lines(TabId),
 }
 
 /// A message sent by the client's subscription state service
@@ -75,7 +94,9 @@ pub enum CliSubscriptionRecv {
 pub enum CliSubscriptionSend {
     Retask(RetaskTarget),
     Output(TabId, OutputChunk),
-    Stopped(TabId),
+    ScrollbackEnd(TabId),
+    Stopped(TabId, TabExit),
+    Lines(TabId, Vec<String>),
 }
 
 /// Terminates the websocket connection & supporing services.
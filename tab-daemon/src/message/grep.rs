@@ -0,0 +1,29 @@
+use tab_api::client::{GrepMatch, GrepQuery};
+
+use crate::state::resize::ConnectionId;
+
+/// A search request for the `GrepService`, which searches the scrollback of all running tabs.
+///
+/// Carried over the `ListenerBus`
+///
+/// Usage:
+/// - Tx from the `ListenerConnectionCarrier`, when the tab-command client sends a grep request.
+/// - Rx from the `GrepService`, which replies with a `GrepSend` message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrepRecv {
+    pub connection: ConnectionId,
+    pub query: GrepQuery,
+}
+
+/// The results of a search, addressed to the connection which requested it.
+///
+/// Carried over the `ListenerBus`
+///
+/// Usage:
+/// - Tx from the `GrepService`, with the matching lines.
+/// - Rx from the `ListenerConnectionCarrier`, which forwards results for it's own connection to the CLI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrepSend {
+    pub connection: ConnectionId,
+    pub matches: Vec<GrepMatch>,
+}
@@ -2,7 +2,7 @@ use crate::state::pty::PtyScrollback;
 
 use tab_api::{
     chunk::{InputChunk, OutputChunk},
-    tab::TabMetadata,
+    tab::{TabExit, TabMetadata},
 };
 
 /// Terminates the PTY connection & supporting services.
@@ -16,6 +16,7 @@ pub struct PtyShutdown {}
 /// - Tx from `PtyService`, to communicate initialization to the daemon and to provide events for CLI connections.
 /// - Rx from `PtyScrollbackService`, to collect scrollback in the daemon process.
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum PtyRecv {
     Init(TabMetadata),
     Scrollback,
@@ -23,6 +24,10 @@ pub enum PtyRecv {
     Resize((u16, u16)),
     Input(InputChunk),
     Terminate,
+    /// Requests that the pty reconnects to the next daemon
+    Reconnect,
+    /// Starts recording the output of the tab, if it is not already being recorded
+    Record,
 }
 
 /// Events generated in the PTY process, forwarded to the Daemon.
@@ -35,9 +40,12 @@ pub enum PtyRecv {
 #[derive(Debug, Clone)]
 pub enum PtySend {
     Started(TabMetadata),
+    /// A running pty has reconnected, and resumed the tab
+    Resumed(TabMetadata),
     Output(OutputChunk),
     Scrollback(PtyScrollback),
-    Stopped,
+    /// The shell has exited, with the given status
+    Stopped(TabExit),
 }
 
 impl PartialEq for PtySend {
@@ -51,6 +59,13 @@ impl PartialEq for PtySend {
                     return false;
                 }
             }
+            PtySend::Resumed(tab) => {
+                if let PtySend::Resumed(other_tab) = other {
+                    return tab == other_tab;
+                } else {
+                    return false;
+                }
+            }
             PtySend::Output(output) => {
                 if let PtySend::Output(other_output) = other {
                     return output == other_output;
@@ -62,9 +77,9 @@ impl PartialEq for PtySend {
                 // we can't implement this, as scrollback contains an async mutex
                 return false;
             }
-            PtySend::Stopped => {
-                if let PtySend::Stopped = other {
-                    return true;
+            PtySend::Stopped(exit) => {
+                if let PtySend::Stopped(other_exit) = other {
+                    return exit == other_exit;
                 } else {
                     return false;
                 }
@@ -0,0 +1,23 @@
+use tab_api::tab::TabId;
+
+use crate::state::resize::ConnectionId;
+
+/// A message received by the `ResizeService`, which tracks the dimensions of each CLI connection,
+/// and resizes tabs according to the resize policy.
+///
+/// Carried over the `ListenerBus`
+///
+/// Usage:
+/// - Rx from the `ResizeService`, which computes the tab size & sends `TabRecv::Resize`.
+/// - Tx from the `ListenerConnectionCarrier`, to report client dimensions, activity, and detachment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResizeRecv {
+    /// The connection is attached to the tab with the given (cols, rows)
+    Resize(ConnectionId, TabId, (u16, u16)),
+    /// The connection has sent input to the tab
+    Input(ConnectionId, TabId),
+    /// The connection has unsubscribed from the tab
+    Unsubscribe(ConnectionId, TabId),
+    /// The connection has closed
+    Disconnect(ConnectionId),
+}
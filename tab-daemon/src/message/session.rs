@@ -0,0 +1,16 @@
+/// A request to the `SessionService`, which persists the running tabs across daemon restarts.
+///
+/// Carried over the `ListenerBus`.
+///
+/// Usage:
+/// - Rx from the `SessionService`, to write session snapshots.
+/// - Tx from the `ListenerConnectionCarrier`, when the tab-command client requests a global shutdown or upgrade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionRecv {
+    /// Writes a snapshot of the running tabs, and their scrollback.
+    Snapshot,
+    /// Writes a final snapshot, then terminates all tabs and shuts down the daemon.
+    Shutdown,
+    /// Writes a final snapshot, then hands the running tabs to the next daemon and shuts down.
+    Handoff,
+}
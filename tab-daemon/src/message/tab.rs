@@ -1,9 +1,12 @@
-use crate::state::{assignment::Assignment, pty::PtyScrollback};
+use crate::{
+    service::pty::scrollback::{ScrollbackReplay, ScrollbackSnapshot},
+    state::{assignment::Assignment, pty::PtyScrollback},
+};
 use std::sync::Arc;
 use tab_api::{
     chunk::{InputChunk, OutputChunk},
     client::RetaskTarget,
-    tab::{TabId, TabMetadata},
+    tab::{TabExit, TabId, TabMetadata},
 };
 
 /// An input (stdin) event for tab, identified by an id.
@@ -59,6 +62,7 @@ impl Eq for TabOutput {
 /// Usage:
 /// - Tx from the `ListenerConnectionCarrier`, to forward lifecycle & stdin from CLI connections
 /// - Tx from the `TabManagerService`, to offer tab assignments to PTY connections
+/// - Tx from the `TabManagerService`, to accept or reject tabs resumed by reconnecting PTY connections
 /// - Rx from the `ListenerPtyCarrier`, to forward events to an established PTY tab.
 /// - Rx from the `RetaskService`, to broadcast retask to subscribed CLI connections.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -72,11 +76,21 @@ pub enum TabRecv {
     Retask(TabId, RetaskTarget),
     Input(TabInput),
     Terminate(TabId),
+    /// Starts recording the output of the tab
+    Record(TabId),
     TerminateAll,
+    /// Requests that all ptys keep running, and reconnect to the next daemon
+    ReconnectAll,
+    /// The tab manager accepted the tab resumed by a reconnecting pty, which is assigned to the tab id
+    AcceptResume(TabMetadata),
+    /// The tab manager rejected the tab resumed by a reconnecting pty, as the id is taken by another tab.
+    /// The pty is terminated.
+    RejectResume(TabMetadata),
 }
 
 /// A cheaply clonable message with the latest tab scrollback.
-/// Receivers can call `msg.scrollback.scrollback()` to clone & iterate over the scrollback buffer.
+/// Receivers can call `msg.snapshot()` to synthesize a redraw of the terminal state,
+/// or `msg.replay()` to list the raw output.
 #[derive(Debug, Clone)]
 pub struct TabScrollback {
     pub id: TabId,
@@ -107,9 +121,22 @@ impl TabScrollback {
         self.scrollback.push(chunk).await;
     }
 
+    #[cfg(test)]
     pub async fn scrollback(&self) -> impl Iterator<Item = OutputChunk> {
         self.scrollback.scrollback().await
     }
+
+    pub async fn snapshot(&self) -> ScrollbackSnapshot {
+        self.scrollback.snapshot().await
+    }
+
+    pub async fn replay(&self) -> ScrollbackReplay {
+        self.scrollback.replay().await
+    }
+
+    pub async fn lines(&self) -> Vec<String> {
+        self.scrollback.lines().await
+    }
 }
 
 /// A message sent from an established tab, to provide lifecycle notification events,
@@ -130,5 +157,6 @@ pub enum TabSend {
     /// If the second argument is None, clients should disconnect
     Retask(TabId, RetaskTarget),
     Output(TabOutput),
-    Stopped(TabId),
+    /// The tab has stopped, with the exit status of the shell
+    Stopped(TabId, TabExit),
 }
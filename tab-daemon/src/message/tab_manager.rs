@@ -1,4 +1,6 @@
-use tab_api::tab::{CreateTabMetadata, TabId};
+use tab_api::tab::{CreateTabMetadata, TabAlert, TabExit, TabId, TabMetadata};
+
+use crate::state::resize::ConnectionId;
 
 /// A message received by the `TabManagerService`, which manages the tab lifecycle and assigns tabs to PTY connections.
 ///
@@ -7,10 +9,30 @@ use tab_api::tab::{CreateTabMetadata, TabId};
 /// Usage:
 /// - Rx from the `TabManagerService`, which creates & closes active tabs.
 /// - Tx into the `ListenerConnectionCarrier`, to request that tabs be created/closed from a CLI connection.
+/// - Tx into the `ListenerConnectionCarrier`, to track the client connections which are attached to each tab.
+/// - Tx into the `ResizeService`, to update the tab dimensions.
 /// - Tx into the `ListenerPtyCarrier`, to notify the manager that a PTY process is terminating (e.g. user typed `exit`)
+/// - Tx into the `ListenerPtyCarrier`, to notify the manager that a running PTY process has reconnected to the daemon
+/// - Tx into the `MonitorService`, to raise alerts on monitored tabs
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TabManagerRecv {
     CreateTab(CreateTabMetadata),
-    UpdateTimestamp(TabId),
+    /// A client connection has subscribed to the tab.  Marks the tab as selected.
+    Attach(ConnectionId, TabId),
+    /// A client connection has unsubscribed from the tab
+    Detach(ConnectionId, TabId),
+    /// A client connection has disconnected, and is detached from all of it's tabs
+    Disconnect(ConnectionId),
+    /// The tab has been resized to the given (cols, rows)
+    Resize(TabId, (u16, u16)),
+    /// Closes the tab, or dismisses the tab if it has exited
     CloseTab(TabId),
+    /// The shell of the tab has exited.  The tab is closed, and listed as exited until it is dismissed.
+    Exited(TabId, TabExit),
+    /// A running pty has reconnected, and resumed the tab with the given metadata
+    ResumeTab(TabMetadata),
+    /// Spawns new ptys for handed off tabs, if their pty has not yet resumed
+    ExpireHandoff,
+    /// The tab monitor has raised an alert.  Ignored if a client is attached to the tab.
+    Alert(TabId, TabAlert),
 }
@@ -6,7 +6,7 @@ use crate::prelude::*;
 use crate::state::tab::TabsState;
 use anyhow::Context;
 use postage::{sink::Sink, stream::Stream};
-use tab_api::client::InitResponse;
+use tab_api::client::{InitResponse, SubscriptionMode};
 
 pub mod subscription;
 
@@ -36,6 +36,7 @@ impl Service for CliService {
 
                 let init = InitResponse {
                     tabs: tabs.tabs.clone(),
+                    exited: tabs.exited.clone(),
                 };
 
                 let init = Response::Init(init);
@@ -61,8 +62,10 @@ impl Service for CliService {
             Self::try_task("run", async move {
                 debug!("cli connection waiting for messages");
 
+                let mut mode = SubscriptionMode::Interactive;
                 while let Some(msg) = rx.recv().await {
-                    Self::recv_websocket(msg, &mut tx_subscription, &mut tx_daemon).await?
+                    Self::recv_websocket(msg, &mut mode, &mut tx_subscription, &mut tx_daemon)
+                        .await?
                 }
 
                 tx_shutdown.send(CliShutdown {}).await?;
@@ -101,9 +104,15 @@ impl Service for CliService {
                         CliSubscriptionSend::Output(id, chunk) => {
                             tx.send(Response::Output(id, chunk)).await?;
                         }
-                        CliSubscriptionSend::Stopped(id) => {
+                        CliSubscriptionSend::ScrollbackEnd(id) => {
+                            tx.send(Response::ScrollbackEnd(id)).await?;
+                        }
+                        CliSubscriptionSend::Stopped(id, exit) => {
                             debug!("Notifying client of termination on tab {:?}", id);
-                            tx.send(Response::TabTerminated(id)).await?;
+                            tx.send(Response::TabTerminated(id, exit)).await?;
+                        }
+                        CliSubscriptionSend::Lines(id, lines) => {
+                            tx.send(Response::ScrollbackLines(id, lines)).await?;
                         }
                     }
                 }
@@ -122,18 +131,36 @@ impl Service for CliService {
 }
 
 impl CliService {
+    /// Forwards a websocket request to the daemon.
+    /// Connections with a read-only subscription cannot send input to, resize, close, disconnect, retask, or record tabs.
+    /// The read-only mode is sticky, and is kept if the connection later subscribes as interactive.
     async fn recv_websocket(
         request: Request,
+        mode: &mut SubscriptionMode,
         mut tx_subscription: impl Sink<Item = CliSubscriptionRecv> + Unpin,
         mut tx_daemon: impl Sink<Item = CliSend> + Unpin,
     ) -> anyhow::Result<()> {
         debug!("received Request: {:?}", &request);
 
+        if *mode != SubscriptionMode::Interactive && Self::is_interactive(&request) {
+            debug!("ignoring request from read-only client: {:?}", &request);
+            return Ok(());
+        }
+
         match request {
-            Request::Subscribe(id) => {
-                debug!("client subscribing to tab {}", id);
+            Request::Subscribe(id, subscription_mode) => {
+                debug!("client subscribing to tab {} ({:?})", id, subscription_mode);
+                if *mode == SubscriptionMode::Interactive {
+                    *mode = subscription_mode;
+                }
+
+                let message = match subscription_mode {
+                    SubscriptionMode::Capture => CliSubscriptionRecv::Capture(id),
+                    _ => CliSubscriptionRecv::Subscribe(id),
+                };
+
                 tx_subscription
-                    .send(CliSubscriptionRecv::Subscribe(id))
+                    .send(message)
                     .await
                     .context("tx_subscription closed")?;
             }
@@ -143,6 +170,16 @@ impl CliService {
                     .send(CliSubscriptionRecv::Unsubscribe(id))
                     .await
                     .context("tx_subscription closed")?;
+                tx_daemon
+                    .send(CliSend::Unsubscribe(id))
+                    .await
+                    .context("tx_daemon closed")?;
+            }
+            Request::ScrollbackLines(id) => {
+                tx_subscription
+                    .send(CliSubscriptionRecv::Lines(id))
+                    .await
+                    .context("tx_subscription closed")?;
             }
             Request::Input(id, stdin) => {
                 debug!("rx input on tab {}, data: {}", id.0, stdin.to_string());
@@ -165,19 +202,45 @@ impl CliService {
                 let message = CliSend::DisconnectTab(id);
                 tx_daemon.send(message).await.context("tx_daemon closed")?;
             }
+            Request::RecordTab(id) => {
+                let message = CliSend::RecordTab(id);
+                tx_daemon.send(message).await.context("tx_daemon closed")?;
+            }
             Request::Retask(id, target) => {
                 // we need to send this along so other attached tabs get retasked
                 let message = CliSend::Retask(id, target);
                 tx_daemon.send(message).await?;
             }
+            Request::Grep(query) => {
+                tx_daemon.send(CliSend::Grep(query)).await?;
+            }
+            Request::Flush => {
+                tx_daemon.send(CliSend::Flush).await?;
+            }
             Request::GlobalShutdown => {
                 tx_daemon.send(CliSend::GlobalShutdown).await?;
             }
+            Request::UpgradeDaemon => {
+                tx_daemon.send(CliSend::UpgradeDaemon).await?;
+            }
         }
 
         Ok(())
     }
 
+    /// Returns true if the request modifies a tab, and requires an interactive connection
+    fn is_interactive(request: &Request) -> bool {
+        matches!(
+            request,
+            Request::Input(..)
+                | Request::ResizeTab(..)
+                | Request::CloseTab(..)
+                | Request::DisconnectTab(..)
+                | Request::Retask(..)
+                | Request::RecordTab(..)
+        )
+    }
+
     async fn recv_daemon(
         msg: CliRecv,
         mut tx_websocket: impl Sink<Item = Response> + Unpin,
@@ -196,6 +259,18 @@ impl CliService {
                     .await
                     .context("tx_websocket closed")?;
             }
+            CliRecv::Grep(matches) => {
+                tx_websocket
+                    .send(Response::Grep(matches))
+                    .await
+                    .context("tx_websocket closed")?;
+            }
+            CliRecv::Flushed => {
+                tx_websocket
+                    .send(Response::Flushed)
+                    .await
+                    .context("tx_websocket closed")?;
+            }
         }
         Ok(())
     }
@@ -208,11 +283,11 @@ mod request_tests {
         bus::CliBus, message::cli::CliSend, message::cli::CliSubscriptionRecv,
         state::tab::TabsState,
     };
-    use lifeline::{assert_completes, Bus, Receiver, Sender, Service};
+    use lifeline::{assert_completes, assert_times_out, Bus, Receiver, Sender, Service};
     use std::collections::HashMap;
     use tab_api::{
         chunk::InputChunk,
-        client::{InitResponse, Request, Response, RetaskTarget},
+        client::{InitResponse, Request, Response, RetaskTarget, SubscriptionMode},
         tab::{CreateTabMetadata, TabId, TabMetadata},
     };
 
@@ -232,7 +307,16 @@ mod request_tests {
             env: HashMap::new(),
             shell: "bash".into(),
             dir: "/".into(),
+            command: None,
             selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
         };
         tabs.tabs.insert(tab_id, tab_metadata.clone());
         tx.send(tabs).await?;
@@ -245,6 +329,7 @@ mod request_tests {
 
             let mut expect_tabs = InitResponse {
                 tabs: HashMap::new(),
+                exited: HashMap::new(),
             };
             expect_tabs.tabs.insert(tab_id, tab_metadata.clone());
             assert_eq!(Some(Response::Init(expect_tabs)), init);
@@ -264,7 +349,8 @@ mod request_tests {
         let mut tx = cli_bus.tx::<Request>()?;
         let mut rx = cli_bus.rx::<CliSubscriptionRecv>()?;
 
-        tx.send(Request::Subscribe(TabId(0))).await?;
+        tx.send(Request::Subscribe(TabId(0), SubscriptionMode::Interactive))
+            .await?;
 
         assert_completes!(async move {
             let msg = rx.recv().await;
@@ -274,6 +360,25 @@ mod request_tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn subscribe_capture() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let _service = CliService::spawn(&cli_bus)?;
+
+        let mut tx = cli_bus.tx::<Request>()?;
+        let mut rx = cli_bus.rx::<CliSubscriptionRecv>()?;
+
+        tx.send(Request::Subscribe(TabId(0), SubscriptionMode::Capture))
+            .await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSubscriptionRecv::Capture(TabId(0))), msg);
+        });
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn unsubscribe() -> anyhow::Result<()> {
         let cli_bus = CliBus::default();
@@ -311,6 +416,60 @@ mod request_tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn read_only_input() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let _service = CliService::spawn(&cli_bus)?;
+
+        let mut tx = cli_bus.tx::<Request>()?;
+        let mut rx = cli_bus.rx::<CliSend>()?;
+        let _rx_subscription = cli_bus.rx::<CliSubscriptionRecv>()?;
+
+        tx.send(Request::Subscribe(TabId(0), SubscriptionMode::ReadOnly))
+            .await?;
+
+        let input = InputChunk { data: vec![1u8] };
+        tx.send(Request::Input(TabId(0), input)).await?;
+        tx.send(Request::ResizeTab(TabId(0), (1, 2))).await?;
+        tx.send(Request::CloseTab(TabId(0))).await?;
+        tx.send(Request::DisconnectTab(TabId(0))).await?;
+        tx.send(Request::Retask(TabId(0), RetaskTarget::Tab(TabId(1))))
+            .await?;
+        tx.send(Request::RecordTab(TabId(0))).await?;
+
+        assert_times_out!(async move {
+            rx.recv().await;
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_only_resubscribe() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let _service = CliService::spawn(&cli_bus)?;
+
+        let mut tx = cli_bus.tx::<Request>()?;
+        let mut rx = cli_bus.rx::<CliSend>()?;
+        let _rx_subscription = cli_bus.rx::<CliSubscriptionRecv>()?;
+
+        tx.send(Request::Subscribe(TabId(0), SubscriptionMode::ReadOnly))
+            .await?;
+        tx.send(Request::Subscribe(TabId(0), SubscriptionMode::Interactive))
+            .await?;
+
+        let input = InputChunk { data: vec![1u8] };
+        tx.send(Request::Input(TabId(0), input)).await?;
+        tx.send(Request::ResizeTab(TabId(0), (1, 2))).await?;
+        tx.send(Request::CloseTab(TabId(0))).await?;
+
+        assert_times_out!(async move {
+            rx.recv().await;
+        });
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn create_tab() -> anyhow::Result<()> {
         let cli_bus = CliBus::default();
@@ -328,7 +487,14 @@ mod request_tests {
             dimensions: (1, 2),
             shell: "shell".into(),
             dir: "/".into(),
+            command: None,
             env,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: false,
+            monitor: None,
+            notify: None,
         };
         tx.send(Request::CreateTab(tab.clone())).await?;
 
@@ -433,6 +599,24 @@ mod request_tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn upgrade_daemon() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let _service = CliService::spawn(&cli_bus)?;
+
+        let mut tx = cli_bus.tx::<Request>()?;
+        let mut rx = cli_bus.rx::<CliSend>()?;
+
+        tx.send(Request::UpgradeDaemon).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSend::UpgradeDaemon), msg);
+        });
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -443,7 +627,7 @@ mod recv_tests {
     use std::collections::HashMap;
     use tab_api::{
         client::Response,
-        tab::{TabId, TabMetadata},
+        tab::{TabExit, TabId, TabMetadata},
     };
 
     #[tokio::test]
@@ -462,7 +646,16 @@ mod recv_tests {
             env: HashMap::new(),
             shell: "shell".into(),
             dir: "/".into(),
+            command: None,
             selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
         };
 
         tx.send(CliRecv::TabStarted(metadata.clone())).await?;
@@ -491,7 +684,16 @@ mod recv_tests {
             env: HashMap::new(),
             shell: "shell".into(),
             dir: "/".into(),
+            command: None,
             selected: 10,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
         };
 
         tx.send(CliRecv::TabUpdated(metadata.clone())).await?;
@@ -511,11 +713,15 @@ mod recv_tests {
         let mut tx = bus.tx::<CliSubscriptionSend>()?;
         let mut rx = bus.rx::<Response>()?;
 
-        tx.send(CliSubscriptionSend::Stopped(TabId(0))).await?;
+        tx.send(CliSubscriptionSend::Stopped(TabId(0), TabExit::code(3)))
+            .await?;
 
         assert_completes!(async move {
             let msg = rx.recv().await;
-            assert_eq!(Some(Response::TabTerminated(TabId(0))), msg);
+            assert_eq!(
+                Some(Response::TabTerminated(TabId(0), TabExit::code(3))),
+                msg
+            );
         });
 
         Ok(())
@@ -1,9 +1,10 @@
 use postage::sink::Sink;
+use std::collections::HashMap;
 use tab_api::{chunk::OutputChunk, client::RetaskTarget, tab::TabId};
 
 use crate::{
     message::cli::CliSend, message::cli::CliSubscriptionRecv, message::cli::CliSubscriptionSend,
-    prelude::*,
+    message::tab::TabScrollback, prelude::*,
 };
 use anyhow::Context;
 
@@ -22,11 +23,14 @@ impl Service for CliSubscriptionService {
             let mut tx_daemon = bus.tx::<CliSend>()?;
 
             Self::try_task("rx", async move {
-                let mut state = SubscriptionState::None;
+                // the client can subscribe to several tabs, when it displays them in panes
+                let mut subscriptions: HashMap<TabId, SubscriptionState> = HashMap::new();
+                // the scrollback of each subscribed tab, which is rendered for the scroll mode pager
+                let mut scrollbacks: HashMap<TabId, TabScrollback> = HashMap::new();
                 while let Some(msg) = rx.recv().await {
                     match msg {
                         CliSubscriptionRecv::Subscribe(id) => {
-                            if state.is_selected(id) {
+                            if subscriptions.contains_key(&id) {
                                 debug!("Ignoring subscription request for {:?}", id);
                                 continue;
                             }
@@ -34,79 +38,117 @@ impl Service for CliSubscriptionService {
                             info!("Subscribing to {:?}", id);
 
                             tx_daemon.send(CliSend::Subscribe(id)).await?;
-                            state = SubscriptionState::AwaitingScrollback(id, Vec::new());
+                            subscriptions.insert(
+                                id,
+                                SubscriptionState::AwaitingScrollback(Replay::Redraw, Vec::new()),
+                            );
+                        }
+                        CliSubscriptionRecv::Capture(id) => {
+                            if subscriptions.contains_key(&id) {
+                                debug!("Ignoring capture request for {:?}", id);
+                                continue;
+                            }
+
+                            info!("Capturing {:?}", id);
+
+                            tx_daemon.send(CliSend::Subscribe(id)).await?;
+                            subscriptions.insert(
+                                id,
+                                SubscriptionState::AwaitingScrollback(Replay::Raw, Vec::new()),
+                            );
                         }
                         CliSubscriptionRecv::Unsubscribe(id) => {
-                            if state.is_selected(id) {
+                            if subscriptions.remove(&id).is_some() {
                                 info!("Unsubscribing from {:?}", id);
-                                state = SubscriptionState::None;
+                                scrollbacks.remove(&id);
                             }
                         }
                         CliSubscriptionRecv::Scrollback(scrollback) => {
-                            if !state.is_selected(scrollback.id) {
-                                continue;
-                            }
-
-                            if let SubscriptionState::AwaitingScrollback(id, buffer) = state {
-                                let mut index = 0usize;
+                            let id = scrollback.id;
+                            let state = match subscriptions.get_mut(&id) {
+                                Some(state) => state,
+                                None => continue,
+                            };
 
+                            if let SubscriptionState::AwaitingScrollback(replay, buffer) = state {
                                 info!("Received scrollback for tab {}", id);
 
-                                for chunk in scrollback.scrollback().await {
-                                    index = Self::send_output(id, index, chunk, &mut tx).await?;
-                                }
+                                let mut index = match replay {
+                                    Replay::Redraw => {
+                                        Self::send_redraw(id, &scrollback, &mut tx).await?
+                                    }
+                                    Replay::Raw => Self::send_raw(id, &scrollback, &mut tx).await?,
+                                };
 
-                                for chunk in buffer {
+                                for chunk in buffer.drain(..) {
                                     index = Self::send_output(id, index, chunk, &mut tx).await?;
                                 }
 
-                                state = SubscriptionState::Selected(id, index);
+                                tx.send(CliSubscriptionSend::ScrollbackEnd(id)).await?;
+
+                                *state = SubscriptionState::Selected(index);
+                                scrollbacks.insert(id, scrollback);
                             }
                         }
                         CliSubscriptionRecv::Retask(from, to) => {
-                            if state.is_selected(from) {
+                            if subscriptions.remove(&from).is_some() {
                                 info!("Retasking subscription from {:?} to {:?}", from, to);
+                                scrollbacks.remove(&from);
 
                                 // if to is none, trigger a disconnect
                                 match to {
                                     RetaskTarget::Tab(id) => {
                                         tx_daemon.send(CliSend::Subscribe(id)).await?;
 
-                                        state =
-                                            SubscriptionState::AwaitingScrollback(id, Vec::new());
-                                    }
-                                    RetaskTarget::Disconnect | RetaskTarget::SelectInteractive => {
-                                        state = SubscriptionState::None;
+                                        subscriptions.insert(
+                                            id,
+                                            SubscriptionState::AwaitingScrollback(
+                                                Replay::Redraw,
+                                                Vec::new(),
+                                            ),
+                                        );
                                     }
+                                    RetaskTarget::Disconnect | RetaskTarget::SelectInteractive => {}
                                 }
 
                                 tx.send(CliSubscriptionSend::Retask(to)).await?;
                             }
                         }
                         CliSubscriptionRecv::Output(output) => {
-                            if let SubscriptionState::AwaitingScrollback(id, ref mut buffer) = state
-                            {
-                                if id == output.id {
+                            let id = output.id;
+                            match subscriptions.get_mut(&id) {
+                                Some(SubscriptionState::AwaitingScrollback(_, buffer)) => {
                                     let chunk = OutputChunk::clone(output.stdout.as_ref());
                                     buffer.push(chunk);
                                 }
-                            } else if let SubscriptionState::Selected(id, ref mut index) = state {
-                                if id == output.id {
+                                Some(SubscriptionState::Selected(index)) => {
                                     let chunk = OutputChunk::clone(output.stdout.as_ref());
                                     *index = Self::send_output(id, *index, chunk, &mut tx).await?;
                                 }
+                                None => {}
                             }
                         }
-                        CliSubscriptionRecv::Stopped(id) => {
-                            if !state.is_selected(id) {
+                        CliSubscriptionRecv::Stopped(id, exit) => {
+                            if !subscriptions.contains_key(&id) {
                                 continue;
                             }
 
-                            tx.send(CliSubscriptionSend::Stopped(id)).await?;
+                            tx.send(CliSubscriptionSend::Stopped(id, exit)).await?;
+                        }
+                        CliSubscriptionRecv::Lines(id) => {
+                            let lines = match scrollbacks.get(&id) {
+                                Some(scrollback) => scrollback.lines().await,
+                                None => {
+                                    debug!("Ignoring lines request for unselected tab {:?}", id);
+                                    Vec::new()
+                                }
+                            };
+
+                            tx.send(CliSubscriptionSend::Lines(id, lines)).await?;
                         }
                     }
 
-                    debug!("subscription state: {:?}", &state);
+                    debug!("subscription state: {:?}", &subscriptions);
                 }
                 Ok(())
             })
@@ -117,6 +159,52 @@ impl Service for CliSubscriptionService {
 }
 
 impl CliSubscriptionService {
+    /// Sends a redraw of the terminal state, rather than replaying the raw output.
+    /// Returns the index of the end of the output included in the redraw.
+    async fn send_redraw(
+        id: TabId,
+        scrollback: &TabScrollback,
+        mut tx: impl Sink<Item = CliSubscriptionSend> + Unpin,
+    ) -> anyhow::Result<usize> {
+        let snapshot = scrollback.snapshot().await;
+
+        if !snapshot.redraw.is_empty() {
+            let chunk = OutputChunk {
+                index: 0,
+                data: snapshot.redraw,
+            };
+
+            tx.send(CliSubscriptionSend::Output(id, chunk)).await?;
+        }
+
+        Ok(snapshot.end)
+    }
+
+    /// Replays the raw scrollback, streaming spilled segments from disk.
+    /// Returns the index of the end of the replayed output.
+    async fn send_raw(
+        id: TabId,
+        scrollback: &TabScrollback,
+        mut tx: impl Sink<Item = CliSubscriptionSend> + Unpin,
+    ) -> anyhow::Result<usize> {
+        let replay = scrollback.replay().await;
+        let mut index = 0;
+
+        for segment in replay.segments {
+            match segment.read() {
+                Ok(chunk) => index = Self::send_output(id, index, chunk, &mut tx).await?,
+                // the segment may have been evicted after it was listed
+                Err(e) => debug!("skipping scrollback segment for tab {}: {}", id, e),
+            }
+        }
+
+        for chunk in replay.chunks {
+            index = Self::send_output(id, index, chunk, &mut tx).await?;
+        }
+
+        Ok(index)
+    }
+
     async fn send_output(
         id: TabId,
         index: usize,
@@ -151,21 +239,22 @@ impl CliSubscriptionService {
     }
 }
 
+/// The state of a subscription to a tab
 #[derive(Debug)]
 enum SubscriptionState {
-    None,
-    AwaitingScrollback(TabId, Vec<OutputChunk>),
-    Selected(TabId, usize),
+    /// Output is buffered until the scrollback has been sent
+    AwaitingScrollback(Replay, Vec<OutputChunk>),
+    /// Output is sent from the given index
+    Selected(usize),
 }
 
-impl SubscriptionState {
-    pub fn is_selected(&self, tab: TabId) -> bool {
-        match &self {
-            SubscriptionState::None => false,
-            SubscriptionState::AwaitingScrollback(id, _) => *id == tab,
-            SubscriptionState::Selected(id, _) => *id == tab,
-        }
-    }
+/// How the scrollback is sent to a new subscription
+#[derive(Debug, Clone, Copy)]
+enum Replay {
+    /// A redraw of the terminal state, for interactive & read-only subscriptions
+    Redraw,
+    /// The raw output, for captures
+    Raw,
 }
 
 #[cfg(test)]
@@ -237,8 +326,43 @@ mod tests {
         let scrollback = TabScrollback::empty(TabId(0));
         scrollback
             .push(OutputChunk {
-                index: 1,
-                data: vec![1, 2],
+                index: 0,
+                data: b"hello".to_vec(),
+            })
+            .await;
+
+        tx.send(CliSubscriptionRecv::Scrollback(scrollback)).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            if let Some(CliSubscriptionSend::Output(TabId(0), chunk)) = msg {
+                assert!(chunk.to_string().contains("hello"));
+            } else {
+                panic!("Expected CliSubscriptionSend::Output, found {:?}", msg);
+            }
+
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSubscriptionSend::ScrollbackEnd(TabId(0))), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn capture_replays_raw_output() -> anyhow::Result<()> {
+        let bus = CliBus::default();
+        let _service = CliSubscriptionService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<CliSubscriptionRecv>()?;
+        let mut rx = bus.rx::<CliSubscriptionSend>()?;
+
+        tx.send(CliSubscriptionRecv::Capture(TabId(0))).await?;
+
+        let scrollback = TabScrollback::empty(TabId(0));
+        scrollback
+            .push(OutputChunk {
+                index: 0,
+                data: b"\x1b[31mhello".to_vec(),
             })
             .await;
 
@@ -250,12 +374,62 @@ mod tests {
                 Some(CliSubscriptionSend::Output(
                     TabId(0),
                     OutputChunk {
-                        index: 1,
-                        data: vec![1, 2]
+                        index: 0,
+                        data: b"\x1b[31mhello".to_vec()
                     }
                 )),
                 msg
             );
+
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSubscriptionSend::ScrollbackEnd(TabId(0))), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn scrollback_truncates_buffered_output() -> anyhow::Result<()> {
+        let bus = CliBus::default();
+        let _service = CliSubscriptionService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<CliSubscriptionRecv>()?;
+        let mut rx = bus.rx::<CliSubscriptionSend>()?;
+
+        tx_subscribe(&mut tx, TabId(0)).await?;
+        tx_chunk(&mut tx, TabId(0), 3, b"lo world".to_vec()).await?;
+
+        let scrollback = TabScrollback::empty(TabId(0));
+        scrollback
+            .push(OutputChunk {
+                index: 0,
+                data: b"hello".to_vec(),
+            })
+            .await;
+
+        tx.send(CliSubscriptionRecv::Scrollback(scrollback)).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert!(matches!(
+                msg,
+                Some(CliSubscriptionSend::Output(TabId(0), _))
+            ));
+
+            let msg = rx.recv().await;
+            assert_eq!(
+                Some(CliSubscriptionSend::Output(
+                    TabId(0),
+                    OutputChunk {
+                        index: 5,
+                        data: b" world".to_vec()
+                    }
+                )),
+                msg
+            );
+
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSubscriptionSend::ScrollbackEnd(TabId(0))), msg);
         });
 
         Ok(())
@@ -299,6 +473,9 @@ mod tests {
         tx_chunk(&mut tx, TabId(0), 1, vec![1, 2]).await?;
 
         assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSubscriptionSend::ScrollbackEnd(TabId(0))), msg);
+
             let msg = rx.recv().await;
             assert_eq!(
                 Some(CliSubscriptionSend::Output(
@@ -329,6 +506,9 @@ mod tests {
         tx_chunk(&mut tx, TabId(0), 2, vec![2, 3, 4]).await?;
 
         assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSubscriptionSend::ScrollbackEnd(TabId(0))), msg);
+
             let msg = rx.recv().await;
             assert_eq!(
                 Some(CliSubscriptionSend::Output(
@@ -403,6 +583,135 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn output_multiple_subscriptions() -> anyhow::Result<()> {
+        let bus = CliBus::default();
+        let _service = CliSubscriptionService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<CliSubscriptionRecv>()?;
+        let mut rx = bus.rx::<CliSubscriptionSend>()?;
+
+        tx_subscribe(&mut tx, TabId(0)).await?;
+        tx_subscribe(&mut tx, TabId(1)).await?;
+        tx_empty_scrollback(&mut tx, TabId(0)).await?;
+        tx_empty_scrollback(&mut tx, TabId(1)).await?;
+        tx_chunk(&mut tx, TabId(1), 0, vec![1]).await?;
+        tx_chunk(&mut tx, TabId(0), 0, vec![0]).await?;
+
+        tx.send(CliSubscriptionRecv::Unsubscribe(TabId(0))).await?;
+        tx_chunk(&mut tx, TabId(0), 1, vec![2]).await?;
+        tx_chunk(&mut tx, TabId(1), 1, vec![3]).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSubscriptionSend::ScrollbackEnd(TabId(0))), msg);
+
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSubscriptionSend::ScrollbackEnd(TabId(1))), msg);
+
+            let msg = rx.recv().await;
+            assert_eq!(
+                Some(CliSubscriptionSend::Output(
+                    TabId(1),
+                    OutputChunk {
+                        index: 0,
+                        data: vec![1]
+                    }
+                )),
+                msg
+            );
+
+            let msg = rx.recv().await;
+            assert_eq!(
+                Some(CliSubscriptionSend::Output(
+                    TabId(0),
+                    OutputChunk {
+                        index: 0,
+                        data: vec![0]
+                    }
+                )),
+                msg
+            );
+
+            // the output of the unsubscribed tab is no longer sent
+            let msg = rx.recv().await;
+            assert_eq!(
+                Some(CliSubscriptionSend::Output(
+                    TabId(1),
+                    OutputChunk {
+                        index: 1,
+                        data: vec![3]
+                    }
+                )),
+                msg
+            );
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn lines() -> anyhow::Result<()> {
+        let bus = CliBus::default();
+        let _service = CliSubscriptionService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<CliSubscriptionRecv>()?;
+        let mut rx = bus.rx::<CliSubscriptionSend>()?;
+
+        tx_subscribe(&mut tx, TabId(0)).await?;
+
+        let scrollback = TabScrollback::empty(TabId(0));
+        scrollback
+            .push(OutputChunk {
+                index: 0,
+                data: b"one\r\ntwo".to_vec(),
+            })
+            .await;
+
+        tx.send(CliSubscriptionRecv::Scrollback(scrollback)).await?;
+        tx.send(CliSubscriptionRecv::Lines(TabId(0))).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert!(matches!(
+                msg,
+                Some(CliSubscriptionSend::Output(TabId(0), _))
+            ));
+
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSubscriptionSend::ScrollbackEnd(TabId(0))), msg);
+
+            let msg = rx.recv().await;
+            assert_eq!(
+                Some(CliSubscriptionSend::Lines(
+                    TabId(0),
+                    vec!["one".to_string(), "two".to_string()]
+                )),
+                msg
+            );
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn lines_unselected() -> anyhow::Result<()> {
+        let bus = CliBus::default();
+        let _service = CliSubscriptionService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<CliSubscriptionRecv>()?;
+        let mut rx = bus.rx::<CliSubscriptionSend>()?;
+
+        tx.send(CliSubscriptionRecv::Lines(TabId(0))).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSubscriptionSend::Lines(TabId(0), vec![])), msg);
+        });
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn retask_tab() -> anyhow::Result<()> {
         let bus = CliBus::default();
@@ -5,8 +5,14 @@ use tab_api::config::data_path;
 use time::Duration;
 use tokio::time;
 
+mod grep;
 mod listener;
+mod monitor;
+mod resize;
 mod retask;
+mod scrollback_budget;
+mod scrollback_registry;
+mod session;
 mod tab_assignment;
 mod tab_manager;
 
@@ -0,0 +1,238 @@
+use std::{collections::VecDeque, io};
+
+use crate::{
+    message::grep::{GrepRecv, GrepSend},
+    prelude::*,
+    state::{scrollback::ScrollbackRegistry, tab::TabsState},
+};
+use tab_api::{
+    client::{GrepMatch, GrepQuery},
+    tab::TabMetadata,
+};
+use tokio::task;
+
+/// Serves grep requests from CLI connections, by searching the scrollback of all running tabs.
+///
+/// - Searches the raw scrollback of each tab, including segments which have been spilled to disk,
+///   and replies to the requesting connection with the matching lines.
+pub struct GrepService {
+    _grep: Lifeline,
+}
+
+impl Service for GrepService {
+    type Bus = ListenerBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let registry = bus.resource::<ScrollbackRegistry>()?;
+
+        let _grep = {
+            let mut rx = bus.rx::<GrepRecv>()?;
+            let rx_tabs = bus.rx::<TabsState>()?;
+            let mut tx = bus.tx::<GrepSend>()?;
+
+            Self::try_task("grep", async move {
+                while let Some(request) = rx.recv().await {
+                    let tabs = rx_tabs.borrow().clone();
+                    let matches = Self::grep(&request.query, tabs, &registry).await;
+
+                    debug!(
+                        "grep for {:?} matched {} lines",
+                        &request.query.pattern,
+                        matches.len()
+                    );
+
+                    let response = GrepSend {
+                        connection: request.connection,
+                        matches,
+                    };
+
+                    tx.send(response).await?;
+                }
+
+                Ok(())
+            })
+        };
+
+        Ok(Self { _grep })
+    }
+}
+
+impl GrepService {
+    async fn grep(
+        query: &GrepQuery,
+        tabs: TabsState,
+        registry: &ScrollbackRegistry,
+    ) -> Vec<GrepMatch> {
+        let handles = registry.handles().await;
+
+        let mut tabs: Vec<&TabMetadata> = tabs
+            .tabs
+            .values()
+            .filter(|tab| handles.contains_key(&tab.id))
+            .collect();
+        tabs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut matches = Vec::new();
+        for tab in tabs {
+            let scrollback = &handles[&tab.id];
+            let mut search = LineSearch::new(query);
+
+            let replay = scrollback.replay().await;
+            let mut index = 0;
+
+            for segment in replay.segments {
+                // segments are decompressed from disk on the blocking thread pool
+                let read = task::spawn_blocking(move || segment.read()).await;
+                match read.unwrap_or_else(|e| Err(io::Error::other(e))) {
+                    Ok(chunk) => {
+                        search.push(chunk.data.as_slice());
+                        index = chunk.end();
+                    }
+                    // the segment may have been evicted after it was listed
+                    Err(e) => debug!("skipping scrollback segment for tab {}: {}", tab.id, e),
+                }
+            }
+
+            for mut chunk in replay.chunks {
+                if chunk.is_before(index) {
+                    continue;
+                }
+
+                if chunk.index != index && chunk.contains(index) {
+                    chunk.truncate_before(index);
+                }
+
+                search.push(chunk.data.as_slice());
+                index = chunk.end();
+            }
+
+            matches.extend(search.finish().into_iter().map(|line| GrepMatch {
+                id: tab.id,
+                name: tab.name.clone(),
+                line,
+            }));
+        }
+
+        matches
+    }
+}
+
+/// Splits raw pty output into lines, and collects the lines which contain the pattern.
+/// Lines may span several chunks.
+struct LineSearch<'q> {
+    pattern: &'q str,
+    limit: Option<usize>,
+    line: Vec<u8>,
+    matches: VecDeque<String>,
+}
+
+impl<'q> LineSearch<'q> {
+    pub fn new(query: &'q GrepQuery) -> Self {
+        Self {
+            pattern: query.pattern.as_str(),
+            limit: query.limit,
+            line: Vec::new(),
+            matches: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        for segment in data.split_inclusive(|byte| *byte == b'\n') {
+            self.line.extend_from_slice(segment);
+
+            if segment.ends_with(b"\n") {
+                self.end_line();
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> Vec<String> {
+        if !self.line.is_empty() {
+            self.end_line();
+        }
+
+        self.matches.into_iter().collect()
+    }
+
+    fn end_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+
+        // carriage returns redraw the line, so only the final text is visible
+        let mut end = line.len();
+        while end > 0 && (line[end - 1] == b'\n' || line[end - 1] == b'\r') {
+            end -= 1;
+        }
+        let start = line[..end]
+            .iter()
+            .rposition(|byte| *byte == b'\r')
+            .map(|index| index + 1)
+            .unwrap_or(0);
+
+        let visible = &line[start..end];
+        let visible = strip_ansi_escapes::strip(visible).unwrap_or_else(|_| visible.to_vec());
+        let line = String::from_utf8_lossy(visible.as_slice());
+
+        if line.trim().is_empty() || !line.contains(self.pattern) {
+            return;
+        }
+
+        self.matches.push_back(line.to_string());
+
+        if let Some(limit) = self.limit {
+            while self.matches.len() > limit {
+                self.matches.pop_front();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineSearch;
+    use tab_api::client::GrepQuery;
+
+    fn query(pattern: &str, limit: Option<usize>) -> GrepQuery {
+        GrepQuery {
+            pattern: pattern.to_string(),
+            limit,
+        }
+    }
+
+    #[test]
+    fn matches_lines() {
+        let query = query("error", None);
+        let mut search = LineSearch::new(&query);
+        search.push(b"ok\nan error\nok\nerror: again");
+
+        assert_eq!(vec!["an error", "error: again"], search.finish());
+    }
+
+    #[test]
+    fn lines_span_chunks() {
+        let query = query("error", None);
+        let mut search = LineSearch::new(&query);
+        search.push(b"ok\nan er");
+        search.push(b"ror\r\n");
+
+        assert_eq!(vec!["an error"], search.finish());
+    }
+
+    #[test]
+    fn strips_ansi() {
+        let query = query("", None);
+        let mut search = LineSearch::new(&query);
+        search.push(b"\x1b[31mer\x1b[0mror\r\nprogress 10%\rprogress 100%\n");
+
+        assert_eq!(vec!["error", "progress 100%"], search.finish());
+    }
+
+    #[test]
+    fn limit_keeps_recent() {
+        let query = query("", Some(2));
+        let mut search = LineSearch::new(&query);
+        search.push(b"one\ntwo\n\nthree\n");
+
+        assert_eq!(vec!["two", "three"], search.finish());
+    }
+}
@@ -1,19 +1,26 @@
 use super::{
-    retask::RetaskService, tab_assignment::TabAssignmentService, tab_manager::TabManagerService,
+    grep::GrepService, monitor::MonitorService, resize::ResizeService, retask::RetaskService,
+    scrollback_budget::ScrollbackBudgetService, scrollback_registry::ScrollbackRegistryService,
+    session::SessionService, tab_assignment::TabAssignmentService, tab_manager::TabManagerService,
 };
 use crate::{
     message::{
         cli::CliShutdown,
-        listener::ListenerShutdown,
         pty::{PtyRecv, PtySend, PtyShutdown},
+        resize::ResizeRecv,
+        session::SessionRecv,
         tab::{TabRecv, TabSend},
     },
     service::{cli::CliService, pty::PtyService},
+    state::{scrollback::ScrollbackRegistry, session::SessionStore},
 };
 use crate::{prelude::*, service::cli::subscription::CliSubscriptionService};
 
 use lifeline::dyn_bus::DynBus;
-use tab_api::pty::{PtyWebsocketRequest, PtyWebsocketResponse};
+use tab_api::{
+    config::{OutputLogConfig, ResizeConfig, ScrollbackConfig},
+    pty::{PtyWebsocketRequest, PtyWebsocketResponse},
+};
 use tab_websocket::{
     bus::{WebsocketCarrier, WebsocketListenerBus},
     message::listener::WebsocketConnectionMessage,
@@ -37,6 +44,12 @@ pub struct ListenerService {
     _tabs: TabManagerService,
     _tab_assignments: TabAssignmentService,
     _retask: RetaskService,
+    _resize: ResizeService,
+    _session: SessionService,
+    _scrollback_registry: ScrollbackRegistryService,
+    _scrollback_budget: ScrollbackBudgetService,
+    _monitor: MonitorService,
+    _grep: GrepService,
     _connection_carrier: ConnectionMessageCarrier,
     _daemon_carrier: ListenerDaemonCarrier,
 }
@@ -59,6 +72,12 @@ impl Service for ListenerService {
         let listener_bus = ListenerBus::default();
         listener_bus.capacity::<TabSend>(128)?;
         listener_bus.capacity::<TabRecv>(128)?;
+        listener_bus.capacity::<ResizeRecv>(128)?;
+        listener_bus.store_resource(bus.resource::<SessionStore>()?);
+        listener_bus.store_resource(bus.resource::<ScrollbackConfig>()?);
+        listener_bus.store_resource(bus.resource::<OutputLogConfig>()?);
+        listener_bus.store_resource(bus.resource::<ResizeConfig>()?);
+        listener_bus.store_resource(ScrollbackRegistry::default());
 
         let _daemon_carrier = listener_bus.carry_from(bus)?;
         let _connection_carrier = listener_bus.carry_from(&websocket_bus)?;
@@ -66,6 +85,12 @@ impl Service for ListenerService {
         let _tab_assignments = TabAssignmentService::spawn(&listener_bus)?;
         let _tabs = TabManagerService::spawn(&listener_bus)?;
         let _retask = RetaskService::spawn(&listener_bus)?;
+        let _resize = ResizeService::spawn(&listener_bus)?;
+        let _scrollback_registry = ScrollbackRegistryService::spawn(&listener_bus)?;
+        let _session = SessionService::spawn(&listener_bus)?;
+        let _scrollback_budget = ScrollbackBudgetService::spawn(&listener_bus)?;
+        let _monitor = MonitorService::spawn(&listener_bus)?;
+        let _grep = GrepService::spawn(&listener_bus)?;
 
         let _new_session = Self::try_task("new_session", Self::new_session(listener_bus));
 
@@ -75,6 +100,12 @@ impl Service for ListenerService {
             _tabs,
             _tab_assignments,
             _retask,
+            _resize,
+            _session,
+            _scrollback_registry,
+            _scrollback_budget,
+            _monitor,
+            _grep,
             _connection_carrier,
             _daemon_carrier,
         })
@@ -89,8 +120,7 @@ impl ListenerService {
 
         let mut rx_conn = bus.rx::<WebsocketConnectionMessage>()?;
 
-        let mut tx_terminate_tabs = bus.tx::<TabRecv>()?;
-        let mut tx_shutdown = bus.tx::<ListenerShutdown>()?;
+        let mut tx_session = bus.tx::<SessionRecv>()?;
 
         while let Some(msg) = rx_conn.recv().await {
             let name = format!("connection_{}", index);
@@ -124,6 +154,7 @@ impl ListenerService {
                     pty_bus.capacity::<PtyRecv>(128)?;
                     pty_bus.capacity::<PtyWebsocketRequest>(128)?;
                     pty_bus.capacity::<PtyWebsocketResponse>(128)?;
+                    pty_bus.store_resource(bus.resource::<SessionStore>()?);
 
                     let _listener_carrier = pty_bus.carry_from(&bus)?;
                     let _websocket_carrier = pty_bus.carry_into(&msg.bus)?;
@@ -138,8 +169,8 @@ impl ListenerService {
                     )
                 }
                 "/shutdown" => {
-                    tx_terminate_tabs.send(TabRecv::TerminateAll).await?;
-                    tx_shutdown.send(ListenerShutdown {}).await?;
+                    // the session service saves the session, and then terminates the tabs
+                    tx_session.send(SessionRecv::Shutdown).await?;
                     break;
                 }
                 _ => {
@@ -222,6 +253,31 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_listener_accepts_unix_connection() -> anyhow::Result<()> {
+        // the socket directory is created by the listener, and is only accessible by the current user
+        let dir = std::env::temp_dir().join(format!("tab-daemon-{}", std::process::id()));
+        std::fs::remove_dir_all(dir.as_path()).ok();
+        let socket = dir.join("daemon.sock");
+
+        let bus = crate::new_bus_with_socket("0.0.1", Some(socket.clone())).await?;
+        let config = bus.resource::<DaemonConfig>()?;
+        assert_eq!(Some(socket.clone()), config.socket);
+
+        let _listener = ListenerService::spawn(&bus)?;
+
+        let websocket_bus = WebsocketConnectionBus::default();
+        let connection =
+            tab_websocket::connect_authorized(config.websocket_url("/cli"), config.auth_token)
+                .await?;
+        websocket_bus.store_resource(WebsocketResource(connection));
+
+        let _connection = WebsocketService::spawn(&websocket_bus)?;
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
     fn assert_status_err<T: Debug>(
         expect: http::StatusCode,
         result: Result<T, tungstenite::Error>,
@@ -0,0 +1,265 @@
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use crate::{
+    message::{tab::TabSend, tab_manager::TabManagerRecv},
+    prelude::*,
+    state::tab::TabsState,
+};
+use tab_api::tab::{TabAlert, TabId, TabMetadata, TabMonitor};
+use tokio::{
+    process::Command,
+    select,
+    time::{self, Instant},
+};
+
+/// How often silent tabs are checked
+const SILENCE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Monitors tabs with `monitor: activity` or `monitor: silence <duration>`, and raises alerts.
+///
+/// - Raises an activity alert when a monitored tab produces output, while no client is attached.
+/// - Raises a silence alert when a monitored tab has produced output, and then been quiet for the duration.
+///
+/// Alerts are recorded in the tab metadata by the `TabManagerService`, which runs the notify command of the tab.
+pub struct MonitorService {
+    _monitor: Lifeline,
+}
+
+impl Service for MonitorService {
+    type Bus = ListenerBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let mut rx = bus.rx::<TabSend>()?;
+        let rx_tabs = bus.rx::<TabsState>()?;
+        let mut tx = bus.tx::<TabManagerRecv>()?;
+
+        let _monitor = Self::try_task("monitor", async move {
+            let mut monitor = Monitor::default();
+            let mut interval = time::interval(SILENCE_INTERVAL);
+
+            loop {
+                select! {
+                    msg = rx.recv() => match msg {
+                        Some(TabSend::Output(output)) => {
+                            let alert = rx_tabs
+                                .borrow()
+                                .tabs
+                                .get(&output.id)
+                                .and_then(|metadata| monitor.output(metadata, Instant::now()));
+
+                            if let Some(alert) = alert {
+                                tx.send(TabManagerRecv::Alert(output.id, alert)).await?;
+                            }
+                        }
+                        Some(TabSend::Stopped(id, _)) => {
+                            monitor.remove(id);
+                        }
+                        Some(_) => {}
+                        None => break,
+                    },
+                    _ = interval.tick() => {
+                        let silent = monitor.silent(&rx_tabs.borrow(), Instant::now());
+
+                        for id in silent {
+                            tx.send(TabManagerRecv::Alert(id, TabAlert::Silence)).await?;
+                        }
+                    }
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _monitor })
+    }
+}
+
+/// Tracks the output of monitored tabs
+#[derive(Debug, Default)]
+struct Monitor {
+    /// The time of the last output, for tabs with a silence monitor which have not yet gone quiet
+    last_output: HashMap<TabId, Instant>,
+}
+
+impl Monitor {
+    /// Records output from the tab, and returns an activity alert if one should be raised
+    pub fn output(&mut self, metadata: &TabMetadata, now: Instant) -> Option<TabAlert> {
+        match metadata.monitor {
+            Some(TabMonitor::Activity) if metadata.attached == 0 && metadata.alert.is_none() => {
+                Some(TabAlert::Activity)
+            }
+            Some(TabMonitor::Silence(_)) => {
+                self.last_output.insert(metadata.id, now);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the tabs which have been quiet for their silence duration.
+    /// Each tab is returned once, until it produces more output.
+    pub fn silent(&mut self, tabs: &TabsState, now: Instant) -> Vec<TabId> {
+        let mut silent = Vec::new();
+
+        self.last_output.retain(|id, last_output| {
+            let seconds = match tabs.tabs.get(id).and_then(|metadata| metadata.monitor) {
+                Some(TabMonitor::Silence(seconds)) => seconds,
+                _ => return false,
+            };
+
+            if now.duration_since(*last_output) < Duration::from_secs(seconds) {
+                return true;
+            }
+
+            silent.push(*id);
+            false
+        });
+
+        silent
+    }
+
+    pub fn remove(&mut self, id: TabId) {
+        self.last_output.remove(&id);
+    }
+}
+
+/// Runs the notify command of the tab, if it has one.
+/// The command is run with `sh -c`, in the tab directory, with TAB_NAME, TAB_ID and TAB_ALERT set.
+pub fn notify(metadata: &TabMetadata, alert: TabAlert) {
+    let notify = match metadata.notify {
+        Some(ref notify) => notify,
+        None => return,
+    };
+
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(notify)
+        .env("TAB_NAME", metadata.name.as_str())
+        .env("TAB_ID", metadata.id.0.to_string())
+        .env("TAB_ALERT", alert.to_string());
+
+    if Path::new(metadata.dir.as_str()).is_dir() {
+        command.current_dir(metadata.dir.as_str());
+    }
+
+    match command.spawn() {
+        Ok(mut child) => {
+            let name = metadata.name.clone();
+            tokio::spawn(async move {
+                match child.wait().await {
+                    Ok(status) if !status.success() => {
+                        warn!("notify command for tab {} exited with {}", name, status)
+                    }
+                    Err(e) => warn!("failed to wait for notify command for tab {}: {}", name, e),
+                    _ => {}
+                }
+            });
+        }
+        Err(e) => warn!(
+            "failed to run notify command for tab {}: {}",
+            metadata.name, e
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Monitor;
+    use crate::state::tab::TabsState;
+    use std::{collections::HashMap, time::Duration};
+    use tab_api::tab::{TabAlert, TabId, TabMetadata, TabMonitor};
+    use tokio::time::Instant;
+
+    fn metadata(monitor: TabMonitor) -> TabMetadata {
+        TabMetadata {
+            id: TabId(0),
+            name: "build/".into(),
+            doc: None,
+            dimensions: (80, 24),
+            env: HashMap::new(),
+            shell: "bash".into(),
+            dir: "/".into(),
+            command: None,
+            selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: Some(monitor),
+            notify: None,
+            alert: None,
+        }
+    }
+
+    fn state(metadata: &TabMetadata) -> TabsState {
+        let mut tabs = HashMap::new();
+        tabs.insert(metadata.id, metadata.clone());
+        TabsState::new(&tabs, &HashMap::new())
+    }
+
+    #[test]
+    fn activity() {
+        let mut monitor = Monitor::default();
+        let mut tab = metadata(TabMonitor::Activity);
+
+        assert_eq!(
+            Some(TabAlert::Activity),
+            monitor.output(&tab, Instant::now())
+        );
+
+        tab.attached = 1;
+        assert_eq!(None, monitor.output(&tab, Instant::now()));
+
+        tab.attached = 0;
+        tab.alert = Some(TabAlert::Activity);
+        assert_eq!(None, monitor.output(&tab, Instant::now()));
+    }
+
+    #[test]
+    fn silence() {
+        let mut monitor = Monitor::default();
+        let tab = metadata(TabMonitor::Silence(30));
+        let tabs = state(&tab);
+        let start = Instant::now();
+
+        // tabs which have not produced output are not silent
+        assert!(monitor.silent(&tabs, start).is_empty());
+
+        assert_eq!(None, monitor.output(&tab, start));
+        assert!(monitor
+            .silent(&tabs, start + Duration::from_secs(29))
+            .is_empty());
+
+        assert_eq!(
+            vec![tab.id],
+            monitor.silent(&tabs, start + Duration::from_secs(30))
+        );
+
+        // the alert is raised once, until more output is produced
+        assert!(monitor
+            .silent(&tabs, start + Duration::from_secs(60))
+            .is_empty());
+    }
+
+    #[test]
+    fn silence_reset() {
+        let mut monitor = Monitor::default();
+        let tab = metadata(TabMonitor::Silence(30));
+        let tabs = state(&tab);
+        let start = Instant::now();
+
+        monitor.output(&tab, start);
+        monitor.output(&tab, start + Duration::from_secs(20));
+
+        assert!(monitor
+            .silent(&tabs, start + Duration::from_secs(40))
+            .is_empty());
+        assert_eq!(
+            vec![tab.id],
+            monitor.silent(&tabs, start + Duration::from_secs(50))
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use tab_api::config::ResizeConfig;
+
+use crate::{
+    message::{resize::ResizeRecv, tab::TabRecv, tab_manager::TabManagerRecv},
+    prelude::*,
+    state::resize::ResizeState,
+};
+
+/// Tracks the dimensions of the CLI connections attached to each tab.
+/// Resizes the tab when clients attach, detach or resize, using the configured `ResizePolicy`.
+pub struct ResizeService {
+    _run: Lifeline,
+}
+
+impl Service for ResizeService {
+    type Bus = ListenerBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let config = bus.resource::<ResizeConfig>()?;
+        let mut rx = bus.rx::<ResizeRecv>()?;
+        let mut tx = bus.tx::<TabRecv>()?;
+        let mut tx_manager = bus.tx::<TabManagerRecv>()?;
+
+        let _run = Self::try_task("run", async move {
+            let policy = config.policy;
+            debug!("resize service started with policy {:?}", policy);
+
+            let mut state = ResizeState::new(policy);
+            while let Some(msg) = rx.recv().await {
+                let resized = match msg {
+                    ResizeRecv::Resize(connection, id, dimensions) => state
+                        .resize(connection, id, dimensions)
+                        .into_iter()
+                        .collect(),
+                    ResizeRecv::Input(connection, id) => {
+                        state.input(connection, id).into_iter().collect()
+                    }
+                    ResizeRecv::Unsubscribe(connection, id) => {
+                        state.detach(connection, id).into_iter().collect()
+                    }
+                    ResizeRecv::Disconnect(connection) => state.disconnect(connection),
+                };
+
+                for (id, dimensions) in resized {
+                    info!("Resizing tab {} to {:?}", id.0, dimensions);
+                    tx.send(TabRecv::Resize(id, dimensions)).await?;
+                    tx_manager
+                        .send(TabManagerRecv::Resize(id, dimensions))
+                        .await?;
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _run })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResizeService;
+    use crate::{
+        bus::ListenerBus,
+        message::{resize::ResizeRecv, tab::TabRecv},
+        state::resize::ConnectionId,
+    };
+    use lifeline::{assert_completes, dyn_bus::DynBus, Bus, Receiver, Sender, Service};
+    use tab_api::{
+        config::{ResizeConfig, ResizePolicy},
+        tab::TabId,
+    };
+
+    #[tokio::test]
+    async fn resize() -> anyhow::Result<()> {
+        let bus = ListenerBus::default();
+        bus.store_resource(ResizeConfig::default());
+        let _service = ResizeService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<ResizeRecv>()?;
+        let mut rx = bus.rx::<TabRecv>()?;
+
+        tx.send(ResizeRecv::Resize(ConnectionId(0), TabId(0), (80, 40)))
+            .await?;
+        tx.send(ResizeRecv::Resize(ConnectionId(1), TabId(0), (100, 30)))
+            .await?;
+        tx.send(ResizeRecv::Disconnect(ConnectionId(0))).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(TabRecv::Resize(TabId(0), (80, 40))), msg);
+
+            let msg = rx.recv().await;
+            assert_eq!(Some(TabRecv::Resize(TabId(0), (80, 30))), msg);
+
+            let msg = rx.recv().await;
+            assert_eq!(Some(TabRecv::Resize(TabId(0), (100, 30))), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn configured_policy() -> anyhow::Result<()> {
+        let bus = ListenerBus::default();
+        bus.store_resource(ResizeConfig {
+            policy: ResizePolicy::Largest,
+        });
+        let _service = ResizeService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<ResizeRecv>()?;
+        let mut rx = bus.rx::<TabRecv>()?;
+
+        tx.send(ResizeRecv::Resize(ConnectionId(0), TabId(0), (80, 40)))
+            .await?;
+        tx.send(ResizeRecv::Resize(ConnectionId(1), TabId(0), (100, 30)))
+            .await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(TabRecv::Resize(TabId(0), (80, 40))), msg);
+
+            let msg = rx.recv().await;
+            assert_eq!(Some(TabRecv::Resize(TabId(0), (100, 40))), msg);
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use crate::{
+    prelude::*,
+    state::{scrollback::ScrollbackRegistry, tab::TabsState},
+};
+use tab_api::{
+    config::ScrollbackConfig,
+    tab::{TabId, TabMetadata},
+};
+use tokio::time;
+
+const BUDGET_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Enforces the daemon-wide scrollback budget, if one is configured.
+///
+/// - Periodically sums the size of the buffers in memory.  If the budget is exceeded,
+///   the oldest scrollback is evicted from idle tabs first, and then from the least recently selected tabs.
+///   Evicted scrollback is spilled to disk, if the tab has a spill directory.
+pub struct ScrollbackBudgetService {
+    _budget: Lifeline,
+}
+
+impl Service for ScrollbackBudgetService {
+    type Bus = ListenerBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let config = bus.resource::<ScrollbackConfig>()?;
+        let registry = bus.resource::<ScrollbackRegistry>()?;
+
+        let _budget = {
+            let rx_tabs = bus.rx::<TabsState>()?;
+
+            Self::try_task("budget", async move {
+                let budget = match config.budget {
+                    Some(budget) => budget,
+                    None => return Ok(()),
+                };
+
+                loop {
+                    time::sleep(BUDGET_INTERVAL).await;
+
+                    let tabs = rx_tabs.borrow().clone();
+                    Self::enforce(budget, tabs, &registry).await;
+                }
+            })
+        };
+
+        Ok(Self { _budget })
+    }
+}
+
+impl ScrollbackBudgetService {
+    async fn enforce(budget: usize, tabs: TabsState, registry: &ScrollbackRegistry) {
+        let handles = registry.handles().await;
+
+        let mut usage = Vec::with_capacity(handles.len());
+        for (id, scrollback) in handles.iter() {
+            if let Some(metadata) = tabs.tabs.get(id) {
+                usage.push((metadata, scrollback.size().await));
+            }
+        }
+
+        for (id, len) in plan_eviction(budget, usage) {
+            if let Some(scrollback) = handles.get(&id) {
+                let freed = scrollback.evict(len).await;
+                debug!("scrollback budget evicted {} bytes from tab {}", freed, id);
+            }
+        }
+    }
+}
+
+/// Calculates the number of bytes to evict from each tab, so the total size fits within the budget.
+/// Unattached tabs are evicted first, and then the least recently selected tabs.
+fn plan_eviction(budget: usize, mut usage: Vec<(&TabMetadata, usize)>) -> Vec<(TabId, usize)> {
+    let total: usize = usage.iter().map(|(_, size)| size).sum();
+    let mut excess = total.saturating_sub(budget);
+
+    usage.sort_by_key(|(metadata, _)| (metadata.attached > 0, metadata.selected));
+
+    let mut plan = Vec::new();
+    for (metadata, size) in usage {
+        if excess == 0 {
+            break;
+        }
+
+        let len = size.min(excess);
+        if len > 0 {
+            plan.push((metadata.id, len));
+            excess -= len;
+        }
+    }
+
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plan_eviction, ScrollbackBudgetService};
+    use crate::state::{pty::PtyScrollback, scrollback::ScrollbackRegistry, tab::TabsState};
+    use tab_api::{
+        chunk::OutputChunk,
+        tab::{TabId, TabMetadata},
+    };
+
+    fn metadata(id: u16, attached: usize, selected: u128) -> TabMetadata {
+        TabMetadata {
+            id: TabId(id),
+            name: format!("tab-{}/", id),
+            doc: None,
+            dimensions: (80, 24),
+            env: Default::default(),
+            shell: "bash".into(),
+            dir: "/".into(),
+            command: None,
+            selected,
+            attached,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
+        }
+    }
+
+    #[test]
+    fn within_budget() {
+        let tab = metadata(0, 0, 0);
+        assert!(plan_eviction(100, vec![(&tab, 100)]).is_empty());
+    }
+
+    #[test]
+    fn idle_tabs_first() {
+        let attached = metadata(0, 1, 0);
+        let idle = metadata(1, 0, 10);
+
+        let plan = plan_eviction(100, vec![(&attached, 80), (&idle, 60)]);
+        assert_eq!(vec![(TabId(1), 40)], plan);
+    }
+
+    #[test]
+    fn least_recently_selected_first() {
+        let recent = metadata(0, 0, 20);
+        let old = metadata(1, 0, 10);
+        let attached = metadata(2, 1, 0);
+
+        let plan = plan_eviction(50, vec![(&recent, 40), (&old, 30), (&attached, 30)]);
+        assert_eq!(vec![(TabId(1), 30), (TabId(0), 20)], plan);
+    }
+
+    #[tokio::test]
+    async fn enforce_history() {
+        let scrollback = PtyScrollback::empty();
+        let mut index = 0;
+
+        // short lines are mostly held in the line history of the terminal emulator
+        for _ in 0..100 {
+            let data = "history line\r\n".repeat(100).into_bytes();
+            let len = data.len();
+            scrollback.push(OutputChunk { index, data }).await;
+            index += len;
+        }
+
+        let budget = 4096;
+        assert!(scrollback.size().await > 10 * budget);
+
+        let tab = metadata(0, 0, 0);
+        let tabs = TabsState {
+            tabs: vec![(tab.id, tab)].into_iter().collect(),
+            ..TabsState::default()
+        };
+
+        let registry = ScrollbackRegistry::default();
+        registry.insert(TabId(0), scrollback.clone()).await;
+
+        ScrollbackBudgetService::enforce(budget, tabs, &registry).await;
+        assert!(scrollback.size().await <= budget);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    message::tab::{TabRecv, TabSend},
+    prelude::*,
+    state::scrollback::ScrollbackRegistry,
+};
+
+/// Collects handles to the scrollback buffers of running tabs, into the `ScrollbackRegistry` resource.
+///
+/// - Requests a handle to the scrollback buffer when a tab starts.
+/// - Registers the handle when the pty replies, and removes it when the tab stops.
+pub struct ScrollbackRegistryService {
+    _scrollback: Lifeline,
+}
+
+impl Service for ScrollbackRegistryService {
+    type Bus = ListenerBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let registry = bus.resource::<ScrollbackRegistry>()?;
+
+        let _scrollback = {
+            let mut rx = bus.rx::<TabSend>()?;
+            let mut tx = bus.tx::<TabRecv>()?;
+
+            Self::try_task("scrollback", async move {
+                while let Some(msg) = rx.recv().await {
+                    match msg {
+                        TabSend::Started(metadata) => {
+                            tx.send(TabRecv::Scrollback(metadata.id)).await?;
+                        }
+                        TabSend::Scrollback(scrollback) => {
+                            registry.insert(scrollback.id, scrollback.scrollback).await;
+                        }
+                        TabSend::Stopped(id, _) => {
+                            registry.remove(id).await;
+                        }
+                        _ => {}
+                    }
+                }
+
+                Ok(())
+            })
+        };
+
+        Ok(Self { _scrollback })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScrollbackRegistryService;
+    use crate::{
+        message::tab::{TabRecv, TabScrollback, TabSend},
+        prelude::*,
+        state::{pty::PtyScrollback, scrollback::ScrollbackRegistry},
+    };
+    use lifeline::{assert_completes, dyn_bus::DynBus};
+    use std::{collections::HashMap, time::Duration};
+    use tab_api::tab::{TabExit, TabId, TabMetadata};
+    use tokio::time;
+
+    fn metadata(id: u16) -> TabMetadata {
+        TabMetadata {
+            id: TabId(id),
+            name: "name".into(),
+            doc: None,
+            dimensions: (1, 2),
+            env: HashMap::new(),
+            shell: "shell".into(),
+            dir: "/".into(),
+            command: None,
+            selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn register() -> anyhow::Result<()> {
+        let bus = ListenerBus::default();
+        let registry = ScrollbackRegistry::default();
+        bus.store_resource(registry.clone());
+
+        let _service = ScrollbackRegistryService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<TabSend>()?;
+        let mut rx = bus.rx::<TabRecv>()?;
+
+        tx.send(TabSend::Started(metadata(1))).await?;
+
+        assert_completes!(async {
+            let msg = rx.recv().await;
+            assert_eq!(Some(TabRecv::Scrollback(TabId(1))), msg);
+        });
+
+        let scrollback = TabScrollback {
+            id: TabId(1),
+            scrollback: PtyScrollback::empty(),
+        };
+        tx.send(TabSend::Scrollback(scrollback)).await?;
+
+        time::sleep(Duration::from_millis(10)).await;
+        assert!(registry.handles().await.contains_key(&TabId(1)));
+
+        tx.send(TabSend::Stopped(TabId(1), TabExit::default()))
+            .await?;
+
+        time::sleep(Duration::from_millis(10)).await;
+        assert!(registry.handles().await.is_empty());
+
+        Ok(())
+    }
+}
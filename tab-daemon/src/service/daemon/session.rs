@@ -0,0 +1,216 @@
+use std::{collections::HashMap, time::Duration};
+
+use crate::{
+    message::{listener::ListenerShutdown, session::SessionRecv, tab::TabRecv},
+    prelude::*,
+    state::{
+        scrollback::ScrollbackRegistry,
+        session::{SessionSnapshot, SessionStore},
+        tab::TabsState,
+    },
+};
+use tab_api::tab::TabMetadata;
+use tokio::time;
+
+/// The maximum number of scrollback bytes which are persisted for each tab (256KB)
+const SCROLLBACK_TAIL_LEN: usize = 262144;
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(2);
+const HANDOFF_DELAY: Duration = Duration::from_millis(100);
+
+/// Persists the running tabs, and a tail of their scrollback, so they can be restored when the daemon restarts.
+///
+/// - Writes a snapshot of the session periodically, if it has changed.
+/// - Serves daemon shutdown requests.  A final snapshot is written before the tabs are terminated.
+/// - Serves daemon upgrade requests.  A final snapshot is written, and the ptys reconnect to the next daemon.
+pub struct SessionService {
+    _interval: Lifeline,
+    _snapshot: Lifeline,
+}
+
+impl Service for SessionService {
+    type Bus = ListenerBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let store = bus.resource::<SessionStore>()?;
+        let registry = bus.resource::<ScrollbackRegistry>()?;
+
+        let _interval = {
+            let mut tx = bus.tx::<SessionRecv>()?;
+
+            Self::try_task("interval", async move {
+                loop {
+                    time::sleep(SNAPSHOT_INTERVAL).await;
+
+                    if tx.send(SessionRecv::Snapshot).await.is_err() {
+                        break;
+                    }
+                }
+
+                Ok(())
+            })
+        };
+
+        let _snapshot = {
+            let mut rx = bus.rx::<SessionRecv>()?;
+            let rx_tabs = bus.rx::<TabsState>()?;
+            let mut tx_tabs = bus.tx::<TabRecv>()?;
+            let mut tx_shutdown = bus.tx::<ListenerShutdown>()?;
+
+            Self::try_task("snapshot", async move {
+                let mut last_snapshot = None;
+
+                while let Some(msg) = rx.recv().await {
+                    if store.is_enabled() {
+                        let tabs = rx_tabs.borrow().clone();
+                        let snapshot = Self::snapshot(&store, tabs, &registry).await;
+
+                        if last_snapshot.as_ref() != Some(&snapshot) {
+                            debug!("writing session snapshot of {} tabs", snapshot.tabs.len());
+                            if let Err(e) = store.save(&snapshot) {
+                                error!("failed to write session snapshot: {}", e);
+                            }
+
+                            last_snapshot = Some(snapshot);
+                        }
+                    }
+
+                    // the snapshot is no longer updated after a shutdown,
+                    // so the tabs remain in the session as they terminate.
+                    match msg {
+                        SessionRecv::Snapshot => {}
+                        SessionRecv::Shutdown => {
+                            info!("session saved, terminating tabs");
+                            tx_tabs.send(TabRecv::TerminateAll).await?;
+                            tx_shutdown.send(ListenerShutdown {}).await?;
+                            break;
+                        }
+                        SessionRecv::Handoff => {
+                            if let Err(e) = store.mark_handoff() {
+                                error!("failed to mark the session handoff: {}", e);
+                            }
+
+                            info!("session saved, handing off tabs");
+                            tx_tabs.send(TabRecv::ReconnectAll).await?;
+
+                            // the reconnect requests need to reach the ptys before the daemon exits
+                            time::sleep(HANDOFF_DELAY).await;
+                            tx_shutdown.send(ListenerShutdown {}).await?;
+                            break;
+                        }
+                    }
+                }
+
+                Ok(())
+            })
+        };
+
+        Ok(Self {
+            _interval,
+            _snapshot,
+        })
+    }
+}
+
+impl SessionService {
+    async fn snapshot(
+        store: &SessionStore,
+        tabs: TabsState,
+        registry: &ScrollbackRegistry,
+    ) -> SessionSnapshot {
+        let handles = registry.handles().await;
+
+        let mut tabs: Vec<TabMetadata> = tabs.tabs.into_values().collect();
+        tabs.sort_by_key(|tab| tab.id.0);
+
+        let mut scrollback = HashMap::new();
+        for tab in tabs.iter() {
+            let data = match handles.get(&tab.id) {
+                Some(handle) => Some(Self::trim_line(handle.tail(SCROLLBACK_TAIL_LEN).await)),
+                // a restored tab may not have started yet.  keep the restored scrollback.
+                None => store.restored_scrollback(tab.id),
+            };
+
+            if let Some(data) = data {
+                scrollback.insert(tab.id, data);
+            }
+        }
+
+        SessionSnapshot { tabs, scrollback }
+    }
+
+    /// If the tail was truncated, removes the partial line at the beginning of the buffer.
+    fn trim_line(mut data: Vec<u8>) -> Vec<u8> {
+        if data.len() < SCROLLBACK_TAIL_LEN {
+            return data;
+        }
+
+        if let Some(newline) = data.iter().position(|byte| *byte == b'\n') {
+            data.drain(0..=newline);
+        }
+
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SessionService;
+    use crate::{
+        message::{listener::ListenerShutdown, session::SessionRecv, tab::TabRecv},
+        prelude::*,
+        state::{scrollback::ScrollbackRegistry, session::SessionStore},
+    };
+    use lifeline::{assert_completes, dyn_bus::DynBus};
+
+    #[tokio::test]
+    async fn shutdown() -> anyhow::Result<()> {
+        let bus = ListenerBus::default();
+        bus.store_resource(SessionStore::default());
+        bus.store_resource(ScrollbackRegistry::default());
+        let _service = SessionService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<SessionRecv>()?;
+        let mut rx_tabs = bus.rx::<TabRecv>()?;
+        let mut rx_shutdown = bus.rx::<ListenerShutdown>()?;
+
+        tx.send(SessionRecv::Shutdown).await?;
+
+        assert_completes!(async move {
+            let msg = rx_tabs.recv().await;
+            assert_eq!(Some(TabRecv::TerminateAll), msg);
+
+            let shutdown = rx_shutdown.recv().await;
+            assert!(shutdown.is_some());
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn handoff() -> anyhow::Result<()> {
+        let bus = ListenerBus::default();
+        bus.store_resource(SessionStore::default());
+        bus.store_resource(ScrollbackRegistry::default());
+        let _service = SessionService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<SessionRecv>()?;
+        let mut rx_tabs = bus.rx::<TabRecv>()?;
+        let mut rx_shutdown = bus.rx::<ListenerShutdown>()?;
+
+        tx.send(SessionRecv::Handoff).await?;
+
+        assert_completes!(
+            async move {
+                let msg = rx_tabs.recv().await;
+                assert_eq!(Some(TabRecv::ReconnectAll), msg);
+
+                let shutdown = rx_shutdown.recv().await;
+                assert!(shutdown.is_some());
+            },
+            500
+        );
+
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
+use super::monitor::notify;
 use crate::{
     message::{tab::TabRecv, tab_manager::TabManagerRecv},
-    state::tab::TabsState,
+    state::{resize::ConnectionId, session::SessionStore, tab::TabsState},
 };
 use crate::{
     message::{tab::TabSend, tab_assignment::AssignTab},
@@ -10,18 +11,30 @@ use anyhow::Context;
 use postage::sink::Sink;
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
 };
-use tab_api::tab::{TabId, TabMetadata};
+use tab_api::{
+    config::{OutputLogConfig, ScrollbackConfig},
+    tab::{ExitedTab, TabExit, TabId, TabMetadata},
+};
+use tokio::time;
+
+/// How long handed off tabs wait for their pty to reconnect, before a new shell is launched.
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Manages the currently running tabs.  This is a point-of-contact between the tab-command and tab-pty clients.
 ///
 /// - Serves 'create tab' requests from the tab-command client.
 /// - Spawns tab-pty processes (OS processes), and issues offers of tab assignment to connected pty clients.
 /// - Terminates tabs when requested by the tab-command client.
+/// - Keeps tabs whose shell has exited, until they are dismissed by the tab-command client.
+/// - Restores the tabs which were running when the daemon last shut down.
+/// - Re-adopts running tabs when their pty reconnects (e.g. after a daemon upgrade or crash).
 pub struct TabManagerService {
     _recv: Lifeline,
+    _expire_handoff: Lifeline,
 }
 
 static TAB_ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
@@ -31,6 +44,11 @@ impl Service for TabManagerService {
     type Lifeline = anyhow::Result<Self>;
 
     fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let store = bus.resource::<SessionStore>()?;
+        let scrollback = bus.resource::<ScrollbackConfig>()?;
+        let output_log = bus.resource::<OutputLogConfig>()?;
+        let handoff = store.take_handoff();
+
         let _recv = {
             let mut rx = bus.rx::<TabManagerRecv>()?;
 
@@ -40,8 +58,39 @@ impl Service for TabManagerService {
             let mut tx_assign_tab = bus.tx::<AssignTab>()?;
 
             let mut tabs: HashMap<TabId, TabMetadata> = HashMap::new();
+            // tabs whose shell exited on its own, which have not been dismissed
+            let mut exited: HashMap<TabId, ExitedTab> = HashMap::new();
+            // the tabs each client connection is subscribed to
+            let mut attached: HashSet<(ConnectionId, TabId)> = HashSet::new();
+            // handed off tabs, which are waiting for their pty to reconnect
+            let mut awaiting: HashSet<TabId> = HashSet::new();
 
             Self::try_task("recv", async move {
+                // restore before serving requests, so new tabs can't take the ids of restored tabs
+                let restored = store.restore().unwrap_or_else(|e| {
+                    error!("failed to restore the previous session: {}", e);
+                    Vec::new()
+                });
+
+                if !restored.is_empty() {
+                    for mut metadata in restored {
+                        metadata.attached = 0;
+                        TAB_ID_COUNTER.fetch_max(metadata.id.0 as usize + 1, Ordering::SeqCst);
+
+                        if handoff {
+                            info!("awaiting pty for tab {} ({})", &metadata.name, metadata.id);
+                            awaiting.insert(metadata.id);
+                        } else {
+                            info!("restoring tab {} ({})", &metadata.name, metadata.id);
+                            tx_assign_tab.send(AssignTab(metadata.clone())).await?;
+                        }
+
+                        tabs.insert(metadata.id, metadata);
+                    }
+
+                    tx_tabs_state.send(TabsState::new(&tabs, &exited)).await?;
+                }
+
                 'msg: while let Some(msg) = rx.recv().await {
                     match msg {
                         TabManagerRecv::CreateTab(create) => {
@@ -51,27 +100,161 @@ impl Service for TabManagerService {
                                 }
                             }
 
+                            // a relaunched tab replaces the exited tab
+                            exited.retain(|_, tab| tab.metadata.name != create.name);
+
                             debug!("recieved request to create tab {}", &create.name);
                             let id = TAB_ID_COUNTER.fetch_add(1, Ordering::SeqCst) as u16;
                             let tab_id = TabId(id);
-                            let tab_metadata = TabMetadata::create(tab_id, create);
+                            let mut tab_metadata = TabMetadata::create(tab_id, create);
+
+                            // resolve the limit now, so the tab keeps it if restored by a daemon with different config
+                            if tab_metadata.scrollback.is_none() {
+                                tab_metadata.scrollback = scrollback.limit;
+                            }
+
+                            if let (Some(_), Some(limit)) = (tab_metadata.log, output_log.limit) {
+                                tab_metadata.log = Some(limit);
+                            }
 
                             tx_assign_tab.send(AssignTab(tab_metadata.clone())).await?;
 
                             tabs.insert(tab_id, tab_metadata);
-                            tx_tabs_state.send(TabsState::new(&tabs)).await?;
+                            tx_tabs_state.send(TabsState::new(&tabs, &exited)).await?;
                         }
-                        TabManagerRecv::UpdateTimestamp(id) => {
+                        TabManagerRecv::Attach(connection, id) => {
+                            attached.insert((connection, id));
+
                             if let Some(metadata) = tabs.get_mut(&id) {
                                 metadata.mark_selected();
+                                metadata.alert = None;
+                            }
+
+                            Self::update_attached(id, &attached, &mut tabs, &mut tx).await?;
+                            tx_tabs_state.send(TabsState::new(&tabs, &exited)).await?;
+                        }
+                        TabManagerRecv::Detach(connection, id) => {
+                            if attached.remove(&(connection, id)) {
+                                Self::update_attached(id, &attached, &mut tabs, &mut tx).await?;
+                                tx_tabs_state.send(TabsState::new(&tabs, &exited)).await?;
+                            }
+                        }
+                        TabManagerRecv::Disconnect(connection) => {
+                            let detached: Vec<TabId> = attached
+                                .iter()
+                                .filter(|(client, _)| *client == connection)
+                                .map(|(_, id)| *id)
+                                .collect();
+
+                            for id in detached.iter() {
+                                attached.remove(&(connection, *id));
+                                Self::update_attached(*id, &attached, &mut tabs, &mut tx).await?;
+                            }
+
+                            if !detached.is_empty() {
+                                tx_tabs_state.send(TabsState::new(&tabs, &exited)).await?;
+                            }
+                        }
+                        TabManagerRecv::Resize(id, dimensions) => {
+                            if let Some(metadata) = tabs.get_mut(&id) {
+                                metadata.dimensions = dimensions;
 
                                 tx.send(TabSend::Updated(metadata.clone())).await?;
+                                tx_tabs_state.send(TabsState::new(&tabs, &exited)).await?;
+                            }
+                        }
+                        TabManagerRecv::ResumeTab(mut metadata) => {
+                            if awaiting.remove(&metadata.id) {
+                                info!("pty resumed tab {} ({})", &metadata.name, metadata.id);
+                                tx_tabs.send(TabRecv::AcceptResume(metadata)).await?;
+                                continue;
+                            }
+
+                            match tabs.get(&metadata.id) {
+                                Some(tab) if tab.name == metadata.name => {
+                                    tx_tabs.send(TabRecv::AcceptResume(metadata)).await?;
+                                }
+                                Some(tab) => {
+                                    warn!(
+                                        "pty resumed tab {} ({}), but the id is taken by tab {}.  terminating the pty.",
+                                        &metadata.name, metadata.id, &tab.name
+                                    );
+                                    tx_tabs.send(TabRecv::RejectResume(metadata)).await?;
+                                }
+                                None => {
+                                    info!("adopting tab {} ({})", &metadata.name, metadata.id);
+                                    tx_tabs
+                                        .send(TabRecv::AcceptResume(metadata.clone()))
+                                        .await?;
+
+                                    metadata.attached =
+                                        Self::count_attached(metadata.id, &attached);
+                                    TAB_ID_COUNTER
+                                        .fetch_max(metadata.id.0 as usize + 1, Ordering::SeqCst);
+
+                                    tabs.insert(metadata.id, metadata);
+                                    tx_tabs_state.send(TabsState::new(&tabs, &exited)).await?;
+                                }
+                            }
+                        }
+                        TabManagerRecv::ExpireHandoff => {
+                            for id in awaiting.drain() {
+                                if let Some(metadata) = tabs.get(&id) {
+                                    warn!(
+                                        "pty did not reconnect to tab {} ({}), launching a new shell",
+                                        &metadata.name, id
+                                    );
+                                    tx_assign_tab.send(AssignTab(metadata.clone())).await?;
+                                }
+                            }
+                        }
+                        TabManagerRecv::Alert(id, alert) => {
+                            if let Some(metadata) = tabs.get_mut(&id) {
+                                if metadata.attached > 0 || metadata.alert == Some(alert) {
+                                    continue;
+                                }
+
+                                info!("tab {} ({}) raised a {} alert", &metadata.name, id, alert);
+                                metadata.alert = Some(alert);
+                                notify(metadata, alert);
+
+                                tx.send(TabSend::Updated(metadata.clone())).await?;
+                                tx_tabs_state.send(TabsState::new(&tabs, &exited)).await?;
                             }
                         }
                         TabManagerRecv::CloseTab(close) => {
+                            if exited.remove(&close).is_some() {
+                                info!("TabManager dismissing exited tab {}", close);
+                                tx_tabs_state.send(TabsState::new(&tabs, &exited)).await?;
+                                continue;
+                            }
+
+                            awaiting.remove(&close);
                             Self::close_tab(
                                 close,
+                                TabExit::default(),
+                                &mut tabs,
+                                &exited,
+                                &mut tx,
+                                &mut tx_tabs,
+                                &mut tx_tabs_state,
+                            )
+                            .await?;
+                        }
+                        TabManagerRecv::Exited(id, exit) => {
+                            // tabs which were closed by the client are not kept
+                            let metadata = match tabs.get(&id) {
+                                Some(metadata) => metadata.clone(),
+                                None => continue,
+                            };
+
+                            awaiting.remove(&id);
+                            exited.insert(id, ExitedTab { metadata, exit });
+                            Self::close_tab(
+                                id,
+                                exit,
                                 &mut tabs,
+                                &exited,
                                 &mut tx,
                                 &mut tx_tabs,
                                 &mut tx_tabs_state,
@@ -84,14 +267,51 @@ impl Service for TabManagerService {
             })
         };
 
-        Ok(Self { _recv })
+        let _expire_handoff = {
+            let mut tx = bus.tx::<TabManagerRecv>()?;
+
+            Self::try_task("expire_handoff", async move {
+                if handoff {
+                    time::sleep(HANDOFF_TIMEOUT).await;
+                    tx.send(TabManagerRecv::ExpireHandoff).await?;
+                }
+
+                Ok(())
+            })
+        };
+
+        Ok(Self {
+            _recv,
+            _expire_handoff,
+        })
     }
 }
 
 impl TabManagerService {
+    /// Updates the number of connections attached to the tab, and notifies clients
+    async fn update_attached(
+        id: TabId,
+        attached: &HashSet<(ConnectionId, TabId)>,
+        tabs: &mut HashMap<TabId, TabMetadata>,
+        mut tx: impl Sink<Item = TabSend> + Unpin,
+    ) -> anyhow::Result<()> {
+        if let Some(metadata) = tabs.get_mut(&id) {
+            metadata.attached = Self::count_attached(id, attached);
+            tx.send(TabSend::Updated(metadata.clone())).await?;
+        }
+
+        Ok(())
+    }
+
+    fn count_attached(id: TabId, attached: &HashSet<(ConnectionId, TabId)>) -> usize {
+        attached.iter().filter(|(_, tab)| *tab == id).count()
+    }
+
     async fn close_tab(
         id: TabId,
+        exit: TabExit,
         tabs: &mut HashMap<TabId, TabMetadata>,
+        exited: &HashMap<TabId, ExitedTab>,
         mut tx: impl Sink<Item = TabSend> + Unpin,
         mut tx_close: impl Sink<Item = TabRecv> + Unpin,
         mut tx_tabs_state: impl Sink<Item = TabsState> + Unpin,
@@ -99,13 +319,13 @@ impl TabManagerService {
         info!("TabManager terminating tab {}", id);
         tabs.remove(&id);
 
-        tx.send(TabSend::Stopped(id))
+        tx.send(TabSend::Stopped(id, exit))
             .await
             .context("tx TabTerminated")
             .ok();
         tx_close.send(TabRecv::Terminate(id)).await.ok();
         tx_tabs_state
-            .send(TabsState::new(&tabs))
+            .send(TabsState::new(&tabs, exited))
             .await
             .context("tx_tabs_state TabsState")
             .ok();
@@ -113,3 +333,76 @@ impl TabManagerService {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::TabManagerService;
+    use crate::{
+        message::{tab::TabRecv, tab_assignment::AssignTab, tab_manager::TabManagerRecv},
+        prelude::*,
+        state::session::SessionStore,
+    };
+    use lifeline::{assert_completes, dyn_bus::DynBus};
+    use std::collections::HashMap;
+    use tab_api::{
+        config::{OutputLogConfig, ScrollbackConfig},
+        tab::{CreateTabMetadata, TabId, TabMetadata},
+    };
+
+    fn create(name: &str) -> CreateTabMetadata {
+        CreateTabMetadata {
+            name: name.into(),
+            dimensions: (1, 2),
+            doc: None,
+            env: HashMap::new(),
+            shell: "shell".into(),
+            dir: "/".into(),
+            command: None,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: false,
+            monitor: None,
+            notify: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn resume_collision() -> anyhow::Result<()> {
+        let bus = ListenerBus::default();
+        bus.store_resource(SessionStore::default());
+        bus.store_resource(ScrollbackConfig::default());
+        bus.store_resource(OutputLogConfig::default());
+
+        let _service = TabManagerService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<TabManagerRecv>()?;
+        let mut rx_assign = bus.rx::<AssignTab>()?;
+        let mut rx = bus.rx::<TabRecv>()?;
+
+        tx.send(TabManagerRecv::CreateTab(create("tab/"))).await?;
+
+        let id = assert_completes!(async move {
+            let AssignTab(assigned) = rx_assign.recv().await.expect("assign");
+            assigned.id
+        });
+
+        // another pty claims the id of the running tab
+        let resumed = TabMetadata::create(id, create("other/"));
+        tx.send(TabManagerRecv::ResumeTab(resumed.clone())).await?;
+
+        // a pty resumes a tab which is unknown to the daemon
+        let adopted = TabMetadata::create(TabId(id.0 + 100), create("adopted/"));
+        tx.send(TabManagerRecv::ResumeTab(adopted.clone())).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(TabRecv::RejectResume(resumed)), msg);
+
+            let msg = rx.recv().await;
+            assert_eq!(Some(TabRecv::AcceptResume(adopted)), msg);
+        });
+
+        Ok(())
+    }
+}
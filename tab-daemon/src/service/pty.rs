@@ -1,4 +1,7 @@
+mod output_log;
+mod recording;
 pub mod scrollback;
+mod spill;
 
 // mod session;
 
@@ -6,10 +9,16 @@ use std::time::Duration;
 
 use crate::message::pty::{PtyRecv, PtySend, PtyShutdown};
 use crate::prelude::*;
+use crate::state::session::SessionStore;
 
 use postage::sink::Sink;
-use tab_api::pty::{PtyWebsocketRequest, PtyWebsocketResponse};
+use tab_api::{
+    chunk::OutputChunk,
+    pty::{PtyWebsocketRequest, PtyWebsocketResponse},
+};
 
+use output_log::PtyOutputLogService;
+use recording::PtyRecordingService;
 use scrollback::PtyScrollbackService;
 use tokio::time;
 
@@ -18,6 +27,8 @@ pub struct PtyService {
     _websocket: Lifeline,
     _daemon: Lifeline,
     _scrollback: PtyScrollbackService,
+    _output_log: PtyOutputLogService,
+    _recording: PtyRecordingService,
 }
 
 impl Service for PtyService {
@@ -33,20 +44,35 @@ impl Service for PtyService {
             let mut rx_websocket = bus.rx::<PtyWebsocketResponse>()?.log(Level::Trace);
             let mut tx_daemon = bus.tx::<PtySend>()?;
             let mut tx_shutdown = bus.tx::<PtyShutdown>()?;
+            let store = bus.resource::<SessionStore>()?;
 
             Self::try_task("websocket", async move {
+                // if scrollback is restored, the pty output is shifted after it
+                let mut offset = 0;
+
                 while let Some(msg) = rx_websocket.recv().await {
                     match msg {
                         PtyWebsocketResponse::Started(metadata) => {
                             info!("PTY process has started on tab {}", metadata.id);
+                            let restored = store.take_scrollback(metadata.id);
                             tx_daemon.send(PtySend::Started(metadata)).await?;
+
+                            offset = Self::restore_scrollback(restored, &mut tx_daemon).await?;
+                        }
+                        PtyWebsocketResponse::Resumed(metadata) => {
+                            info!("PTY process has resumed tab {}", metadata.id);
+                            let restored = store.take_scrollback(metadata.id);
+                            tx_daemon.send(PtySend::Resumed(metadata)).await?;
+
+                            offset = Self::restore_scrollback(restored, &mut tx_daemon).await?;
                         }
-                        PtyWebsocketResponse::Output(output) => {
+                        PtyWebsocketResponse::Output(mut output) => {
+                            output.index += offset;
                             tx_daemon.send(PtySend::Output(output)).await?;
                         }
-                        PtyWebsocketResponse::Stopped => {
-                            info!("PTY process has terminated");
-                            tx_daemon.send(PtySend::Stopped).await?;
+                        PtyWebsocketResponse::Stopped(exit) => {
+                            info!("PTY process has terminated: {}", exit);
+                            tx_daemon.send(PtySend::Stopped(exit)).await?;
 
                             // this sleep is not visible to the user
                             time::sleep(Duration::from_millis(500)).await;
@@ -85,7 +111,11 @@ impl Service for PtyService {
                             info!("PTY process terminating due to user request");
                             tx_websocket.send(PtyWebsocketRequest::Terminate).await?;
                         }
-                        PtyRecv::Scrollback => {}
+                        PtyRecv::Reconnect => {
+                            info!("PTY process reconnecting to the next daemon");
+                            tx_websocket.send(PtyWebsocketRequest::Reconnect).await?;
+                        }
+                        PtyRecv::Scrollback | PtyRecv::Record => {}
                     }
                 }
 
@@ -94,33 +124,64 @@ impl Service for PtyService {
         };
 
         let _scrollback = PtyScrollbackService::spawn(bus)?;
+        let _output_log = PtyOutputLogService::spawn(bus)?;
+        let _recording = PtyRecordingService::spawn(bus)?;
 
         Ok(PtyService {
             _websocket,
             _daemon,
             _scrollback,
+            _output_log,
+            _recording,
         })
     }
 }
 
+impl PtyService {
+    /// Replays restored scrollback before the pty output, returning the offset for the following output chunks.
+    async fn restore_scrollback(
+        restored: Option<Vec<u8>>,
+        mut tx: impl Sink<Item = PtySend> + Unpin,
+    ) -> anyhow::Result<usize> {
+        let mut data = match restored {
+            Some(data) => data,
+            None => return Ok(0),
+        };
+
+        if !data.ends_with(b"\n") {
+            data.extend_from_slice(b"\r\n");
+        }
+
+        let offset = data.len();
+        let chunk = OutputChunk { index: 0, data };
+        tx.send(PtySend::Output(chunk)).await?;
+
+        Ok(offset)
+    }
+}
+
 #[cfg(test)]
 mod websocket_tests {
     use super::PtyService;
+    use crate::state::session::SessionStore;
     use crate::{
         bus::PtyBus,
         message::pty::{PtySend, PtyShutdown},
     };
-    use lifeline::{assert_completes, assert_times_out, Bus, Receiver, Sender, Service};
+    use lifeline::{
+        assert_completes, assert_times_out, dyn_bus::DynBus, Bus, Receiver, Sender, Service,
+    };
     use std::collections::HashMap;
     use tab_api::{
         chunk::OutputChunk,
         pty::PtyWebsocketResponse,
-        tab::{TabId, TabMetadata},
+        tab::{TabExit, TabId, TabMetadata},
     };
 
     #[tokio::test]
     async fn started() -> anyhow::Result<()> {
         let bus = PtyBus::default();
+        bus.store_resource(SessionStore::default());
         let _service = PtyService::spawn(&bus)?;
 
         let mut tx = bus.tx::<PtyWebsocketResponse>()?;
@@ -134,7 +195,16 @@ mod websocket_tests {
             env: HashMap::new(),
             shell: "shell".into(),
             dir: "/".into(),
+            command: None,
             selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
         };
         tx.send(PtyWebsocketResponse::Started(tab.clone())).await?;
 
@@ -146,9 +216,48 @@ mod websocket_tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn resumed() -> anyhow::Result<()> {
+        let bus = PtyBus::default();
+        bus.store_resource(SessionStore::default());
+        let _service = PtyService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<PtyWebsocketResponse>()?;
+        let mut rx = bus.rx::<PtySend>()?;
+
+        let tab = TabMetadata {
+            id: TabId(0),
+            name: "name".into(),
+            doc: Some("doc".into()),
+            dimensions: (1, 2),
+            env: HashMap::new(),
+            shell: "shell".into(),
+            dir: "/".into(),
+            command: None,
+            selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
+        };
+        tx.send(PtyWebsocketResponse::Resumed(tab.clone())).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(PtySend::Resumed(tab)), msg);
+        });
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn output() -> anyhow::Result<()> {
         let bus = PtyBus::default();
+        bus.store_resource(SessionStore::default());
         let _service = PtyService::spawn(&bus)?;
 
         let mut tx = bus.tx::<PtyWebsocketResponse>()?;
@@ -172,18 +281,20 @@ mod websocket_tests {
     #[tokio::test]
     async fn stopped() -> anyhow::Result<()> {
         let bus = PtyBus::default();
+        bus.store_resource(SessionStore::default());
         let _service = PtyService::spawn(&bus)?;
 
         let mut tx = bus.tx::<PtyWebsocketResponse>()?;
         let mut rx = bus.rx::<PtySend>()?;
         let mut rx_shutdown = bus.rx::<PtyShutdown>()?;
 
-        tx.send(PtyWebsocketResponse::Stopped).await?;
+        tx.send(PtyWebsocketResponse::Stopped(TabExit::code(0)))
+            .await?;
 
         assert_completes!(
             async move {
                 let msg = rx.recv().await;
-                assert_eq!(Some(PtySend::Stopped), msg);
+                assert_eq!(Some(PtySend::Stopped(TabExit::code(0))), msg);
 
                 let _shutdown_msg = rx_shutdown.recv().await;
             },
@@ -196,16 +307,18 @@ mod websocket_tests {
     #[tokio::test]
     async fn stopped_terminates() -> anyhow::Result<()> {
         let bus = PtyBus::default();
+        bus.store_resource(SessionStore::default());
         let _service = PtyService::spawn(&bus)?;
 
         let mut tx = bus.tx::<PtyWebsocketResponse>()?;
         let mut rx = bus.rx::<PtySend>()?;
 
-        tx.send(PtyWebsocketResponse::Stopped).await?;
+        tx.send(PtyWebsocketResponse::Stopped(TabExit::code(0)))
+            .await?;
 
         assert_completes!(async {
             let msg = rx.recv().await;
-            assert_eq!(Some(PtySend::Stopped), msg);
+            assert_eq!(Some(PtySend::Stopped(TabExit::code(0))), msg);
         });
 
         assert_times_out!(async {
@@ -219,8 +332,8 @@ mod websocket_tests {
 #[cfg(test)]
 mod daemon_tests {
     use super::PtyService;
-    use crate::{bus::PtyBus, message::pty::PtyRecv};
-    use lifeline::{assert_completes, Bus, Receiver, Sender, Service};
+    use crate::{bus::PtyBus, message::pty::PtyRecv, state::session::SessionStore};
+    use lifeline::{assert_completes, dyn_bus::DynBus, Bus, Receiver, Sender, Service};
     use std::collections::HashMap;
     use tab_api::{
         chunk::InputChunk,
@@ -231,6 +344,7 @@ mod daemon_tests {
     #[tokio::test]
     async fn init() -> anyhow::Result<()> {
         let bus = PtyBus::default();
+        bus.store_resource(SessionStore::default());
         let _service = PtyService::spawn(&bus)?;
 
         let mut tx = bus.tx::<PtyRecv>()?;
@@ -244,7 +358,16 @@ mod daemon_tests {
             env: HashMap::new(),
             shell: "shell".into(),
             dir: "/".into(),
+            command: None,
             selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
         };
         tx.send(PtyRecv::Init(tab.clone())).await?;
 
@@ -259,6 +382,7 @@ mod daemon_tests {
     #[tokio::test]
     async fn input() -> anyhow::Result<()> {
         let bus = PtyBus::default();
+        bus.store_resource(SessionStore::default());
         let _service = PtyService::spawn(&bus)?;
 
         let mut tx = bus.tx::<PtyRecv>()?;
@@ -278,6 +402,7 @@ mod daemon_tests {
     #[tokio::test]
     async fn resize() -> anyhow::Result<()> {
         let bus = PtyBus::default();
+        bus.store_resource(SessionStore::default());
         let _service = PtyService::spawn(&bus)?;
 
         let mut tx = bus.tx::<PtyRecv>()?;
@@ -296,6 +421,7 @@ mod daemon_tests {
     #[tokio::test]
     async fn terminate() -> anyhow::Result<()> {
         let bus = PtyBus::default();
+        bus.store_resource(SessionStore::default());
         let _service = PtyService::spawn(&bus)?;
 
         let mut tx = bus.tx::<PtyRecv>()?;
@@ -310,4 +436,23 @@ mod daemon_tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn reconnect() -> anyhow::Result<()> {
+        let bus = PtyBus::default();
+        bus.store_resource(SessionStore::default());
+        let _service = PtyService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<PtyRecv>()?;
+        let mut rx = bus.rx::<PtyWebsocketRequest>()?;
+
+        tx.send(PtyRecv::Reconnect).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(PtyWebsocketRequest::Reconnect), msg);
+        });
+
+        Ok(())
+    }
 }
@@ -0,0 +1,264 @@
+use crate::{
+    message::pty::{PtyRecv, PtySend},
+    prelude::*,
+};
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+};
+use tab_api::{
+    config::output_log_path,
+    tab::{OutputLogLimit, TabMetadata},
+};
+use tokio::select;
+
+/// Appends the output of a tab to a rolling log file, with ANSI escape sequences removed.
+///
+/// Logging is enabled for tabs which are created with `log: true`.  The log is written to `logs/<tab>.log`,
+/// and is rotated to `<tab>.log.1`, `<tab>.log.2`, etc when it reaches the size limit.
+pub struct PtyOutputLogService {
+    _log: Lifeline,
+}
+
+impl Service for PtyOutputLogService {
+    type Bus = PtyBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let mut rx_daemon = bus.rx::<PtyRecv>()?;
+        let mut rx_pty = bus.rx::<PtySend>()?;
+
+        let _log = Self::try_task("log", async move {
+            let mut log: Option<strip_ansi_escapes::Writer<RollingLog>> = None;
+
+            loop {
+                select! {
+                    msg = rx_daemon.recv() => match msg {
+                        Some(PtyRecv::Init(tab)) => {
+                            log = Self::open(&tab);
+                        }
+                        Some(_) => {}
+                        None => break,
+                    },
+                    msg = rx_pty.recv() => match msg {
+                        // a reconnecting pty is not initialized by the daemon
+                        Some(PtySend::Resumed(tab)) => {
+                            if log.is_none() {
+                                log = Self::open(&tab);
+                            }
+                        }
+                        Some(PtySend::Output(chunk)) => {
+                            if let Some(ref mut writer) = log {
+                                let result = writer
+                                    .write_all(chunk.data.as_slice())
+                                    .and_then(|_| writer.flush());
+
+                                if let Err(e) = result {
+                                    warn!("failed to write output log: {}", e);
+                                    log = None;
+                                }
+                            }
+                        }
+                        Some(PtySend::Stopped(_)) => {
+                            log = None;
+                        }
+                        Some(_) => {}
+                        None => break,
+                    }
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _log })
+    }
+}
+
+impl PtyOutputLogService {
+    fn open(metadata: &TabMetadata) -> Option<strip_ansi_escapes::Writer<RollingLog>> {
+        let limit = metadata.log?;
+
+        let result = output_log_path(metadata.name.as_str())
+            .and_then(|path| Ok(RollingLog::open(path, limit)?));
+
+        match result {
+            Ok(log) => {
+                info!("Logging the output of tab {}", metadata.id);
+                Some(strip_ansi_escapes::Writer::new(log))
+            }
+            Err(e) => {
+                warn!("failed to open output log for tab {}: {}", metadata.id, e);
+                None
+            }
+        }
+    }
+}
+
+/// A log file which is rotated when it exceeds the size limit.
+/// Rotated files are renamed with a numeric suffix, and only `limit.files` rotated files are kept.
+struct RollingLog {
+    path: PathBuf,
+    limit: OutputLogLimit,
+    file: BufWriter<File>,
+    len: u64,
+}
+
+impl RollingLog {
+    pub fn open(path: PathBuf, limit: OutputLogLimit) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let len = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            limit,
+            file: BufWriter::new(file),
+            len,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    /// Shifts each rotated file up by one index, removing the oldest, and starts a new log file.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.limit.files > 0 {
+            for index in (1..self.limit.files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(from, self.rotated_path(index + 1))?;
+                }
+            }
+
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+
+        self.file = BufWriter::new(file);
+        self.len = 0;
+
+        Ok(())
+    }
+}
+
+impl Write for RollingLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.len > 0 && self.len + buf.len() as u64 > self.limit.bytes {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.len += written as u64;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RollingLog;
+    use std::{env, fs, io::Write, path::PathBuf, process};
+    use tab_api::tab::OutputLogLimit;
+
+    fn log_dir(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("tab-output-log-{}-{}", name, process::id()))
+    }
+
+    #[test]
+    fn append() -> anyhow::Result<()> {
+        let dir = log_dir("append");
+        let path = dir.join("tab.log");
+        let limit = OutputLogLimit::default();
+
+        let mut log = RollingLog::open(path.clone(), limit)?;
+        log.write_all(b"first\n")?;
+        log.flush()?;
+        drop(log);
+
+        let mut log = RollingLog::open(path.clone(), limit)?;
+        log.write_all(b"second\n")?;
+        log.flush()?;
+
+        assert_eq!("first\nsecond\n", fs::read_to_string(&path)?);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn rotate() -> anyhow::Result<()> {
+        let dir = log_dir("rotate");
+        let path = dir.join("tab.log");
+        let limit = OutputLogLimit { bytes: 8, files: 2 };
+
+        let mut log = RollingLog::open(path.clone(), limit)?;
+        for line in &["one\n", "two\n", "three\n", "four\n"] {
+            log.write_all(line.as_bytes())?;
+        }
+        log.flush()?;
+
+        assert_eq!("four\n", fs::read_to_string(&path)?);
+        assert_eq!("three\n", fs::read_to_string(dir.join("tab.log.1"))?);
+        assert_eq!("one\ntwo\n", fs::read_to_string(dir.join("tab.log.2"))?);
+        assert!(!dir.join("tab.log.3").exists());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn rotate_without_files() -> anyhow::Result<()> {
+        let dir = log_dir("truncate");
+        let path = dir.join("tab.log");
+        let limit = OutputLogLimit { bytes: 4, files: 0 };
+
+        let mut log = RollingLog::open(path.clone(), limit)?;
+        log.write_all(b"one\n")?;
+        log.write_all(b"two\n")?;
+        log.flush()?;
+
+        assert_eq!("two\n", fs::read_to_string(&path)?);
+        assert!(!dir.join("tab.log.1").exists());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn strip_ansi() -> anyhow::Result<()> {
+        let dir = log_dir("strip");
+        let path = dir.join("tab.log");
+
+        let log = RollingLog::open(path.clone(), OutputLogLimit::default())?;
+        let mut writer = strip_ansi_escapes::Writer::new(log);
+        writer.write_all(b"\x1b[32mgre")?;
+        writer.write_all(b"en\x1b[0")?;
+        writer.write_all(b"m\r\n")?;
+        writer.flush()?;
+
+        assert_eq!("green\n", fs::read_to_string(&path)?);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,360 @@
+use crate::{
+    message::pty::{PtyRecv, PtySend},
+    prelude::*,
+};
+
+use serde_json::json;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use tab_api::{config::recordings_path, tab::TabMetadata};
+use tokio::select;
+
+/// Records the output of a tab to an asciicast v2 file, which can be played back with `tab --replay`.
+///
+/// Recording starts when the tab is initialized with `record: true`, or when a client requests it with `tab --record`.
+/// Output and resize events are timestamped relative to the start of the recording.
+pub struct PtyRecordingService {
+    _record: Lifeline,
+}
+
+impl Service for PtyRecordingService {
+    type Bus = PtyBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let mut rx_daemon = bus.rx::<PtyRecv>()?;
+        let mut rx_pty = bus.rx::<PtySend>()?;
+
+        let _record = Self::try_task("record", async move {
+            let mut metadata: Option<TabMetadata> = None;
+            let mut dimensions = (0, 0);
+            let mut recording: Option<Recording<BufWriter<File>>> = None;
+
+            loop {
+                select! {
+                    msg = rx_daemon.recv() => match msg {
+                        Some(PtyRecv::Init(tab)) => {
+                            dimensions = tab.dimensions;
+                            if tab.record {
+                                recording = Self::start(&tab, dimensions);
+                            }
+
+                            metadata = Some(tab);
+                        }
+                        Some(PtyRecv::Resize(size)) => {
+                            dimensions = size;
+                            if let Some(ref mut active) = recording {
+                                if let Err(e) = active.resize(size) {
+                                    warn!("failed to write recording: {}", e);
+                                    recording = None;
+                                }
+                            }
+                        }
+                        Some(PtyRecv::Record) => {
+                            if let (None, Some(tab)) = (&recording, &metadata) {
+                                recording = Self::start(tab, dimensions);
+                            }
+                        }
+                        Some(_) => {}
+                        None => break,
+                    },
+                    msg = rx_pty.recv() => match msg {
+                        // a reconnecting pty is not initialized by the daemon
+                        Some(PtySend::Resumed(tab)) => {
+                            dimensions = tab.dimensions;
+                            if tab.record && recording.is_none() {
+                                recording = Self::start(&tab, dimensions);
+                            }
+
+                            metadata = Some(tab);
+                        }
+                        Some(PtySend::Output(chunk)) => {
+                            if let Some(ref mut active) = recording {
+                                if let Err(e) = active.output(chunk.data.as_slice()) {
+                                    warn!("failed to write recording: {}", e);
+                                    recording = None;
+                                }
+                            }
+                        }
+                        Some(PtySend::Stopped(_)) => {
+                            recording = None;
+                        }
+                        Some(_) => {}
+                        None => break,
+                    }
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _record })
+    }
+}
+
+impl PtyRecordingService {
+    fn start(metadata: &TabMetadata, dimensions: (u16, u16)) -> Option<Recording<BufWriter<File>>> {
+        let result = recordings_path().and_then(|dir| {
+            let (path, file) = create_file(dir.as_path(), metadata.name.as_str())?;
+            info!(
+                "Recording tab {} to {}",
+                metadata.id,
+                path.to_string_lossy()
+            );
+
+            let recording = Recording::new(BufWriter::new(file), metadata, dimensions)?;
+            Ok(recording)
+        });
+
+        match result {
+            Ok(recording) => Some(recording),
+            Err(e) => {
+                warn!("failed to start recording tab {}: {}", metadata.id, e);
+                None
+            }
+        }
+    }
+}
+
+/// Creates a new recording file for the tab, named with the tab name and the current time.
+fn create_file(dir: &Path, name: &str) -> io::Result<(PathBuf, File)> {
+    fs::create_dir_all(dir)?;
+
+    let name = name.trim_end_matches('/').replace("/", "_");
+    let timestamp = unix_time();
+
+    let mut suffix = 0;
+    loop {
+        let filename = match suffix {
+            0 => format!("{}-{}.cast", name, timestamp),
+            n => format!("{}-{}-{}.cast", name, timestamp, n),
+        };
+
+        let path = dir.join(filename);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => suffix += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|dur| dur.as_secs())
+        .unwrap_or(0)
+}
+
+/// Writes asciicast v2 events.  The header is written when the recording is created,
+/// and each event is flushed so the recording is complete if the daemon exits.
+struct Recording<W: Write> {
+    writer: W,
+    start: Instant,
+    /// Trailing bytes of the last output chunk, which are an incomplete UTF-8 sequence
+    partial: Vec<u8>,
+}
+
+impl<W: Write> Recording<W> {
+    pub fn new(mut writer: W, metadata: &TabMetadata, dimensions: (u16, u16)) -> io::Result<Self> {
+        let mut env = serde_json::Map::new();
+        env.insert("SHELL".to_string(), json!(metadata.shell));
+        if let Some(term) = metadata.env.get("TERM") {
+            env.insert("TERM".to_string(), json!(term));
+        }
+
+        let header = json!({
+            "version": 2,
+            "width": dimensions.0,
+            "height": dimensions.1,
+            "timestamp": unix_time(),
+            "title": metadata.name,
+            "env": env,
+        });
+
+        writeln!(writer, "{}", header)?;
+        writer.flush()?;
+
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+            partial: Vec::new(),
+        })
+    }
+
+    /// Writes an output event.  Output chunks can split multi-byte characters,
+    /// so an incomplete sequence at the end of the data is held until the next chunk.
+    pub fn output(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut bytes = std::mem::take(&mut self.partial);
+        bytes.extend_from_slice(data);
+
+        let mut text = String::with_capacity(bytes.len());
+        let mut remaining = bytes.as_slice();
+
+        loop {
+            match std::str::from_utf8(remaining) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    break;
+                }
+                Err(e) => {
+                    let (valid, after) = remaining.split_at(e.valid_up_to());
+                    text.push_str(std::str::from_utf8(valid).unwrap_or_default());
+
+                    match e.error_len() {
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            remaining = &after[len..];
+                        }
+                        None => {
+                            self.partial = after.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        self.event("o", text.as_str())
+    }
+
+    /// Writes a resize event, for the given (cols, rows)
+    pub fn resize(&mut self, dimensions: (u16, u16)) -> io::Result<()> {
+        let size = format!("{}x{}", dimensions.0, dimensions.1);
+        self.event("r", size.as_str())
+    }
+
+    fn event(&mut self, code: &str, data: &str) -> io::Result<()> {
+        let time = (self.start.elapsed().as_secs_f64() * 1_000_000.0).round() / 1_000_000.0;
+
+        writeln!(self.writer, "{}", json!([time, code, data]))?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create_file, Recording};
+    use serde_json::Value;
+    use std::{collections::HashMap, env, fs, process};
+    use tab_api::tab::{TabId, TabMetadata};
+
+    fn metadata() -> TabMetadata {
+        let mut env = HashMap::new();
+        env.insert("TERM".to_string(), "xterm-256color".to_string());
+
+        TabMetadata {
+            id: TabId(0),
+            name: "ops/".into(),
+            doc: None,
+            dimensions: (80, 24),
+            env,
+            shell: "/bin/bash".into(),
+            dir: "/".into(),
+            command: None,
+            selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: true,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
+        }
+    }
+
+    fn lines(recording: Recording<Vec<u8>>) -> Vec<Value> {
+        String::from_utf8(recording.writer)
+            .expect("recording should be utf8")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("line should be json"))
+            .collect()
+    }
+
+    #[test]
+    fn header() -> anyhow::Result<()> {
+        let recording = Recording::new(Vec::new(), &metadata(), (100, 30))?;
+        let lines = lines(recording);
+
+        assert_eq!(1, lines.len());
+        assert_eq!(2, lines[0]["version"]);
+        assert_eq!(100, lines[0]["width"]);
+        assert_eq!(30, lines[0]["height"]);
+        assert_eq!("ops/", lines[0]["title"]);
+        assert_eq!("xterm-256color", lines[0]["env"]["TERM"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn events() -> anyhow::Result<()> {
+        let mut recording = Recording::new(Vec::new(), &metadata(), (80, 24))?;
+        recording.output(b"hello\r\n")?;
+        recording.resize((120, 40))?;
+        let lines = lines(recording);
+
+        assert_eq!(3, lines.len());
+        assert!(lines[1][0].as_f64().is_some());
+        assert_eq!("o", lines[1][1]);
+        assert_eq!("hello\r\n", lines[1][2]);
+        assert_eq!("r", lines[2][1]);
+        assert_eq!("120x40", lines[2][2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn output_split_character() -> anyhow::Result<()> {
+        let data = "a→b".as_bytes();
+
+        let mut recording = Recording::new(Vec::new(), &metadata(), (80, 24))?;
+        recording.output(&data[0..2])?;
+        recording.output(&data[2..])?;
+        let lines = lines(recording);
+
+        assert_eq!(3, lines.len());
+        assert_eq!("a", lines[1][2]);
+        assert_eq!("→b", lines[2][2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn output_invalid_utf8() -> anyhow::Result<()> {
+        let mut recording = Recording::new(Vec::new(), &metadata(), (80, 24))?;
+        recording.output(&[b'a', 0xff, b'b'])?;
+        let lines = lines(recording);
+
+        assert_eq!("a\u{fffd}b", lines[1][2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn file_names_are_unique() -> anyhow::Result<()> {
+        let dir = env::temp_dir().join(format!("tab-recording-{}", process::id()));
+
+        let (first, _) = create_file(dir.as_path(), "ops/run/")?;
+        let (second, _) = create_file(dir.as_path(), "ops/run/")?;
+
+        assert_ne!(first, second);
+        assert!(first
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("ops_run-"));
+
+        fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+}
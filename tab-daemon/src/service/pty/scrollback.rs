@@ -1,18 +1,31 @@
+use super::spill::{count_lines, ScrollbackSpill, SpillSegment, SpillWrite};
 use crate::{
     message::pty::{PtyRecv, PtySend},
     prelude::*,
     state::pty::PtyScrollback,
 };
 
-use std::{collections::VecDeque, sync::Arc};
-use tab_api::chunk::OutputChunk;
-use tokio::sync::Mutex;
+use std::{collections::VecDeque, io, sync::Arc};
+use tab_api::{
+    chunk::OutputChunk,
+    config::scrollback_path,
+    emulator::TerminalEmulator,
+    tab::{ScrollbackLimit, TabMetadata},
+};
+use tokio::{sync::Mutex, task};
 
-// 128MB memory limit
+// 128MB memory limit, if the tab and the daemon config don't specify one
 static MAX_CAPACITY: usize = 134217728;
 static MAX_CHUNK_LEN: usize = 4096;
-
-/// Spawns with a pty connection, and maintains a scrollback buffer.  Provides scrollback for tab-command clients
+// 1MB of recent output is held in memory, when older output is spilled to disk
+static HOT_WINDOW_LEN: usize = 1048576;
+// output is spilled in 512KB segments
+static SEGMENT_LEN: usize = 524288;
+// the pty size is sent on init, but output can be received first
+static DEFAULT_DIMENSIONS: (u16, u16) = (80, 24);
+
+/// Spawns with a pty connection, and maintains a scrollback buffer and terminal emulator.
+/// Provides scrollback for tab-command clients
 pub struct PtyScrollbackService {
     _serve: Lifeline,
     _update: Lifeline,
@@ -32,10 +45,22 @@ impl Service for PtyScrollbackService {
 
             Self::try_task("serve", async move {
                 while let Some(msg) = rx.recv().await {
-                    if let PtyRecv::Scrollback = msg {
-                        let scrollback = serve_scrollback.handle();
-                        let response = PtySend::Scrollback(scrollback);
-                        tx.send(response).await?;
+                    match msg {
+                        PtyRecv::Init(metadata) => {
+                            serve_scrollback.init(&metadata).await;
+                        }
+                        PtyRecv::Resize(dimensions) => {
+                            serve_scrollback.resize(dimensions).await;
+                        }
+                        PtyRecv::Scrollback => {
+                            let scrollback = serve_scrollback.handle();
+                            let response = PtySend::Scrollback(scrollback);
+                            tx.send(response).await?;
+                        }
+                        PtyRecv::Terminate => {
+                            serve_scrollback.discard_spill().await;
+                        }
+                        _ => {}
                     }
                 }
 
@@ -48,8 +73,11 @@ impl Service for PtyScrollbackService {
 
             Self::try_task("serve", async move {
                 while let Some(msg) = rx.recv().await {
-                    if let PtySend::Output(output) = msg {
-                        buffer.push(output).await;
+                    match msg {
+                        PtySend::Output(output) => buffer.push(output).await,
+                        // a reconnecting pty is not initialized by the daemon
+                        PtySend::Resumed(metadata) => buffer.init(&metadata).await,
+                        _ => {}
                     }
                 }
 
@@ -64,84 +92,143 @@ impl Service for PtyScrollbackService {
 #[derive(Debug, Clone)]
 struct ScrollbackManager {
     arc: Arc<Mutex<ScrollbackBuffer>>,
-    filter: AnsiFilter,
 }
 
 impl ScrollbackManager {
     pub fn new() -> Self {
         Self {
             arc: Arc::new(Mutex::new(ScrollbackBuffer::new())),
-            filter: Self::ansi_filter(),
         }
     }
 
-    /// Several ANSI escape sequences that should not be replayed
-    pub fn ansi_filter() -> AnsiFilter {
-        AnsiFilter::new(vec![
-            // replace ESC [ 6n, Device Status Report
-            //   this sequence is echoed as keyboard characters,
-            //   and the tab session may not be running the same application as it was before
-            "\x1b[6n".as_bytes().iter().copied().collect(),
-            // replace ESC ] ** ; ? \x07, Operating System Command
-            //   similarly, this sequence results in the terminal emulator echoing characters
-            //   reference: https://www.xfree86.org/current/ctlseqs.html
-            "\x1b]\x00\x00;?\x07".as_bytes().iter().copied().collect(),
-            // replace ESC [ ** c, Send Device Attributes (Primary DA)
-            //   similarly, this sequence results in the terminal emulator echoing characters
-            //   reference: https://www.xfree86.org/current/ctlseqs.html
-            "\x1b]\x00\x00c".as_bytes().iter().copied().collect(),
-            // replace ESC [ = 0 c, Send Device Attributes (Tertiary DA)
-            //   similarly, this sequence results in the terminal emulator echoing characters
-            //   reference: https://www.xfree86.org/current/ctlseqs.html
-            "\x1b]=0c".as_bytes().iter().copied().collect(),
-            // replace ESC [ > ** ; ** ; 0 c, Send Device Attributes (Secondary DA)
-            //   similarly, this sequence results in the terminal emulator echoing characters
-            //   reference: https://www.xfree86.org/current/ctlseqs.html
-            "\x1b]>\x00\x00;\x00\x00;0c"
-                .as_bytes()
-                .iter()
-                .copied()
-                .collect(),
-        ])
-    }
-
     pub fn handle(&self) -> PtyScrollback {
         PtyScrollback::new(self.arc.clone())
     }
 
-    pub async fn push(&self, mut output: OutputChunk) {
-        // replace ANSI escape sequences that should not be repeated when scrollback is re-played.
-        self.filter.filter(&mut output.data);
+    pub async fn push(&self, output: OutputChunk) {
+        let write = {
+            let mut buffer = self.arc.lock().await;
+            buffer.push(output);
+            buffer.take_spill()
+        };
 
+        // output continues to be received while the segment is written
+        if let Some(write) = write {
+            tokio::spawn(write_spill(self.arc.clone(), write));
+        }
+    }
+
+    pub async fn init(&self, metadata: &TabMetadata) {
         let mut buffer = self.arc.lock().await;
-        buffer.push(output);
+
+        match scrollback_path(metadata.id) {
+            Ok(dir) => buffer.set_spill(ScrollbackSpill::new(dir)),
+            Err(e) => warn!(
+                "scrollback for tab {} will not spill to disk: {}",
+                metadata.id, e
+            ),
+        }
+
+        buffer.set_limit(
+            metadata
+                .scrollback
+                .unwrap_or(ScrollbackLimit::Bytes(MAX_CAPACITY)),
+        );
+        buffer.resize(metadata.dimensions);
+    }
+
+    pub async fn discard_spill(&self) {
+        let mut buffer = self.arc.lock().await;
+        buffer.discard_spill();
+    }
+
+    pub async fn resize(&self, dimensions: (u16, u16)) {
+        let mut buffer = self.arc.lock().await;
+        buffer.resize(dimensions);
     }
 }
 
+/// Writes a spilled segment on the blocking thread pool, so the buffer lock is not held during disk I/O.
+/// The chunks remain in memory until the segment is recorded in the buffer.
+pub async fn write_spill(buffer: Arc<Mutex<ScrollbackBuffer>>, write: SpillWrite) {
+    let result = match task::spawn_blocking(move || write.run()).await {
+        Ok(result) => result,
+        Err(e) => Err(io::Error::other(e)),
+    };
+
+    buffer.lock().await.finish_spill(result);
+}
+
+/// A synthesized redraw of the terminal state, which can be sent to a freshly reset terminal
 #[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrollbackSnapshot {
+    /// The index of the end of the pty output which was included in the redraw
+    pub end: usize,
+    /// Output which redraws the line history, screen, cursor, and terminal modes
+    pub redraw: Vec<u8>,
+}
+
+/// The raw scrollback of a tab.  Spilled segments are read from disk as they are replayed.
+#[derive(Debug, Clone)]
+pub struct ScrollbackReplay {
+    /// Compressed segments of older output, from oldest to newest
+    pub segments: Vec<SpillSegment>,
+    /// The output which is held in memory, following the segments
+    pub chunks: Vec<OutputChunk>,
+}
+
+/// Stores raw pty output, and tracks the terminal state of the tab.
+/// Recent output is held in memory, and older output is spilled to compressed segment files, if a spill is configured.
+#[derive(Debug)]
 pub struct ScrollbackBuffer {
+    /// The number of bytes in the queue
     size: usize,
+    /// The number of newlines in the queue
+    lines: usize,
+    limit: ScrollbackLimit,
     queue: VecDeque<OutputChunk>,
+    spill: Option<ScrollbackSpill>,
+    /// A segment which has been prepared, and is waiting to be written by the owner of the buffer
+    pending: Option<SpillWrite>,
+    /// True while a segment is being written.  The chunks are held at the front of the queue until it completes.
+    spilling: bool,
+    /// True if a segment write has failed.  Output is then held in memory, up to the scrollback limit.
+    spill_failed: bool,
+    emulator: TerminalEmulator,
 }
 
 impl ScrollbackBuffer {
     pub fn new() -> Self {
         ScrollbackBuffer {
             size: 0,
+            lines: 0,
+            limit: ScrollbackLimit::Bytes(MAX_CAPACITY),
             queue: VecDeque::new(),
+            spill: None,
+            pending: None,
+            spilling: false,
+            spill_failed: false,
+            emulator: TerminalEmulator::new(DEFAULT_DIMENSIONS),
         }
     }
 
-    pub fn push(&mut self, mut chunk: OutputChunk) {
-        while self.size > MAX_CAPACITY {
-            if let Some(chunk) = self.queue.pop_front() {
-                let front_len = chunk.len();
+    pub fn push(&mut self, chunk: OutputChunk) {
+        self.emulator.push(&chunk);
+        self.append(chunk);
 
-                // use saturating sub, just in case there was a calculation error and front_len is larger than size
-                let _ = self.size.saturating_sub(front_len);
-            }
+        // the most recent chunk is always retained
+        while self.chunk_count() > 1 && self.exceeds_limit() {
+            self.pop_front();
         }
 
+        if self.can_spill() && self.size > HOT_WINDOW_LEN {
+            self.prepare_spill(self.size - (HOT_WINDOW_LEN - SEGMENT_LEN));
+        }
+    }
+
+    fn append(&mut self, mut chunk: OutputChunk) {
+        self.lines += count_lines(chunk.data.as_slice());
+
         // If we get several small buffers, concat them.
         // This saves a lot of overhead for chunk id / channel storage over the websocket.
         // It does cause the client to 'miss' chunks, but that is part of the API contract.
@@ -174,174 +261,504 @@ impl ScrollbackBuffer {
         self.queue.push_back(chunk);
     }
 
-    pub fn clone_queue(&self) -> VecDeque<OutputChunk> {
-        self.queue.clone()
+    /// Sets the scrollback limit, evicting the oldest output if the buffer exceeds it
+    pub fn set_limit(&mut self, limit: ScrollbackLimit) {
+        self.limit = limit;
+        self.emulator.set_history_limit(limit);
+
+        while self.chunk_count() > 1 && self.exceeds_limit() {
+            self.pop_front();
+        }
     }
-}
-#[derive(Debug, Clone)]
-struct AnsiFilter {
-    sequences: Vec<Vec<u8>>,
-}
 
-impl Default for AnsiFilter {
-    fn default() -> Self {
-        todo!()
+    /// Spills older output into compressed segment files.  Ignored if a spill is already configured.
+    pub fn set_spill(&mut self, spill: ScrollbackSpill) {
+        if self.spill.is_none() {
+            self.spill = Some(spill);
+        }
+    }
+
+    /// Removes the spilled segment files, and any output they contain
+    pub fn discard_spill(&mut self) {
+        self.spill = None;
+        self.pending = None;
     }
-}
 
-impl AnsiFilter {
-    pub fn new<T>(iter: T) -> Self
-    where
-        T: IntoIterator<Item = Vec<u8>>,
-    {
-        let sequences: Vec<Vec<u8>> = iter.into_iter().collect();
-        Self { sequences }
+    /// Takes the segment which is waiting to be written.
+    /// The caller must run the write without holding the buffer lock, and then call `finish_spill`.
+    pub fn take_spill(&mut self) -> Option<SpillWrite> {
+        self.pending.take()
     }
 
+    /// Records the result of a segment write.  On success, the chunks are removed from memory.
+    /// On failure, the chunks are retained, and no further output is spilled.
+    pub fn finish_spill(&mut self, result: io::Result<SpillSegment>) {
+        self.spilling = false;
+
+        let segment = match result {
+            Ok(segment) => segment,
+            Err(e) => {
+                error!(
+                    "failed to spill scrollback to disk, output will be held in memory: {}",
+                    e
+                );
+                self.spill_failed = true;
+                return;
+            }
+        };
+
+        // the chunks may have been removed by the scrollback limit, or the spill discarded, while the segment was written
+        let front = self.queue.front().map(OutputChunk::start);
+        if self.spill.is_none() || front != Some(segment.start()) {
+            segment.discard();
+            return;
+        }
+
+        let mut removed = 0;
+        while removed < segment.size() {
+            match self.pop_memory() {
+                Some(chunk) => removed += chunk.len(),
+                None => break,
+            }
+        }
+
+        if let Some(ref mut spill) = self.spill {
+            spill.push_segment(segment);
+        }
+    }
+
+    /// The number of bytes held in memory, including pty output and the line history of the terminal emulator
+    pub fn size(&self) -> usize {
+        self.size + self.emulator.history_size()
+    }
+
+    /// The number of bytes of pty output held in memory
     #[cfg(test)]
-    pub fn from_sequence(vec: Vec<u8>) -> Self {
-        Self {
-            sequences: vec![vec],
+    pub fn queue_size(&self) -> usize {
+        self.size
+    }
+
+    /// Evicts the oldest output from memory, until at least `len` bytes are freed.
+    /// Chunks of pty output are evicted first, and then lines from the history of the terminal emulator.
+    /// Returns the number of bytes which were freed.
+    pub fn evict(&mut self, len: usize) -> usize {
+        let mut freed = self.evict_queue(len);
+
+        if freed < len {
+            freed += self.emulator.evict_history(len - freed);
         }
+
+        freed
     }
 
-    pub fn filter(&self, buf: &mut Vec<u8>) {
-        for seq in &self.sequences {
-            Self::filter_seq(seq.as_slice(), buf);
+    /// Evicts the oldest chunks from memory, until at least `len` bytes are freed.  The most recent chunk is always retained.
+    /// If a spill is configured, the chunks are prepared for a segment write, and are freed once it completes.
+    /// Returns the number of bytes which were (or will be) freed.
+    fn evict_queue(&mut self, len: usize) -> usize {
+        if self.spill.is_some() && !self.spill_failed {
+            return if self.can_spill() {
+                self.prepare_spill(len)
+            } else {
+                0
+            };
+        }
+
+        let mut freed = 0;
+        while freed < len && self.queue.len() > 1 {
+            if let Some(chunk) = self.pop_memory() {
+                freed += chunk.len();
+            }
         }
+
+        freed
     }
 
-    fn filter_seq(sequence: &[u8], buf: &mut Vec<u8>) {
-        if sequence.is_empty() {
-            return;
+    fn can_spill(&self) -> bool {
+        self.spill.is_some() && !self.spilling && !self.spill_failed
+    }
+
+    /// Prepares a segment write, containing the oldest chunks in memory, until it holds at least `len` bytes.
+    /// The most recent chunk is always retained.  Returns the number of bytes in the segment.
+    fn prepare_spill(&mut self, len: usize) -> usize {
+        let spill = match self.spill {
+            Some(ref mut spill) => spill,
+            None => return 0,
+        };
+
+        let mut size = 0;
+        let mut chunks = Vec::new();
+        for chunk in self.queue.iter().take(self.queue.len().saturating_sub(1)) {
+            if size >= len {
+                break;
+            }
+
+            size += chunk.len();
+            chunks.push(chunk.clone());
         }
 
-        let mut index = 0;
-        let mut seq_index = 0;
+        if let Some(write) = spill.prepare(chunks) {
+            self.pending = Some(write);
+            self.spilling = true;
+        }
 
-        while index <= buf.len() {
-            if seq_index >= sequence.len() {
-                debug!(
-                    "a filtered ansi sequence was matched by the scrollback processor: {:?}",
-                    sequence
-                );
-                debug!(
-                    "the folowing data will be removed: {:?}",
-                    &buf[index - sequence.len()..index]
-                );
+        size
+    }
+
+    fn chunk_count(&self) -> usize {
+        let segments = self.spill.as_ref().map(ScrollbackSpill::len).unwrap_or(0);
+        segments + self.queue.len()
+    }
 
-                buf.drain(index - sequence.len()..index);
-                index -= sequence.len();
-                seq_index = 0;
+    fn exceeds_limit(&self) -> bool {
+        let (size, lines) = match self.spill {
+            Some(ref spill) => (self.size + spill.size(), self.lines + spill.lines()),
+            None => (self.size, self.lines),
+        };
+
+        match self.limit {
+            ScrollbackLimit::Bytes(bytes) => size > bytes,
+            ScrollbackLimit::Lines(limit) => lines > limit,
+        }
+    }
+
+    /// Removes the oldest output, from the spill if available, or from memory
+    fn pop_front(&mut self) {
+        if let Some(ref mut spill) = self.spill {
+            if spill.pop_front().is_some() {
+                return;
             }
+        }
 
-            if index < buf.len()
-                && (sequence[seq_index] == 0u8 || buf[index] == sequence[seq_index])
-            {
-                seq_index += 1;
-            } else {
-                seq_index = 0;
+        self.pop_memory();
+    }
+
+    /// Removes the oldest chunk held in memory
+    fn pop_memory(&mut self) -> Option<OutputChunk> {
+        let chunk = self.queue.pop_front()?;
+
+        // use saturating sub, just in case there was a calculation error
+        self.size = self.size.saturating_sub(chunk.len());
+        self.lines = self
+            .lines
+            .saturating_sub(count_lines(chunk.data.as_slice()));
+
+        Some(chunk)
+    }
+
+    /// Resizes the terminal emulator to the given (cols, rows)
+    pub fn resize(&mut self, dimensions: (u16, u16)) {
+        self.emulator.resize(dimensions);
+    }
+
+    /// Synthesizes a redraw of the current terminal state
+    pub fn snapshot(&self) -> ScrollbackSnapshot {
+        ScrollbackSnapshot {
+            end: self.emulator.end(),
+            redraw: self.emulator.redraw(),
+        }
+    }
+
+    /// Renders the line history and screen of the terminal emulator, as styled lines
+    pub fn lines(&self) -> Vec<String> {
+        self.emulator.lines()
+    }
+
+    /// Lists the raw scrollback, so it can be replayed without holding the buffer lock
+    pub fn replay(&self) -> ScrollbackReplay {
+        let segments = self
+            .spill
+            .as_ref()
+            .map(ScrollbackSpill::segments)
+            .unwrap_or_default();
+
+        ScrollbackReplay {
+            segments,
+            chunks: self.queue.iter().cloned().collect(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn clone_queue(&self) -> VecDeque<OutputChunk> {
+        self.queue.clone()
+    }
+
+    /// Copies up to `len` bytes from the end of the buffer in memory
+    pub fn tail(&self, len: usize) -> Vec<u8> {
+        let mut chunks = Vec::new();
+        let mut remaining = len;
+
+        for chunk in self.queue.iter().rev() {
+            if remaining == 0 {
+                break;
             }
 
-            index += 1;
+            let start = chunk.len().saturating_sub(remaining);
+            chunks.push(&chunk.data[start..]);
+            remaining -= chunk.len() - start;
         }
+
+        chunks.into_iter().rev().flatten().copied().collect()
     }
 }
 
-/// General tests of the ANSI filter utility
+/// Tests of the scrollback buffer
 #[cfg(test)]
-mod tests {
-    use super::AnsiFilter;
+mod buffer_tests {
+    use super::{ScrollbackBuffer, ScrollbackSpill, HOT_WINDOW_LEN, MAX_CHUNK_LEN};
+    use std::{env, path::PathBuf, process};
+    use tab_api::{chunk::OutputChunk, tab::ScrollbackLimit};
+
+    fn buffer(chunks: &[&[u8]]) -> ScrollbackBuffer {
+        let mut buffer = ScrollbackBuffer::new();
+        let mut index = 0;
+
+        for data in chunks {
+            // pad each chunk so they are not concatenated
+            let mut data = data.to_vec();
+            data.resize(MAX_CHUNK_LEN, b'-');
+
+            let len = data.len();
+            buffer.push(OutputChunk { index, data });
+            index += len;
+        }
+
+        buffer
+    }
 
     #[test]
-    fn test_replace() {
-        let filter = AnsiFilter::from_sequence(vec![2, 3]);
+    fn tail_within_chunk() {
+        let mut buffer = ScrollbackBuffer::new();
+        buffer.push(OutputChunk {
+            index: 0,
+            data: b"abcdef".to_vec(),
+        });
+
+        assert_eq!(b"def".to_vec(), buffer.tail(3));
+    }
 
-        let mut buf = vec![1, 2, 3, 4];
-        filter.filter(&mut buf);
+    #[test]
+    fn tail_exceeds_buffer() {
+        let mut buffer = ScrollbackBuffer::new();
+        buffer.push(OutputChunk {
+            index: 0,
+            data: b"abc".to_vec(),
+        });
+
+        assert_eq!(b"abc".to_vec(), buffer.tail(10));
+    }
 
-        assert_eq!(buf, vec![1, 4])
+    #[test]
+    fn tail_across_chunks() {
+        let buffer = buffer(&[b"a", b"b"]);
+
+        let tail = buffer.tail(MAX_CHUNK_LEN + 2);
+        assert_eq!(MAX_CHUNK_LEN + 2, tail.len());
+        assert_eq!(b"--b", &tail[0..3]);
     }
 
     #[test]
-    fn test_replace_first() {
-        let mut buf = vec![1, 2, 3, 4];
+    fn limit_bytes() {
+        let mut buffer = buffer(&[b"a", b"b", b"c"]);
+        buffer.set_limit(ScrollbackLimit::Bytes(2 * MAX_CHUNK_LEN));
+
+        assert_eq!(2 * MAX_CHUNK_LEN, buffer.queue_size());
+        assert_eq!(b'b', buffer.clone_queue()[0].data[0]);
+
+        buffer.push(OutputChunk {
+            index: 3 * MAX_CHUNK_LEN,
+            data: vec![b'd'; MAX_CHUNK_LEN],
+        });
 
-        let filter = AnsiFilter::from_sequence(vec![1, 2]);
-        filter.filter(&mut buf);
+        assert_eq!(2 * MAX_CHUNK_LEN, buffer.queue_size());
+        assert_eq!(b'c', buffer.clone_queue()[0].data[0]);
+    }
+
+    #[test]
+    fn limit_lines() {
+        let mut buffer = buffer(&[b"a\n", b"b\n", b"c\n"]);
+        buffer.set_limit(ScrollbackLimit::Lines(1));
 
-        assert_eq!(buf, vec![3, 4])
+        assert_eq!(1, buffer.clone_queue().len());
+        assert_eq!(MAX_CHUNK_LEN, buffer.queue_size());
+        assert_eq!(b'c', buffer.clone_queue()[0].data[0]);
     }
 
     #[test]
-    fn test_replace_last() {
-        let mut buf = vec![1, 2, 3, 4];
-        let filter = AnsiFilter::from_sequence(vec![4]);
-        filter.filter(&mut buf);
-        assert_eq!(buf, vec![1, 2, 3])
+    fn limit_retains_last_chunk() {
+        let mut buffer = ScrollbackBuffer::new();
+        buffer.set_limit(ScrollbackLimit::Bytes(2));
+        buffer.push(OutputChunk {
+            index: 0,
+            data: b"abcdef".to_vec(),
+        });
+
+        assert_eq!(6, buffer.queue_size());
+        assert_eq!(b"abcdef".to_vec(), buffer.tail(6));
     }
 
     #[test]
-    fn test_wildcard() {
-        let filter = AnsiFilter::from_sequence(vec![2, 0]);
+    fn evict() {
+        let mut buffer = buffer(&[b"a", b"b", b"c"]);
+        let history = buffer.size() - buffer.queue_size();
 
-        let mut buf = vec![1, 2, 3, 4];
-        filter.filter(&mut buf);
+        assert_eq!(2 * MAX_CHUNK_LEN, buffer.evict(MAX_CHUNK_LEN + 1));
+        assert_eq!(MAX_CHUNK_LEN, buffer.queue_size());
+        assert_eq!(MAX_CHUNK_LEN + history, buffer.size());
 
-        assert_eq!(buf, vec![1, 4])
+        // once the pty output is evicted, the line history is trimmed
+        assert!(buffer.evict(MAX_CHUNK_LEN) >= MAX_CHUNK_LEN.min(history));
+        assert_eq!(MAX_CHUNK_LEN, buffer.queue_size());
     }
 
     #[test]
-    fn test_separated_matches() {
-        let filter = AnsiFilter::from_sequence(vec![2, 4]);
+    fn size_includes_history() {
+        let mut buffer = ScrollbackBuffer::new();
+        let lines = "line\r\n".repeat(100);
+        buffer.push(OutputChunk {
+            index: 0,
+            data: lines.clone().into_bytes(),
+        });
+
+        // the screen holds 24 rows, and the rest of the lines are in the history
+        let history = 4 * (100 - 23);
+        assert_eq!(lines.len() + history, buffer.size());
+
+        assert_eq!(history, buffer.evict(history));
+        assert_eq!(lines.len(), buffer.size());
+    }
+
+    fn spill_dir(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("tab-scrollback-{}-{}", process::id(), name))
+    }
+
+    /// Pushes long numbered lines into the buffer, until it contains `len` bytes
+    fn push_lines(buffer: &mut ScrollbackBuffer, len: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut line = 0;
+
+        while data.len() < len {
+            let mut chunk = format!("line {} ", line).into_bytes();
+            chunk.resize(MAX_CHUNK_LEN - 2, b'-');
+            chunk.extend(b"\r\n");
+            line += 1;
 
-        let mut buf = vec![1, 2, 3, 4, 5];
-        filter.filter(&mut buf);
+            buffer.push(OutputChunk {
+                index: data.len(),
+                data: chunk.clone(),
+            });
 
-        assert_eq!(buf, vec![1, 2, 3, 4, 5])
+            if let Some(write) = buffer.take_spill() {
+                buffer.finish_spill(write.run());
+            }
+
+            data.extend(chunk);
+        }
+
+        data
     }
-}
 
-/// Specific sequences that tab must remove from scrollback buffers
-#[cfg(test)]
-mod sequence_tests {
-    use super::ScrollbackManager;
+    fn replay(buffer: &ScrollbackBuffer) -> anyhow::Result<Vec<u8>> {
+        let replay = buffer.replay();
+        let mut data = Vec::new();
+
+        for segment in replay.segments {
+            data.extend(segment.read()?.data);
+        }
+
+        for chunk in replay.chunks {
+            data.extend(chunk.data);
+        }
+
+        Ok(data)
+    }
+
+    #[test]
+    fn spill() -> anyhow::Result<()> {
+        let dir = spill_dir("spill");
+        let mut buffer = ScrollbackBuffer::new();
+        buffer.set_spill(ScrollbackSpill::new(dir.clone()));
+
+        let data = push_lines(&mut buffer, 3 * HOT_WINDOW_LEN);
+
+        assert!(buffer.queue_size() <= HOT_WINDOW_LEN);
+        assert!(!buffer.replay().segments.is_empty());
+        assert!(dir.is_dir());
+        assert_eq!(data, replay(&buffer)?);
+
+        buffer.discard_spill();
+        assert!(!dir.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn spill_limit() -> anyhow::Result<()> {
+        let mut buffer = ScrollbackBuffer::new();
+        buffer.set_spill(ScrollbackSpill::new(spill_dir("spill-limit")));
+        buffer.set_limit(ScrollbackLimit::Bytes(2 * HOT_WINDOW_LEN));
+
+        let data = push_lines(&mut buffer, 4 * HOT_WINDOW_LEN);
+        let replay = replay(&buffer)?;
+
+        assert!(replay.len() <= 2 * HOT_WINDOW_LEN);
+        assert!(data.ends_with(replay.as_slice()));
+
+        Ok(())
+    }
 
     #[test]
-    fn device_status_report() {
-        let filter = ScrollbackManager::ansi_filter();
+    fn spill_output_while_writing() -> anyhow::Result<()> {
+        let mut buffer = ScrollbackBuffer::new();
+        buffer.set_spill(ScrollbackSpill::new(spill_dir("spill-writing")));
 
-        let mut sequence = "start-\x1b[6n-end"
-            .as_bytes()
-            .into_iter()
-            .copied()
-            .collect();
-        filter.filter(&mut sequence);
+        let mut data = push_lines(&mut buffer, 3 * HOT_WINDOW_LEN);
 
-        assert_eq!("start--end".as_bytes(), sequence);
+        // output which arrives during the write is retained, and the evicted chunks remain readable
+        let index = data.len();
+        let chunk = vec![b'-'; HOT_WINDOW_LEN];
+        buffer.push(OutputChunk {
+            index,
+            data: chunk.clone(),
+        });
+        data.extend(chunk);
+
+        let write = buffer.take_spill().expect("spill write");
+        assert_eq!(data, replay(&buffer)?);
+
+        buffer.finish_spill(write.run());
+        assert!(buffer.queue_size() <= 2 * HOT_WINDOW_LEN);
+        assert_eq!(data, replay(&buffer)?);
+
+        Ok(())
     }
 
     #[test]
-    fn operating_system_command() {
-        let filter = ScrollbackManager::ansi_filter();
+    fn spill_failure_retains_output() -> anyhow::Result<()> {
+        // the spill directory can't be created inside a file
+        let file = spill_dir("spill-failure");
+        std::fs::write(&file, b"")?;
+
+        let mut buffer = ScrollbackBuffer::new();
+        buffer.set_spill(ScrollbackSpill::new(file.join("spill")));
+
+        let data = push_lines(&mut buffer, 3 * HOT_WINDOW_LEN);
 
-        let mut sequence = "start-\x1b]10;?\x07-end"
-            .as_bytes()
-            .into_iter()
-            .copied()
-            .collect();
-        filter.filter(&mut sequence);
+        assert!(buffer.replay().segments.is_empty());
+        assert_eq!(data.len(), buffer.queue_size());
+        assert_eq!(data, replay(&buffer)?);
 
-        assert_eq!("start--end".as_bytes(), sequence);
+        std::fs::remove_file(&file)?;
+        Ok(())
     }
 
     #[test]
-    fn bug_open_source() {
-        let filter = ScrollbackManager::ansi_filter();
+    fn without_spill() -> anyhow::Result<()> {
+        let mut buffer = ScrollbackBuffer::new();
+        let data = push_lines(&mut buffer, 2 * HOT_WINDOW_LEN);
 
-        let mut sequence = "open-source".as_bytes().into_iter().copied().collect();
-        filter.filter(&mut sequence);
+        assert!(buffer.replay().segments.is_empty());
+        assert_eq!(data, replay(&buffer)?);
 
-        assert_eq!("open-source".as_bytes(), sequence);
+        Ok(())
     }
 }
@@ -0,0 +1,285 @@
+use crate::prelude::*;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::PathBuf,
+};
+use tab_api::chunk::OutputChunk;
+
+/// Older scrollback for a tab, which has been spilled from memory into compressed segment files.
+/// The segment directory is removed when the spill is dropped.
+#[derive(Debug)]
+pub struct ScrollbackSpill {
+    dir: PathBuf,
+    segments: VecDeque<SpillSegment>,
+    /// The uncompressed number of bytes in the segments
+    size: usize,
+    /// The number of newlines in the segments
+    lines: usize,
+    created: bool,
+}
+
+/// A segment file which has been prepared by the spill.
+/// The write is performed by `run`, which can be called on a blocking thread, without access to the spill.
+#[derive(Debug)]
+pub struct SpillWrite {
+    dir: PathBuf,
+    /// If true, the directory is cleared and created before the segment is written
+    create: bool,
+    path: PathBuf,
+    index: usize,
+    chunks: Vec<OutputChunk>,
+}
+
+/// A compressed segment file, containing contiguous pty output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpillSegment {
+    path: PathBuf,
+    index: usize,
+    len: usize,
+    lines: usize,
+}
+
+impl ScrollbackSpill {
+    /// Constructs a spill which writes segments into the given directory.
+    /// The directory is cleared and created when the first segment is written.
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            segments: VecDeque::new(),
+            size: 0,
+            lines: 0,
+            created: false,
+        }
+    }
+
+    /// Prepares a new segment file containing the chunks, which can be written without access to the spill.
+    /// The chunks must be contiguous.  Returns None if there are no chunks.
+    pub fn prepare(&mut self, chunks: Vec<OutputChunk>) -> Option<SpillWrite> {
+        let index = chunks.first()?.start();
+        let create = !self.created;
+        self.created = true;
+
+        Some(SpillWrite {
+            dir: self.dir.clone(),
+            create,
+            path: self.dir.join(format!("{}.gz", index)),
+            index,
+            chunks,
+        })
+    }
+
+    /// Records a segment file which has been written
+    pub fn push_segment(&mut self, segment: SpillSegment) {
+        self.size += segment.len;
+        self.lines += segment.lines;
+        self.segments.push_back(segment);
+    }
+
+    /// Compresses the chunks into a new segment file.  The chunks must be contiguous.
+    #[cfg(test)]
+    pub fn write(&mut self, chunks: Vec<OutputChunk>) -> io::Result<()> {
+        if let Some(write) = self.prepare(chunks) {
+            let segment = write.run()?;
+            self.push_segment(segment);
+        }
+
+        Ok(())
+    }
+
+    /// Removes the oldest segment file, and returns the segment
+    pub fn pop_front(&mut self) -> Option<SpillSegment> {
+        let segment = self.segments.pop_front()?;
+
+        if let Err(e) = fs::remove_file(&segment.path) {
+            warn!(
+                "failed to remove scrollback segment {}: {}",
+                segment.path.display(),
+                e
+            );
+        }
+
+        self.size = self.size.saturating_sub(segment.len);
+        self.lines = self.lines.saturating_sub(segment.lines);
+
+        Some(segment)
+    }
+
+    /// The segments, from oldest to newest
+    pub fn segments(&self) -> Vec<SpillSegment> {
+        self.segments.iter().cloned().collect()
+    }
+
+    /// The number of segment files
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// The uncompressed number of bytes in the segments
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The number of newlines in the segments
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+}
+
+impl Drop for ScrollbackSpill {
+    fn drop(&mut self) {
+        // the first write may have failed to create the directory
+        if !self.created || !self.dir.exists() {
+            return;
+        }
+
+        if let Err(e) = fs::remove_dir_all(&self.dir) {
+            warn!(
+                "failed to remove scrollback directory {}: {}",
+                self.dir.display(),
+                e
+            );
+        }
+    }
+}
+
+impl SpillWrite {
+    /// Compresses the chunks into the segment file.  Performs blocking disk I/O.
+    pub fn run(self) -> io::Result<SpillSegment> {
+        if self.create {
+            // a previous daemon may have exited without removing the directory
+            if self.dir.exists() {
+                fs::remove_dir_all(&self.dir)?;
+            }
+
+            fs::create_dir_all(&self.dir)?;
+        }
+
+        let file = BufWriter::new(File::create(&self.path)?);
+        let mut encoder = GzEncoder::new(file, Compression::fast());
+
+        let mut len = 0;
+        let mut lines = 0;
+        for chunk in self.chunks.iter() {
+            encoder.write_all(chunk.data.as_slice())?;
+            len += chunk.len();
+            lines += count_lines(chunk.data.as_slice());
+        }
+
+        encoder.finish()?.flush()?;
+
+        Ok(SpillSegment {
+            path: self.path,
+            index: self.index,
+            len,
+            lines,
+        })
+    }
+}
+
+impl SpillSegment {
+    /// The index of the first byte of output in the segment
+    pub fn start(&self) -> usize {
+        self.index
+    }
+
+    /// The uncompressed number of bytes in the segment
+    pub fn size(&self) -> usize {
+        self.len
+    }
+
+    /// Removes a segment file which was never recorded in the spill
+    pub fn discard(self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!(
+                "failed to remove scrollback segment {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+
+    /// Decompresses the segment from disk.
+    /// Fails if the segment has been evicted since it was listed.
+    pub fn read(&self) -> io::Result<OutputChunk> {
+        let file = BufReader::new(File::open(&self.path)?);
+        let mut decoder = GzDecoder::new(file);
+
+        let mut data = Vec::with_capacity(self.len);
+        decoder.read_to_end(&mut data)?;
+
+        Ok(OutputChunk {
+            index: self.index,
+            data,
+        })
+    }
+}
+
+pub fn count_lines(data: &[u8]) -> usize {
+    data.iter().filter(|byte| **byte == b'\n').count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScrollbackSpill;
+    use std::{env, path::PathBuf, process};
+    use tab_api::chunk::OutputChunk;
+
+    fn spill_dir(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("tab-spill-{}-{}", process::id(), name))
+    }
+
+    fn chunk(index: usize, data: &[u8]) -> OutputChunk {
+        OutputChunk {
+            index,
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn write_read() -> anyhow::Result<()> {
+        let mut spill = ScrollbackSpill::new(spill_dir("write-read"));
+        spill.write(vec![chunk(4, b"ab\n"), chunk(7, b"cd\n")])?;
+
+        assert_eq!(1, spill.len());
+        assert_eq!(6, spill.size());
+        assert_eq!(2, spill.lines());
+
+        let segments = spill.segments();
+        assert_eq!(chunk(4, b"ab\ncd\n"), segments[0].read()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn pop_front() -> anyhow::Result<()> {
+        let mut spill = ScrollbackSpill::new(spill_dir("pop-front"));
+        spill.write(vec![chunk(0, b"ab\n")])?;
+        spill.write(vec![chunk(3, b"cde")])?;
+
+        let segment = spill.pop_front().expect("segment");
+        assert_eq!(3, segment.len);
+        assert!(segment.read().is_err());
+
+        assert_eq!(1, spill.len());
+        assert_eq!(3, spill.size());
+        assert_eq!(0, spill.lines());
+
+        Ok(())
+    }
+
+    #[test]
+    fn drop_removes_dir() -> anyhow::Result<()> {
+        let dir = spill_dir("drop");
+        let mut spill = ScrollbackSpill::new(dir.clone());
+        spill.write(vec![chunk(0, b"ab")])?;
+        assert!(dir.is_dir());
+
+        drop(spill);
+        assert!(!dir.exists());
+
+        Ok(())
+    }
+}
@@ -1,3 +1,6 @@
 pub mod assignment;
 pub mod pty;
+pub mod resize;
+pub mod scrollback;
+pub mod session;
 pub mod tab;
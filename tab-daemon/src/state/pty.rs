@@ -1,9 +1,14 @@
-use crate::service::pty::scrollback::ScrollbackBuffer;
+use crate::service::pty::scrollback::{
+    write_spill, ScrollbackBuffer, ScrollbackReplay, ScrollbackSnapshot,
+};
 
 use std::sync::Arc;
-use tab_api::{chunk::OutputChunk, tab::TabId};
+use tab_api::tab::TabId;
 use tokio::sync::Mutex;
 
+#[cfg(test)]
+use tab_api::chunk::OutputChunk;
+
 /// The state of the pty connection, either None, or Assigned
 #[derive(Debug, Clone)]
 pub enum PtyState {
@@ -34,14 +39,6 @@ impl PtyState {
             false
         }
     }
-
-    /// Unwraps the state, returning a TabId if assigned, or panic if unassigned
-    pub fn unwrap(&self) -> TabId {
-        match self {
-            PtyState::None => panic!("Unwrap called on a PtyState::None value"),
-            PtyState::Assigned(id) => *id,
-        }
-    }
 }
 
 /// A wrapper around a scrollback buffer that can be cheaply cloned, and transmitted over broadcast channels.
@@ -68,8 +65,50 @@ impl PtyScrollback {
         self.scrollback.lock().await.push(chunk);
     }
 
+    #[cfg(test)]
     pub async fn scrollback(&self) -> impl Iterator<Item = OutputChunk> {
         let scrollback = self.scrollback.lock().await.clone_queue();
         scrollback.into_iter()
     }
+
+    /// Synthesizes a redraw of the current terminal state, for a newly subscribed client
+    pub async fn snapshot(&self) -> ScrollbackSnapshot {
+        self.scrollback.lock().await.snapshot()
+    }
+
+    /// Renders the line history and screen of the tab, as styled lines
+    pub async fn lines(&self) -> Vec<String> {
+        self.scrollback.lock().await.lines()
+    }
+
+    /// Lists the raw scrollback, including segments which have been spilled to disk
+    pub async fn replay(&self) -> ScrollbackReplay {
+        self.scrollback.lock().await.replay()
+    }
+
+    /// The number of bytes held in memory by the scrollback buffer, including the line history of the terminal emulator
+    pub async fn size(&self) -> usize {
+        self.scrollback.lock().await.size()
+    }
+
+    /// Evicts at least `len` bytes of the oldest output from memory, if available.  Returns the number of bytes freed.
+    /// Spilled output is written to disk after the buffer lock is released.
+    pub async fn evict(&self, len: usize) -> usize {
+        let (freed, write) = {
+            let mut buffer = self.scrollback.lock().await;
+            let freed = buffer.evict(len);
+            (freed, buffer.take_spill())
+        };
+
+        if let Some(write) = write {
+            write_spill(self.scrollback.clone(), write).await;
+        }
+
+        freed
+    }
+
+    /// Copies up to `len` bytes from the end of the scrollback buffer
+    pub async fn tail(&self, len: usize) -> Vec<u8> {
+        self.scrollback.lock().await.tail(len)
+    }
 }
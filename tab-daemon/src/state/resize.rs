@@ -0,0 +1,241 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tab_api::{config::ResizePolicy, tab::TabId};
+
+static NEXT_CONNECTION: AtomicUsize = AtomicUsize::new(0);
+
+/// Identifies a CLI connection to the daemon
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConnectionId(pub usize);
+
+impl ConnectionId {
+    /// Allocates a unique identifier for a new connection
+    pub fn next() -> Self {
+        Self(NEXT_CONNECTION.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// The dimensions of an attached client, and the sequence number of it's latest activity.
+#[derive(Debug, Clone)]
+struct ClientSize {
+    dimensions: (u16, u16),
+    active: u64,
+}
+
+/// Tracks the dimensions of each attached CLI connection, and computes tab sizes using the resize policy.
+/// A connection can be attached to several tabs, when it displays them in panes.
+///
+/// Methods return the tabs which need to be resized, if the computed size has changed.
+#[derive(Debug)]
+pub struct ResizeState {
+    policy: ResizePolicy,
+    clients: HashMap<(ConnectionId, TabId), ClientSize>,
+    sizes: HashMap<TabId, (u16, u16)>,
+    activity: u64,
+}
+
+impl ResizeState {
+    pub fn new(policy: ResizePolicy) -> Self {
+        Self {
+            policy,
+            clients: HashMap::new(),
+            sizes: HashMap::new(),
+            activity: 0,
+        }
+    }
+
+    /// Attaches the connection to the tab with the given (cols, rows), or updates the dimensions.
+    pub fn resize(
+        &mut self,
+        connection: ConnectionId,
+        tab: TabId,
+        dimensions: (u16, u16),
+    ) -> Option<(TabId, (u16, u16))> {
+        let active = self.touch();
+        let client = ClientSize { dimensions, active };
+
+        self.clients.insert((connection, tab), client);
+        self.update(tab)
+    }
+
+    /// Records input from the connection.  Only affects the tab size with `ResizePolicy::Recent`.
+    pub fn input(&mut self, connection: ConnectionId, tab: TabId) -> Option<(TabId, (u16, u16))> {
+        if self.policy != ResizePolicy::Recent {
+            return None;
+        }
+
+        let active = self.touch();
+        match self.clients.get_mut(&(connection, tab)) {
+            Some(client) => client.active = active,
+            None => return None,
+        }
+
+        self.update(tab)
+    }
+
+    /// Detaches the connection from the tab, if it is attached.
+    pub fn detach(&mut self, connection: ConnectionId, tab: TabId) -> Option<(TabId, (u16, u16))> {
+        self.clients.remove(&(connection, tab))?;
+        self.update(tab)
+    }
+
+    /// Detaches the connection from all tabs.
+    pub fn disconnect(&mut self, connection: ConnectionId) -> Vec<(TabId, (u16, u16))> {
+        let tabs: Vec<TabId> = self
+            .clients
+            .keys()
+            .filter(|(client, _)| *client == connection)
+            .map(|(_, tab)| *tab)
+            .collect();
+
+        let mut resized = Vec::new();
+        for tab in tabs {
+            self.clients.remove(&(connection, tab));
+            resized.extend(self.update(tab));
+        }
+
+        resized
+    }
+
+    fn touch(&mut self) -> u64 {
+        self.activity += 1;
+        self.activity
+    }
+
+    fn update(&mut self, tab: TabId) -> Option<(TabId, (u16, u16))> {
+        match self.compute(tab) {
+            Some(size) => {
+                if self.sizes.insert(tab, size) == Some(size) {
+                    return None;
+                }
+
+                Some((tab, size))
+            }
+            None => {
+                // the next client to attach always resizes the tab
+                self.sizes.remove(&tab);
+                None
+            }
+        }
+    }
+
+    fn compute(&self, tab: TabId) -> Option<(u16, u16)> {
+        let clients = self
+            .clients
+            .iter()
+            .filter(|((_, client_tab), _)| *client_tab == tab)
+            .map(|(_, client)| client);
+
+        match self.policy {
+            ResizePolicy::Smallest => clients
+                .map(|client| client.dimensions)
+                .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1))),
+            ResizePolicy::Largest => clients
+                .map(|client| client.dimensions)
+                .reduce(|a, b| (a.0.max(b.0), a.1.max(b.1))),
+            ResizePolicy::Recent => clients
+                .max_by_key(|client| client.active)
+                .map(|client| client.dimensions),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConnectionId, ResizeState};
+    use tab_api::{config::ResizePolicy, tab::TabId};
+
+    #[test]
+    fn single_client() {
+        let mut state = ResizeState::new(ResizePolicy::Smallest);
+
+        let resized = state.resize(ConnectionId(0), TabId(0), (80, 24));
+        assert_eq!(Some((TabId(0), (80, 24))), resized);
+
+        let resized = state.resize(ConnectionId(0), TabId(0), (80, 24));
+        assert_eq!(None, resized);
+
+        let resized = state.resize(ConnectionId(0), TabId(0), (100, 30));
+        assert_eq!(Some((TabId(0), (100, 30))), resized);
+    }
+
+    #[test]
+    fn smallest() {
+        let mut state = ResizeState::new(ResizePolicy::Smallest);
+
+        state.resize(ConnectionId(0), TabId(0), (80, 40));
+        let resized = state.resize(ConnectionId(1), TabId(0), (100, 30));
+        assert_eq!(Some((TabId(0), (80, 30))), resized);
+
+        let resized = state.disconnect(ConnectionId(0));
+        assert_eq!(vec![(TabId(0), (100, 30))], resized);
+    }
+
+    #[test]
+    fn largest() {
+        let mut state = ResizeState::new(ResizePolicy::Largest);
+
+        state.resize(ConnectionId(0), TabId(0), (80, 40));
+        let resized = state.resize(ConnectionId(1), TabId(0), (100, 30));
+        assert_eq!(Some((TabId(0), (100, 40))), resized);
+
+        let resized = state.detach(ConnectionId(1), TabId(0));
+        assert_eq!(Some((TabId(0), (80, 40))), resized);
+    }
+
+    #[test]
+    fn recent() {
+        let mut state = ResizeState::new(ResizePolicy::Recent);
+
+        state.resize(ConnectionId(0), TabId(0), (80, 40));
+        let resized = state.resize(ConnectionId(1), TabId(0), (100, 30));
+        assert_eq!(Some((TabId(0), (100, 30))), resized);
+
+        let resized = state.input(ConnectionId(0), TabId(0));
+        assert_eq!(Some((TabId(0), (80, 40))), resized);
+
+        let resized = state.input(ConnectionId(0), TabId(0));
+        assert_eq!(None, resized);
+    }
+
+    #[test]
+    fn input_ignored() {
+        let mut state = ResizeState::new(ResizePolicy::Smallest);
+
+        state.resize(ConnectionId(0), TabId(0), (80, 40));
+        state.resize(ConnectionId(1), TabId(0), (100, 30));
+
+        assert_eq!(None, state.input(ConnectionId(1), TabId(0)));
+    }
+
+    #[test]
+    fn multiple_tabs() {
+        let mut state = ResizeState::new(ResizePolicy::Smallest);
+
+        state.resize(ConnectionId(0), TabId(0), (100, 40));
+        state.resize(ConnectionId(1), TabId(0), (80, 30));
+
+        // a connection which displays tabs in panes is attached to each tab
+        let resized = state.resize(ConnectionId(1), TabId(1), (40, 30));
+        assert_eq!(Some((TabId(1), (40, 30))), resized);
+
+        let resized = state.detach(ConnectionId(1), TabId(0));
+        assert_eq!(Some((TabId(0), (100, 40))), resized);
+
+        let resized = state.disconnect(ConnectionId(1));
+        assert!(resized.is_empty());
+    }
+
+    #[test]
+    fn reattach() {
+        let mut state = ResizeState::new(ResizePolicy::Smallest);
+
+        state.resize(ConnectionId(0), TabId(0), (80, 24));
+        assert!(state.disconnect(ConnectionId(0)).is_empty());
+
+        let resized = state.resize(ConnectionId(1), TabId(0), (80, 24));
+        assert_eq!(Some((TabId(0), (80, 24))), resized);
+    }
+}
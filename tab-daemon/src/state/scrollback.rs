@@ -0,0 +1,33 @@
+use crate::state::pty::PtyScrollback;
+
+use lifeline::impl_storage_clone;
+use std::{collections::HashMap, sync::Arc};
+use tab_api::tab::TabId;
+use tokio::sync::Mutex;
+
+/// Handles to the scrollback buffers of the running tabs, shared by the daemon services.
+///
+/// Stored as a resource on the `ListenerBus`, and maintained by the `ScrollbackRegistryService`.
+#[derive(Debug, Clone, Default)]
+pub struct ScrollbackRegistry {
+    handles: Arc<Mutex<HashMap<TabId, PtyScrollback>>>,
+}
+
+impl_storage_clone!(ScrollbackRegistry);
+
+impl ScrollbackRegistry {
+    /// Registers the scrollback buffer of the tab, replacing any previous buffer
+    pub async fn insert(&self, id: TabId, scrollback: PtyScrollback) {
+        self.handles.lock().await.insert(id, scrollback);
+    }
+
+    /// Removes the scrollback buffer of the tab
+    pub async fn remove(&self, id: TabId) {
+        self.handles.lock().await.remove(&id);
+    }
+
+    /// Clones the handles, so the buffers can be accessed without holding the registry lock
+    pub async fn handles(&self) -> HashMap<TabId, PtyScrollback> {
+        self.handles.lock().await.clone()
+    }
+}
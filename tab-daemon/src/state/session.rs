@@ -0,0 +1,287 @@
+use lifeline::impl_storage_clone;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tab_api::tab::{TabId, TabMetadata};
+
+static TABS_FILE: &str = "tabs.yml";
+static SCROLLBACK_EXTENSION: &str = "scrollback";
+static HANDOFF_FILE: &str = "handoff";
+
+/// The persisted tabs and scrollback tails, written to the session directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionSnapshot {
+    pub tabs: Vec<TabMetadata>,
+    pub scrollback: HashMap<TabId, Vec<u8>>,
+}
+
+/// Persists tab metadata & scrollback into the session directory, so tabs can be restored when the daemon restarts.
+///
+/// The default store is disabled, and never touches the filesystem.
+#[derive(Debug, Clone, Default)]
+pub struct SessionStore {
+    dir: Option<PathBuf>,
+    restored: Arc<Mutex<HashMap<TabId, Vec<u8>>>>,
+}
+
+impl_storage_clone!(SessionStore);
+
+impl SessionStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir: Some(dir),
+            restored: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Whether the store persists sessions to the filesystem
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    /// Loads the persisted tabs.  The restored scrollback is held until it is taken by the tab's pty connection.
+    pub fn restore(&self) -> anyhow::Result<Vec<TabMetadata>> {
+        let dir = match self.dir {
+            Some(ref dir) => dir,
+            None => return Ok(Vec::new()),
+        };
+
+        let tabs_file = dir.join(TABS_FILE);
+        if !tabs_file.is_file() {
+            return Ok(Vec::new());
+        }
+
+        let reader = BufReader::new(File::open(tabs_file)?);
+        let tabs: Vec<TabMetadata> = serde_yaml::from_reader(reader)?;
+
+        let mut restored = self.restored.lock().unwrap();
+        for tab in tabs.iter() {
+            let path = Self::scrollback_file(dir, tab.id);
+            if path.is_file() {
+                restored.insert(tab.id, std::fs::read(path)?);
+            }
+        }
+
+        Ok(tabs)
+    }
+
+    /// Takes the restored scrollback for the tab, which should be replayed before the shell output.
+    pub fn take_scrollback(&self, id: TabId) -> Option<Vec<u8>> {
+        self.restored.lock().unwrap().remove(&id)
+    }
+
+    /// Copies the restored scrollback for the tab, if it has not yet been taken.
+    pub fn restored_scrollback(&self, id: TabId) -> Option<Vec<u8>> {
+        self.restored.lock().unwrap().get(&id).cloned()
+    }
+
+    /// Marks the session as handed off.  The ptys are still running, and will reconnect to the next daemon.
+    pub fn mark_handoff(&self) -> anyhow::Result<()> {
+        if let Some(ref dir) = self.dir {
+            std::fs::create_dir_all(dir)?;
+            File::create(dir.join(HANDOFF_FILE))?;
+        }
+
+        Ok(())
+    }
+
+    /// Determines whether the previous daemon handed off the session, and clears the mark.
+    pub fn take_handoff(&self) -> bool {
+        let path = match self.dir {
+            Some(ref dir) => dir.join(HANDOFF_FILE),
+            None => return false,
+        };
+
+        std::fs::remove_file(path).is_ok()
+    }
+
+    /// Writes the snapshot, replacing the previous session.
+    pub fn save(&self, snapshot: &SessionSnapshot) -> anyhow::Result<()> {
+        let dir = match self.dir {
+            Some(ref dir) => dir,
+            None => return Ok(()),
+        };
+
+        // if the data directory has been removed, the daemon is shutting down.  don't re-create it.
+        if dir.parent().map(|parent| !parent.is_dir()).unwrap_or(false) {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(dir)?;
+
+        for (id, data) in snapshot.scrollback.iter() {
+            Self::write_file(Self::scrollback_file(dir, *id).as_path(), |file| {
+                std::io::Write::write_all(file, data.as_slice())?;
+                Ok(())
+            })?;
+        }
+
+        Self::write_file(dir.join(TABS_FILE).as_path(), |file| {
+            serde_yaml::to_writer(file, &snapshot.tabs)?;
+            Ok(())
+        })?;
+
+        // remove the scrollback of closed tabs
+        let retained: HashSet<PathBuf> = snapshot
+            .scrollback
+            .keys()
+            .map(|id| Self::scrollback_file(dir, *id))
+            .collect();
+
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_scrollback = path
+                .extension()
+                .map(|ext| ext == SCROLLBACK_EXTENSION)
+                .unwrap_or(false);
+
+            if is_scrollback && !retained.contains(&path) {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn scrollback_file(dir: &Path, id: TabId) -> PathBuf {
+        dir.join(format!("{}.{}", id.0, SCROLLBACK_EXTENSION))
+    }
+
+    /// Writes the file atomically, so a daemon crash cannot corrupt the previous session.
+    fn write_file<F>(path: &Path, write: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(&mut BufWriter<File>) -> anyhow::Result<()>,
+    {
+        let tmp = path.with_extension("tmp");
+
+        {
+            let mut writer = BufWriter::new(File::create(tmp.as_path())?);
+            write(&mut writer)?;
+            std::io::Write::flush(&mut writer)?;
+        }
+
+        std::fs::rename(tmp, path)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SessionSnapshot, SessionStore};
+    use std::{collections::HashMap, path::PathBuf};
+    use tab_api::tab::{TabId, TabMetadata};
+
+    fn test_dir(name: &str) -> PathBuf {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("tab-session-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(dir.as_path()).ok();
+        dir
+    }
+
+    fn scrollback(id: u16, data: &[u8]) -> HashMap<TabId, Vec<u8>> {
+        let mut map = HashMap::new();
+        map.insert(TabId(id), data.to_vec());
+        map
+    }
+
+    fn tab(id: u16) -> TabMetadata {
+        TabMetadata {
+            id: TabId(id),
+            name: format!("tab-{}/", id),
+            doc: None,
+            dimensions: (80, 24),
+            env: HashMap::new(),
+            shell: "bash".into(),
+            dir: "/".into(),
+            command: None,
+            selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
+        }
+    }
+
+    #[test]
+    fn disabled() -> anyhow::Result<()> {
+        let store = SessionStore::default();
+        let snapshot = SessionSnapshot {
+            tabs: vec![tab(0)],
+            scrollback: HashMap::new(),
+        };
+
+        store.save(&snapshot)?;
+        assert_eq!(Vec::<TabMetadata>::new(), store.restore()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn save_restore() -> anyhow::Result<()> {
+        let dir = test_dir("save_restore");
+        let store = SessionStore::new(dir.clone());
+
+        let snapshot = SessionSnapshot {
+            tabs: vec![tab(0), tab(1)],
+            scrollback: scrollback(0, b"scrollback"),
+        };
+        store.save(&snapshot)?;
+
+        let restored = SessionStore::new(dir.clone());
+        assert_eq!(snapshot.tabs, restored.restore()?);
+        assert_eq!(
+            Some(b"scrollback".to_vec()),
+            restored.take_scrollback(TabId(0))
+        );
+        assert_eq!(None, restored.take_scrollback(TabId(0)));
+        assert_eq!(None, restored.take_scrollback(TabId(1)));
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn handoff() -> anyhow::Result<()> {
+        let dir = test_dir("handoff");
+        let store = SessionStore::new(dir.clone());
+
+        store.save(&SessionSnapshot::default())?;
+        assert!(!store.take_handoff());
+
+        store.mark_handoff()?;
+        assert!(store.take_handoff());
+        assert!(!store.take_handoff());
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn save_removes_closed() -> anyhow::Result<()> {
+        let dir = test_dir("save_removes_closed");
+        let store = SessionStore::new(dir.clone());
+
+        store.save(&SessionSnapshot {
+            tabs: vec![tab(0)],
+            scrollback: scrollback(0, b"scrollback"),
+        })?;
+
+        store.save(&SessionSnapshot::default())?;
+
+        let restored = SessionStore::new(dir.clone());
+        assert_eq!(Vec::<TabMetadata>::new(), restored.restore()?);
+        assert_eq!(1, std::fs::read_dir(dir.as_path())?.count());
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -1,16 +1,22 @@
 use std::collections::HashMap;
-use tab_api::tab::{TabId, TabMetadata};
+use tab_api::tab::{ExitedTab, TabId, TabMetadata};
 
 type TabsMap = HashMap<TabId, TabMetadata>;
+type ExitedMap = HashMap<TabId, ExitedTab>;
 
-/// The currently running tabs
+/// The currently running tabs, and the tabs whose shell has exited
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct TabsState {
     pub tabs: TabsMap,
+    /// Tabs whose shell exited on its own, which have not yet been dismissed
+    pub exited: ExitedMap,
 }
 
 impl TabsState {
-    pub fn new(tabs: &TabsMap) -> Self {
-        Self { tabs: tabs.clone() }
+    pub fn new(tabs: &TabsMap, exited: &ExitedMap) -> Self {
+        Self {
+            tabs: tabs.clone(),
+            exited: exited.clone(),
+        }
     }
 }
@@ -1,4 +1,6 @@
+mod connection;
 mod main;
 mod pty;
+pub use connection::*;
 pub use main::*;
 pub use pty::*;
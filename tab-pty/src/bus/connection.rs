@@ -0,0 +1,19 @@
+use crate::prelude::*;
+use postage::{broadcast, mpsc};
+use tab_api::pty::{PtyWebsocketRequest, PtyWebsocketResponse};
+use tab_websocket::bus::WebsocketMessageBus;
+
+lifeline_bus!(pub struct ConnectionBus);
+
+impl Message<ConnectionBus> for PtyWebsocketRequest {
+    type Channel = broadcast::Sender<Self>;
+}
+
+impl Message<ConnectionBus> for PtyWebsocketResponse {
+    type Channel = mpsc::Sender<Self>;
+}
+
+impl WebsocketMessageBus for ConnectionBus {
+    type Send = PtyWebsocketResponse;
+    type Recv = PtyWebsocketRequest;
+}
@@ -4,7 +4,7 @@ use tab_api::{
     config::DaemonConfig,
     pty::{PtyWebsocketRequest, PtyWebsocketResponse},
 };
-use tab_websocket::{bus::WebsocketMessageBus, resource::connection::WebsocketResource};
+use tab_websocket::resource::connection::WebsocketResource;
 
 lifeline_bus!(pub struct MainBus);
 
@@ -22,7 +22,3 @@ impl Message<MainBus> for MainShutdown {
 
 impl Resource<MainBus> for DaemonConfig {}
 impl Resource<MainBus> for WebsocketResource {}
-impl WebsocketMessageBus for MainBus {
-    type Send = PtyWebsocketResponse;
-    type Recv = PtyWebsocketRequest;
-}
@@ -77,7 +77,7 @@ async fn spawn() -> anyhow::Result<(
     let bus = MainBus::default();
     bus.capacity::<PtyWebsocketRequest>(64)?;
 
-    let ws_url = format!("ws://127.0.0.1:{}/pty", config.port);
+    let ws_url = config.websocket_url("/pty");
     let websocket = tab_websocket::connect_authorized(ws_url, config.auth_token.clone()).await?;
     bus.store_resource(WebsocketResource(websocket));
     bus.store_resource(config);
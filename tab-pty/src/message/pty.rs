@@ -1,6 +1,9 @@
 use lifeline::impl_storage_clone;
 use std::{collections::HashMap, path::PathBuf};
-use tab_api::chunk::{InputChunk, OutputChunk};
+use tab_api::{
+    chunk::{InputChunk, OutputChunk},
+    tab::TabExit,
+};
 
 /// Terminates the process, websocket connection, and via cancellation the connected PTY shell session
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -34,7 +37,8 @@ pub enum PtyRequest {
 #[derive(Debug, Clone)]
 pub enum PtyResponse {
     Output(OutputChunk),
-    Terminated,
+    /// The shell has exited, with the given status
+    Terminated(TabExit),
 }
 
 /// Describes options which can be set for the launched shell process
@@ -50,6 +54,10 @@ pub struct PtyOptions {
     pub working_directory: PathBuf,
     /// Environment variables to set for the launched process.
     pub env: HashMap<String, String>,
+    /// A command which is typed into the shell when it starts, and each time it is restarted
+    pub startup_command: Option<String>,
+    /// If true, the shell can be restarted (or the tab closed) after it exits
+    pub remain_on_exit: bool,
 }
 
 impl_storage_clone!(PtyOptions);
@@ -12,6 +12,7 @@ use tab_api::{
     config::history_path,
     env::is_raw_mode,
     pty::{PtyWebsocketRequest, PtyWebsocketResponse},
+    tab::TabExit,
 };
 use time::Duration;
 use tokio::time;
@@ -143,6 +144,8 @@ impl ClientService {
                         args,
                         working_directory: working_directory.clone(),
                         env,
+                        startup_command: create.command.clone(),
+                        remain_on_exit: create.remain_on_exit,
                     };
 
                     pty_bus.store_resource::<PtyOptions>(options);
@@ -154,11 +157,15 @@ impl ClientService {
                 }
                 PtyWebsocketRequest::Input(_) => {}
                 PtyWebsocketRequest::Resize(_) => {}
+                // reconnects are handled by the ConnectionService
+                PtyWebsocketRequest::Reconnect => {}
                 PtyWebsocketRequest::Terminate => {
                     // in case we somehow get a pty termination request, but don't have a session running,
                     // send a main shutdown message
                     time::sleep(Duration::from_millis(2000)).await;
-                    tx.send(PtyWebsocketResponse::Stopped).await.ok();
+                    tx.send(PtyWebsocketResponse::Stopped(TabExit::default()))
+                        .await
+                        .ok();
                     tx_shutdown.send(MainShutdown {}).await?;
                 }
             }
@@ -241,7 +248,9 @@ impl ClientSessionService {
                     //   we'll forcefully kill it.
                     time::sleep(Duration::from_millis(1000)).await;
                     warn!("Shell process did not shut down within the 1 second timeout.");
-                    tx_websocket.send(PtyWebsocketResponse::Stopped).await?;
+                    tx_websocket
+                        .send(PtyWebsocketResponse::Stopped(TabExit::default()))
+                        .await?;
                     tx_shutdown.send(PtyShutdown {}).await?;
                 }
                 _ => {}
@@ -261,10 +270,10 @@ impl ClientSessionService {
                 PtyResponse::Output(out) => {
                     tx.send(PtyWebsocketResponse::Output(out)).await?;
                 }
-                PtyResponse::Terminated => {
+                PtyResponse::Terminated(exit) => {
                     debug!("pty child process terminated");
 
-                    tx.send(PtyWebsocketResponse::Stopped).await?;
+                    tx.send(PtyWebsocketResponse::Stopped(exit)).await?;
 
                     // this sleep is not visible to the user
                     time::sleep(Duration::from_millis(100)).await;
@@ -348,6 +357,7 @@ mod tests {
     use postage::{sink::Sink, stream::Stream};
     use tab_api::{
         pty::{PtyWebsocketRequest, PtyWebsocketResponse},
+        tab::TabExit,
         tab::TabId,
         tab::TabMetadata,
     };
@@ -373,7 +383,16 @@ mod tests {
             env: HashMap::new(),
             shell: "/usr/bin/env sh".into(),
             dir: current_dir.to_string_lossy().into(),
+            command: None,
             selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
         }))
         .await?;
 
@@ -388,7 +407,16 @@ mod tests {
                     env: HashMap::new(),
                     shell: "/usr/bin/env sh".into(),
                     dir: current_dir.to_string_lossy().into(),
+                    command: None,
                     selected: 0,
+                    attached: 0,
+                    scrollback: None,
+                    record: false,
+                    remain_on_exit: false,
+                    log: None,
+                    monitor: None,
+                    notify: None,
+                    alert: None,
                 })),
                 created
             );
@@ -397,6 +425,56 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn launch_command() -> anyhow::Result<()> {
+        let bus = MainBus::default();
+        let _service = ClientService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<PtyWebsocketRequest>()?;
+        let mut rx = bus.rx::<PtyWebsocketResponse>()?;
+
+        let current_dir = std::env::current_dir().unwrap();
+        tx.send(PtyWebsocketRequest::Init(TabMetadata {
+            id: TabId(0),
+            name: "name".into(),
+            doc: None,
+            dimensions: (80, 24),
+            env: HashMap::new(),
+            shell: "sh".into(),
+            dir: current_dir.to_string_lossy().into(),
+            command: Some("echo startup-$((1 + 2))".into()),
+            selected: 0,
+            attached: 0,
+            scrollback: None,
+            record: false,
+            remain_on_exit: false,
+            log: None,
+            monitor: None,
+            notify: None,
+            alert: None,
+        }))
+        .await?;
+
+        assert_completes!(
+            async move {
+                let mut output = String::new();
+                while let Some(msg) = rx.recv().await {
+                    if let PtyWebsocketResponse::Output(chunk) = msg {
+                        output += std::str::from_utf8(chunk.data.as_slice()).unwrap_or("");
+                        if output.contains("startup-3") {
+                            break;
+                        }
+                    }
+                }
+
+                assert!(output.contains("startup-3"));
+            },
+            2000
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn terminate_escape() -> anyhow::Result<()> {
         let bus = MainBus::default();
@@ -420,7 +498,7 @@ mod tests {
 
         assert_completes!(async {
             let msg = rx.recv().await;
-            assert_eq!(Some(PtyWebsocketResponse::Stopped), msg)
+            assert_eq!(Some(PtyWebsocketResponse::Stopped(TabExit::default())), msg)
         });
 
         assert_completes!(async {
@@ -438,6 +516,7 @@ mod client_session_tests {
     use postage::{sink::Sink, stream::Stream};
     use tab_api::{
         chunk::InputChunk, chunk::OutputChunk, pty::PtyWebsocketRequest, pty::PtyWebsocketResponse,
+        tab::TabExit,
     };
     use tokio::time;
 
@@ -458,6 +537,8 @@ mod client_session_tests {
             command: "/usr/bin/env sh".to_string(),
             args: vec![],
             env: HashMap::new(),
+            startup_command: None,
+            remain_on_exit: false,
         }
     }
     #[tokio::test]
@@ -536,7 +617,7 @@ mod client_session_tests {
 
         assert_completes!(async {
             let msg = rx_websocket.recv().await;
-            assert_eq!(Some(PtyWebsocketResponse::Stopped), msg)
+            assert_eq!(Some(PtyWebsocketResponse::Stopped(TabExit::default())), msg)
         });
 
         assert_completes!(async {
@@ -583,11 +664,11 @@ mod client_session_tests {
         let mut rx = bus.rx::<PtyWebsocketResponse>()?;
         let mut rx_shutdown = bus.rx::<PtyShutdown>()?;
 
-        tx.send(PtyResponse::Terminated).await?;
+        tx.send(PtyResponse::Terminated(TabExit::code(3))).await?;
 
         assert_completes!(async move {
             let msg = rx.recv().await;
-            assert_eq!(Some(PtyWebsocketResponse::Stopped), msg);
+            assert_eq!(Some(PtyWebsocketResponse::Stopped(TabExit::code(3))), msg);
         });
 
         assert_times_out!(
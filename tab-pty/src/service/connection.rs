@@ -0,0 +1,203 @@
+use crate::{message::pty::MainShutdown, prelude::*};
+
+use anyhow::bail;
+use lifeline::dyn_bus::DynBus;
+use std::time::{Duration, Instant};
+use tab_api::{
+    config::{is_running, load_daemon_file, DaemonConfig},
+    pty::{PtyWebsocketRequest, PtyWebsocketResponse},
+    tab::TabMetadata,
+};
+use tab_websocket::{bus::WebsocketConnectionBus, resource::connection::WebsocketResource};
+use tokio::{select, time};
+
+/// How long the pty waits for a daemon, after the connection is lost.
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(300);
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_millis(100);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(1);
+
+/// How a websocket connection to the daemon ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Disconnect {
+    /// The pty is shutting down
+    Closed,
+    /// The connection was lost unexpectedly, e.g. the daemon crashed
+    Dropped,
+    /// The daemon requested a reconnect, and is handing off the tabs to the next daemon
+    Handoff,
+}
+
+/// Maintains the websocket connection to the daemon.
+/// When the daemon is upgraded, or the connection is lost, keeps the shell running and reconnects with backoff.
+pub struct ConnectionService {
+    _run: Lifeline,
+}
+
+impl Service for ConnectionService {
+    type Bus = MainBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let websocket = bus.resource::<WebsocketResource>()?;
+        let config = bus.resource::<DaemonConfig>()?;
+
+        let rx = bus.rx::<PtyWebsocketResponse>()?;
+        let tx = bus.tx::<PtyWebsocketRequest>()?;
+        let tx_shutdown = bus.tx::<MainShutdown>()?;
+
+        let _run = Self::try_task("run", Self::run(websocket, config, rx, tx, tx_shutdown));
+
+        Ok(Self { _run })
+    }
+}
+
+impl ConnectionService {
+    async fn run(
+        mut websocket: WebsocketResource,
+        mut config: DaemonConfig,
+        mut rx: impl Stream<Item = PtyWebsocketResponse> + Unpin,
+        mut tx: impl Sink<Item = PtyWebsocketRequest> + Unpin,
+        mut tx_shutdown: impl Sink<Item = MainShutdown> + Unpin,
+    ) -> anyhow::Result<()> {
+        // the running tab, which is re-registered with the next daemon
+        let mut metadata = None;
+
+        loop {
+            let disconnect = Self::connection(websocket, &mut metadata, &mut rx, &mut tx).await?;
+            if disconnect == Disconnect::Closed {
+                break;
+            }
+
+            if metadata.is_none() {
+                info!("daemon disconnected, but the tab has not started");
+                tx_shutdown.send(MainShutdown {}).await.ok();
+                break;
+            }
+
+            if disconnect == Disconnect::Dropped {
+                warn!("lost the connection to the daemon at pid {}", config.pid);
+            }
+
+            match Self::reconnect(&config, disconnect == Disconnect::Handoff).await {
+                Ok((next_config, next_websocket)) => {
+                    info!("reconnected to the daemon at pid {}", next_config.pid);
+                    config = next_config;
+                    websocket = next_websocket;
+                }
+                Err(e) => {
+                    error!("failed to reconnect to the daemon: {}", e);
+                    tx_shutdown.send(MainShutdown {}).await.ok();
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Drives a single websocket connection, until the pty shuts down or the websocket disconnects.
+    async fn connection(
+        websocket: WebsocketResource,
+        metadata: &mut Option<TabMetadata>,
+        mut rx: impl Stream<Item = PtyWebsocketResponse> + Unpin,
+        mut tx: impl Sink<Item = PtyWebsocketRequest> + Unpin,
+    ) -> anyhow::Result<Disconnect> {
+        let bus = ConnectionBus::default();
+        bus.capacity::<PtyWebsocketRequest>(64)?;
+
+        let websocket_bus = WebsocketConnectionBus::default();
+        websocket_bus.store_resource(websocket);
+        let _carrier = websocket_bus.carry_from(&bus)?;
+
+        let mut tx_websocket = bus.tx::<PtyWebsocketResponse>()?;
+        let mut rx_websocket = bus.rx::<PtyWebsocketRequest>()?;
+
+        // drop the buses, so rx_websocket closes when the websocket disconnects
+        drop(bus);
+        drop(websocket_bus);
+
+        if let Some(ref metadata) = metadata {
+            let message = PtyWebsocketResponse::Resumed(metadata.clone());
+            tx_websocket.send(message).await?;
+        }
+
+        let mut disconnect = Disconnect::Dropped;
+        loop {
+            select! {
+                msg = rx.recv() => {
+                    let msg = match msg {
+                        Some(msg) => msg,
+                        None => {
+                            disconnect = Disconnect::Closed;
+                            break;
+                        }
+                    };
+
+                    if let PtyWebsocketResponse::Started(ref started) = msg {
+                        *metadata = Some(started.clone());
+                    }
+
+                    if tx_websocket.send(msg).await.is_err() {
+                        break;
+                    }
+                },
+                msg = rx_websocket.recv() => {
+                    match msg {
+                        Some(PtyWebsocketRequest::Reconnect) => {
+                            debug!("daemon requested a reconnect");
+                            disconnect = Disconnect::Handoff;
+                        }
+                        Some(msg) => tx.send(msg).await?,
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        Ok(disconnect)
+    }
+
+    /// Waits for a running daemon, and connects to it.  Retries with backoff.
+    /// After a handoff, the previous daemon is exiting, and only the next daemon is accepted.
+    async fn reconnect(
+        previous: &DaemonConfig,
+        handoff: bool,
+    ) -> anyhow::Result<(DaemonConfig, WebsocketResource)> {
+        let start = Instant::now();
+        let mut backoff = RECONNECT_BACKOFF_MIN;
+
+        loop {
+            match Self::try_connect(previous, handoff).await {
+                Ok(Some(connection)) => return Ok(connection),
+                Ok(None) => {}
+                Err(e) => debug!("failed to connect to the daemon: {}", e),
+            }
+
+            if Instant::now().duration_since(start) > RECONNECT_TIMEOUT {
+                bail!("timeout while waiting for the daemon");
+            }
+
+            time::sleep(backoff).await;
+            backoff = (backoff * 2).min(RECONNECT_BACKOFF_MAX);
+        }
+    }
+
+    async fn try_connect(
+        previous: &DaemonConfig,
+        handoff: bool,
+    ) -> anyhow::Result<Option<(DaemonConfig, WebsocketResource)>> {
+        let config = match load_daemon_file()? {
+            Some(config) => config,
+            None => return Ok(None),
+        };
+
+        if (handoff && config.pid == previous.pid) || !is_running(&config) {
+            return Ok(None);
+        }
+
+        let url = config.websocket_url("/pty");
+        let websocket = tab_websocket::connect_authorized(url, config.auth_token.clone()).await?;
+
+        Ok(Some((config, WebsocketResource(websocket))))
+    }
+}
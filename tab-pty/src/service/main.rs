@@ -1,20 +1,15 @@
 use crate::{message::pty::MainShutdown, prelude::*};
 
-use super::client::ClientService;
-use lifeline::dyn_bus::DynBus;
+use super::{client::ClientService, connection::ConnectionService};
 
 use tab_api::config::data_path;
-use tab_websocket::{
-    bus::{WebsocketCarrier, WebsocketConnectionBus},
-    resource::connection::WebsocketResource,
-};
 use time::Duration;
 use tokio::time;
 
 /// The main service for the tab-pty module.  Connects to the daemon & handles shutdown.
 pub struct MainService {
     _pty: ClientService,
-    _carrier: WebsocketCarrier,
+    _connection: ConnectionService,
     _shutdown: TabdirShutdownService,
 }
 
@@ -23,11 +18,7 @@ impl Service for MainService {
     type Lifeline = anyhow::Result<Self>;
 
     fn spawn(bus: &Self::Bus) -> Self::Lifeline {
-        let websocket = bus.resource::<WebsocketResource>()?;
-        let websocket_connection_bus = WebsocketConnectionBus::default();
-        websocket_connection_bus.store_resource(websocket);
-
-        let _carrier = websocket_connection_bus.carry_from(bus)?;
+        let _connection = ConnectionService::spawn(bus)?;
 
         debug!("Launching MainService");
         let _pty = ClientService::spawn(bus)?;
@@ -35,7 +26,7 @@ impl Service for MainService {
 
         Ok(Self {
             _pty,
-            _carrier,
+            _connection,
             _shutdown,
         })
     }
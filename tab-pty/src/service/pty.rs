@@ -2,20 +2,29 @@ use crate::message::pty::{PtyOptions, PtyRequest, PtyResponse, PtyShutdown};
 use crate::prelude::*;
 
 use postage::barrier;
-use std::{process::Stdio, time::Duration};
+use std::{
+    os::unix::process::ExitStatusExt,
+    process::{ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use tab_api::{
     chunk::{InputChunk, OutputChunk},
     env::forward_env,
+    tab::TabExit,
 };
 use tab_pty_process::{
-    unix::{UnixPtyMaster, UnixPtySystem, UnixPtyWrite},
+    unix::{UnixPtyMaster, UnixPtySystem},
     Child, Master, PtySystem, PtySystemInstance, PtySystemOptions, Size,
 };
 
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     process::Command,
-    time,
+    select, time,
 };
 
 static CHUNK_LEN: usize = 4096;
@@ -56,42 +65,178 @@ impl Service for PtyService {
 
 impl PtyService {
     async fn run(
-        options: PtyOptions,
-        rx_request: impl Stream<Item = PtyRequest> + Unpin + Send + 'static,
+        mut options: PtyOptions,
+        mut rx_request: impl Stream<Item = PtyRequest> + Unpin + Send + 'static,
         mut rx_shutdown: impl Stream<Item = PtyShutdown> + Unpin,
-        tx_response: impl Sink<Item = PtyResponse> + Clone + Unpin + Send + 'static,
+        mut tx_response: impl Sink<Item = PtyResponse> + Clone + Unpin + Send + 'static,
     ) -> anyhow::Result<()> {
-        let system = Self::create_pty(options).await?;
+        // the output index continues when the shell is restarted, so the daemon keeps the scrollback
+        let index = Arc::new(AtomicUsize::new(0));
+
+        loop {
+            let shell = Self::run_shell(
+                &mut options,
+                index.clone(),
+                &mut rx_request,
+                tx_response.clone(),
+            );
+
+            let (exit, terminated) = select! {
+                result = shell => result?,
+                _ = rx_shutdown.recv() => return Ok(()),
+            };
+
+            if terminated || !options.remain_on_exit {
+                tx_response.send(PtyResponse::Terminated(exit)).await?;
+                break;
+            }
+
+            Self::write_banner(exit, index.as_ref(), &mut tx_response).await?;
+
+            let restart = select! {
+                restart = Self::await_restart(&mut options, &mut rx_request) => restart,
+                _ = rx_shutdown.recv() => return Ok(()),
+            };
+
+            if !restart {
+                tx_response.send(PtyResponse::Terminated(exit)).await?;
+                break;
+            }
+
+            info!("Restarting the shell");
+        }
+
+        rx_shutdown.recv().await;
+
+        Ok(())
+    }
+
+    /// Launches the shell, and forwards input and output until it exits.
+    /// Returns the exit status, and true if the shell was terminated by a shutdown request.
+    async fn run_shell(
+        options: &mut PtyOptions,
+        index: Arc<AtomicUsize>,
+        rx_request: &mut (impl Stream<Item = PtyRequest> + Unpin),
+        tx_response: impl Sink<Item = PtyResponse> + Unpin + Send + 'static,
+    ) -> anyhow::Result<(TabExit, bool)> {
+        let system = Self::create_pty(options.clone()).await?;
         let (tx_barrier, mut rx_barrier) = barrier::channel();
 
         // stdout reader
         let _output = Self::task(
             "output",
-            Self::read_output(system.read, tx_response.clone(), tx_barrier),
+            Self::read_output(system.read, index, tx_response, tx_barrier),
         );
 
-        let _input = Self::task(
-            "input",
-            Self::write_input(system.master, system.write, rx_request),
-        );
+        let master = system.master;
+        let mut stdin = system.write;
+
+        // the startup command is typed into the interactive shell.
+        // when the command exits, the user is dropped back into the shell.
+        if let Some(ref command) = options.startup_command {
+            debug!("running startup command: {}", command);
+            let data = format!("{}\n", command).into_bytes();
+            Self::write_stdin(&mut stdin, InputChunk { data }).await;
+        }
 
         let child = system.child;
-        let mut tx_exit = tx_response.clone();
-        let _exit_code = Self::try_task("exit_code", async move {
-            let exit_code = child.wait().await?;
+        let exit_code = async move {
+            let exit_code = child.wait().await;
             rx_barrier.recv().await;
+            exit_code
+        };
+        tokio::pin!(exit_code);
 
-            info!("Shell successfully terminated with exit code {}", exit_code);
-            tx_exit.send(PtyResponse::Terminated).await?;
+        let mut terminated = false;
+        let mut closed = false;
 
-            Ok(())
-        });
+        loop {
+            select! {
+                exit_code = &mut exit_code => {
+                    let exit_code = exit_code?;
+                    info!("Shell successfully terminated with exit code {}", exit_code);
 
-        rx_shutdown.recv().await;
+                    return Ok((Self::tab_exit(exit_code), terminated));
+                }
+                request = rx_request.recv(), if !closed => match request {
+                    Some(PtyRequest::Resize(dimensions)) => {
+                        options.dimensions = dimensions;
+                        Self::resize(&master, dimensions).await;
+                    }
+                    Some(PtyRequest::Input(chunk)) => Self::write_stdin(&mut stdin, chunk).await,
+                    Some(PtyRequest::Shutdown) => {
+                        debug!("terminating pty");
+                        terminated = true;
+                        stdin.shutdown().await?;
+                    }
+                    None => {
+                        debug!("stdin loop terminated");
+                        closed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Writes the `[exited N] press r to restart, q to close` banner to the tab output
+    async fn write_banner(
+        exit: TabExit,
+        index: &AtomicUsize,
+        mut tx: impl Sink<Item = PtyResponse> + Unpin,
+    ) -> anyhow::Result<()> {
+        let status = match exit.code {
+            Some(code) => format!("exited {}", code),
+            None => "exited".to_string(),
+        };
+
+        let banner = format!(
+            "\r\n\x1b[7m[{}] press r to restart, q to close\x1b[0m\r\n",
+            status
+        );
+
+        let data = banner.into_bytes();
+        let index = index.fetch_add(data.len(), Ordering::SeqCst);
+        tx.send(PtyResponse::Output(OutputChunk { index, data }))
+            .await?;
 
         Ok(())
     }
 
+    /// Waits for the user to press `r` to restart the shell, or `q` to close the tab.
+    /// Returns true if the shell should be restarted.
+    async fn await_restart(
+        options: &mut PtyOptions,
+        rx_request: &mut (impl Stream<Item = PtyRequest> + Unpin),
+    ) -> bool {
+        while let Some(request) = rx_request.recv().await {
+            match request {
+                PtyRequest::Input(chunk) => {
+                    for byte in chunk.data {
+                        match byte {
+                            b'r' | b'R' => return true,
+                            b'q' | b'Q' => return false,
+                            _ => {}
+                        }
+                    }
+                }
+                // the restarted shell is launched with the current size of the tab
+                PtyRequest::Resize(dimensions) => options.dimensions = dimensions,
+                PtyRequest::Shutdown => return false,
+            }
+        }
+
+        false
+    }
+
+    /// Converts the exit status of the shell.  Like shells, a signal is reported as 128 + the signal number.
+    fn tab_exit(status: ExitStatus) -> TabExit {
+        let code = status
+            .code()
+            .or_else(|| status.signal().map(|signal| 128 + signal));
+
+        TabExit { code }
+    }
+
     async fn create_pty(options: PtyOptions) -> anyhow::Result<PtySystemInstance<UnixPtySystem>> {
         let mut child = Command::new(options.command);
         child.current_dir(options.working_directory);
@@ -106,26 +251,17 @@ impl PtyService {
 
         let system = UnixPtySystem::spawn(child, PtySystemOptions { raw_mode: false })?;
 
-        let size = Size {
-            rows: options.dimensions.0,
-            cols: options.dimensions.1,
-        };
-
-        system
-            .master
-            .resize(size)
-            .await
-            .expect("failed to resize pty");
+        Self::resize(&system.master, options.dimensions).await;
 
         Ok(system)
     }
 
     async fn read_output(
         mut channel: impl AsyncReadExt + Unpin,
+        index: Arc<AtomicUsize>,
         mut tx: impl Sink<Item = PtyResponse> + Unpin,
         _output_barrier: barrier::Sender,
     ) {
-        let mut index = 0usize;
         let mut buffer = vec![0u8; CHUNK_LEN];
         while let Ok(read) = channel.read(buffer.as_mut_slice()).await {
             if read == 0 {
@@ -138,45 +274,28 @@ impl PtyService {
             let mut buf = vec![0; read];
             buf.copy_from_slice(&buffer[0..read]);
 
+            let index = index.fetch_add(read, Ordering::SeqCst);
             let chunk = OutputChunk { index, data: buf };
             let response = PtyResponse::Output(chunk);
 
             tx.send(response).await.ok();
-            index += read;
 
             time::sleep(Duration::from_micros(150)).await;
         }
     }
 
-    async fn write_input(
-        master: UnixPtyMaster,
-        mut stdin: UnixPtyWrite,
-        mut rx: impl Stream<Item = PtyRequest> + Unpin,
-    ) -> anyhow::Result<()> {
-        while let Some(request) = rx.recv().await {
-            match request {
-                PtyRequest::Resize(dimensions) => {
-                    let size = Size {
-                        cols: dimensions.0,
-                        rows: dimensions.1,
-                    };
-
-                    if let Err(e) = master.resize(size).await {
-                        error!("failed to resize pty: {:?}", e);
-                    }
+    /// Resizes the pty to the given (cols, rows)
+    async fn resize(master: &UnixPtyMaster, dimensions: (u16, u16)) {
+        let size = Size {
+            cols: dimensions.0,
+            rows: dimensions.1,
+        };
 
-                    debug!("resized to dimensions: {:?}", &dimensions);
-                }
-                PtyRequest::Input(chunk) => Self::write_stdin(&mut stdin, chunk).await,
-                PtyRequest::Shutdown => {
-                    debug!("terminating pty");
-                    stdin.shutdown().await?;
-                }
-            }
+        if let Err(e) = master.resize(size).await {
+            error!("failed to resize pty: {:?}", e);
         }
 
-        debug!("stdin loop terminated");
-        Ok(())
+        debug!("resized to dimensions: {:?}", &dimensions);
     }
 
     async fn write_stdin(mut stdin: impl AsyncWriteExt + Unpin, mut chunk: InputChunk) {
@@ -3,7 +3,16 @@ use clap::Arg;
 use clap::ArgMatches;
 
 pub fn init() -> ArgMatches<'static> {
-    app().get_matches()
+    let matches = app().get_matches();
+
+    // the text sent with --send may contain whitespace, so only the tab name is validated
+    if let Some(mut values) = matches.values_of("SEND-TAB") {
+        if let Err(e) = validate_tab_name(values.next().unwrap_or_default().to_string()) {
+            clap::Error::value_validation_auto(e).exit();
+        }
+    }
+
+    matches
 }
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -91,6 +100,14 @@ fn app() -> App<'static, 'static> {
                 .display_order(0)
                 .help("Lists the active tabs"),
         )
+        .arg(
+            Arg::with_name("FORMAT")
+                .long("format")
+                .takes_value(true)
+                .requires("LIST")
+                .possible_values(&["text", "json", "yaml"])
+                .help("Sets the output format of `tab --list`"),
+        )
         .arg(
             Arg::with_name("SHUTDOWN")
                 .short("W")
@@ -99,6 +116,13 @@ fn app() -> App<'static, 'static> {
                 .display_order(2)
                 .help("Terminates the tab daemon and all active pty sessions"),
         )
+        .arg(
+            Arg::with_name("UPGRADE-DAEMON")
+                .long("upgrade-daemon")
+                .takes_value(false)
+                .display_order(3)
+                .help("Relaunches the tab daemon with the current executable, without terminating the active pty sessions"),
+        )
         .arg(
             Arg::with_name("CLOSE-TAB")
                 .short("w")
@@ -121,6 +145,107 @@ fn app() -> App<'static, 'static> {
                 .validator(validate_tab_name)
                 .help("Disconnects any active sessions for the given tabs")
         )
+        .arg(
+            Arg::with_name("WATCH-TAB")
+                .long("watch")
+                .takes_value(true)
+                .value_name("TAB")
+                .validator(validate_tab_name)
+                .conflicts_with_all(&["CLOSE-TAB", "DISCONNECT-TAB", "LIST", "SHUTDOWN", "UPGRADE-DAEMON"])
+                .help("Attaches to the running tab in read-only mode.  Output is displayed, but input is not sent to the tab.")
+        )
+        .arg(
+            Arg::with_name("PANES")
+                .long("panes")
+                .takes_value(true)
+                .multiple(true)
+                .min_values(1)
+                .value_name("TABS")
+                .validator(validate_tab_name)
+                .conflicts_with_all(&["CAPTURE-TAB", "CLOSE-TAB", "DISCONNECT-TAB", "GREP", "LIST", "RECORD-TAB", "REPLAY", "SEND-TAB", "SHUTDOWN", "TAB-NAME", "UPGRADE-DAEMON", "WATCH-TAB"])
+                .help("Opens the tabs side by side in panes, creating them if needed.  Bind the FocusNextPane and FocusPreviousPane actions to move between panes.")
+        )
+        .arg(
+            Arg::with_name("VERTICAL")
+                .long("vertical")
+                .takes_value(false)
+                .requires("PANES")
+                .help("Stacks the panes from top to bottom, rather than side by side")
+        )
+        .arg(
+            Arg::with_name("SEND-TAB")
+                .long("send")
+                .takes_value(true)
+                .min_values(1)
+                .max_values(2)
+                .value_name("TAB")
+                .conflicts_with_all(&["CLOSE-TAB", "DISCONNECT-TAB", "LIST", "SHUTDOWN", "UPGRADE-DAEMON", "WATCH-TAB"])
+                .help("Sends the text to the tab as input, creating the tab if needed.  Escapes such as \\n are interpreted.  If the text is omitted, stdin is sent.")
+        )
+        .arg(
+            Arg::with_name("CAPTURE-TAB")
+                .long("capture")
+                .takes_value(true)
+                .value_name("TAB")
+                .validator(validate_tab_name)
+                .conflicts_with_all(&["CLOSE-TAB", "DISCONNECT-TAB", "LIST", "SEND-TAB", "SHUTDOWN", "UPGRADE-DAEMON", "WATCH-TAB"])
+                .help("Prints the scrollback of the running tab to stdout, and exits")
+        )
+        .arg(
+            Arg::with_name("LINES")
+                .long("lines")
+                .takes_value(true)
+                .value_name("N")
+                .requires("CAPTURE-TAB")
+                .validator(validate_lines)
+                .help("Prints only the last N lines of the captured scrollback")
+        )
+        .arg(
+            Arg::with_name("STRIP-ANSI")
+                .long("strip-ansi")
+                .requires("CAPTURE-TAB")
+                .help("Removes ANSI escape sequences from the captured output")
+        )
+        .arg(
+            Arg::with_name("FOLLOW")
+                .long("follow")
+                .requires("CAPTURE-TAB")
+                .help("Continues to print new output from the tab, until it terminates")
+        )
+        .arg(
+            Arg::with_name("GREP")
+                .long("grep")
+                .takes_value(true)
+                .value_name("PATTERN")
+                .conflicts_with_all(&["CAPTURE-TAB", "CLOSE-TAB", "DISCONNECT-TAB", "LIST", "SEND-TAB", "SHUTDOWN", "UPGRADE-DAEMON", "WATCH-TAB"])
+                .help("Searches the scrollback of all running tabs, and prints the lines which contain the pattern")
+        )
+        .arg(
+            Arg::with_name("RECORD-TAB")
+                .long("record")
+                .takes_value(true)
+                .value_name("TAB")
+                .validator(validate_tab_name)
+                .conflicts_with_all(&["CAPTURE-TAB", "CLOSE-TAB", "DISCONNECT-TAB", "GREP", "LIST", "SEND-TAB", "SHUTDOWN", "UPGRADE-DAEMON", "WATCH-TAB"])
+                .help("Records the output of the tab to an asciicast file in the daemon's recordings directory, creating the tab if needed")
+        )
+        .arg(
+            Arg::with_name("REPLAY")
+                .long("replay")
+                .takes_value(true)
+                .value_name("FILE")
+                .conflicts_with_all(&["CAPTURE-TAB", "CLOSE-TAB", "DISCONNECT-TAB", "GREP", "LIST", "RECORD-TAB", "SEND-TAB", "SHUTDOWN", "UPGRADE-DAEMON", "WATCH-TAB"])
+                .help("Plays back an asciicast recording in the terminal")
+        )
+        .arg(
+            Arg::with_name("SPEED")
+                .long("speed")
+                .takes_value(true)
+                .value_name("N")
+                .requires("REPLAY")
+                .validator(validate_speed)
+                .help("Plays back the recording N times faster than the original")
+        )
         .arg(
             Arg::with_name("COMPLETION")
                 .long("completion")
@@ -134,7 +259,7 @@ fn app() -> App<'static, 'static> {
                 .help("Switches to the provided tab")
                 .required(false)
                 .value_name("TAB")
-                .conflicts_with_all(&["CLOSE-TAB", "LIST", "SHUTDOWN"])
+                .conflicts_with_all(&["CAPTURE-TAB", "CLOSE-TAB", "GREP", "LIST", "RECORD-TAB", "REPLAY", "SEND-TAB", "SHUTDOWN", "UPGRADE-DAEMON", "WATCH-TAB"])
                 .validator(validate_select_tab_name)
                 .index(1),
         )
@@ -149,6 +274,20 @@ fn validate_select_tab_name(name: String) -> Result<(), String> {
     validate_tab_name(name)
 }
 
+fn validate_lines(lines: String) -> Result<(), String> {
+    lines
+        .parse::<usize>()
+        .map(|_| ())
+        .map_err(|_| "lines must be a non-negative integer".into())
+}
+
+fn validate_speed(speed: String) -> Result<(), String> {
+    match speed.parse::<f64>() {
+        Ok(speed) if speed > 0.0 && speed.is_finite() => Ok(()),
+        _ => Err("speed must be a positive number".into()),
+    }
+}
+
 fn validate_tab_name(name: String) -> Result<(), String> {
     if name.starts_with('-') {
         return Err("tab name may not begin with a dash".into());
@@ -26,6 +26,48 @@ _tab() {
         COMPREPLY=( $(compgen -W "${TABS}" -- $cur) )
         return 0
         ;;
+    --watch)
+        TABS=$(tab --_autocomplete_close_tab)
+        COMPREPLY=( $(compgen -W "${TABS}" -- $cur) )
+        return 0
+        ;;
+    --capture)
+        TABS=$(tab --_autocomplete_close_tab)
+        COMPREPLY=( $(compgen -W "${TABS}" -- $cur) )
+        return 0
+        ;;
+    --lines)
+        return 0
+        ;;
+    --grep)
+        return 0
+        ;;
+    --record)
+        TABS=$(tab --_autocomplete_tab)
+        COMPREPLY=( $(compgen -W "${TABS}" -- $cur) )
+        return 0
+        ;;
+    --replay)
+        COMPREPLY=( $(compgen -f -X '!*.cast' -- $cur) $(compgen -d -- $cur) )
+        return 0
+        ;;
+    --speed)
+        return 0
+        ;;
+    --panes)
+        TABS=$(tab --_autocomplete_tab)
+        COMPREPLY=( $(compgen -W "${TABS}" -- $cur) )
+        return 0
+        ;;
+    --send)
+        TABS=$(tab --_autocomplete_tab)
+        COMPREPLY=( $(compgen -W "${TABS}" -- $cur) )
+        return 0
+        ;;
+    --format)
+        COMPREPLY=( $(compgen -W "text json yaml" -- $cur) )
+        return 0
+        ;;
     --completion)
         COMPREPLY=( $(compgen -W "bash elvish fish powershell zsh") )
         return 0
@@ -39,7 +81,7 @@ _tab() {
         return 0
         ;;
     -*)
-        opts=" -h --help -l --list -w --close -z --disconnect -k --check -W --shutdown -V --version --completion <TAB> "
+        opts=" -h --help -l --list --format -w --close -z --disconnect --watch --panes --vertical --send --capture --lines --strip-ansi --follow --grep --record --replay --speed -k --check -W --shutdown --upgrade-daemon -V --version --completion <TAB> "
         COMPREPLY=( $(compgen -W "${opts}" -- $cur) )
         return 0
         ;;
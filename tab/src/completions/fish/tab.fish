@@ -4,11 +4,25 @@ complete -c tab  -f -a '(tab --_autocomplete_tab)'
 # hack here with `-o w`, to get fish to insert a space after the `tab -w` completion
 complete -c tab -n "__fish_use_subcommand" -o w -l close -d 'closes the tab with the given name' -x -a '(tab --_autocomplete_close_tab)'
 complete -c tab -n "__fish_use_subcommand" -o z -l disconnect -d 'disconnects any active sessions for the given tab' -x -a '(tab --_autocomplete_close_tab)'
+complete -c tab -n "__fish_use_subcommand" -l watch -d 'attaches to the running tab in read-only mode' -x -a '(tab --_autocomplete_close_tab)'
+complete -c tab -n "__fish_use_subcommand" -l send -d 'sends input to the tab, creating it if needed' -x -a '(tab --_autocomplete_tab)'
+complete -c tab -n "__fish_use_subcommand" -l panes -d 'opens the tabs side by side in panes' -x -a '(tab --_autocomplete_tab)'
+complete -c tab -l vertical -d 'stacks the panes from top to bottom'
+complete -c tab -n "__fish_use_subcommand" -l capture -d 'prints the scrollback of the running tab' -x -a '(tab --_autocomplete_close_tab)'
+complete -c tab -l lines -d 'prints only the last N lines of the captured scrollback' -x
+complete -c tab -l strip-ansi -d 'removes ANSI escape sequences from the captured output'
+complete -c tab -l follow -d 'continues to print new output from the captured tab'
+complete -c tab -n "__fish_use_subcommand" -l grep -d 'prints the lines in the scrollback of all running tabs which contain the pattern' -x
+complete -c tab -n "__fish_use_subcommand" -l record -d 'records the output of the tab to an asciicast file' -x -a '(tab --_autocomplete_tab)'
+complete -c tab -n "__fish_use_subcommand" -l replay -d 'plays back an asciicast recording' -r
+complete -c tab -l speed -d 'sets the playback speed of `tab --replay`' -x
 
 complete -c tab -l completion -d 'prints raw autocomplete scripts' -x -a 'bash elvish fish powershell zsh'
 complete -c tab -n "__fish_use_subcommand" -s k -l check -d 'checks the current workspace for errors and warnings'
 complete -c tab -n "__fish_use_subcommand" -s l -l list -d 'lists the active tabs'
+complete -c tab -l format -d 'sets the output format of `tab --list`' -x -a 'text json yaml'
 complete -c tab -n "__fish_use_subcommand" -s W -l shutdown -d 'terminates the tab daemon and all active pty sessions'
+complete -c tab -n "__fish_use_subcommand" -l upgrade-daemon -d 'relaunches the tab daemon, without terminating the active pty sessions'
 complete -c tab -n "__fish_use_subcommand" -s h -l help -d 'Prints help information'
 complete -c tab -n "__fish_use_subcommand" -s V -l version -d 'Prints version information'
 
@@ -25,10 +25,24 @@ Register-ArgumentCompleter -Native -CommandName 'tab' -ScriptBlock {
             [CompletionResult]::new('--_launch', '_launch', [CompletionResultType]::ParameterName, 'launches the daemon or a new pty process with `tab --_launch [daemon|pty]')
             [CompletionResult]::new('-w', 'w', [CompletionResultType]::ParameterName, 'closes the tab with the given name')
             [CompletionResult]::new('--close', 'close', [CompletionResultType]::ParameterName, 'closes the tab with the given name')
+            [CompletionResult]::new('--watch', 'watch', [CompletionResultType]::ParameterName, 'attaches to the running tab in read-only mode')
+            [CompletionResult]::new('--send', 'send', [CompletionResultType]::ParameterName, 'sends input to the tab, creating it if needed')
+            [CompletionResult]::new('--panes', 'panes', [CompletionResultType]::ParameterName, 'opens the tabs side by side in panes')
+            [CompletionResult]::new('--vertical', 'vertical', [CompletionResultType]::ParameterName, 'stacks the panes from top to bottom')
+            [CompletionResult]::new('--capture', 'capture', [CompletionResultType]::ParameterName, 'prints the scrollback of the running tab')
+            [CompletionResult]::new('--lines', 'lines', [CompletionResultType]::ParameterName, 'prints only the last N lines of the captured scrollback')
+            [CompletionResult]::new('--strip-ansi', 'strip-ansi', [CompletionResultType]::ParameterName, 'removes ANSI escape sequences from the captured output')
+            [CompletionResult]::new('--follow', 'follow', [CompletionResultType]::ParameterName, 'continues to print new output from the captured tab')
+            [CompletionResult]::new('--grep', 'grep', [CompletionResultType]::ParameterName, 'prints the lines in the scrollback of all running tabs which contain the pattern')
+            [CompletionResult]::new('--record', 'record', [CompletionResultType]::ParameterName, 'records the output of the tab to an asciicast file')
+            [CompletionResult]::new('--replay', 'replay', [CompletionResultType]::ParameterName, 'plays back an asciicast recording')
+            [CompletionResult]::new('--speed', 'speed', [CompletionResultType]::ParameterName, 'sets the playback speed of `tab --replay`')
             [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'lists the active tabs')
             [CompletionResult]::new('--list', 'list', [CompletionResultType]::ParameterName, 'lists the active tabs')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'sets the output format of `tab --list`')
             [CompletionResult]::new('-W', 'W', [CompletionResultType]::ParameterName, 'terminates the tab daemon and all active pty sessions')
             [CompletionResult]::new('--shutdown', 'shutdown', [CompletionResultType]::ParameterName, 'terminates the tab daemon and all active pty sessions')
+            [CompletionResult]::new('--upgrade-daemon', 'upgrade-daemon', [CompletionResultType]::ParameterName, 'relaunches the tab daemon, without terminating the active pty sessions')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Prints help information')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Prints help information')
             [CompletionResult]::new('-V', 'V', [CompletionResultType]::ParameterName, 'Prints version information')
@@ -0,0 +1,148 @@
+mod common;
+use common::*;
+
+async fn create_tab(session: &TestSession, tab: &str) -> anyhow::Result<()> {
+    let result = session
+        .command()
+        .tab(tab)
+        .await_stdout("$", 3000)
+        .stdin("echo first-$((20+22))\n")
+        .await_stdout("first-42", 300)
+        .stdin("echo second-$((20+22))\n")
+        .await_stdout("second-42", 300)
+        .await_stdout("$", 300)
+        .disconnect()
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    Ok(())
+}
+
+/// Tests that `tab --capture` prints the scrollback, and exits.
+#[tokio::test]
+async fn capture() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+    create_tab(&session, "capture/").await?;
+
+    let (status, stdout) = session.command().capture("capture/", &[]).await?;
+
+    assert_eq!(Some(0), status.code());
+    assert!(stdout.contains("first-42"));
+    assert!(stdout.contains("second-42"));
+
+    Ok(())
+}
+
+/// Tests that `--lines` and `--strip-ansi` truncate and clean the captured scrollback.
+#[tokio::test]
+async fn capture_lines() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+    create_tab(&session, "capture-lines/").await?;
+
+    let (status, stdout) = session
+        .command()
+        .capture("capture-lines/", &["--lines", "2", "--strip-ansi"])
+        .await?;
+
+    assert_eq!(Some(0), status.code());
+    assert!(!stdout.contains("first-42"));
+    assert!(stdout.contains("second-42"));
+    assert!(!stdout.contains('\x1b'));
+    assert!(!stdout.contains('\r'));
+
+    Ok(())
+}
+
+/// Tests that `--follow` prints new output, and exits when the tab terminates.
+#[tokio::test]
+async fn capture_follow() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+    create_tab(&session, "capture-follow/").await?;
+
+    let mut interactive = session.command();
+    interactive
+        .tab("capture-follow/")
+        .await_stdout("second-42", 3000)
+        .delay_ms(1000)
+        .stdin("echo live-$((20+22))\n")
+        .await_stdout("live-42", 300)
+        .await_stdout("$", 300)
+        .stdin("exit\n")
+        .await_stdout("exit", 300);
+
+    let follow = session.command();
+    let follow = follow.capture("capture-follow/", &["--follow"]);
+
+    let (interactive, follow) = tokio::join!(interactive.run(), follow);
+    let (interactive, (status, stdout)) = (interactive?, follow?);
+
+    assert_eq!(Some(0), interactive.exit_status.code());
+    assert_eq!(Some(0), status.code());
+    assert!(stdout.contains("first-42"));
+    assert!(stdout.contains("live-42"));
+
+    Ok(())
+}
+
+/// Tests that capturing a tab which isn't running fails.
+#[tokio::test]
+async fn capture_missing() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let (status, stdout) = session.command().capture("missing/", &[]).await?;
+
+    assert_eq!(Some(1), status.code());
+    assert!(stdout.is_empty());
+    assert!(session.command().tabs().await?.is_empty());
+
+    Ok(())
+}
+
+/// Tests that `tab --capture` replays scrollback which has been spilled to disk,
+/// and that the spilled segments are removed when the tab is closed.
+#[tokio::test]
+async fn capture_spilled() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let result = session
+        .command()
+        .tab("capture-spilled/")
+        .await_stdout("$", 3000)
+        .stdin("seq -f 'spilled-%g' 1 100000; echo done-$((20+22))\n")
+        .await_stdout("done-42", 30000)
+        .disconnect()
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    let spill = session.runtime_dir().join("scrollback").join("0");
+    assert!(spill.is_dir());
+
+    let (status, stdout) = session
+        .command()
+        .capture("capture-spilled/", &["--strip-ansi"])
+        .await?;
+
+    assert_eq!(Some(0), status.code());
+    assert!(stdout.lines().any(|line| line.trim() == "spilled-1"));
+    assert!(stdout.lines().any(|line| line.trim() == "spilled-100000"));
+
+    let result = session
+        .command()
+        .tab("capture-spilled/")
+        .await_stdout("done-42", 3000)
+        .stdin("$TAB_BIN --close\n")
+        .await_stdout("close", 300)
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    assert!(!spill.exists());
+
+    Ok(())
+}
@@ -54,12 +54,17 @@ pub struct TestSession {
 struct TestContext {
     binary: PathBuf,
     runtime_dir: PathBuf,
+    unix_socket: bool,
 }
 
 /// Represents & executes a single invocation of the `tab` binary.
 pub struct TestCommand {
     context: Arc<TestContext>,
     pub tab: String,
+    pub watch: bool,
+    pub panes: Vec<String>,
+    pub dir: Option<PathBuf>,
+    pub config: Option<PathBuf>,
     pub actions: Vec<Action>,
     pub strict_timeout: bool,
 }
@@ -72,6 +77,31 @@ impl TestCommand {
         self
     }
 
+    /// Sets the working directory of the command, which is used to discover workspace configuration
+    pub fn dir<T: Into<PathBuf>>(&mut self, dir: T) -> &mut Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the global config file of the command, with `TAB_CONFIG`
+    pub fn config<T: Into<PathBuf>>(&mut self, config: T) -> &mut Self {
+        self.config = Some(config.into());
+        self
+    }
+
+    /// Attaches to the tab in read-only mode, with `tab --watch`
+    pub fn watch<T: ToString>(&mut self, value: T) -> &mut Self {
+        self.tab = value.to_string();
+        self.watch = true;
+        self
+    }
+
+    /// Opens the tabs side by side in panes, with `tab --panes`
+    pub fn panes(&mut self, tabs: &[&str]) -> &mut Self {
+        self.panes = tabs.iter().map(|tab| tab.to_string()).collect();
+        self
+    }
+
     /// Lists all running tab sessions
     pub async fn tabs(&self) -> anyhow::Result<Vec<String>> {
         let mut command = self.command();
@@ -94,6 +124,159 @@ impl TestCommand {
         Ok(tabs)
     }
 
+    /// Lists the tabs with `tab --list --format <format>`, and returns stdout
+    pub async fn list(&self, format: &str) -> anyhow::Result<String> {
+        let mut command = self.command();
+        command.arg("--list").arg("--format").arg(format);
+
+        let child = command.spawn()?;
+        let mut stdout = child.stdout.expect("couldn't get child stdout");
+
+        let mut string = String::new();
+        stdout.read_to_string(&mut string).await?;
+
+        Ok(string)
+    }
+
+    /// Captures the tab with `tab --capture <tab> [args]`, and returns the exit status and stdout
+    pub async fn capture(&self, tab: &str, args: &[&str]) -> anyhow::Result<(ExitStatus, String)> {
+        let mut command = self.command();
+        command.arg("--capture").arg(tab).args(args);
+
+        let mut child = command.spawn()?;
+        let mut stdout = child.stdout.take().expect("couldn't get child stdout");
+
+        let mut string = String::new();
+        stdout.read_to_string(&mut string).await?;
+        let status = child.wait().await?;
+
+        Ok((status, string))
+    }
+
+    /// Searches the scrollback of the running tabs with `tab --grep <pattern>`, and captures stdout.
+    pub async fn grep(&self, pattern: &str) -> anyhow::Result<(ExitStatus, String)> {
+        let mut command = self.command();
+        command.arg("--grep").arg(pattern);
+
+        let mut child = command.spawn()?;
+        let mut stdout = child.stdout.take().expect("couldn't get child stdout");
+
+        let mut string = String::new();
+        stdout.read_to_string(&mut string).await?;
+        let status = child.wait().await?;
+
+        Ok((status, string))
+    }
+
+    /// Starts recording the tab with `tab --record <tab>`, and waits for the command to exit.
+    pub async fn record(&self, tab: &str) -> anyhow::Result<ExitStatus> {
+        let mut command = self.command();
+        command.arg("--record").arg(tab);
+
+        let status = command.spawn()?.wait().await?;
+
+        Ok(status)
+    }
+
+    /// Plays back the recording with `tab --replay <file> [args]`, and captures stdout.
+    pub async fn replay(&self, file: &Path, args: &[&str]) -> anyhow::Result<(ExitStatus, String)> {
+        let mut command = self.command();
+        command.arg("--replay").arg(file).args(args);
+
+        let mut child = command.spawn()?;
+        let mut stdout = child.stdout.take().expect("couldn't get child stdout");
+
+        let mut string = String::new();
+        stdout.read_to_string(&mut string).await?;
+        let status = child.wait().await?;
+
+        Ok((status, string))
+    }
+
+    /// Sends the text to the tab with `tab --send <tab> <text>`, and waits for the command to exit.
+    pub async fn send(&self, tab: &str, text: &str) -> anyhow::Result<ExitStatus> {
+        let mut command = self.command();
+        command.arg("--send").arg(tab).arg(text);
+
+        let status = command.spawn()?.wait().await?;
+
+        Ok(status)
+    }
+
+    /// Sends the data to the tab with `tab --send <tab>`, writing it to stdin.
+    pub async fn send_stdin(&self, tab: &str, data: &[u8]) -> anyhow::Result<ExitStatus> {
+        let mut command = self.command();
+        command.arg("--send").arg(tab);
+
+        let mut child = command.spawn()?;
+        let mut stdin = child.stdin.take().expect("couldn't get child stdin");
+        stdin.write_all(data).await?;
+        drop(stdin);
+
+        let status = child.wait().await?;
+
+        Ok(status)
+    }
+
+    /// Closes the tabs with `tab --close <tabs>`, and waits for the command to exit.
+    pub async fn close(&self, tabs: &[&str]) -> anyhow::Result<ExitStatus> {
+        let mut command = self.command();
+        command.arg("--close").args(tabs);
+
+        let status = command.spawn()?.wait().await?;
+
+        Ok(status)
+    }
+
+    /// Shuts down the daemon, which saves the running tabs.
+    pub async fn shutdown(&self) -> anyhow::Result<ExitStatus> {
+        let mut command = self.command();
+        command.arg("--shutdown");
+
+        let status = command.spawn()?.wait().await?;
+
+        // the daemon process exits shortly after the command
+        time::sleep(Duration::from_millis(500)).await;
+
+        Ok(status)
+    }
+
+    /// Relaunches the daemon, and waits for the command to exit.  The running ptys reconnect to the new daemon.
+    pub async fn upgrade_daemon(&self) -> anyhow::Result<ExitStatus> {
+        let mut command = self.command();
+        command.arg("--upgrade-daemon");
+
+        let status = command.spawn()?.wait().await?;
+
+        Ok(status)
+    }
+
+    /// Kills the daemon process, leaving the pty processes running.
+    pub async fn kill_daemon(&self) -> anyhow::Result<()> {
+        let daemon_file = self.context.runtime_dir.join("daemon-pid.yml");
+        let contents = std::fs::read_to_string(daemon_file)?;
+
+        let pid = contents
+            .lines()
+            .find_map(|line| line.strip_prefix("pid: "))
+            .context("daemon pid not found")?;
+
+        let status = tokio::process::Command::new("kill")
+            .arg("-9")
+            .arg(pid.trim())
+            .status()
+            .await?;
+
+        if !status.success() {
+            anyhow::bail!("failed to kill daemon pid {}", pid);
+        }
+
+        // the ptys notice the dropped connection, and begin reconnecting
+        time::sleep(Duration::from_millis(500)).await;
+
+        Ok(())
+    }
+
     /// Writes stdin to the tab session
     pub fn stdin<T: ToString>(&mut self, value: T) -> &mut Self {
         let action = Action::Stdin(value.to_string().as_bytes().to_owned());
@@ -159,7 +342,15 @@ impl TestCommand {
         info!("Tab command initializing: {}", self.tab.as_str());
 
         let mut run = self.command();
-        run.arg(self.tab.as_str());
+        if self.watch {
+            run.arg("--watch");
+        }
+
+        if self.panes.is_empty() {
+            run.arg(self.tab.as_str());
+        } else {
+            run.arg("--panes").args(self.panes.iter());
+        }
 
         let mut child = run.spawn()?;
         let mut stdin = child.stdin.take().expect("couldn't get child stdin");
@@ -215,21 +406,24 @@ impl TestCommand {
                             );
                             let mut buf = vec![0u8; 32];
                             let start_time = Instant::now();
+                            // only the newly read output is searched, so large outputs aren't scanned repeatedly
+                            let mut search_from = search_index;
                             loop {
                                 debug!(
                                     "Searching from [{}..{}] in: '{}'",
-                                    search_index,
+                                    search_from,
                                     stdout_buffer.len(),
-                                    std::str::from_utf8(&stdout_buffer[search_index..])
+                                    std::str::from_utf8(&stdout_buffer[search_from..])
                                         .unwrap_or("")
                                         .replace("\r", " ")
                                         .replace("\n", " ")
                                 );
 
                                 if let Some(index) = find_subsequence(
-                                    &stdout_buffer[search_index..],
+                                    &stdout_buffer[search_from..],
                                     match_target.as_slice(),
                                 ) {
+                                    let index = search_from - search_index + index;
                                     info!(
                                         "Stdout match for {} found at index [{}..{}] after {} ms",
                                         string,
@@ -264,6 +458,12 @@ impl TestCommand {
                                     break;
                                 }
 
+                                // a match may begin in the output which has already been searched
+                                search_from = stdout_buffer
+                                    .len()
+                                    .saturating_sub(match_target.len().saturating_sub(1))
+                                    .max(search_index);
+
                                 let timeout = time::timeout(Duration::from_millis(1000), async {
                                     stdout
                                         .read_buf(&mut buf.as_mut_slice())
@@ -339,12 +539,21 @@ impl TestCommand {
                     .to_string(),
             )
             .env("TAB_RAW_MODE", "false")
+            .env("TAB_UNIX_SOCKET", self.context.unix_socket.to_string())
             .env("TAB", "")
             .env("TAB_ID", "")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::inherit());
 
+        if let Some(ref dir) = self.dir {
+            run.current_dir(dir);
+        }
+
+        if let Some(ref config) = self.config {
+            run.env("TAB_CONFIG", config);
+        }
+
         run
     }
 }
@@ -357,6 +566,7 @@ fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
 
 /// The result of a `tab` command execution
 /// Includes the stdout of the process (with ansi escape codes removed), and the process exit status.
+#[allow(dead_code)]
 pub struct TestResult {
     pub stdout: String,
     pub snapshot: String,
@@ -368,6 +578,15 @@ impl TestSession {
     /// Constructs a new `tab` session, generating a temp directory for the tab daemon.
     /// When the TestSession value is dropped, the daemon & pty sessions shut down.
     pub fn new() -> anyhow::Result<Self> {
+        Self::with_transport(false)
+    }
+
+    /// Constructs a new `tab` session, where the daemon listens on a Unix socket.
+    pub fn new_unix_socket() -> anyhow::Result<Self> {
+        Self::with_transport(true)
+    }
+
+    fn with_transport(unix_socket: bool) -> anyhow::Result<Self> {
         setup();
 
         let dir = tempdir().context("failed to create tempdir")?;
@@ -383,6 +602,7 @@ impl TestSession {
         let context = TestContext {
             runtime_dir: dir.path().to_path_buf(),
             binary,
+            unix_socket,
         };
 
         Ok(Self {
@@ -391,6 +611,11 @@ impl TestSession {
         })
     }
 
+    /// The runtime directory of the daemon & pty sessions.
+    pub fn runtime_dir(&self) -> &Path {
+        self.context.runtime_dir.as_path()
+    }
+
     /// The path to the tab binary which will be executed by commands.
     pub fn binary(&self) -> &Path {
         &self.context.binary.as_path()
@@ -401,6 +626,10 @@ impl TestSession {
         TestCommand {
             context: self.context.clone(),
             tab: "tab".into(),
+            watch: false,
+            panes: Vec::new(),
+            config: None,
+            dir: None,
             actions: Vec::new(),
             strict_timeout: false,
         }
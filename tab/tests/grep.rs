@@ -0,0 +1,50 @@
+mod common;
+use common::*;
+
+async fn create_tab(session: &TestSession, tab: &str, text: &str) -> anyhow::Result<()> {
+    let result = session
+        .command()
+        .tab(tab)
+        .await_stdout("$", 3000)
+        .stdin(format!("echo {}-$((20+22))\n", text).as_str())
+        .await_stdout(format!("{}-42", text).as_str(), 300)
+        .await_stdout("$", 300)
+        .disconnect()
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    Ok(())
+}
+
+/// Tests that `tab --grep` prints the matching lines of each tab, prefixed by the tab name.
+#[tokio::test]
+async fn grep() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+    create_tab(&session, "grep-a/", "error").await?;
+    create_tab(&session, "grep-b/", "ok").await?;
+
+    let (status, stdout) = session.command().grep("error-42").await?;
+
+    assert_eq!(Some(0), status.code());
+    assert!(stdout.contains("grep-a/: error-42"));
+    assert!(!stdout.contains("grep-b/"));
+    assert!(!stdout.contains('\x1b'));
+
+    Ok(())
+}
+
+/// Tests that `tab --grep` exits with status 1, if no lines match.
+#[tokio::test]
+async fn grep_no_match() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+    create_tab(&session, "grep-none/", "ok").await?;
+
+    let (status, stdout) = session.command().grep("missing-pattern").await?;
+
+    assert_eq!(Some(1), status.code());
+    assert!(stdout.is_empty());
+
+    Ok(())
+}
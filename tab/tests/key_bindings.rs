@@ -0,0 +1,123 @@
+mod common;
+use common::*;
+
+/// Tests that the DetachAndRun action disconnects from the tab, and runs the command in place of the tab command.
+#[tokio::test]
+async fn detach_and_run() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let config = session.runtime_dir().join("config.yml");
+    std::fs::write(
+        &config,
+        "key_bindings:\n  - action:\n      DetachAndRun: echo detached-$((20+22))\n    keys: ctrl-A d\n",
+    )?;
+
+    let result = session
+        .command()
+        .config(&config)
+        .tab("detach/")
+        .await_stdout("$", 3000)
+        .stdin_bytes(&[1, b'd'])
+        .await_stdout("detached-42", 3000)
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    Ok(())
+}
+
+/// Tests that the SendPrefix action sends the first key of the binding to the tab.
+#[tokio::test]
+async fn send_prefix() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let config = session.runtime_dir().join("config.yml");
+    std::fs::write(
+        &config,
+        "key_bindings:\n  - action: SendPrefix\n    keys: x x\n  - action: Disconnect\n    keys: x d\n",
+    )?;
+
+    let result = session
+        .command()
+        .config(&config)
+        .tab("prefix/")
+        .await_stdout("$", 3000)
+        .stdin("echo '1'")
+        .stdin("xx")
+        .stdin("'2'\n")
+        .await_stdout("1x2", 3000)
+        .stdin("xd")
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    Ok(())
+}
+
+/// Tests that bindings can use the alt modifier, which is sent as an ESC prefix.
+#[tokio::test]
+async fn alt_key() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let config = session.runtime_dir().join("config.yml");
+    std::fs::write(
+        &config,
+        "key_bindings:\n  - action: Disconnect\n    keys: alt-d\n",
+    )?;
+
+    let result = session
+        .command()
+        .config(&config)
+        .tab("alt/")
+        .await_stdout("$", 3000)
+        .stdin_bytes(b"\x1bd")
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    Ok(())
+}
+
+/// Tests that the CloseTab action is ignored when watching a tab, so a read-only client can't close it.
+#[tokio::test]
+async fn close_tab_read_only() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let config = session.runtime_dir().join("config.yml");
+    std::fs::write(
+        &config,
+        "key_bindings:\n  - action: CloseTab\n    keys: ctrl-A x\n  - action: Disconnect\n    keys: ctrl-A d\n",
+    )?;
+
+    let result = session
+        .command()
+        .tab("watched/")
+        .await_stdout("$", 3000)
+        .disconnect()
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    let result = session
+        .command()
+        .config(&config)
+        .watch("watched/")
+        .await_stdout("$", 3000)
+        .stdin_bytes(&[1, b'x'])
+        .delay_ms(500)
+        .stdin_bytes(&[1, b'd'])
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+    assert_eq!(
+        vec!["watched/".to_string()],
+        session.command().tabs().await?
+    );
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+mod common;
+use common::*;
+
+/// Tests that selecting a layout declared in the repository tab.yml opens it's tabs in panes.
+#[tokio::test]
+async fn layout() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let repo = session.runtime_dir().join("proj");
+    std::fs::create_dir_all(&repo)?;
+    std::fs::write(
+        repo.join("tab.yml"),
+        "repo: proj\ntabs:\n  - tab: left\n  - tab: right\nlayout:\n  - layout: dev\n    horizontal: [left, right]\n",
+    )?;
+
+    let status = session
+        .command()
+        .send("proj/right/", "echo right-$((20+22))\\n")
+        .await?;
+    assert_eq!(Some(0), status.code());
+
+    let result = session
+        .command()
+        .dir(repo)
+        .tab("proj/dev/")
+        .await_stdout("right-42", 5000)
+        .delay_ms(500)
+        .stdin("echo left-$((20+22))\n")
+        .await_stdout("left-42", 3000)
+        .stdin("exit\n")
+        .delay_ms(1000)
+        .stdin("exit\n")
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+mod common;
+use common::*;
+
+/// Tests the machine-readable output of `tab --list --format json`
+#[tokio::test]
+async fn list_json() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let result = session
+        .command()
+        .tab("list/")
+        .await_stdout("$", 3000)
+        .disconnect()
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    // give the daemon time to detach the disconnected client
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    let output = session.command().list("json").await?;
+    let entries: serde_json::Value = serde_json::from_str(output.as_str())?;
+
+    let entry = entries
+        .as_array()
+        .expect("expected an array of tabs")
+        .iter()
+        .find(|entry| entry["name"] == "list/")
+        .expect("expected the list/ tab");
+
+    assert_eq!("/bin/bash", entry["shell"]);
+
+    let running = &entry["running"];
+    assert!(running["id"].is_u64());
+    assert_eq!(
+        2,
+        running["dimensions"].as_array().map(Vec::len).unwrap_or(0)
+    );
+    assert!(running["selected"].as_u64().unwrap_or(0) > 0);
+    assert_eq!(false, running["attached"]);
+
+    Ok(())
+}
+
+/// Tests the machine-readable output of `tab --list --format yaml`
+#[tokio::test]
+async fn list_yaml() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let result = session
+        .command()
+        .tab("list/")
+        .await_stdout("$", 3000)
+        .disconnect()
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    let output = session.command().list("yaml").await?;
+    assert!(output.contains("name: list/"));
+    assert!(output.contains("running:"));
+
+    Ok(())
+}
+
+/// Tests that the attached command exits with the exit code of the shell,
+/// and that the exited tab is listed until it is dismissed with `tab --close`
+#[tokio::test]
+async fn list_exited() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let result = session
+        .command()
+        .tab("exited/")
+        .await_stdout("$", 3000)
+        .stdin("exit 3\n")
+        .run()
+        .await?;
+
+    assert_eq!(Some(3), result.exit_status.code());
+
+    let output = session.command().list("json").await?;
+    let entries: serde_json::Value = serde_json::from_str(output.as_str())?;
+
+    let entry = entries
+        .as_array()
+        .expect("expected an array of tabs")
+        .iter()
+        .find(|entry| entry["name"] == "exited/")
+        .expect("expected the exited/ tab");
+
+    assert!(entry["running"].is_null());
+    assert_eq!(3, entry["exited"]["code"]);
+
+    let status = session.command().close(&["exited/"]).await?;
+    assert_eq!(Some(0), status.code());
+
+    let output = session.command().list("json").await?;
+    assert!(!output.contains("exited/"));
+
+    Ok(())
+}
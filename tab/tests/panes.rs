@@ -0,0 +1,34 @@
+mod common;
+use common::*;
+
+/// Tests that tabs opened in panes display their scrollback, and that input is sent to the focused pane.
+/// When the tab in a pane exits, the pane is closed, and the command exits with the last pane.
+#[tokio::test]
+async fn panes() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let status = session
+        .command()
+        .send("right/", "echo right-$((20+22))\\n")
+        .await?;
+    assert_eq!(Some(0), status.code());
+
+    let result = session
+        .command()
+        .panes(&["left/", "right/"])
+        .await_stdout("right-42", 5000)
+        .delay_ms(500)
+        .stdin("echo left-$((20+22))\n")
+        .await_stdout("left-42", 3000)
+        .stdin("exit\n")
+        .delay_ms(1000)
+        .stdin("echo after-$((20+22))\n")
+        .await_stdout("after-42", 3000)
+        .stdin("exit\n")
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use insta::assert_snapshot;
+
+mod common;
+use common::*;
+
+/// Tests that a tab is restored after the daemon restarts.
+/// Covers daemon shutdown, session persistence, and restored scrollback
+#[tokio::test]
+async fn persist() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let result = session
+        .command()
+        .tab("persist/")
+        .await_stdout("$", 3000)
+        .stdin("echo foo\n")
+        .await_stdout("echo foo", 300)
+        .await_stdout("$", 300)
+        .disconnect()
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    let status = session.command().shutdown().await?;
+    assert_eq!(Some(0), status.code());
+
+    let result = session
+        .command()
+        .tab("persist/")
+        .await_stdout("foo", 3000)
+        .await_stdout("$", 3000)
+        .stdin("exit\n")
+        .await_stdout("exit", 300)
+        .complete_snapshot()
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+    assert_snapshot!("restored", &result.snapshot);
+
+    Ok(())
+}
@@ -3,46 +3,45 @@ use insta::assert_snapshot;
 mod common;
 use common::*;
 
-/// Tests that a session can be established, disconnected from, and re-established
-/// Covers connection, ctrl-W, disconnection, and scrollback
+/// Tests that running shells survive a daemon crash.
+/// Covers pty reconnection, and re-adoption of the running tab by the next daemon
 #[tokio::test]
 async fn reconnect() -> anyhow::Result<()> {
     let session = TestSession::new()?;
 
-    for i in 0..10 {
-        reconnect_iter(&session, i).await?;
-    }
-
-    Ok(())
-}
-
-async fn reconnect_iter(session: &TestSession, iter: usize) -> anyhow::Result<()> {
-    let tab = format!("reconnect/{}/", iter);
     let result = session
         .command()
-        .tab(tab.as_str())
+        .tab("reconnect/")
         .await_stdout("$", 3000)
-        .stdin("echo foo\n")
-        .await_stdout("echo foo", 300)
+        .stdin("SHELL_STATE=42; echo foo\n")
+        .await_stdout("foo", 300)
         .await_stdout("$", 300)
         .disconnect()
         .run()
         .await?;
 
     assert_eq!(Some(0), result.exit_status.code());
-    assert_snapshot!("before", result.snapshot);
+
+    // give the daemon time to save the session
+    tokio::time::sleep(std::time::Duration::from_millis(2500)).await;
+    session.command().kill_daemon().await?;
 
     let result = session
         .command()
-        .tab(tab.as_str())
-        .await_stdout("foo", 1000)
+        .tab("reconnect/")
+        .await_stdout("foo", 3000)
+        .await_stdout("$", 3000)
+        .stdin("echo state-$SHELL_STATE\n")
+        .await_stdout("state-42", 3000)
+        .await_stdout("$", 300)
         .stdin("exit\n")
         .await_stdout("exit", 300)
         .complete_snapshot()
         .run()
         .await?;
+
     assert_eq!(Some(0), result.exit_status.code());
-    assert_snapshot!("after", &result.snapshot);
+    assert_snapshot!("resumed", &result.snapshot);
 
     Ok(())
 }
@@ -0,0 +1,45 @@
+mod common;
+use common::*;
+
+use std::{path::PathBuf, time::Duration};
+use tokio::time;
+
+/// Tests that `tab --record` writes the tab output to an asciicast file, and `tab --replay` plays it back.
+#[tokio::test]
+async fn record() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let status = session.command().record("record/").await?;
+    assert_eq!(Some(0), status.code());
+
+    let status = session
+        .command()
+        .send("record/", "echo recorded-$((20+22))\\n")
+        .await?;
+    assert_eq!(Some(0), status.code());
+
+    time::sleep(Duration::from_millis(500)).await;
+
+    let recordings = session.runtime_dir().join("recordings");
+    let files: Vec<PathBuf> = std::fs::read_dir(recordings)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+
+    assert_eq!(1, files.len());
+    let file = &files[0];
+    assert!(file.to_string_lossy().ends_with(".cast"));
+
+    let contents = std::fs::read_to_string(file)?;
+    assert!(contents.contains("\"version\":2"));
+    assert!(contents.contains("recorded-42"));
+
+    let (status, stdout) = session
+        .command()
+        .replay(file.as_path(), &["--speed", "100"])
+        .await?;
+
+    assert_eq!(Some(0), status.code());
+    assert!(stdout.contains("recorded-42"));
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+mod common;
+use common::*;
+
+/// Tests that a tab with `remain_on_exit: true` stays open when the shell exits,
+/// and that the shell can be restarted with `r`, and the tab closed with `q`.
+#[tokio::test]
+async fn remain_on_exit() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let workspace = session.runtime_dir().join("workspace");
+    std::fs::create_dir_all(&workspace)?;
+    std::fs::write(
+        workspace.join("tab.yml"),
+        "workspace:\n  - tab: remain\n    remain_on_exit: true\n",
+    )?;
+
+    let result = session
+        .command()
+        .dir(workspace)
+        .tab("remain/")
+        .await_stdout("$", 3000)
+        .stdin("exit 3\n")
+        .await_stdout("[exited 3] press r to restart, q to close", 3000)
+        .stdin("r")
+        .await_stdout("$", 3000)
+        .stdin("exit 5\n")
+        .await_stdout("[exited 5] press r to restart, q to close", 3000)
+        .stdin("q")
+        .run()
+        .await?;
+
+    assert_eq!(Some(5), result.exit_status.code());
+
+    let output = session.command().list("json").await?;
+    let entries: serde_json::Value = serde_json::from_str(output.as_str())?;
+
+    let entry = entries
+        .as_array()
+        .expect("expected an array of tabs")
+        .iter()
+        .find(|entry| entry["name"] == "remain/")
+        .expect("expected the remain/ tab");
+
+    assert!(entry["running"].is_null());
+    assert_eq!(5, entry["exited"]["code"]);
+
+    Ok(())
+}
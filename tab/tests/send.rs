@@ -0,0 +1,61 @@
+mod common;
+use common::*;
+
+/// Tests that `tab --send` creates the tab, and types the text into the shell.
+#[tokio::test]
+async fn send() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let status = session
+        .command()
+        .send("send/", "echo sent-$((20+22))\\n")
+        .await?;
+
+    assert_eq!(Some(0), status.code());
+    assert_eq!(vec!["send/".to_string()], session.command().tabs().await?);
+
+    let result = session
+        .command()
+        .tab("send/")
+        .await_stdout("sent-42", 3000)
+        .await_stdout("$", 300)
+        .stdin("exit\n")
+        .await_stdout("exit", 300)
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    Ok(())
+}
+
+/// Tests that `tab --send` forwards stdin to a running tab, when the text is omitted.
+#[tokio::test]
+async fn send_stdin() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let mut interactive = session.command();
+    interactive
+        .tab("send-stdin/")
+        .await_stdout("$", 3000)
+        .await_stdout("piped-42", 5000)
+        .await_stdout("$", 300)
+        .stdin("exit\n")
+        .await_stdout("exit", 300);
+
+    let send = async {
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        session
+            .command()
+            .send_stdin("send-stdin/", b"echo piped-$((20+22))\n")
+            .await
+    };
+
+    let (interactive, status) = tokio::join!(interactive.run(), send);
+    let (interactive, status) = (interactive?, status?);
+
+    assert_eq!(Some(0), status.code());
+    assert_eq!(Some(0), interactive.exit_status.code());
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+mod common;
+use common::*;
+
+/// Tests that the status bar shows the current tab, and that the tab is sized to the rows above the status bar.
+#[tokio::test]
+async fn status_bar() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let config = session.runtime_dir().join("config.yml");
+    std::fs::write(&config, "status_bar:\n  enabled: true\n  clock: false\n")?;
+
+    let result = session
+        .command()
+        .config(&config)
+        .tab("status/")
+        .await_stdout("$", 3000)
+        .stdin("stty size\n")
+        .await_stdout("23 80", 3000)
+        .await_stdout(" status/ │", 3000)
+        .stdin("exit\n")
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use insta::assert_snapshot;
+use std::os::unix::fs::PermissionsExt;
+
+mod common;
+use common::*;
+
+/// Tests that a session can be established when the daemon listens on a Unix socket
+/// Covers the cli & pty connections over the socket, and scrollback
+#[tokio::test]
+async fn unix_socket() -> anyhow::Result<()> {
+    let session = TestSession::new_unix_socket()?;
+
+    let result = session
+        .command()
+        .tab("unix-socket/")
+        .await_stdout("$", 3000)
+        .stdin("echo foo\n")
+        .await_stdout("foo", 300)
+        .await_stdout("$", 300)
+        .disconnect()
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    // the socket directory is only accessible by the current user
+    let dir = session.runtime_dir().join("sockets");
+    let mode = std::fs::metadata(&dir)?.permissions().mode();
+    assert_eq!(0o700, mode & 0o777);
+
+    let sockets = std::fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().map(|ext| ext == "sock") == Some(true))
+        .count();
+    assert_eq!(1, sockets);
+
+    let result = session
+        .command()
+        .tab("unix-socket/")
+        .await_stdout("foo", 3000)
+        .await_stdout("$", 300)
+        .stdin("exit\n")
+        .await_stdout("exit", 300)
+        .complete_snapshot()
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+    assert_snapshot!("after", &result.snapshot);
+
+    Ok(())
+}
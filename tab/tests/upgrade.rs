@@ -0,0 +1,46 @@
+use insta::assert_snapshot;
+
+mod common;
+use common::*;
+
+/// Tests that running shells survive a daemon upgrade.
+/// Covers the daemon handoff, pty reconnection, and restored scrollback
+#[tokio::test]
+async fn upgrade() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let result = session
+        .command()
+        .tab("upgrade/")
+        .await_stdout("$", 3000)
+        .stdin("SHELL_STATE=42; echo foo\n")
+        .await_stdout("foo", 300)
+        .await_stdout("$", 300)
+        .disconnect()
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    let status = session.command().upgrade_daemon().await?;
+    assert_eq!(Some(0), status.code());
+
+    let result = session
+        .command()
+        .tab("upgrade/")
+        .await_stdout("foo", 3000)
+        .await_stdout("$", 3000)
+        .stdin("echo state-$SHELL_STATE\n")
+        .await_stdout("state-42", 1000)
+        .await_stdout("$", 300)
+        .stdin("exit\n")
+        .await_stdout("exit", 300)
+        .complete_snapshot()
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+    assert_snapshot!("resumed", &result.snapshot);
+
+    Ok(())
+}
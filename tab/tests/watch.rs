@@ -0,0 +1,70 @@
+mod common;
+use common::*;
+
+/// Tests that a watching client receives scrollback and live output, but can't send input to the tab.
+#[tokio::test]
+async fn watch() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let result = session
+        .command()
+        .tab("watch/")
+        .await_stdout("$", 3000)
+        .stdin("echo ready-$((20+22))\n")
+        .await_stdout("ready-42", 300)
+        .await_stdout("$", 300)
+        .disconnect()
+        .run()
+        .await?;
+
+    assert_eq!(Some(0), result.exit_status.code());
+
+    let mut watcher = session.command();
+    watcher
+        .watch("watch/")
+        .await_stdout("ready-42", 3000)
+        .await_stdout("live-42", 5000)
+        .stdin("echo typed-$((20+22))\n")
+        .delay_ms(500)
+        .disconnect();
+
+    let mut interactive = session.command();
+    interactive
+        .tab("watch/")
+        .await_stdout("ready-42", 3000)
+        .delay_ms(1000)
+        .stdin("echo live-$((20+22))\n")
+        .await_stdout("live-42", 300)
+        .delay_ms(1500)
+        .stdin("echo done-$((20+22))\n")
+        .await_stdout("done-42", 300)
+        .await_stdout("$", 300)
+        .stdin("exit\n")
+        .await_stdout("exit", 300);
+
+    let (watcher, interactive) = tokio::join!(watcher.run(), interactive.run());
+    let (watcher, interactive) = (watcher?, interactive?);
+
+    assert_eq!(Some(0), watcher.exit_status.code());
+    assert!(watcher.stdout.contains("ready-42"));
+    assert!(watcher.stdout.contains("live-42"));
+
+    assert_eq!(Some(0), interactive.exit_status.code());
+    assert!(interactive.stdout.contains("done-42"));
+    assert!(!interactive.stdout.contains("typed"));
+
+    Ok(())
+}
+
+/// Tests that watching a tab which isn't running fails, rather than creating the tab.
+#[tokio::test]
+async fn watch_missing() -> anyhow::Result<()> {
+    let session = TestSession::new()?;
+
+    let result = session.command().watch("missing/").run().await?;
+
+    assert_eq!(Some(1), result.exit_status.code());
+    assert!(session.command().tabs().await?.is_empty());
+
+    Ok(())
+}